@@ -1,11 +1,29 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
 
+use crate::effect::ContinuousEffect;
 use crate::effect::Effect;
 use crate::effect::EffectTrigger;
+use crate::effect::Replacement;
+use crate::effect::StatModifier;
+use crate::Game;
+use crate::GameObject;
+use crate::GameState;
+use crate::Timing;
+
+/// The name of the [`EffectInfo::Number`](crate::effect::EffectInfo::Number) choice that decides
+/// the value of X in a variable cost
+pub const VARIABLE_COST_INFO: &str = "X";
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Cost {
     pub corp1_scrip: u64,
     pub corp2_scrip: u64,
@@ -13,6 +31,118 @@ pub struct Cost {
     pub corp4_scrip: u64,
     pub corp5_scrip: u64,
     pub any_scrip: u64,
+    /// How much scrip of any kind has to be paid for each point of X
+    pub variable_scrip: u64,
+}
+
+/// The kinds of scrip, one for each corporation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum ScripKind {
+    Corp1,
+    Corp2,
+    Corp3,
+    Corp4,
+    Corp5,
+}
+
+impl ScripKind {
+    pub const ALL: [ScripKind; 5] = [
+        ScripKind::Corp1,
+        ScripKind::Corp2,
+        ScripKind::Corp3,
+        ScripKind::Corp4,
+        ScripKind::Corp5,
+    ];
+}
+
+/// The most scrip of a single kind a card may reasonably ask for
+pub const MAX_SCRIP_PER_KIND: u64 = 99;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CostError {
+    #[error("The {kind} part of the cost is {value}, which is more than the allowed {max}")]
+    ScripTooLarge {
+        kind: &'static str,
+        value: u64,
+        max: u64,
+    },
+    #[error("The total cost of {total} exceeds the maximum of {max}")]
+    TotalTooLarge { total: u64, max: u64 },
+}
+
+impl Cost {
+    /// The scrip of the kind this cost asks for, without scrip of any kind
+    pub fn scrip_of(&self, kind: ScripKind) -> u64 {
+        match kind {
+            ScripKind::Corp1 => self.corp1_scrip,
+            ScripKind::Corp2 => self.corp2_scrip,
+            ScripKind::Corp3 => self.corp3_scrip,
+            ScripKind::Corp4 => self.corp4_scrip,
+            ScripKind::Corp5 => self.corp5_scrip,
+        }
+    }
+
+    /// The sum of all fixed scrip in this cost, X is counted as zero
+    pub fn total(&self) -> u64 {
+        [
+            self.corp1_scrip,
+            self.corp2_scrip,
+            self.corp3_scrip,
+            self.corp4_scrip,
+            self.corp5_scrip,
+            self.any_scrip,
+        ]
+        .into_iter()
+        .fold(0, u64::saturating_add)
+    }
+
+    /// Checks that the cost is sensible, and if given, that its total does not exceed `max_total`
+    pub fn validate(&self, max_total: Option<u64>) -> Result<(), CostError> {
+        let parts = [
+            ("corp1", self.corp1_scrip),
+            ("corp2", self.corp2_scrip),
+            ("corp3", self.corp3_scrip),
+            ("corp4", self.corp4_scrip),
+            ("corp5", self.corp5_scrip),
+            ("any", self.any_scrip),
+            ("variable", self.variable_scrip),
+        ];
+
+        for (kind, value) in parts {
+            if value > MAX_SCRIP_PER_KIND {
+                return Err(CostError::ScripTooLarge {
+                    kind,
+                    value,
+                    max: MAX_SCRIP_PER_KIND,
+                });
+            }
+        }
+
+        if let Some(max) = max_total {
+            let total = self.total();
+            if total > max {
+                return Err(CostError::TotalTooLarge { total, max });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The cost in its canonical form, a cost of zero is the same as having no cost
+    pub fn canonical(cost: Option<Cost>) -> Option<Cost> {
+        cost.filter(|c| *c != Cost::default())
+    }
+
+    /// Turns the variable part of the cost into a fixed one, given the chosen value of X
+    pub fn with_variable(&self, x: u64) -> Cost {
+        Cost {
+            any_scrip: self
+                .any_scrip
+                .saturating_add(self.variable_scrip.saturating_mul(x)),
+            variable_scrip: 0,
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -20,40 +150,179 @@ pub struct CardKind {
     pub kind: BaseCardKind,
 }
 
-#[derive(Debug)]
-pub enum AgentSubKind {
-    Mercenary,
+/// An identifier for a card subkind, like `Mercenary`
+///
+/// Subkinds are plain data so that card sets can introduce new ones. Which subkinds exist is
+/// decided by a [`SubKindRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SubKind(Arc<str>);
+
+impl SubKind {
+    pub fn new(name: &str) -> SubKind {
+        SubKind(Arc::from(name))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
 }
 
-#[derive(Debug)]
-pub enum BuildingSubKind {}
+impl Borrow<str> for SubKind {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+/// All subkinds known to a game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SubKindRegistry {
+    subkinds: HashSet<SubKind>,
+}
+
+/// The agent subkinds of the base game
+pub const BASE_AGENT_SUBKINDS: [&str; 5] = ["Mercenary", "Netrunner", "Fixer", "Enforcer", "Drone"];
+
+/// The building subkinds of the base game
+pub const BASE_BUILDING_SUBKINDS: [&str; 4] = ["Factory", "Datacenter", "Safehouse", "Outpost"];
+
+impl SubKindRegistry {
+    /// The subkinds of the base game
+    pub fn base() -> SubKindRegistry {
+        let mut registry = SubKindRegistry::default();
+        for name in BASE_AGENT_SUBKINDS
+            .into_iter()
+            .chain(BASE_BUILDING_SUBKINDS)
+        {
+            registry.register(name);
+        }
+        registry
+    }
+
+    /// Adds a subkind, returning its interned identifier
+    pub fn register(&mut self, name: &str) -> SubKind {
+        if let Some(subkind) = self.get(name) {
+            return subkind;
+        }
+
+        let subkind = SubKind::new(name);
+        self.subkinds.insert(subkind.clone());
+        subkind
+    }
+
+    pub fn get(&self, name: &str) -> Option<SubKind> {
+        self.subkinds.get(name).cloned()
+    }
+
+    pub fn contains(&self, subkind: &SubKind) -> bool {
+        self.subkinds.contains(subkind)
+    }
+}
+
+/// Computes a stat of an agent from the game, like "power equal to the cards in your hand"
+///
+/// Implementations may not ask for the stats of `agent` itself, as those are computed through
+/// this.
+pub trait DynamicStat: Debug + Sync + Send {
+    /// The stat of `agent` in `state`, before its counters and other modifiers
+    fn compute(&self, game: &Game, state: &GameState, agent: &GameObject) -> u64;
+}
+static_assertions::assert_obj_safe!(DynamicStat);
 
 #[derive(Debug)]
 pub enum AgentPower {
     Fixed(u64),
-    Special,
+    Special(Box<dyn DynamicStat>),
+}
+
+impl AgentPower {
+    pub fn of(&self, game: &Game, state: &GameState, agent: &GameObject) -> u64 {
+        match self {
+            AgentPower::Fixed(power) => *power,
+            AgentPower::Special(stat) => stat.compute(game, state, agent),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum AgentToughness {
     Fixed(u64),
-    Special,
+    Special(Box<dyn DynamicStat>),
+}
+
+impl AgentToughness {
+    pub fn of(&self, game: &Game, state: &GameState, agent: &GameObject) -> u64 {
+        match self {
+            AgentToughness::Fixed(toughness) => *toughness,
+            AgentToughness::Special(stat) => stat.compute(game, state, agent),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum BaseCardKind {
     Agent {
-        subkind: AgentSubKind,
+        subkind: SubKind,
         power: AgentPower,
         toughness: AgentToughness,
     },
     Building {
-        subkind: BuildingSubKind,
+        subkind: SubKind,
     },
     Quickhack,
     Program,
 }
 
+impl BaseCardKind {
+    pub fn subkind(&self) -> Option<&SubKind> {
+        match self {
+            BaseCardKind::Agent { subkind, .. } | BaseCardKind::Building { subkind } => {
+                Some(subkind)
+            }
+            BaseCardKind::Quickhack | BaseCardKind::Program => None,
+        }
+    }
+
+    pub fn tag(&self) -> BaseCardKindTag {
+        match self {
+            BaseCardKind::Agent { .. } => BaseCardKindTag::Agent,
+            BaseCardKind::Building { .. } => BaseCardKindTag::Building,
+            BaseCardKind::Quickhack => BaseCardKindTag::Quickhack,
+            BaseCardKind::Program => BaseCardKindTag::Program,
+        }
+    }
+}
+
+/// The kind of a card, without any of the kind specific details
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BaseCardKindTag {
+    Agent,
+    Building,
+    Quickhack,
+    Program,
+}
+
+impl BaseCardKindTag {
+    /// Whether cards of this kind stay on the battlefield once they resolved
+    pub fn is_permanent(self) -> bool {
+        match self {
+            BaseCardKindTag::Agent | BaseCardKindTag::Building | BaseCardKindTag::Program => true,
+            BaseCardKindTag::Quickhack => false,
+        }
+    }
+
+    /// When cards of this kind may be played, unless the format says otherwise
+    pub fn default_timing(self) -> Timing {
+        match self {
+            BaseCardKindTag::Quickhack => Timing::Any,
+            BaseCardKindTag::Agent | BaseCardKindTag::Building | BaseCardKindTag::Program => {
+                Timing::MainPhase
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TriggeredCardEffect {
     pub trigger: EffectTrigger,
@@ -63,6 +332,9 @@ pub struct TriggeredCardEffect {
 #[derive(Debug)]
 pub struct ActivatedCardEffect {
     pub cost: Cost,
+    /// Whether the object is exhausted to activate this, so that it can only be used once until
+    /// the object is readied again
+    pub exhaust: bool,
     pub effect: Vec<Effect>,
 }
 
@@ -78,14 +350,117 @@ pub enum CardEffect {
     Static(StaticCardEffect),
 }
 
+impl CardEffect {
+    pub fn effects(&self) -> &[Effect] {
+        match self {
+            CardEffect::Triggered(triggered) => &triggered.effects,
+            CardEffect::Activated(activated) => &activated.effect,
+            CardEffect::Static(static_effect) => std::slice::from_ref(&static_effect.effect),
+        }
+    }
+}
+
+/// Another way to play a card, instead of paying its scrip cost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlternativeCost {
+    /// Discarding this many other cards from hand
+    Discard { count: usize },
+    /// Losing this much life
+    PayLife { amount: usize },
+    /// Nothing at all, as long as the condition holds
+    Free { condition: PlayCondition },
+}
+
+/// What has to hold for an [`AlternativeCost::Free`] to be usable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayCondition {
+    /// The player controls no agents
+    NoAgents,
+    /// The player has at most this much life
+    LifeAtMost(usize),
+}
+
+/// Simple abilities the engine handles by itself, without any effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Keyword {
+    /// May be played whenever its controller has priority, like a quickhack
+    Fast,
+    /// Can not be chosen as a target
+    Untargetable,
+    /// Can not be discarded
+    Persistent,
+    /// Agents with it may use abilities that exhaust them the turn they enter the battlefield
+    Rush,
+    /// Agents with it deal their combat damage before those without it
+    Overclocked,
+    /// A player may only control one object of this card, extras are put into their discard pile
+    Unique,
+}
+
 #[derive(Debug)]
 pub struct CardBehaviour {
     pub cost: Option<Cost>,
     pub kind: Vec<CardKind>,
     pub effects: Vec<CardEffect>,
+    pub keywords: Vec<Keyword>,
+    /// Ways to play the card instead of paying `cost`
+    pub alternative_costs: Vec<AlternativeCost>,
+}
+
+impl CardBehaviour {
+    pub fn has_keyword(&self, keyword: Keyword) -> bool {
+        self.keywords.contains(&keyword)
+    }
+
+    /// Whether any of the card's kinds is `tag`
+    pub fn is_kind(&self, tag: BaseCardKindTag) -> bool {
+        self.kind.iter().any(|k| k.kind.tag() == tag)
+    }
+
+    pub fn is_agent(&self) -> bool {
+        self.is_kind(BaseCardKindTag::Agent)
+    }
+
+    pub fn is_building(&self) -> bool {
+        self.is_kind(BaseCardKindTag::Building)
+    }
+
+    /// Whether the card stays on the battlefield once it resolved
+    pub fn is_permanent(&self) -> bool {
+        self.kind.iter().any(|k| k.kind.tag().is_permanent())
+    }
+
+    /// The subkinds of all of the card's kinds
+    pub fn subkinds(&self) -> impl Iterator<Item = &SubKind> {
+        self.kind.iter().filter_map(|k| k.kind.subkind())
+    }
+
+    pub fn has_subkind(&self, subkind: &SubKind) -> bool {
+        self.subkinds().any(|s| s == subkind)
+    }
+
+    /// The stat modifiers of the static effects
+    pub fn stat_modifiers(&self) -> impl Iterator<Item = &StatModifier> {
+        self.effects.iter().filter_map(|e| match e {
+            CardEffect::Static(StaticCardEffect {
+                effect: Effect::Continuous(ContinuousEffect::ModifyStats(modifier)),
+            }) => Some(modifier),
+            _ => None,
+        })
+    }
+
+    /// The replacements of the static effects
+    pub fn replacements(&self) -> impl Iterator<Item = &Replacement> {
+        self.effects.iter().filter_map(|e| match e {
+            CardEffect::Static(StaticCardEffect {
+                effect: Effect::Continuous(ContinuousEffect::Replace(replacement)),
+            }) => Some(replacement),
+            _ => None,
+        })
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(transparent)]
 pub struct CardId(uuid::Uuid);
 
@@ -95,8 +470,52 @@ impl CardId {
     }
 }
 
+impl std::str::FromStr for CardId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(CardId)
+    }
+}
+
 #[derive(Debug)]
 pub struct Card {
     pub id: CardId,
+    /// Bumped whenever the behaviour of the card changes, so that games are not resumed with a
+    /// different card than they were played with
+    pub version: u32,
     pub behaviour: CardBehaviour,
+    pub layout: CardLayout,
+}
+
+impl Card {
+    pub fn is_double_faced(&self) -> bool {
+        matches!(self.layout, CardLayout::DoubleFaced { .. })
+    }
+
+    /// The halves that may be played from hand, with whether it is the second half
+    pub fn halves(&self) -> impl Iterator<Item = (bool, &CardBehaviour)> {
+        let second = match &self.layout {
+            CardLayout::Split { second } => Some((true, second)),
+            _ => None,
+        };
+        std::iter::once((false, &self.behaviour)).chain(second)
+    }
+}
+
+/// Which other behaviour a card has besides its [`Card::behaviour`], if any
+#[derive(Debug, Default)]
+pub enum CardLayout {
+    #[default]
+    Single,
+    /// Objects of the card turn to the back face when they are transformed
+    ///
+    /// Only the stats, static and activated effects of the active face apply, triggered effects
+    /// are always those of the front face.
+    DoubleFaced { back: CardBehaviour },
+    /// Either half may be played from hand, each with its own cost and effects
+    ///
+    /// The object keeps being the played half until it leaves the stack, or the battlefield if it
+    /// is a permanent.
+    Split { second: CardBehaviour },
 }
@@ -0,0 +1,239 @@
+//! The changes between two game states
+//!
+//! Instead of sending every game state in full, a [`GameStateDelta`] only carries what changed
+//! since the state before, and applying it to that state gives the new one.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::card::ScripKind;
+use crate::effect::DelayedTrigger;
+use crate::DamageShield;
+use crate::GameObject;
+use crate::GameStage;
+use crate::GameState;
+use crate::GameZone;
+use crate::ObjectId;
+use crate::Phase;
+use crate::PlayerId;
+use crate::TemporaryEffect;
+use crate::ZoneId;
+
+/// The changes to the objects of a single zone
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ZoneDelta {
+    pub zone: ZoneId,
+    /// Objects that left the zone
+    pub removed: Vec<ObjectId>,
+    /// Objects that entered the zone or changed in it, in their new form
+    pub changed: Vec<GameObject>,
+    /// The new order of the objects, `None` if it follows from removing and appending
+    pub order: Option<Vec<ObjectId>>,
+}
+
+/// Everything that differs between two game states, see [`GameStateDelta::between`]
+///
+/// Fields that did not change are `None` or empty. Life totals, poison counters and action counts
+/// only list the players whose value changed, `None` if the player has no value anymore.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct GameStateDelta {
+    pub zones: Vec<ZoneDelta>,
+    /// Zones that ceased to exist, like the emblems of an eliminated player
+    pub removed_zones: Vec<ZoneId>,
+    pub active_player_order: Option<Vec<PlayerId>>,
+    pub unpassed_players: Option<Vec<PlayerId>>,
+    pub game_stage: Option<GameStage>,
+    pub phase: Option<Phase>,
+    pub delayed_triggers: Option<Vec<DelayedTrigger>>,
+    pub actions_this_priority: HashMap<PlayerId, Option<usize>>,
+    pub actions_this_turn: HashMap<PlayerId, Option<usize>>,
+    pub scrip: Option<HashMap<PlayerId, HashMap<ScripKind, u64>>>,
+    pub floating_scrip: Option<HashMap<PlayerId, HashMap<ScripKind, u64>>>,
+    pub life: HashMap<PlayerId, Option<usize>>,
+    pub poison: HashMap<PlayerId, Option<usize>>,
+    pub turn: Option<usize>,
+    pub shields: Option<Vec<DamageShield>>,
+    pub temporary_effects: Option<Vec<TemporaryEffect>>,
+}
+
+fn changed<T: PartialEq + Clone>(from: &T, to: &T) -> Option<T> {
+    (from != to).then(|| to.clone())
+}
+
+/// The entries of `to` that differ from `from`
+fn changed_counts(
+    from: &HashMap<PlayerId, usize>,
+    to: &HashMap<PlayerId, usize>,
+) -> HashMap<PlayerId, Option<usize>> {
+    from.keys()
+        .chain(to.keys())
+        .map(|p| (*p, to.get(p).copied()))
+        .filter(|(p, value)| from.get(p).copied() != *value)
+        .collect()
+}
+
+fn apply_counts(counts: &mut HashMap<PlayerId, usize>, delta: &HashMap<PlayerId, Option<usize>>) {
+    for (player, value) in delta {
+        match value {
+            Some(value) => counts.insert(*player, *value),
+            None => counts.remove(player),
+        };
+    }
+}
+
+impl ZoneDelta {
+    fn between(zone: ZoneId, from: &[GameObject], to: &[GameObject]) -> Option<ZoneDelta> {
+        let removed: Vec<_> = from
+            .iter()
+            .filter(|o| !to.iter().any(|t| t.id == o.id))
+            .map(|o| o.id)
+            .collect();
+        let changed: Vec<_> = to
+            .iter()
+            .filter(|o| !from.iter().any(|f| f == *o))
+            .cloned()
+            .collect();
+
+        let mut expected: Vec<_> = from
+            .iter()
+            .map(|o| o.id)
+            .filter(|o| !removed.contains(o))
+            .collect();
+        expected.extend(
+            changed
+                .iter()
+                .map(|o| o.id)
+                .filter(|o| !from.iter().any(|f| f.id == *o)),
+        );
+        let order: Vec<_> = to.iter().map(|o| o.id).collect();
+
+        if removed.is_empty() && changed.is_empty() && expected == order {
+            return None;
+        }
+        Some(ZoneDelta {
+            zone,
+            removed,
+            changed,
+            order: (expected != order).then_some(order),
+        })
+    }
+
+    fn apply(&self, objects: &mut Vec<GameObject>) {
+        objects.retain(|o| !self.removed.contains(&o.id));
+        for object in &self.changed {
+            match objects.iter_mut().find(|o| o.id == object.id) {
+                Some(existing) => *existing = object.clone(),
+                None => objects.push(object.clone()),
+            }
+        }
+        if let Some(order) = &self.order {
+            objects.sort_by_key(|o| order.iter().position(|id| *id == o.id));
+        }
+    }
+}
+
+impl GameStateDelta {
+    /// What has to change to turn `from` into `to`
+    pub fn between(from: &GameState, to: &GameState) -> GameStateDelta {
+        let zones = to
+            .zones
+            .iter()
+            .filter_map(|(zone, contents)| match from.zones.get(zone) {
+                Some(before) => ZoneDelta::between(*zone, &before.objects, &contents.objects),
+                // New zones are listed even if they are empty
+                None => Some(ZoneDelta {
+                    zone: *zone,
+                    removed: vec![],
+                    changed: contents.objects.to_vec(),
+                    order: None,
+                }),
+            })
+            .collect();
+        let removed_zones = from
+            .zones
+            .keys()
+            .filter(|zone| !to.zones.contains_key(*zone))
+            .copied()
+            .collect();
+
+        GameStateDelta {
+            zones,
+            removed_zones,
+            active_player_order: changed(&from.active_player_order, &to.active_player_order),
+            unpassed_players: changed(&from.unpassed_players, &to.unpassed_players),
+            game_stage: changed(&from.game_stage, &to.game_stage),
+            phase: changed(&from.phase, &to.phase),
+            delayed_triggers: changed(&from.delayed_triggers, &to.delayed_triggers),
+            actions_this_priority: changed_counts(
+                &from.actions_this_priority,
+                &to.actions_this_priority,
+            ),
+            actions_this_turn: changed_counts(&from.actions_this_turn, &to.actions_this_turn),
+            scrip: changed(&from.scrip, &to.scrip),
+            floating_scrip: changed(&from.floating_scrip, &to.floating_scrip),
+            life: changed_counts(&from.life, &to.life),
+            poison: changed_counts(&from.poison, &to.poison),
+            turn: changed(&from.turn, &to.turn),
+            shields: changed(&from.shields, &to.shields),
+            temporary_effects: changed(&from.temporary_effects, &to.temporary_effects),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == GameStateDelta::default()
+    }
+
+    /// Turns the game state the delta was computed from into the one it was computed to
+    pub fn apply(&self, state: &mut GameState) {
+        for zone in &self.removed_zones {
+            state.zones.remove(zone);
+        }
+        for delta in &self.zones {
+            let contents = state
+                .zones
+                .entry(delta.zone)
+                .or_insert_with(GameZone::empty);
+            delta.apply(&mut contents.objects);
+        }
+
+        if let Some(order) = &self.active_player_order {
+            state.active_player_order = order.clone();
+        }
+        if let Some(unpassed) = &self.unpassed_players {
+            state.unpassed_players = unpassed.clone();
+        }
+        if let Some(stage) = &self.game_stage {
+            state.game_stage = stage.clone();
+        }
+        if let Some(phase) = self.phase {
+            state.phase = phase;
+        }
+        if let Some(triggers) = &self.delayed_triggers {
+            state.delayed_triggers = triggers.clone();
+        }
+        apply_counts(
+            &mut state.actions_this_priority,
+            &self.actions_this_priority,
+        );
+        apply_counts(&mut state.actions_this_turn, &self.actions_this_turn);
+        if let Some(scrip) = &self.scrip {
+            state.scrip = scrip.clone();
+        }
+        if let Some(floating) = &self.floating_scrip {
+            state.floating_scrip = floating.clone();
+        }
+        apply_counts(&mut state.life, &self.life);
+        apply_counts(&mut state.poison, &self.poison);
+        if let Some(turn) = self.turn {
+            state.turn = turn;
+        }
+        if let Some(shields) = &self.shields {
+            state.shields = shields.clone();
+        }
+        if let Some(effects) = &self.temporary_effects {
+            state.temporary_effects = effects.clone();
+        }
+    }
+}
@@ -1,15 +1,23 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::time::Duration;
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::card::BaseCardKindTag;
+use crate::card::CardId;
+use crate::card::SubKind;
 use crate::Game;
 use crate::GameAtom;
 use crate::ObjectId;
+use crate::Phase;
+use crate::PlayerId;
 use crate::TargetId;
+use crate::ZoneKind;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EffectTrigger {
     /// These are the 'main' card effects. This is only useful on cards played onto the stack.
     ///
@@ -17,10 +25,48 @@ pub enum EffectTrigger {
     OnResolve,
     /// This effect triggers whenever a card is played onto the stack
     OnPlay,
-    /// This effect triggers whenever a player draws a card
+    /// This effect triggers whenever a player draws a card, while the object is on the
+    /// battlefield
     ///
     /// Note: This does not trigger when something 'moves' between zones.
     OnDraw,
+    /// These effects only run once a [`DelayedTrigger`] registered by the same card fires
+    Delayed,
+    /// This effect triggers whenever the object enters the battlefield
+    OnEnterBattlefield,
+    /// This effect triggers whenever the object leaves the battlefield
+    OnLeaveBattlefield,
+    /// This effect triggers at the start of its controller's turn, while the object is on the
+    /// battlefield
+    OnTurnStart,
+    /// This effect triggers whenever the object moves into a zone of the given kind
+    OnEnterZone(ZoneKind),
+    /// This effect triggers once a zone of the given kind holds at least `count` objects, while
+    /// the object is on the battlefield
+    ///
+    /// Only the zones of the object's controller are counted, shared zones are counted as a
+    /// whole.
+    OnZoneCount { zone: ZoneKind, count: usize },
+}
+
+/// When a [`DelayedTrigger`] fires
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum DelayedTriggerEvent {
+    /// At the beginning of the next phase of the given kind
+    BeginningOfPhase(Phase),
+}
+
+/// A one-shot trigger, registered by an effect, that fires at a later point in the game
+///
+/// Once it fires the `effect`th [`EffectTrigger::Delayed`] effect of `card` is executed with the
+/// given `info`, after which the trigger is removed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct DelayedTrigger {
+    pub event: DelayedTriggerEvent,
+    pub source: ObjectId,
+    pub card: CardId,
+    pub effect: usize,
+    pub info: HashMap<String, EffectInfo>,
 }
 
 #[derive(Debug)]
@@ -30,14 +76,213 @@ pub enum Effect {
 }
 static_assertions::assert_impl_all!(Effect: Send, Sync);
 
+/// Which player something belongs to, as seen from the player making a choice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativePlayer {
+    You,
+    /// Another player of your team
+    Teammate,
+    /// Any player not on your team
+    Opponent,
+}
+
+impl RelativePlayer {
+    /// Whether `player` is this, as seen from `from`
+    pub fn holds(self, game: &Game, player: PlayerId, from: PlayerId) -> bool {
+        match self {
+            RelativePlayer::You => player == from,
+            RelativePlayer::Teammate => player != from && game.are_allies(player, from),
+            RelativePlayer::Opponent => !game.are_allies(player, from),
+        }
+    }
+}
+
+/// The zones in which objects can be targeted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetZone {
+    Battlefield,
+    Stack,
+}
+
+/// Limits what can be chosen as a target
+///
+/// Without any restriction only players and agents on the battlefield can be targeted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetRestriction {
+    /// Only players
+    Player,
+    /// Only objects in the given zone
+    Zone(TargetZone),
+    /// Only objects whose card is of the given kind
+    CardKind(BaseCardKindTag),
+    /// Only objects whose card has the given subkind
+    SubKind(SubKind),
+    /// Only players, or objects controlled by players, relative to the choosing player
+    Controller(RelativePlayer),
+    /// Only agents on the battlefield whose current power is within the given bounds (inclusive)
+    Power { min: Option<u64>, max: Option<u64> },
+    /// All of the given restrictions have to hold
+    All(Vec<TargetRestriction>),
+    /// At least one of the given restrictions has to hold
+    Any(Vec<TargetRestriction>),
+    /// The given restriction may not hold
+    Not(Box<TargetRestriction>),
+}
+
+impl TargetRestriction {
+    /// Only objects on the stack of the given kind, like "target quickhack on the stack"
+    ///
+    /// Any object on the stack may be targeted if `kind` is `None`.
+    pub fn on_stack(kind: Option<BaseCardKindTag>) -> TargetRestriction {
+        let stack = TargetRestriction::Zone(TargetZone::Stack);
+        match kind {
+            Some(kind) => TargetRestriction::All(vec![stack, TargetRestriction::CardKind(kind)]),
+            None => stack,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum EffectInfoRequest {
-    SingleTarget { restriction: Option<()> },
+    SingleTarget {
+        restriction: Option<TargetRestriction>,
+        /// Whether the player may decline to choose a target ("you may...")
+        ///
+        /// If declined, the effect is skipped on resolution.
+        optional: bool,
+    },
+    /// Asks the player for between `min` and `max` (inclusive) distinct targets
+    ///
+    /// Fewer than `min` may be chosen if there are not enough legal targets.
+    MultipleTargets {
+        min: usize,
+        max: usize,
+        restriction: Option<TargetRestriction>,
+    },
+    /// Asks the player for a number between `min` and `max` (inclusive)
+    ///
+    /// If it is named [`VARIABLE_COST_INFO`](crate::card::VARIABLE_COST_INFO) it also decides the
+    /// variable part of the card's cost.
+    Number { min: u64, max: u64 },
+    /// Lets the controller search their library for a card matching the restriction
+    ///
+    /// Unlike other requests this is asked when the effect resolves, as the library changes in
+    /// the meantime. As the library is hidden, the player may always fail to find a card, in
+    /// which case no info is given.
+    LibrarySearch {
+        restriction: Option<TargetRestriction>,
+    },
+    /// Lets the controller look at the top `count` cards of their library and put them back in
+    /// any order
+    ///
+    /// Like [`LibrarySearch`](EffectInfoRequest::LibrarySearch) this is asked when the effect
+    /// resolves. If `allow_bottom` is set, any of the cards may be put on the bottom instead.
+    LibraryArrangement { count: usize, allow_bottom: bool },
+    /// Lets the controller choose `count` cards from their hand to discard
+    ///
+    /// This is asked when the effect resolves. If the hand holds no more than `count` cards, all
+    /// of them are chosen without asking.
+    Discard { count: usize },
+    /// Makes `chooser` choose `count` cards from the hand of the player chosen for the
+    /// [`SingleTarget`](EffectInfoRequest::SingleTarget) named `target`, who discards them
+    ///
+    /// Like [`Discard`](EffectInfoRequest::Discard) this is asked when the effect resolves. The
+    /// chooser is shown the whole hand, even if they are not its owner.
+    TargetDiscard {
+        target: String,
+        count: usize,
+        chooser: DiscardChooser,
+    },
+    /// Lets the controller choose `count` objects in a zone of the kind, like "choose a card in
+    /// your hand"
+    ///
+    /// The zone belongs to the player chosen for the [`SingleTarget`](EffectInfoRequest::SingleTarget)
+    /// named `target`, or to the controller without one. This is asked when the effect resolves,
+    /// if the zone holds no more than `count` objects all of them are chosen without asking.
+    ChooseFromZone {
+        zone: ZoneKind,
+        target: Option<String>,
+        count: usize,
+    },
+    /// Lets the controller put the objects chosen for the
+    /// [`ChooseFromZone`](EffectInfoRequest::ChooseFromZone) named `of` in order
+    ///
+    /// This is asked when the effect resolves, after the objects were chosen.
+    Ordering { of: String },
+}
+
+/// Who picks the cards of a [`EffectInfoRequest::TargetDiscard`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscardChooser {
+    /// The controller of the effect, after looking at the other player's hand
+    Controller,
+    /// The player whose hand it is
+    Owner,
+}
+
+impl EffectInfoRequest {
+    pub fn is_optional(&self) -> bool {
+        match self {
+            EffectInfoRequest::SingleTarget { optional, .. } => *optional,
+            EffectInfoRequest::MultipleTargets { .. } => false,
+            EffectInfoRequest::Number { .. } => false,
+            EffectInfoRequest::LibrarySearch { .. } => false,
+            EffectInfoRequest::LibraryArrangement { .. } => false,
+            EffectInfoRequest::Discard { .. } => false,
+            EffectInfoRequest::TargetDiscard { .. } => false,
+            EffectInfoRequest::ChooseFromZone { .. } => false,
+            EffectInfoRequest::Ordering { .. } => false,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum EffectInfo {
     SingleTarget(TargetId),
+    /// The distinct targets chosen for [`EffectInfoRequest::MultipleTargets`]
+    MultipleTargets(Vec<TargetId>),
+    Number(u64),
+    /// The object found in the library of the effect's controller
+    LibraryCard(ObjectId),
+    /// Where the looked at cards of the controller's library go, the first of each is the
+    /// topmost
+    LibraryArrangement {
+        top: Vec<ObjectId>,
+        bottom: Vec<ObjectId>,
+    },
+    /// The cards chosen to be discarded from the controller's hand, or from the hand of the
+    /// targeted player for [`EffectInfoRequest::TargetDiscard`]
+    Discarded(Vec<ObjectId>),
+    /// The objects chosen for [`EffectInfoRequest::ChooseFromZone`]
+    ZoneObjects(Vec<ObjectId>),
+    /// The objects of an [`EffectInfoRequest::Ordering`], the first one first
+    Ordering(Vec<ObjectId>),
+}
+
+/// Something an effect may do beyond changing the game through ordinary atoms
+///
+/// Cards from untrusted sources declare what they need, so that hosts can restrict them through
+/// [`crate::FormatRules::allowed_capabilities`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
+pub enum Capability {
+    /// Looking at libraries and at the hands of other players
+    ReadHiddenZones,
+    /// Shuffling, or anything else that depends on the game's randomness
+    Randomness,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 2] = [Capability::ReadHiddenZones, Capability::Randomness];
+
+    /// The capability an effect needs to return the atom, if any
+    pub fn required_by(atom: &GameAtom) -> Option<Capability> {
+        match atom {
+            GameAtom::ShuffleLibrary { .. }
+            | GameAtom::ShuffleHandIntoLibrary { .. }
+            | GameAtom::RandomChoice { .. } => Some(Capability::Randomness),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -46,12 +291,26 @@ pub enum ExecuteFailure {
     InvalidEffectInfo { name: String },
     #[error("No controller was found for effect")]
     NoControllerFound,
+    #[error("The effect of {card:?} did not finish within {limit:?}")]
+    TimedOut { card: CardId, limit: Duration },
+    #[error("The effect of {card:?} did something it did not declare: {capability:?}")]
+    UndeclaredCapability {
+        card: CardId,
+        capability: Capability,
+    },
 }
 
 #[async_trait::async_trait]
 pub trait InstantEffect: Debug + Sync + Send {
     fn get_required_info(&self) -> HashMap<String, EffectInfoRequest>;
 
+    /// What the effect may do, anything else fails its execution
+    ///
+    /// Effects written in Rust are trusted with everything.
+    fn capabilities(&self) -> Vec<Capability> {
+        Capability::ALL.to_vec()
+    }
+
     async fn execute(
         &self,
         info: HashMap<String, EffectInfo>,
@@ -64,4 +323,52 @@ static_assertions::assert_impl_all!(dyn InstantEffect: Send, Sync);
 static_assertions::assert_obj_safe!(InstantEffect);
 
 #[derive(Debug)]
-pub enum ContinuousEffect {}
+pub enum ContinuousEffect {
+    /// Changes the cost of playing cards while its source is on the battlefield
+    ModifyCost(CostModifier),
+    /// Changes the power and toughness of agents while its source is on the battlefield
+    ModifyStats(StatModifier),
+    /// Changes how something happens while its source is on the battlefield
+    Replace(Replacement),
+}
+
+/// Something that happens instead of what usually would
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Replacement {
+    /// Players draw from another of their zones, like "You draw from your discard instead"
+    ///
+    /// Cards are drawn from the top of the zone.
+    DrawFrom {
+        /// The players affected, relative to the controller of the source
+        player: RelativePlayer,
+        zone: ZoneKind,
+    },
+}
+
+/// Makes agents stronger or weaker, like "Your other agents get +1/+1"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatModifier {
+    /// Only agents controlled by these players, relative to the controller of the source
+    ///
+    /// Every agent is affected if `None`.
+    pub player: Option<RelativePlayer>,
+    /// Only agents of this subkind, every agent if `None`
+    pub subkind: Option<SubKind>,
+    /// Whether the source itself is affected, if it is an agent
+    pub affects_source: bool,
+    pub power: i64,
+    pub toughness: i64,
+}
+
+/// Makes playing some cards more or less expensive, like "Quickhacks cost 1 less"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostModifier {
+    /// Only cards of this kind are affected, every card if `None`
+    pub kind: Option<BaseCardKindTag>,
+    /// Only cards played by these players, relative to the controller of the source
+    ///
+    /// Every player is affected if `None`.
+    pub player: Option<RelativePlayer>,
+    /// Added to the scrip of any kind the cost asks for, negative to reduce it
+    pub any_scrip: i64,
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::card::CardId;
+use crate::view::StackEntry;
+use crate::view::ZoneCounts;
+use crate::GameAtom;
+use crate::GameId;
+use crate::ObjectId;
+use crate::PlayerId;
+use crate::ZoneId;
+
+/// Things that happened in a game that are of interest to everyone watching it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub enum GameEvent {
+    /// The amount of objects in some player's zones changed
+    ZoneCountsChanged {
+        counts: HashMap<PlayerId, ZoneCounts>,
+    },
+    /// Something was put on or taken off the stack, or the choices of its objects changed
+    StackChanged { stack: Vec<StackEntry> },
+    /// An object moved from one zone to another
+    ObjectMoved {
+        object: ObjectId,
+        card: Option<CardId>,
+        from: ZoneId,
+        to: ZoneId,
+    },
+    /// A player got an emblem, which is put into their emblem zone
+    EmblemCreated { player: PlayerId, card: CardId },
+    /// A player drew cards, each of which also moved from their library to their hand
+    CardsDrawn { player: PlayerId, count: usize },
+    /// An object was shown to all players, the object stays where it is
+    ObjectRevealed {
+        object: ObjectId,
+        card: CardId,
+        zone: ZoneId,
+    },
+    /// A double-faced object turned over, `transformed` tells whether its back face is now up
+    ObjectTransformed { object: ObjectId, transformed: bool },
+    /// One of several options was picked at random, for example a coin was flipped
+    RandomChoiceMade { picked: usize, options: usize },
+    /// The player takes the first turn of the game
+    StartingPlayerChosen { player: PlayerId },
+    /// A player could not be reached, so a default answer was chosen for them
+    DefaultChoiceMade { player: PlayerId },
+    /// A player was removed from the game, everything they controlled was exiled
+    PlayerEliminated { player: PlayerId },
+    /// The shared client of a hot-seat game has to be handed to this player
+    ///
+    /// Only sent to hot-seat clients, the following prompts are meant for this player.
+    SeatSwitched { player: PlayerId },
+}
+
+/// A batch of atoms that was applied to a game, and the game state it produced
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppliedAtoms {
+    pub game: GameId,
+    /// The index of the game state the atoms were applied to
+    pub base: usize,
+    /// The index of the resulting game state
+    pub state: usize,
+    pub atoms: Vec<GameAtom>,
+}
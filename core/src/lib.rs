@@ -1,20 +1,47 @@
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::sync::Arc;
+use std::time::Duration;
 
+use card::BaseCardKind;
+use card::BaseCardKindTag;
 use card::Card;
+use card::CardBehaviour;
+use card::CardEffect;
 use card::CardId;
+use card::CardLayout;
+use card::CostError;
+use card::ScripKind;
+use card::SubKind;
+use card::SubKindRegistry;
+use effect::Capability;
+use effect::DelayedTrigger;
+use effect::Effect;
 use effect::EffectInfo;
 use effect::ExecuteFailure;
+use effect::Replacement;
 use rand::Rng;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use uuid::Uuid;
 
 pub mod card;
+pub mod delta;
 pub mod effect;
+pub mod event;
+pub mod log;
+pub mod matches;
 pub mod meta;
 pub mod outside;
+pub mod save;
+pub mod schema;
+pub mod view;
+
+mod pairs;
 
 pub fn get_seeded_uuid(rng: &mut impl Rng) -> uuid::Uuid {
     let mut random_bytes: [u8; 16] = [0; 16];
@@ -32,6 +59,10 @@ impl GameId {
     pub fn new() -> Self {
         Self(Uuid::new_v4())
     }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        self.0.as_bytes()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
@@ -39,32 +70,128 @@ pub enum SourceId {
     Player(PlayerId),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 pub enum TargetId {
     Player(PlayerId),
     Object(ObjectId),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub enum GameAtom {
     /// Starts the game
     /// Only valid at the beginning of the game
     StartGame,
+    /// Lets `player` take the first turn, after which hands are drawn
+    ///
+    /// Only valid while the starting player is chosen.
+    SetStartingPlayer {
+        player: PlayerId,
+    },
     KeepHand {
         player: PlayerId,
     },
+    /// Ends the game, after which nothing happens anymore
+    EndGame {
+        outcome: GameOutcome,
+    },
+    /// Removes a player from a running game, exiling everything they control
+    ///
+    /// Once only one player remains they win the game.
+    EliminatePlayer {
+        player: PlayerId,
+    },
     ShuffleHandIntoLibrary {
         player: PlayerId,
     },
+    /// Counts a mulligan for the player, only valid before the game started
+    TakeMulligan {
+        player: PlayerId,
+    },
+    /// Moves the given objects from the player's hand to the bottom of their library
+    ///
+    /// The first object ends up topmost.
+    PutOnLibraryBottom {
+        player: PlayerId,
+        objects: Vec<ObjectId>,
+    },
     DrawCards {
         player: PlayerId,
         count: usize,
     },
+    /// Moves up to `count` objects from the top of the player's library to their discard, one at a
+    /// time
+    Mill {
+        player: PlayerId,
+        count: usize,
+    },
     DealDamage {
         amount: usize,
         source: ObjectId,
         target: TargetId,
     },
+    /// Prevents the next `amount` damage that would be dealt to the target this turn
+    AddDamageShield {
+        target: TargetId,
+        amount: usize,
+    },
+    /// Turns a double-faced object on the battlefield to its other face
+    TransformObject {
+        object: ObjectId,
+    },
+    /// Takes an object off the stack without resolving it, putting it into its owner's discard
+    ///
+    /// Countered triggers cease to exist.
+    CounterObject {
+        object: ObjectId,
+    },
+    /// Puts a card-less object standing for a triggered effect on the stack
+    ///
+    /// The object is given its id while the atom is applied, drawn from the game's randomness.
+    PutTriggerOnStack {
+        controller: PlayerId,
+        trigger: StackTrigger,
+    },
+    /// Attaches the choices its controller made to a trigger on the stack
+    ChooseTriggerInfo {
+        object: ObjectId,
+        #[serde(with = "pairs")]
+        #[schemars(with = "Vec<((usize, String), EffectInfo)>")]
+        choices: HashMap<(usize, String), EffectInfo>,
+    },
+    /// Takes a trigger off the stack, it ceases to exist
+    RemoveTrigger {
+        object: ObjectId,
+    },
+    /// Puts +1/+1 counters on the object, or -1/-1 counters if `amount` is negative
+    AddCounters {
+        object: ObjectId,
+        amount: i64,
+    },
+    /// Changes the game until the effect expires, see [`TemporaryEffect`]
+    AddTemporaryEffect {
+        modifier: TemporaryModifier,
+        duration: EffectDuration,
+    },
+    /// Adds to the life total of a player
+    GainLife {
+        player: PlayerId,
+        amount: usize,
+    },
+    /// Removes from the life total of a player, unlike damage this can not be prevented
+    LoseLife {
+        player: PlayerId,
+        amount: usize,
+    },
+    /// Gives the player an emblem carrying the effects of the card
+    CreateEmblem {
+        player: PlayerId,
+        card: CardId,
+    },
+    /// Gives a player poison counters, they lose once they have [`FormatRules::poison_limit`]
+    AddPoison {
+        player: PlayerId,
+        amount: usize,
+    },
     PassPriority {
         player: PlayerId,
     },
@@ -72,10 +199,103 @@ pub enum GameAtom {
         player: PlayerId,
         from: ZoneId,
         object: ObjectId,
+        #[serde(with = "pairs")]
+        #[schemars(with = "Vec<((usize, String), EffectInfo)>")]
         choices: HashMap<(usize, String), EffectInfo>,
+        /// Whether the second half of a split card is played
+        #[serde(default)]
+        second_half: bool,
     },
     ResetPriority,
     PopStack,
+    SetObjectVisibility {
+        object: ObjectId,
+        visibility: ObjectVisibility,
+    },
+    /// Moves an object from one zone to another
+    MoveObject {
+        object: ObjectId,
+        from: ZoneId,
+        to: ZoneId,
+    },
+    /// Moves the game to the next phase, starting the next player's turn after the cleanup phase
+    AdvancePhase,
+    /// Adds scrip to the player's pool
+    AddScrip {
+        player: PlayerId,
+        kind: ScripKind,
+        amount: u64,
+    },
+    /// Adds scrip to the player's floating pool, which is emptied when the phase ends
+    AddFloatingScrip {
+        player: PlayerId,
+        kind: ScripKind,
+        amount: u64,
+    },
+    /// Spends scrip of the player, floating scrip first, fails if they have less than the amount
+    SpendScrip {
+        player: PlayerId,
+        kind: ScripKind,
+        amount: u64,
+    },
+    /// Exhausts a ready object on the battlefield, fails if it is exhausted already
+    Exhaust {
+        object: ObjectId,
+    },
+    /// Readies an object on the battlefield
+    Ready {
+        object: ObjectId,
+    },
+    RegisterDelayedTrigger {
+        trigger: DelayedTrigger,
+    },
+    RemoveDelayedTrigger {
+        trigger: DelayedTrigger,
+    },
+    /// Shows an object to all players, without moving it
+    RevealObject {
+        object: ObjectId,
+    },
+    /// Shuffles the player's library, without moving any object into or out of it
+    ShuffleLibrary {
+        player: PlayerId,
+    },
+    /// Moves the given objects from the player's hand to their discard, in order
+    DiscardCards {
+        player: PlayerId,
+        objects: Vec<ObjectId>,
+    },
+    /// Puts the given objects of the player's library on its top and bottom
+    ///
+    /// The first object of each list ends up topmost. Objects of the library that are not given
+    /// keep their relative order in between.
+    ArrangeLibrary {
+        player: PlayerId,
+        top: Vec<ObjectId>,
+        bottom: Vec<ObjectId>,
+    },
+    /// Applies one of the options, picked with the game's randomness
+    ///
+    /// A coin flip has two options, picking a random target has one per target. As the game's
+    /// randomness is part of the game, replays pick the same option again.
+    RandomChoice {
+        options: Vec<Vec<GameAtom>>,
+    },
+}
+
+impl GameAtom {
+    /// The player whose decision the atom records, `None` for atoms that follow from the rules
+    pub fn actor(&self) -> Option<PlayerId> {
+        match self {
+            GameAtom::SetStartingPlayer { player }
+            | GameAtom::KeepHand { player }
+            | GameAtom::TakeMulligan { player }
+            | GameAtom::PutOnLibraryBottom { player, .. }
+            | GameAtom::PassPriority { player }
+            | GameAtom::PlayerPlayCard { player, .. } => Some(*player),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -84,43 +304,421 @@ pub enum GameError {
     GameAlreadyRunning,
     #[error("An RPC error was encountered")]
     RPCError(#[from] tarpc::client::RpcError),
+    #[error("The object ({object:?}) is already exhausted")]
+    AlreadyExhausted { object: ObjectId },
+    #[error("The starting player was set after it had already been chosen")]
+    StartingPlayerAlreadyChosen,
     #[error("A keep hand atom was generated during normal game running")]
     KeepHandDuringGame,
+    #[error("A mulligan atom was generated during normal game running")]
+    MulliganDuringGame,
+    #[error("A player can only be eliminated while the game is running")]
+    EliminationOutsideGame,
+    #[error("The player ({player:?}) is not or no longer part of the game")]
+    PlayerNotInGame { player: PlayerId },
+    #[error("The player ({player:?}) played more cards than the format allows")]
+    ActionLimitReached { player: PlayerId },
+    #[error("The player ({player:?}) has to pay {needed} scrip, but only {available} can be used")]
+    CannotAfford {
+        player: PlayerId,
+        needed: u64,
+        available: u64,
+    },
     #[error("An invalid action was selected")]
     InvalidAction {
         list_length: usize,
         selected_action: usize,
     },
+    #[error("A choice outside of the offered ones was selected")]
+    InvalidChoice { list_length: usize, selected: usize },
     #[error("The expected object ({object:?}) could not be found in {zone:?}")]
     ObjectNotFoundInZone { zone: ZoneId, object: ObjectId },
     #[error("A player was marked as passing although they either already passed, or its not their moment to pass")]
     InvalidPlayerPassing { player: PlayerId },
+    #[error("The object ({object:?}) could not be found in any zone")]
+    ObjectNotFound { object: ObjectId },
     #[error("An object was expected to contain an underlying card, but it did not")]
     NoUnderlyingCard { object: ObjectId },
     #[error("A card id was given without the existing card underneath")]
     CardNotFound { card: CardId },
     #[error("A response was given with more or less than the required amount")]
     InvalidChoiceAmount { expected: usize, received: usize },
+    #[error("A number outside of {min}..={max} was chosen: {received}")]
+    InvalidNumberChoice { min: u64, max: u64, received: u64 },
     #[error("An effect failed to execute")]
     EffectExecuteFailure {
         #[source]
         failure: ExecuteFailure,
     },
+    #[error("The object ({object:?}) was expected to have a controller, but it did not")]
+    NoControllerFound { object: ObjectId },
     #[error("A given card was not implemented correctly")]
     InvalidCardState,
+    #[error("{assigned} combat damage was assigned, but exactly {expected} has to be")]
+    InvalidDamageTotal { expected: u64, assigned: u64 },
+    #[error("Damage was assigned to blocker {blocker:?} before all blockers ahead of it were dealt lethal damage")]
+    DamageAssignedOutOfOrder { blocker: ObjectId },
+    #[error("A random choice was given no options to pick from")]
+    EmptyRandomChoice,
+    #[error("The object ({object:?}) can not transform, its card has a single face")]
+    SingleFaced { object: ObjectId },
+    #[error("The game can not be rolled back to game state {state}")]
+    RollbackUnavailable { state: usize },
+    #[error("The game state {state} was compacted and can not be rebuilt")]
+    StateUnavailable { state: usize },
+    #[error("The zone {zone:?} does not exist in this game")]
+    ZoneNotFound { zone: ZoneId },
+    #[error("An atom tried to move objects from {zone:?} into the same zone")]
+    SameZone { zone: ZoneId },
+    #[error("Damage to the object ({object:?}) is not supported yet")]
+    ObjectDamageUnsupported { object: ObjectId },
+}
+
+impl GameError {
+    /// Whether the error was caused by an answer that can simply be asked for again
+    ///
+    /// The game is left as it was before the refused answer.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            GameError::InvalidAction { .. }
+                | GameError::InvalidChoice { .. }
+                | GameError::InvalidChoiceAmount { .. }
+                | GameError::InvalidNumberChoice { .. }
+                | GameError::InvalidDamageTotal { .. }
+                | GameError::DamageAssignedOutOfOrder { .. }
+                | GameError::CannotAfford { .. }
+        )
+    }
+}
+
+/// The version of the rules implemented by the engine
+///
+/// Bump this whenever a change would make an existing game play out differently.
+pub const RULES_VERSION: u32 = 2;
+
+/// The versions a game was created with
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct GameVersions {
+    pub rules: u32,
+    /// See [`schema::SCHEMA_VERSION`]
+    pub schema: u32,
+    /// The version of each card in the decks of the players
+    pub cards: HashMap<CardId, u32>,
+}
+
+impl GameVersions {
+    /// The current versions, for the cards of the given players
+    pub fn current<'p>(
+        cards: &HashMap<CardId, Card>,
+        players: impl IntoIterator<Item = &'p Player>,
+    ) -> GameVersions {
+        GameVersions {
+            rules: RULES_VERSION,
+            schema: schema::SCHEMA_VERSION,
+            cards: players
+                .into_iter()
+                .flat_map(|p| &p.initial_cards)
+                .filter_map(|id| Some((*id, cards.get(id)?.version)))
+                .collect(),
+        }
+    }
+
+    /// How the recorded versions differ from the current ones
+    pub fn skew(&self, cards: &HashMap<CardId, Card>) -> Vec<VersionSkew> {
+        let mut skew = vec![];
+        if self.rules != RULES_VERSION {
+            skew.push(VersionSkew::Rules {
+                recorded: self.rules,
+                current: RULES_VERSION,
+            });
+        }
+        if self.schema != schema::SCHEMA_VERSION {
+            skew.push(VersionSkew::Schema {
+                recorded: self.schema,
+                current: schema::SCHEMA_VERSION,
+            });
+        }
+        for (card, recorded) in &self.cards {
+            let current = cards.get(card).map(|c| c.version);
+            if current != Some(*recorded) {
+                skew.push(VersionSkew::Card {
+                    card: *card,
+                    recorded: *recorded,
+                    current,
+                });
+            }
+        }
+        skew
+    }
+}
+
+/// A difference between the versions a game was created with and the current ones
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum VersionSkew {
+    #[error("The game was played with rules version {recorded}, but the engine has {current}")]
+    Rules { recorded: u32, current: u32 },
+    #[error("The game was recorded with schema version {recorded}, but the engine has {current}")]
+    Schema { recorded: u32, current: u32 },
+    #[error("The card ({card:?}) was at version {recorded}, but now is at {current:?}")]
+    Card {
+        card: CardId,
+        recorded: u32,
+        /// `None` if the card does not exist anymore
+        current: Option<u32>,
+    },
+}
+
+/// Why a recorded game could not be rebuilt from its atom history
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    #[error("The atoms applied to game state {state} failed: {error}")]
+    Atoms { state: usize, error: GameError },
+    #[error("The atoms were applied to game state {state}, but the latest one is {latest}")]
+    OutOfOrder { state: usize, latest: usize },
+    #[error("The replayed game state {state} differs from the recorded one")]
+    Diverged { state: usize },
+    #[error("{recorded} values were drawn from the randomness at game state {state}, but {drawn} when replayed")]
+    DrawsDiverged {
+        state: usize,
+        recorded: u64,
+        drawn: u64,
+    },
 }
 
 pub enum VerificationError {
-    PlayerInvalidCard { id: PlayerId, card: CardId },
+    PlayerInvalidCard {
+        id: PlayerId,
+        card: CardId,
+    },
+    InvalidCardCost {
+        card: CardId,
+        error: CostError,
+    },
+    UnknownSubKind {
+        card: CardId,
+        subkind: SubKind,
+    },
+    UnknownTeamMember {
+        player: PlayerId,
+    },
+    ForbiddenCapability {
+        card: CardId,
+        capability: Capability,
+    },
+    /// The deck of the player has fewer or more cards than the format allows
+    InvalidDeckSize {
+        id: PlayerId,
+        size: usize,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Rules that differ between the different game formats
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct FormatRules {
+    /// The highest total scrip cost a card may have to be legal in this format
+    pub max_total_cost: Option<u64>,
+    /// How many cards the active player may keep in their hand at the end of their turn
+    #[serde(default)]
+    pub max_hand_size: Option<usize>,
+    #[serde(default)]
+    pub mulligan: MulliganRule,
+    /// How many cards every player draws as their first hand, [`STARTING_HAND_SIZE`] if `None`
+    #[serde(default)]
+    pub starting_hand_size: Option<usize>,
+    /// The life total every player starts with, [`STARTING_LIFE`] if `None`
+    #[serde(default)]
+    pub starting_life: Option<usize>,
+    /// How many poison counters make a player lose, [`POISON_LIMIT`] if `None`
+    #[serde(default)]
+    pub poison_limit: Option<usize>,
+    /// Whether the decks of all players are shuffled into a single library everyone draws from
+    ///
+    /// A card from the shared library belongs to whoever drew it, see [`GameObject::owner`].
+    #[serde(default)]
+    pub shared_library: bool,
+    /// The fewest cards a deck may have
+    #[serde(default)]
+    pub min_deck_size: Option<usize>,
+    /// The most cards a deck may have
+    #[serde(default)]
+    pub max_deck_size: Option<usize>,
+    /// How many cards a player may play before they have to pass priority
+    #[serde(default)]
+    pub max_actions_per_priority: Option<usize>,
+    /// How many cards a player may play during a single turn, so that games stay finite
+    #[serde(default)]
+    pub max_actions_per_turn: Option<usize>,
+    /// When cards of a kind may be played, if it differs from the kind's default
+    #[serde(default)]
+    pub timing: HashMap<BaseCardKindTag, Timing>,
+    /// Variant rules the players agreed on before the game
+    #[serde(default)]
+    pub side_rules: Vec<SideRule>,
+    /// What card effects may do, `None` allows everything
+    #[serde(default)]
+    pub allowed_capabilities: Option<Vec<Capability>>,
+    /// How much time every player has for answering prompts, `None` gives them all they want
+    #[serde(default)]
+    pub clock: Option<ChessClock>,
+}
+
+/// A time bank per player, drained while they are asked something
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ChessClock {
+    /// The time every player starts the game with
+    pub bank: Duration,
+    pub on_timeout: TimeoutAction,
+}
+
+impl ChessClock {
+    /// The time the player has left after spending `spent`
+    pub fn remaining(&self, spent: Duration) -> Duration {
+        self.bank.saturating_sub(spent)
+    }
+}
+
+/// What happens to a player whose time bank ran out
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutAction {
+    /// The player concedes the game
+    Concede,
+    /// Every following prompt to the player is answered right away, passing where possible
+    Pass,
+}
+
+/// An optional variant rule, enabled through [`FormatRules::side_rules`]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum SideRule {
+    /// Every player exiles the top `cards` cards of their library when the game starts
+    ///
+    /// What the winner gets for them is up to whoever hosts the game.
+    Wager { cards: usize },
+    /// The first player to gather `amount` scrip of the given kind wins the game
+    Bounty { kind: ScripKind, amount: u64 },
+}
+
+impl FormatRules {
+    pub fn starting_hand_size(&self) -> usize {
+        self.starting_hand_size.unwrap_or(STARTING_HAND_SIZE)
+    }
+
+    pub fn starting_life(&self) -> usize {
+        self.starting_life.unwrap_or(STARTING_LIFE)
+    }
+
+    pub fn poison_limit(&self) -> usize {
+        self.poison_limit.unwrap_or(POISON_LIMIT)
+    }
+
+    /// Whether a deck of the given size may be played in this format
+    pub fn allows_deck_size(&self, size: usize) -> bool {
+        !self.min_deck_size.is_some_and(|min| size < min)
+            && !self.max_deck_size.is_some_and(|max| size > max)
+    }
+
+    pub fn timing_of(&self, kind: BaseCardKindTag) -> Timing {
+        self.timing
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| kind.default_timing())
+    }
+
+    pub fn allows_capability(&self, capability: Capability) -> bool {
+        match &self.allowed_capabilities {
+            Some(allowed) => allowed.contains(&capability),
+            None => true,
+        }
+    }
+
+    /// Whether the player may still play cards, or has to pass priority
+    pub fn allows_action(&self, state: &GameState, player: PlayerId) -> bool {
+        let within = |limit: Option<usize>, counts: &HashMap<PlayerId, usize>| {
+            !limit.is_some_and(|limit| counts.get(&player).copied().unwrap_or(0) >= limit)
+        };
+
+        within(self.max_actions_per_priority, &state.actions_this_priority)
+            && within(self.max_actions_per_turn, &state.actions_this_turn)
+    }
+}
+
+/// How many cards every player draws as their first hand
+pub const STARTING_HAND_SIZE: usize = 7;
+
+/// The life total every player starts the game with
+pub const STARTING_LIFE: usize = 20;
+
+/// How many poison counters make a player lose the game
+pub const POISON_LIMIT: usize = 10;
+
+/// How players may trade their starting hand for a new one
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MulliganRule {
+    /// Every new hand has one card fewer than the one before
+    #[default]
+    OneFewer,
+    /// Like [`MulliganRule::OneFewer`], but the first new hand still has all cards
+    FreeFirst,
+    /// Every new hand has all cards, but once kept one card per mulligan taken is put on the
+    /// bottom of the library
+    London,
+}
+
+impl MulliganRule {
+    /// How many cards are drawn for the new hand after taking the given amount of mulligans
+    ///
+    /// `starting` is the size of the first hand, see [`FormatRules::starting_hand_size`].
+    pub fn cards_drawn(self, starting: usize, mulligans: usize) -> usize {
+        match self {
+            MulliganRule::OneFewer => starting.saturating_sub(mulligans),
+            MulliganRule::FreeFirst => starting.saturating_sub(mulligans.saturating_sub(1)),
+            MulliganRule::London => starting,
+        }
+    }
+
+    /// How many cards are put on the bottom of the library when keeping after the given amount of
+    /// mulligans
+    pub fn cards_to_bottom(self, starting: usize, mulligans: usize) -> usize {
+        match self {
+            MulliganRule::OneFewer | MulliganRule::FreeFirst => 0,
+            MulliganRule::London => mulligans.min(starting),
+        }
+    }
+
+    /// How many cards are left in the kept hand after taking the given amount of mulligans
+    pub fn hand_size(self, starting: usize, mulligans: usize) -> usize {
+        self.cards_drawn(starting, mulligans)
+            .saturating_sub(self.cards_to_bottom(starting, mulligans))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub enum PlayerAction {
-    PlayCard { from: ZoneId, object: ObjectId },
+    PlayCard {
+        from: ZoneId,
+        object: ObjectId,
+        /// The index of the card's alternative cost paid instead of its cost, if any
+        #[serde(default)]
+        alternative: Option<usize>,
+        /// Whether the second half of a split card is played
+        #[serde(default)]
+        second_half: bool,
+    },
     PassPriority,
+    /// Uses the `ability`th activated effect of an object on the battlefield
+    ActivateAbility {
+        object: ObjectId,
+        ability: usize,
+    },
+    /// Attacks the next opponent in turn order with a ready agent, who may block it with theirs
+    Attack {
+        attacker: ObjectId,
+    },
+    /// Leaves the game, which is lost for the player
+    Concede,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(transparent)]
 pub struct PlayerId(Uuid);
 
@@ -135,20 +733,160 @@ impl PlayerId {
 pub struct Player {
     pub id: PlayerId,
     pub initial_cards: Vec<CardId>,
+    /// Standing answers to recurring prompts, consulted before the player is asked
+    #[serde(default)]
+    pub preferences: PlayerPreferences,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+/// Per-player choices that are remembered for the whole game
+///
+/// Long games tend to ask the same questions over and over again, these allow a player to answer
+/// them once up front.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct PlayerPreferences {
+    /// Keep the current hand without being asked
+    pub always_keep_hand: bool,
+    /// Pick the choice automatically if a prompt only has a single legal answer
+    pub auto_select_single_choice: bool,
+    /// Pass priority right after playing a card instead of being asked
+    pub auto_pass_after_play: bool,
+    /// Show the projected result of each action and ask for confirmation before committing it
+    pub preview_actions: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 pub enum ZoneId {
     Hand(PlayerId),
     Library(PlayerId),
     Discard(PlayerId),
     Battlefield,
     Stack,
+    /// Objects removed from the game
+    Exile,
+    /// The emblems of a player, effects attached to them that are not cards
+    Emblems(PlayerId),
+    /// The single library all players draw from, see [`FormatRules::shared_library`]
+    SharedLibrary,
+    /// A zone introduced by a card set, described by its [`ZoneMetadata`]
+    Custom {
+        key: ZoneKey,
+        player: Option<PlayerId>,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl ZoneId {
+    pub fn metadata(&self) -> Option<ZoneMetadata> {
+        let (name, ordered, visibility, per_player) = match self {
+            ZoneId::Hand(_) => ("Hand", false, ZoneVisibility::Owner, true),
+            ZoneId::Library(_) => ("Library", true, ZoneVisibility::Hidden, true),
+            ZoneId::Discard(_) => ("Discard", true, ZoneVisibility::Public, true),
+            ZoneId::Battlefield => ("Battlefield", false, ZoneVisibility::Public, false),
+            ZoneId::Stack => ("Stack", true, ZoneVisibility::Public, false),
+            ZoneId::Exile => ("Exile", false, ZoneVisibility::Public, false),
+            ZoneId::Emblems(_) => ("Emblems", false, ZoneVisibility::Hidden, true),
+            ZoneId::SharedLibrary => ("Shared library", true, ZoneVisibility::Hidden, false),
+            ZoneId::Custom { .. } => return None,
+        };
+
+        Some(ZoneMetadata {
+            name: name.to_string(),
+            ordered,
+            visibility,
+            per_player,
+        })
+    }
+
+    pub fn kind(&self) -> ZoneKind {
+        match self {
+            ZoneId::Hand(_) => ZoneKind::Hand,
+            ZoneId::Library(_) => ZoneKind::Library,
+            ZoneId::Discard(_) => ZoneKind::Discard,
+            ZoneId::Battlefield => ZoneKind::Battlefield,
+            ZoneId::Stack => ZoneKind::Stack,
+            ZoneId::Exile => ZoneKind::Exile,
+            ZoneId::Emblems(_) => ZoneKind::Emblems,
+            ZoneId::SharedLibrary => ZoneKind::SharedLibrary,
+            ZoneId::Custom { key, .. } => ZoneKind::Custom(*key),
+        }
+    }
+
+    /// The zone of this kind belonging to `player`, if it is a per-player zone
+    pub fn of(kind: ZoneKind, player: PlayerId) -> ZoneId {
+        match kind {
+            ZoneKind::Hand => ZoneId::Hand(player),
+            ZoneKind::Library => ZoneId::Library(player),
+            ZoneKind::Discard => ZoneId::Discard(player),
+            ZoneKind::Battlefield => ZoneId::Battlefield,
+            ZoneKind::Stack => ZoneId::Stack,
+            ZoneKind::Exile => ZoneId::Exile,
+            ZoneKind::Emblems => ZoneId::Emblems(player),
+            ZoneKind::SharedLibrary => ZoneId::SharedLibrary,
+            ZoneKind::Custom(key) => ZoneId::Custom {
+                key,
+                player: Some(player),
+            },
+        }
+    }
+
+    /// The player the zone belongs to, `None` for zones shared by all players
+    pub fn player(&self) -> Option<PlayerId> {
+        match self {
+            ZoneId::Hand(player)
+            | ZoneId::Library(player)
+            | ZoneId::Discard(player)
+            | ZoneId::Emblems(player) => Some(*player),
+            ZoneId::Battlefield | ZoneId::Stack | ZoneId::Exile | ZoneId::SharedLibrary => None,
+            ZoneId::Custom { player, .. } => *player,
+        }
+    }
+}
+
+/// A zone without the player it belongs to, like "a discard"
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
+pub enum ZoneKind {
+    Hand,
+    Library,
+    Discard,
+    Battlefield,
+    Stack,
+    Exile,
+    Emblems,
+    SharedLibrary,
+    Custom(ZoneKey),
+}
+
+/// Identifies a kind of custom zone
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(transparent)]
-pub struct Objects(pub Vec<GameObject>);
+pub struct ZoneKey(pub Uuid);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZoneVisibility {
+    /// Everyone can see the objects in the zone
+    Public,
+    /// Only the player the zone belongs to can see its objects
+    Owner,
+    /// Nobody can see the objects in the zone
+    Hidden,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ZoneMetadata {
+    pub name: String,
+    /// Whether the order of objects in the zone is meaningful
+    pub ordered: bool,
+    pub visibility: ZoneVisibility,
+    /// Whether every player has their own zone of this kind
+    pub per_player: bool,
+}
+
+/// The objects of a zone, shared between game states until one of them changes them
+///
+/// Cloning is cheap, the objects are only copied once they are mutated through a shared handle.
+/// Applying atoms thus only copies the zones they touch instead of the whole game.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Objects(pub Arc<Vec<GameObject>>);
 
 impl std::ops::Deref for Objects {
     type Target = Vec<GameObject>;
@@ -160,11 +898,11 @@ impl std::ops::Deref for Objects {
 
 impl std::ops::DerefMut for Objects {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        Arc::make_mut(&mut self.0)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct GameZone {
     pub objects: Objects,
 }
@@ -172,18 +910,18 @@ pub struct GameZone {
 impl GameZone {
     pub fn empty() -> GameZone {
         GameZone {
-            objects: Objects(vec![]),
+            objects: Objects(Arc::new(vec![])),
         }
     }
 
     pub fn with(objects: Vec<GameObject>) -> GameZone {
         GameZone {
-            objects: Objects(objects),
+            objects: Objects(Arc::new(objects)),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(transparent)]
 pub struct LibraryCardId(uuid::Uuid);
 
@@ -193,7 +931,7 @@ impl LibraryCardId {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(transparent)]
 pub struct ObjectId(pub uuid::Uuid);
 
@@ -203,7 +941,7 @@ impl ObjectId {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct GameObject {
     pub id: ObjectId,
     /// An identifier for that tracks a card throughout the whole game, no two objects in the same
@@ -214,7 +952,38 @@ pub struct GameObject {
     /// Objects only have a controller on the stack and battlefield
     pub controller: Option<PlayerId>,
     /// Any choices associated to the object
+    #[serde(with = "pairs")]
+    #[schemars(with = "Vec<((usize, String), EffectInfo)>")]
     pub choices: HashMap<(usize, String), EffectInfo>,
+    /// Who knows what this object is
+    #[serde(default)]
+    pub visibility: ObjectVisibility,
+    /// Exhausted objects can not use abilities that exhaust them until they are readied
+    #[serde(default)]
+    pub exhausted: bool,
+    /// The turn the object entered the battlefield, if it is there
+    #[serde(default)]
+    pub entered_turn: Option<usize>,
+    /// How many +1/+1 counters are on the object, negative for -1/-1 counters
+    ///
+    /// Counters are lost when the object changes zones.
+    #[serde(default)]
+    pub counters: i64,
+    /// Whether the back face of a double-faced card is active, see [`card::CardLayout`]
+    #[serde(default)]
+    pub transformed: bool,
+    /// Whether the second half of a split card was played, see [`card::CardLayout`]
+    #[serde(default)]
+    pub second_half: bool,
+    /// The triggered effect a card-less object on the stack stands for
+    #[serde(default)]
+    pub trigger: Option<StackTrigger>,
+    /// The player whose zones the object returns to
+    ///
+    /// Cards drawn from a [`ZoneId::SharedLibrary`] belong to whoever drew them. Objects without
+    /// an owner go to the zones of their controller.
+    #[serde(default)]
+    pub owner: Option<PlayerId>,
 }
 impl GameObject {
     pub fn from_card(rand: &mut impl Rng, underlying_card: CardId) -> GameObject {
@@ -224,30 +993,563 @@ impl GameObject {
             underlying_card: Some(underlying_card),
             controller: None,
             choices: HashMap::new(),
+            visibility: ObjectVisibility::FaceUp,
+            exhausted: false,
+            entered_turn: None,
+            counters: 0,
+            transformed: false,
+            second_half: false,
+            trigger: None,
+            owner: None,
+        }
+    }
+
+    pub fn from_trigger(id: ObjectId, controller: PlayerId, trigger: StackTrigger) -> GameObject {
+        GameObject {
+            id,
+            library_card_id: None,
+            underlying_card: None,
+            controller: Some(controller),
+            choices: HashMap::new(),
+            visibility: ObjectVisibility::FaceUp,
+            exhausted: false,
+            entered_turn: None,
+            counters: 0,
+            transformed: false,
+            second_half: false,
+            trigger: Some(trigger),
+            owner: Some(controller),
+        }
+    }
+
+    /// The active face or half of the object's card
+    pub fn behaviour<'c>(&self, cards: &'c HashMap<CardId, Card>) -> Option<&'c CardBehaviour> {
+        let card = cards.get(&self.underlying_card?)?;
+        Some(match &card.layout {
+            CardLayout::DoubleFaced { back } if self.transformed => back,
+            CardLayout::Split { second } if self.second_half => second,
+            _ => &card.behaviour,
+        })
+    }
+
+    /// Whether the player may know what this object is, given they can see the zone it is in
+    pub fn is_known_to(&self, player: PlayerId) -> bool {
+        match &self.visibility {
+            ObjectVisibility::FaceUp => true,
+            ObjectVisibility::FaceDown { known_to } => known_to.contains(&player),
+        }
+    }
+
+    /// The object with everything that would identify it removed
+    pub fn redacted(&self) -> GameObject {
+        GameObject {
+            id: self.id,
+            library_card_id: None,
+            underlying_card: None,
+            controller: self.controller,
+            choices: HashMap::new(),
+            visibility: self.visibility.clone(),
+            exhausted: self.exhausted,
+            entered_turn: self.entered_turn,
+            counters: self.counters,
+            transformed: self.transformed,
+            second_half: self.second_half,
+            trigger: self.trigger.clone(),
+            owner: self.owner,
+        }
+    }
+
+    /// The player whose discard the object goes to, its owner or else its controller
+    pub fn owner_or_controller(&self) -> Option<PlayerId> {
+        self.owner.or(self.controller)
+    }
+}
+
+/// A triggered effect of a card waiting on the stack, see [`GameObject::trigger`]
+///
+/// Each effect of a triggered card effect is put on the stack as its own object.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+pub struct StackTrigger {
+    /// The object whose card triggered
+    pub source: ObjectId,
+    pub card: CardId,
+    /// The index of the triggered card effect among the effects of the card
+    pub ability: usize,
+    /// The index of the effect among those of the triggered card effect
+    pub effect: usize,
+    /// Whether the controller already made the choices the effect asks for
+    pub choices_made: bool,
+}
+
+impl StackTrigger {
+    /// The effect the trigger stands for, triggers always use the front face of cards
+    pub fn effect_of<'c>(&self, card: &'c Card) -> Option<&'c Effect> {
+        match card.behaviour.effects.get(self.ability)? {
+            CardEffect::Triggered(triggered) => triggered.effects.get(self.effect),
+            _ => None,
         }
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, JsonSchema)]
+pub enum ObjectVisibility {
+    /// Anyone who can see the zone knows what the object is
+    #[default]
+    FaceUp,
+    /// Only the given players know what the object is, which might be nobody
+    FaceDown { known_to: Vec<PlayerId> },
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum GameStage {
-    KeepHand { players_keeping: HashSet<PlayerId> },
+    /// Before hands are drawn, `chooser` decides whether they play first
+    ChooseStartingPlayer {
+        chooser: PlayerId,
+    },
+    KeepHand {
+        players_keeping: HashSet<PlayerId>,
+        /// How many mulligans each player took so far
+        #[serde(default)]
+        mulligans: HashMap<PlayerId, usize>,
+    },
     GameRunning,
+    /// The game is over
+    Finished {
+        outcome: GameOutcome,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// How a game ended
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum GameOutcome {
+    Won {
+        winner: PlayerId,
+    },
+    /// All remaining players belong to the same team
+    TeamWon {
+        team: TeamId,
+    },
+    Draw,
+}
+
+/// Why a player left the game before it ended, if not because of the game's rules
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum EliminationCause {
+    Conceded,
+    /// Their [`ChessClock`] ran out
+    TimedOut,
+}
+
+/// How a game ended and why, as reported to everything outside of the engine
+///
+/// Unlike [`GameOutcome`] this also tells games that were decided by the players leaving apart
+/// from those that were played out.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+pub enum GameResult {
+    /// The winners beat everyone else, or met a win condition
+    Won {
+        winners: Vec<PlayerId>,
+    },
+    Draw,
+    /// Every other player conceded
+    Conceded {
+        winners: Vec<PlayerId>,
+        conceded: Vec<PlayerId>,
+    },
+    /// At least one other player ran out of time
+    TimedOut {
+        winners: Vec<PlayerId>,
+        timed_out: Vec<PlayerId>,
+    },
+    /// The game was stopped before it was decided, nobody wins
+    Abandoned,
+}
+
+impl GameResult {
+    pub fn winners(&self) -> &[PlayerId] {
+        match self {
+            GameResult::Won { winners }
+            | GameResult::Conceded { winners, .. }
+            | GameResult::TimedOut { winners, .. } => winners,
+            GameResult::Draw | GameResult::Abandoned => &[],
+        }
+    }
+
+    pub fn is_winner(&self, player: PlayerId) -> bool {
+        self.winners().contains(&player)
+    }
+}
+
+/// A group of players sharing a win condition
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
+#[serde(transparent)]
+pub struct TeamId(pub usize);
+
+impl GameStage {
+    /// How many mulligans the player took, zero once the game is running
+    pub fn mulligans_of(&self, player: PlayerId) -> usize {
+        match self {
+            GameStage::KeepHand { mulligans, .. } => mulligans.get(&player).copied().unwrap_or(0),
+            GameStage::ChooseStartingPlayer { .. }
+            | GameStage::GameRunning
+            | GameStage::Finished { .. } => 0,
+        }
+    }
+}
+
+/// When a card may be played
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    /// Whenever its controller has priority
+    Any,
+    /// Only during its controller's own main phase, while the stack is empty
+    MainPhase,
+}
+
+impl Timing {
+    pub fn allows(self, state: &GameState, player: PlayerId) -> bool {
+        match self {
+            Timing::Any => true,
+            Timing::MainPhase => {
+                state.active_player_order.first() == Some(&player)
+                    && state.phase == Phase::Main
+                    && state.get_stack().objects.is_empty()
+            }
+        }
+    }
+}
+
+/// The phases of a single turn, in order
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
+pub enum Phase {
+    Recovery,
+    TurnStart,
+    Draw,
+    Main,
+    TurnEnd,
+    Cleanup,
+}
+
+impl Phase {
+    /// The phase following this one in the same turn, if any
+    pub fn next(self) -> Option<Phase> {
+        match self {
+            Phase::Recovery => Some(Phase::TurnStart),
+            Phase::TurnStart => Some(Phase::Draw),
+            Phase::Draw => Some(Phase::Main),
+            Phase::Main => Some(Phase::TurnEnd),
+            Phase::TurnEnd => Some(Phase::Cleanup),
+            Phase::Cleanup => None,
+        }
+    }
+
+    /// Whether players receive priority during this phase
+    pub fn has_priority(self) -> bool {
+        !matches!(self, Phase::Recovery | Phase::Cleanup)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct GameState {
+    #[serde(with = "pairs")]
     pub zones: hashbrown::HashMap<ZoneId, GameZone>,
     /// The turn order, index 0 is the active player
     pub active_player_order: Vec<PlayerId>,
     /// Players who have not yet passed since the last stack-modifying action
     pub unpassed_players: Vec<PlayerId>,
     pub game_stage: GameStage,
+    /// The phase of the active player's turn
+    pub phase: Phase,
+    /// Triggers waiting for their event, see [`DelayedTrigger`]
+    pub delayed_triggers: Vec<DelayedTrigger>,
+    /// Cards played by each player since they last passed priority
+    #[serde(default)]
+    pub actions_this_priority: HashMap<PlayerId, usize>,
+    /// Cards played by each player during the current turn
+    #[serde(default)]
+    pub actions_this_turn: HashMap<PlayerId, usize>,
+    /// The scrip each player has available
+    #[serde(default)]
+    pub scrip: HashMap<PlayerId, HashMap<ScripKind, u64>>,
+    /// Scrip produced for the current phase only, it is lost once the phase ends
+    #[serde(default)]
+    pub floating_scrip: HashMap<PlayerId, HashMap<ScripKind, u64>>,
+    /// The life total of each player
+    #[serde(default)]
+    pub life: HashMap<PlayerId, usize>,
+    /// The poison counters of each player
+    #[serde(default)]
+    pub poison: HashMap<PlayerId, usize>,
+    /// How many turns have passed since the first one
+    #[serde(default)]
+    pub turn: usize,
+    /// Damage prevention that lasts until the end of the turn, older shields are used up first
+    #[serde(default)]
+    pub shields: Vec<DamageShield>,
+    /// Buffs and control changes that expire when a turn ends
+    #[serde(default)]
+    pub temporary_effects: Vec<TemporaryEffect>,
+    /// Where each object is, derived from the zones
+    #[serde(skip)]
+    pub index: ObjectIndex,
 }
+
+/// The position of each object, by zone, see [`GameState::find_object`]
+///
+/// Every zone has its own index, so game states share the indexes of the zones that did not change
+/// between them, like they share the objects. Entries may be stale, as the zones are changed
+/// directly while atoms are applied. Every entry is checked against the zone it points to before it
+/// is used. The index is derived data, so it is neither persisted nor compared.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectIndex(Arc<hashbrown::HashMap<ZoneId, Arc<hashbrown::HashMap<ObjectId, usize>>>>);
+
+impl ObjectIndex {
+    /// Whether both indexes use the same positions for the zone, instead of copies
+    pub fn shares_zone(&self, other: &ObjectIndex, zone: ZoneId) -> bool {
+        match (self.0.get(&zone), other.0.get(&zone)) {
+            (Some(ours), Some(theirs)) => Arc::ptr_eq(ours, theirs),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for ObjectIndex {
+    fn eq(&self, _: &ObjectIndex) -> bool {
+        true
+    }
+}
+
+/// Prevents the next `amount` damage that would be dealt to `target`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct DamageShield {
+    pub target: TargetId,
+    pub amount: usize,
+}
+
+/// How long a [`TemporaryEffect`] lasts
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum EffectDuration {
+    /// Until the cleanup phase of the current turn ends
+    UntilEndOfTurn,
+    /// Until the cleanup phase of the following turn ends
+    UntilEndOfNextTurn,
+}
+
+impl EffectDuration {
+    /// The last turn an effect created during `turn` lasts for
+    pub fn last_turn(self, turn: usize) -> usize {
+        match self {
+            EffectDuration::UntilEndOfTurn => turn,
+            EffectDuration::UntilEndOfNextTurn => turn + 1,
+        }
+    }
+}
+
+/// What a [`TemporaryEffect`] changes while it lasts
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum TemporaryModifier {
+    /// Adds to the power and toughness of an agent
+    Buff {
+        object: ObjectId,
+        power: i64,
+        toughness: i64,
+    },
+    /// The player controls the object on the battlefield
+    GainControl { object: ObjectId, player: PlayerId },
+}
+
+/// A change to the game that ends on its own once its last turn is over
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct TemporaryEffect {
+    pub modifier: TemporaryModifier,
+    /// See [`GameState::turn`]
+    pub last_turn: usize,
+    /// Who controlled the object before a [`TemporaryModifier::GainControl`], they get it back
+    /// once the effect expires
+    pub previous_controller: Option<PlayerId>,
+}
+
 impl GameState {
     pub fn get_hand(&self, p: PlayerId) -> &GameZone {
         self.zones.get(&ZoneId::Hand(p)).unwrap()
     }
 
+    /// Whether the player was eliminated from the game
+    pub fn is_eliminated(&self, p: PlayerId) -> bool {
+        !self.active_player_order.contains(&p)
+    }
+
+    /// Uses up the shields of the target, returns how much of the damage is still dealt
+    pub fn absorb_damage(&mut self, target: TargetId, mut amount: usize) -> usize {
+        for shield in self.shields.iter_mut().filter(|s| s.target == target) {
+            let absorbed = shield.amount.min(amount);
+            shield.amount -= absorbed;
+            amount -= absorbed;
+        }
+        self.shields.retain(|s| s.amount > 0);
+        amount
+    }
+
+    /// The zone the player draws from, their library unless a replacement says otherwise
+    ///
+    /// If several replacements apply, the one whose source arrived first wins.
+    pub fn draw_zone_of(&self, game: &Game, player: PlayerId) -> ZoneId {
+        self.effect_sources()
+            .filter_map(|source| Some((source.controller?, source.behaviour(&game.cards)?)))
+            .flat_map(|(controller, behaviour)| {
+                behaviour
+                    .replacements()
+                    .map(move |replacement| (controller, replacement))
+            })
+            .find_map(|(controller, replacement)| match replacement {
+                Replacement::DrawFrom {
+                    player: relative,
+                    zone,
+                } if relative.holds(game, player, controller) => Some(ZoneId::of(*zone, player)),
+                _ => None,
+            })
+            .filter(|zone| *zone != ZoneId::Hand(player) && self.zones.contains_key(zone))
+            .unwrap_or(game.library_of(player))
+    }
+
+    /// How much the temporary effects add to the power and toughness of the object
+    pub fn buff_of(&self, object: ObjectId) -> (i64, i64) {
+        self.temporary_effects
+            .iter()
+            .filter_map(|e| match e.modifier {
+                TemporaryModifier::Buff {
+                    object: buffed,
+                    power,
+                    toughness,
+                } if buffed == object => Some((power, toughness)),
+                _ => None,
+            })
+            .fold((0, 0), |(p, t), (power, toughness)| {
+                (p + power, t + toughness)
+            })
+    }
+
+    /// The power and toughness of an agent on the battlefield
+    ///
+    /// The stats of its card are changed by its counters, the [`effect::StatModifier`]s of the
+    /// [`GameState::effect_sources`] and its temporary buffs, but never go below zero. `game`
+    /// provides the cards and teams, this may be any of its game states. `None` if the object is
+    /// not an agent on the battlefield.
+    pub fn agent_stats(&self, game: &Game, object: ObjectId) -> Option<(u64, u64)> {
+        let battlefield = &self.get_battlefield().objects;
+        let agent = battlefield.iter().find(|o| o.id == object)?;
+        let behaviour = agent.behaviour(&game.cards)?;
+        let (power, toughness) = behaviour.kind.iter().find_map(|k| match &k.kind {
+            BaseCardKind::Agent {
+                power, toughness, ..
+            } => Some((
+                power.of(game, self, agent) as i64,
+                toughness.of(game, self, agent) as i64,
+            )),
+            _ => None,
+        })?;
+
+        let (continuous_power, continuous_toughness) = self
+            .effect_sources()
+            .filter_map(|source| {
+                Some((
+                    source.id,
+                    source.controller?,
+                    source.behaviour(&game.cards)?,
+                ))
+            })
+            .flat_map(|(source, controller, behaviour)| {
+                behaviour
+                    .stat_modifiers()
+                    .map(move |modifier| (source, controller, modifier))
+            })
+            .filter(|(source, controller, modifier)| {
+                (modifier.affects_source || *source != object)
+                    && modifier
+                        .subkind
+                        .as_ref()
+                        .map_or(true, |subkind| behaviour.has_subkind(subkind))
+                    && modifier.player.map_or(true, |relative| {
+                        agent
+                            .controller
+                            .is_some_and(|player| relative.holds(game, player, *controller))
+                    })
+            })
+            .fold((0, 0), |(p, t), (_, _, modifier)| {
+                (p + modifier.power, t + modifier.toughness)
+            });
+        let (buff_power, buff_toughness) = self.buff_of(object);
+
+        let stat = |base: i64, change: i64| (base + agent.counters + change).max(0) as u64;
+        Some((
+            stat(power, continuous_power + buff_power),
+            stat(toughness, continuous_toughness + buff_toughness),
+        ))
+    }
+
+    /// Ends the turn of the active player, everything that only lasted for it expires
+    ///
+    /// Control changes are undone newest first, so that objects stolen several times end up with
+    /// their original controller.
+    pub fn end_turn(&mut self) {
+        self.actions_this_priority.clear();
+        self.actions_this_turn.clear();
+        self.shields.clear();
+        self.floating_scrip.clear();
+
+        let turn = self.turn;
+        let (expired, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.temporary_effects)
+            .into_iter()
+            .partition(|e| e.last_turn <= turn);
+        self.temporary_effects = kept;
+
+        let battlefield = &mut self.zones.get_mut(&ZoneId::Battlefield).unwrap().objects;
+        for effect in expired.iter().rev() {
+            let TemporaryModifier::GainControl { object, player } = effect.modifier else {
+                continue;
+            };
+            // Objects that left the battlefield meanwhile are new objects
+            if let Some(obj) = battlefield
+                .iter_mut()
+                .find(|o| o.id == object && o.controller == Some(player))
+            {
+                obj.controller = effect.previous_controller;
+            }
+        }
+
+        self.turn += 1;
+    }
+
+    pub fn life_of(&self, p: PlayerId) -> usize {
+        self.life.get(&p).copied().unwrap_or(0)
+    }
+
+    pub fn poison_of(&self, p: PlayerId) -> usize {
+        self.poison.get(&p).copied().unwrap_or(0)
+    }
+
+    pub fn scrip_of(&self, p: PlayerId, kind: ScripKind) -> u64 {
+        self.scrip
+            .get(&p)
+            .and_then(|pool| pool.get(&kind))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn floating_scrip_of(&self, p: PlayerId, kind: ScripKind) -> u64 {
+        self.floating_scrip
+            .get(&p)
+            .and_then(|pool| pool.get(&kind))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The scrip the player could spend right now, floating or not
+    pub fn available_scrip_of(&self, p: PlayerId, kind: ScripKind) -> u64 {
+        self.scrip_of(p, kind)
+            .saturating_add(self.floating_scrip_of(p, kind))
+    }
+
     pub fn get_stack(&self) -> &GameZone {
         self.zones.get(&ZoneId::Stack).unwrap()
     }
@@ -256,21 +1558,226 @@ impl GameState {
         self.zones.get(&ZoneId::Battlefield).unwrap()
     }
 
+    /// The objects whose static and triggered effects apply, those on the battlefield and all
+    /// emblems
+    pub fn effect_sources(&self) -> impl Iterator<Item = &GameObject> {
+        self.get_battlefield().objects.iter().chain(
+            self.zones
+                .iter()
+                .filter(|(id, _)| matches!(id, ZoneId::Emblems(_)))
+                .flat_map(|(_, zone)| zone.objects.iter()),
+        )
+    }
+
     pub fn get_object_from_zone(&self, from: ZoneId, obj: ObjectId) -> Option<&GameObject> {
+        if let Some((zone, object)) = self.indexed(obj) {
+            return (zone == from).then_some(object);
+        }
         let zone = self.zones.get(&from)?;
         zone.objects.iter().find(|o| o.id == obj)
     }
+
+    /// The object where the index says it is, if it is still there
+    fn indexed(&self, obj: ObjectId) -> Option<(ZoneId, &GameObject)> {
+        let (zone, idx) = self
+            .index
+            .0
+            .iter()
+            .find_map(|(zone, positions)| Some((zone, positions.get(&obj)?)))?;
+        let object = self.zones.get(zone)?.objects.get(*idx)?;
+        (object.id == obj).then_some((*zone, object))
+    }
+
+    /// A hash of the zones, player order, life totals, poison counters, turn and phase
+    ///
+    /// It does not depend on the order of any map, so equal game states have equal fingerprints.
+    /// Meant to tell diverging copies of a game apart, the hash may change with the Rust version.
+    pub fn fingerprint(&self) -> u64 {
+        fn hash_of(value: impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+        fn unordered(hashes: impl Iterator<Item = u64>) -> u64 {
+            hashes.fold(0, u64::wrapping_add)
+        }
+
+        let zones = unordered(self.zones.iter().map(|(zone, contents)| {
+            let objects: Vec<_> = contents
+                .objects
+                .iter()
+                .map(|o| (o.id, o.underlying_card, o.controller, o.exhausted))
+                .collect();
+            hash_of((zone, objects))
+        }));
+        let life = unordered(self.life.iter().map(hash_of));
+        let poison = unordered(self.poison.iter().map(hash_of));
+
+        hash_of((
+            zones,
+            &self.active_player_order,
+            life,
+            poison,
+            self.turn,
+            self.phase,
+        ))
+    }
+
+    /// Looks for the object in all zones
+    ///
+    /// Objects that did not move since atoms were last applied are found through the index, the
+    /// others by walking every zone.
+    pub fn find_object(&self, obj: ObjectId) -> Option<(ZoneId, &GameObject)> {
+        self.indexed(obj).or_else(|| {
+            self.zones
+                .iter()
+                .find_map(|(zone, z)| z.objects.iter().find(|o| o.id == obj).map(|o| (*zone, o)))
+        })
+    }
+
+    /// Brings the index up to date after the zones changed from those of `previous`
+    ///
+    /// Only zones that are no longer shared with `previous` are walked and get a new index, see
+    /// [`Objects`]. Without an index to start from, all of them are.
+    pub fn update_index(&mut self, previous: &GameState) {
+        let changed: Vec<ZoneId> = self
+            .zones
+            .iter()
+            .filter(|(zone, contents)| {
+                previous.index.0.is_empty()
+                    || previous.zones.get(*zone).map_or(true, |before| {
+                        !Arc::ptr_eq(&before.objects.0, &contents.objects.0)
+                    })
+            })
+            .map(|(zone, _)| *zone)
+            .collect();
+        let removed = self
+            .index
+            .0
+            .keys()
+            .any(|zone| !self.zones.contains_key(zone));
+        if changed.is_empty() && !removed {
+            return;
+        }
+
+        // Only the map from the zones to their indexes is copied
+        let index = Arc::make_mut(&mut self.index.0);
+        index.retain(|zone, _| self.zones.contains_key(zone));
+        for zone in changed {
+            let positions = self.zones[&zone]
+                .objects
+                .iter()
+                .enumerate()
+                .map(|(idx, object)| (object.id, idx))
+                .collect();
+            index.insert(zone, Arc::new(positions));
+        }
+    }
+
+    pub fn find_object_mut(&mut self, obj: ObjectId) -> Option<(ZoneId, &mut GameObject)> {
+        // Only the zone holding the object is copied, see [`Objects`]
+        let (zone, _) = self.find_object(obj)?;
+        self.zones
+            .get_mut(&zone)?
+            .objects
+            .iter_mut()
+            .find(|o| o.id == obj)
+            .map(|o| (zone, o))
+    }
+
+    /// The zone, if the game has it
+    pub fn zone_mut(&mut self, zone: ZoneId) -> Result<&mut GameZone, GameError> {
+        self.zones
+            .get_mut(&zone)
+            .ok_or(GameError::ZoneNotFound { zone })
+    }
+
+    /// Two different zones at once, to move objects between them
+    pub fn zone_pair_mut(
+        &mut self,
+        from: ZoneId,
+        to: ZoneId,
+    ) -> Result<[&mut GameZone; 2], GameError> {
+        let missing = [from, to]
+            .into_iter()
+            .find(|zone| !self.zones.contains_key(zone));
+        if let Some(zone) = missing {
+            return Err(GameError::ZoneNotFound { zone });
+        }
+        // Both zones exist, so only asking for the same zone twice fails
+        self.zones
+            .get_many_mut([&from, &to])
+            .ok_or(GameError::SameZone { zone: from })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Game {
     #[serde(skip)]
     pub cards: Arc<std::collections::HashMap<CardId, Card>>,
+    /// Games saved before the subkinds were kept only know the base game subkinds
+    #[serde(default = "base_subkinds")]
+    pub subkinds: Arc<SubKindRegistry>,
     pub id: GameId,
     pub players: std::collections::HashMap<PlayerId, Player>,
     pub rand: rand_xoshiro::Xoshiro256StarStar,
+    #[serde(default)]
+    pub format: FormatRules,
+    /// The description of all custom zones in this game
+    #[serde(default)]
+    pub custom_zones: std::collections::HashMap<ZoneKey, ZoneMetadata>,
+    /// The team of each player, players without one only play for themselves
+    #[serde(default)]
+    pub teams: std::collections::HashMap<PlayerId, TeamId>,
+    /// What the game was created with, games from before this was recorded have all versions at 0
+    #[serde(default)]
+    pub versions: GameVersions,
+    /// How many prompts were sent, the sequence number of the next one
+    #[serde(default)]
+    pub prompts: u64,
+    /// The time each player spent answering prompts, drained from their [`ChessClock`]
+    #[serde(default)]
+    pub time_spent: std::collections::HashMap<PlayerId, Duration>,
+    /// The players who left the game on their own or ran out of time
+    #[serde(default)]
+    pub eliminated_by: std::collections::HashMap<PlayerId, EliminationCause>,
     pub game_states: Vec<GameState>,
     pub history: Vec<(usize, Vec<GameAtom>)>,
+    /// The randomness as it was when atoms were applied to the game state at the index
+    ///
+    /// Games recorded before this was kept can only be rolled back to their latest game state.
+    #[serde(default)]
+    pub rand_history: Vec<(usize, rand_xoshiro::Xoshiro256StarStar)>,
+    /// How many values were drawn from the randomness while atoms were applied to the game state
+    /// at the index
+    #[serde(default)]
+    pub rand_draws: Vec<(usize, u64)>,
+    /// When atoms were applied to the game state at the index, in milliseconds since the Unix epoch
+    #[serde(default)]
+    pub applied_at: Vec<(usize, u64)>,
+    /// How old game states are thinned out, `None` keeps all of them
+    #[serde(default)]
+    pub compaction: Option<Compaction>,
+    /// The index of the first entry of `game_states`, the ones before it were compacted
+    #[serde(default)]
+    pub first_state: usize,
+    /// The compacted game states that were kept in full, by index
+    #[serde(default)]
+    pub snapshots: Vec<(usize, GameState)>,
+}
+
+fn base_subkinds() -> Arc<SubKindRegistry> {
+    Arc::new(SubKindRegistry::base())
+}
+
+/// Thins out old game states to bound the memory of long games
+///
+/// Once a game state is older than the `keep_recent` latest ones only every `every`th one is kept,
+/// the others are rebuilt from the nearest kept one and the atom history when asked for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Compaction {
+    pub every: usize,
+    pub keep_recent: usize,
 }
 
 impl Game {
@@ -278,17 +1785,125 @@ impl Game {
         self.game_states.last().unwrap()
     }
 
-    pub fn get_controller_of(&self, object: ObjectId) -> Option<PlayerId> {
-        let state = self.latest_gamestate();
-        let bf = state.zones.get(&ZoneId::Battlefield).unwrap();
-        let stack = state.zones.get(&ZoneId::Stack).unwrap();
+    /// A copy of the game with only its latest game state, to try atoms out on
+    ///
+    /// The latest game state keeps its index, but nothing from before it can be looked up.
+    pub fn without_history(&self) -> Game {
+        Game {
+            cards: self.cards.clone(),
+            subkinds: self.subkinds.clone(),
+            id: self.id,
+            players: self.players.clone(),
+            rand: self.rand.clone(),
+            format: self.format.clone(),
+            custom_zones: self.custom_zones.clone(),
+            teams: self.teams.clone(),
+            versions: self.versions.clone(),
+            prompts: self.prompts,
+            time_spent: self.time_spent.clone(),
+            eliminated_by: self.eliminated_by.clone(),
+            game_states: vec![self.latest_gamestate().clone()],
+            history: vec![],
+            rand_history: vec![],
+            rand_draws: vec![],
+            applied_at: vec![],
+            compaction: None,
+            first_state: self.latest_index(),
+            snapshots: vec![],
+        }
+    }
 
-        let obj = bf
-            .objects
+    /// The index of the latest game state, the one the next atoms are applied to
+    pub fn latest_index(&self) -> usize {
+        self.first_state + self.game_states.len() - 1
+    }
+
+    /// The game state at the index, unless it was compacted away
+    pub fn recorded_state(&self, idx: usize) -> Option<&GameState> {
+        match idx.checked_sub(self.first_state) {
+            Some(offset) => self.game_states.get(offset),
+            None => self
+                .snapshots
+                .iter()
+                .find(|(snapshot, _)| *snapshot == idx)
+                .map(|(_, state)| state),
+        }
+    }
+
+    /// The game state the game started with, it is never compacted away
+    pub fn first_gamestate(&self) -> &GameState {
+        self.recorded_state(0).unwrap()
+    }
+
+    /// The randomness the first atoms were applied with, the seed to replay the game from
+    pub fn initial_seed(&self) -> Option<&rand_xoshiro::Xoshiro256StarStar> {
+        self.rand_history
+            .iter()
+            .find(|(idx, _)| *idx == 0)
+            .map(|(_, rand)| rand)
+    }
+
+    /// How many values the atoms applied to the game state at the index drew from the randomness
+    pub fn rand_draws_at(&self, idx: usize) -> Option<u64> {
+        self.rand_draws
+            .iter()
+            .find(|(state, _)| *state == idx)
+            .map(|(_, draws)| *draws)
+    }
+
+    /// The atom batches that led to the recorded game states, in order
+    ///
+    /// Games persisted by older engines also recorded batches that were refused, those are left
+    /// out.
+    pub fn committed_history(&self) -> schema::Replay {
+        let latest = self.latest_index();
+        self.history
             .iter()
-            .chain(stack.objects.iter())
-            .find(|o| o.id == object)?;
+            .enumerate()
+            .filter(|(idx, (base, _))| {
+                *base < latest && !self.history[idx + 1..].iter().any(|(b, _)| b == base)
+            })
+            .map(|(_, batch)| batch.clone())
+            .collect()
+    }
+
+    pub fn zone_metadata(&self, zone: ZoneId) -> Option<ZoneMetadata> {
+        match zone {
+            ZoneId::Custom { key, .. } => self.custom_zones.get(&key).cloned(),
+            zone => zone.metadata(),
+        }
+    }
 
-        obj.controller
+    pub fn team_of(&self, player: PlayerId) -> Option<TeamId> {
+        self.teams.get(&player).copied()
+    }
+
+    /// Whether both players play on the same side, a player is always their own ally
+    pub fn are_allies(&self, a: PlayerId, b: PlayerId) -> bool {
+        a == b
+            || self
+                .team_of(a)
+                .is_some_and(|team| self.team_of(b) == Some(team))
+    }
+
+    pub fn preferences_of(&self, player: PlayerId) -> Option<&PlayerPreferences> {
+        self.players.get(&player).map(|p| &p.preferences)
+    }
+
+    /// The library the player draws from and shuffles into, unless a replacement says otherwise
+    pub fn library_of(&self, player: PlayerId) -> ZoneId {
+        if self.format.shared_library {
+            ZoneId::SharedLibrary
+        } else {
+            ZoneId::Library(player)
+        }
+    }
+
+    pub fn get_controller_of(&self, object: ObjectId) -> Option<PlayerId> {
+        let (zone, obj) = self.latest_gamestate().find_object(object)?;
+        match zone {
+            ZoneId::Battlefield | ZoneId::Stack => obj.controller,
+            _ => None,
+        }
     }
 }
@@ -0,0 +1,111 @@
+//! Archiving games as JSON lines
+//!
+//! A log starts with a [`LogHeader`] line holding everything needed to set the game up again,
+//! followed by one [`LogEntry`] line per committed batch of atoms, in order.
+
+use std::io::Write;
+
+use rand_xoshiro::Xoshiro256StarStar;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::FormatRules;
+use crate::Game;
+use crate::GameAtom;
+use crate::GameId;
+use crate::Player;
+use crate::PlayerId;
+
+/// Bumped whenever logs of the previous version can no longer be read
+pub const LOG_FORMAT_VERSION: u32 = 1;
+
+/// The first line of a log
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogHeader {
+    pub format_version: u32,
+    pub game: GameId,
+    /// The randomness the first atoms were applied with, see [`Game::initial_seed`]
+    pub seed: Option<Xoshiro256StarStar>,
+    /// The players in the order they were seated
+    pub players: Vec<Player>,
+    pub format: FormatRules,
+    /// The [`GameState::fingerprint`](crate::GameState::fingerprint) of the first game state
+    pub state_hash: u64,
+}
+
+/// A batch of atoms that was committed to the game
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LogEntry {
+    /// The index of the game state the atoms were applied to
+    pub base: usize,
+    /// The player whose decision the batch records, see [`GameAtom::actor`]
+    pub actor: Option<PlayerId>,
+    /// When the atoms were applied, in milliseconds since the Unix epoch, if it was recorded
+    pub timestamp: Option<u64>,
+    pub atoms: Vec<GameAtom>,
+    /// The fingerprint of the resulting game state, `None` if it was compacted away
+    pub state_hash: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "line", rename_all = "snake_case")]
+pub enum LogLine {
+    Header(Box<LogHeader>),
+    Entry(LogEntry),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LogError {
+    #[error("The log could not be written")]
+    Io(#[from] std::io::Error),
+    #[error("A line of the log could not be serialized")]
+    Json(#[from] serde_json::Error),
+}
+
+impl Game {
+    /// The lines of the game's log, batches that were refused or never committed are left out
+    pub fn log(&self) -> Vec<LogLine> {
+        let first = self.first_gamestate();
+        let header = LogHeader {
+            format_version: LOG_FORMAT_VERSION,
+            game: self.id,
+            seed: self.initial_seed().cloned(),
+            players: first
+                .active_player_order
+                .iter()
+                .filter_map(|p| self.players.get(p).cloned())
+                .collect(),
+            format: self.format.clone(),
+            state_hash: first.fingerprint(),
+        };
+
+        let entries = self.committed_history().into_iter().map(|(base, atoms)| {
+            LogLine::Entry(LogEntry {
+                base,
+                actor: atoms.iter().find_map(GameAtom::actor),
+                // Like the batches, the last timestamp recorded for a game state is the committed one
+                timestamp: self
+                    .applied_at
+                    .iter()
+                    .rev()
+                    .find(|(idx, _)| *idx == base)
+                    .map(|(_, at)| *at),
+                atoms,
+                state_hash: self.recorded_state(base + 1).map(|s| s.fingerprint()),
+            })
+        });
+
+        std::iter::once(LogLine::Header(Box::new(header)))
+            .chain(entries)
+            .collect()
+    }
+
+    /// Writes the game's log to `out`, one JSON object per line
+    pub fn write_log(&self, mut out: impl Write) -> Result<(), LogError> {
+        for line in self.log() {
+            serde_json::to_writer(&mut out, &line)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
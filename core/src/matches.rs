@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::card::CardId;
+use crate::GameResult;
+use crate::Player;
+use crate::PlayerId;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct MatchId(Uuid);
+
+impl MatchId {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// A player taking part in a match
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatchPlayer {
+    /// The player, their initial cards are the deck for the first game
+    pub player: Player,
+    /// Cards that may be swapped with cards of the deck between games
+    pub sideboard: Vec<CardId>,
+}
+
+/// The state of a match as reported to the server
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    /// How each game played so far ended, in order
+    pub games: Vec<GameResult>,
+    /// The winner of the match, once it is decided
+    pub winner: Option<PlayerId>,
+    /// Whether no more games will be played
+    pub finished: bool,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SideboardError {
+    #[error("The match could not be found")]
+    UnknownMatch,
+    #[error("The player ({player:?}) does not take part in the match")]
+    UnknownPlayer { player: PlayerId },
+    #[error("The card ({card:?}) is not part of the deck")]
+    NotInDeck { card: CardId },
+    #[error("The card ({card:?}) is not part of the sideboard")]
+    NotInSideboard { card: CardId },
+}
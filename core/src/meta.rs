@@ -6,21 +6,145 @@ use futures::Stream;
 use futures::StreamExt;
 use futures::TryFutureExt;
 use futures::TryStreamExt;
+use std::time::Duration;
 use tarpc::transport::channel::ChannelError;
 use tarpc::transport::channel::UnboundedChannel;
+
 use thiserror::Error;
 use tracing::debug;
 use tracing::warn;
 
+use crate::card::CardId;
+use crate::matches::MatchId;
+use crate::matches::MatchPlayer;
+use crate::matches::MatchResult;
+use crate::matches::SideboardError;
+use crate::FormatRules;
 use crate::GameId;
+use crate::GameResult;
+use crate::ObjectId;
 use crate::Player;
+use crate::PlayerId;
+
+/// Everything needed to create a single game
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GameSpec {
+    /// The seats in turn order
+    pub seats: Vec<Seat>,
+    /// The rules the game is played with
+    #[serde(default)]
+    pub format: FormatRules,
+}
+
+/// Binds a web user and the deck they chose to a player and their place at the table
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Seat {
+    /// The name of the web user sitting here
+    pub user: String,
+    /// The player of the user in the engine, their initial cards are the chosen deck
+    pub player: Player,
+}
+
+/// Identifies a single attempt of a lobby to start its game
+///
+/// Creating a game twice with the same key gives the game created first, so that simultaneous
+/// starts of the same lobby end up in the same game.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct CreationKey {
+    pub lobby: String,
+    /// Counts up every time the lobby starts a new game, for example after the last one ended
+    pub attempt: u64,
+}
+
+/// What the engine tells about a game it runs
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GameStatus {
+    /// The seats in turn order, used to find out who is who when reconnecting or spectating
+    pub seats: Vec<Seat>,
+    /// How the game ended, `None` while it is still running
+    pub result: Option<GameResult>,
+}
+
+/// How much a single running game weighs on the engine
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GameDiagnostics {
+    pub game: GameId,
+    /// How many game states the game keeps
+    pub states: usize,
+    /// A rough guess of the bytes held by the game
+    pub memory_estimate: usize,
+    /// How long the current prompt has been waiting for an answer, `None` if nobody is asked
+    pub pending_prompt_age: Option<Duration>,
+    /// How long ago the game last asked something or made progress
+    pub idle_for: Duration,
+}
+
+/// The workload of an engine, to find games that are stuck or grow too large
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Diagnostics {
+    pub games: Vec<GameDiagnostics>,
+    /// How many matches the engine runs
+    pub matches: usize,
+    /// How long the engine has been running
+    pub uptime: Duration,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CreateGamesError {
+    #[error("Only {available} more games can be created, but {requested} were requested")]
+    NotEnoughCapacity { requested: usize, available: usize },
+    #[error("The game at position {index} could not be verified")]
+    InvalidGame { index: usize },
+}
 
 /// The protocol between the Server and the Engine
 #[tarpc::service]
 pub trait Meta {
-    async fn create_game(players: Vec<Player>) -> GameId;
+    /// Creates a game with the given seats, the first seat takes the first turn
+    ///
+    /// If a game was already created with the same key, that game is returned instead, even if
+    /// the engine is full.
+    async fn create_game(
+        seats: Vec<Seat>,
+        key: Option<CreationKey>,
+        format: FormatRules,
+    ) -> Result<GameId, CreateGamesError>;
+
+    /// Creates all given games, or none of them if any could not be created
+    ///
+    /// This is used to start a whole tournament round at once.
+    async fn create_games(games: Vec<GameSpec>) -> Result<Vec<GameId>, CreateGamesError>;
 
     async fn destroy_game(game: GameId);
+
+    /// The status of a running game, if this engine knows it
+    async fn get_game_status(game: GameId) -> Option<GameStatus>;
+
+    /// The cards behind objects of the player's pending prompt, for those not described in it
+    ///
+    /// Objects that are not part of the prompt, or once it has been answered, give `None`.
+    async fn get_prompt_objects(
+        game: GameId,
+        player: PlayerId,
+        objects: Vec<ObjectId>,
+    ) -> Vec<Option<CardId>>;
+
+    /// Starts a match of up to `best_of` games between the players, played one after another
+    async fn create_match(best_of: usize, players: Vec<MatchPlayer>) -> MatchId;
+
+    /// Swaps a card of the player's deck with one of their sideboard, starting with the next game
+    async fn swap_sideboard_card(
+        match_id: MatchId,
+        player: PlayerId,
+        out: CardId,
+        into: CardId,
+    ) -> Result<(), SideboardError>;
+
+    /// The games played so far, and the winner once the match is decided
+    async fn get_match_result(match_id: MatchId) -> Option<MatchResult>;
+
+    /// The workload of every running game and of the engine as a whole
+    async fn diagnostics() -> Diagnostics;
 }
 
 // This code is adapted from the comments in https://github.com/google/tarpc/issues/300
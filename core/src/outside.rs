@@ -1,26 +1,221 @@
 #![allow(clippy::too_many_arguments)]
 
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::card::CardId;
+use crate::event::GameEvent;
+use crate::view::PlayerView;
 use crate::GameId;
 use crate::ObjectId;
+use crate::Phase;
 use crate::PlayerAction;
 use crate::PlayerId;
 use crate::TargetId;
+use crate::ZoneId;
+
+/// Sent along with every prompt, so that it can be presented without any further state
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct PromptContext {
+    /// The player whose turn it is
+    pub active_player: Option<PlayerId>,
+    /// The player who is currently allowed to act
+    pub priority_holder: Option<PlayerId>,
+    pub phase: Phase,
+    /// How many objects are currently on the stack
+    pub stack_depth: usize,
+    /// Numbers the prompts of a game, every prompt gets a higher one than the previous
+    ///
+    /// Every prompt is answered by the reply to its own request, a client that is given a prompt
+    /// with a sequence number it already answered can ignore it.
+    #[serde(default)]
+    pub sequence: u64,
+}
+
+/// How many objects of a prompt are described up front
+pub const OBJECT_PAGE_SIZE: usize = 25;
+
+/// Objects shown in a prompt, of which only the first page is described up front
+///
+/// Answers index into `ids`. While the prompt is pending, the cards behind the other objects can
+/// be fetched with [`Meta::get_prompt_objects`](crate::meta::Meta::get_prompt_objects).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+pub struct ObjectList {
+    pub ids: Vec<ObjectId>,
+    /// The cards of the first objects, in the order of `ids`
+    ///
+    /// Face-down objects the player may not know are given without their card.
+    pub cards: Vec<Option<CardId>>,
+}
+
+impl ObjectList {
+    /// Describes only the first [`OBJECT_PAGE_SIZE`] of the given objects
+    pub fn paged(objects: &[(ObjectId, Option<CardId>)]) -> ObjectList {
+        ObjectList {
+            ids: objects.iter().map(|(id, _)| *id).collect(),
+            cards: objects
+                .iter()
+                .take(OBJECT_PAGE_SIZE)
+                .map(|(_, card)| *card)
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Whether the cards of some objects have to be fetched separately
+    pub fn is_partial(&self) -> bool {
+        self.cards.len() < self.ids.len()
+    }
+}
 
 #[tarpc::service]
 pub trait Outside {
-    async fn get_player_keeping(game_id: GameId, asked_players: Vec<PlayerId>) -> Vec<PlayerId>;
+    async fn get_player_keeping(
+        game_id: GameId,
+        prompt: PromptContext,
+        asked_players: Vec<PlayerId>,
+    ) -> Vec<PlayerId>;
     async fn get_next_player_action_from(
         game_id: GameId,
+        prompt: PromptContext,
         player: PlayerId,
         player_actions: Vec<PlayerAction>,
     ) -> usize;
     async fn get_target_choices_from_given(
         game_id: GameId,
+        prompt: PromptContext,
         player: PlayerId,
         source: ObjectId,
         name: String,
         choices: Vec<TargetId>,
         count: usize,
+        optional: bool,
     ) -> Vec<usize>;
-    async fn get_player_passing(game_id: GameId, player: PlayerId) -> bool;
+    /// Asks for between `min` and `max` distinct targets out of the given choices
+    ///
+    /// Returns the indices of the chosen targets.
+    async fn get_multiple_target_choices(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        min: usize,
+        max: usize,
+    ) -> Vec<usize>;
+    async fn get_number_choice(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        min: u64,
+        max: u64,
+    ) -> u64;
+    /// Returns the index of the chosen card, or `None` if the player fails to find one
+    async fn get_library_search_choice(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: ObjectList,
+    ) -> Option<usize>;
+    /// Shows the player the top cards of their library, topmost first
+    ///
+    /// Returns the indices of the cards to put back on top and those to put on the bottom, the
+    /// first of each ending up topmost. Every card has to be given exactly once, and the bottom
+    /// list has to be empty unless `allow_bottom` is set.
+    async fn get_library_arrangement(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        cards: ObjectList,
+        allow_bottom: bool,
+    ) -> (Vec<usize>, Vec<usize>);
+    /// Asks the player which `count` of the given objects in `zone` to choose
+    ///
+    /// If the player may not look at the zone, `choices` describes none of the cards and they
+    /// choose blindly.
+    async fn get_zone_choice(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        zone: ZoneId,
+        choices: ObjectList,
+        count: usize,
+    ) -> Vec<usize>;
+    /// Asks the player to put the given objects in order
+    ///
+    /// Returns the index of every object exactly once, the first one first. As with
+    /// [`get_zone_choice`](Outside::get_zone_choice), objects the player may not look at are
+    /// described without their cards.
+    async fn get_ordering(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        items: ObjectList,
+    ) -> Vec<usize>;
+    /// Asks the player which `count` of the given cards in their hand to discard
+    async fn get_discard_choice(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Vec<usize>;
+    /// Asks the player which `count` of the given cards in their hand to put on the bottom of
+    /// their library, the first chosen ends up topmost
+    async fn get_cards_to_bottom(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Vec<usize>;
+    /// Asks the attacking player to order the blockers of `attacker` and split its `damage`
+    /// among them
+    ///
+    /// Returns the index of every blocker in the chosen order, together with the damage it is
+    /// assigned. A blocker may only be assigned damage once all blockers before it were assigned
+    /// lethal damage.
+    async fn get_damage_assignment(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        attacker: ObjectId,
+        blockers: Vec<(ObjectId, CardId)>,
+        damage: u64,
+    ) -> Vec<(usize, u64)>;
+    async fn get_player_passing(game_id: GameId, prompt: PromptContext, player: PlayerId) -> bool;
+    /// Asks the player deciding who starts whether they play first
+    ///
+    /// Otherwise the next player in turn order starts and they draw first.
+    async fn get_play_first(game_id: GameId, prompt: PromptContext, player: PlayerId) -> bool;
+    async fn notify_event(game_id: GameId, event: GameEvent);
+    /// Tells the player their last answer was refused, they are asked again right away
+    async fn notify_error(game_id: GameId, player: PlayerId, error_description: String);
+    /// Shows the player what the game would look like after their action, returns whether to
+    /// go ahead with it
+    async fn confirm_action(
+        game_id: GameId,
+        prompt: PromptContext,
+        player: PlayerId,
+        preview: PlayerView,
+    ) -> bool;
 }
@@ -0,0 +1,132 @@
+//! Persisting whole games
+//!
+//! The card database is not part of a saved game, as it is shared between all games of an engine.
+//! It is attached again when the game is loaded.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rand_xoshiro::Xoshiro256StarStar;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::card::Card;
+use crate::card::CardId;
+use crate::schema::Replay;
+use crate::FormatRules;
+use crate::Game;
+use crate::GameId;
+use crate::GameState;
+use crate::Player;
+use crate::PlayerId;
+use crate::TeamId;
+use crate::ZoneKey;
+use crate::ZoneMetadata;
+
+/// Bumped whenever saved games of the previous version can no longer be loaded
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// A game as it is persisted, with its randomness, game states and history
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedGame {
+    pub format_version: u32,
+    pub game: Game,
+}
+
+/// Everything needed to play a game again bit for bit, see [`Game::export_seed`]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SeedExport {
+    pub game: GameId,
+    /// The players in the order they were seated
+    pub players: Vec<Player>,
+    pub format: FormatRules,
+    pub teams: HashMap<PlayerId, TeamId>,
+    pub custom_zones: HashMap<ZoneKey, ZoneMetadata>,
+    pub first_state: GameState,
+    /// The randomness the first atoms were applied with
+    pub seed: Xoshiro256StarStar,
+    /// The committed atom batches, in order
+    pub history: Replay,
+    /// How many values each batch drew from the randomness, by the index it was applied to
+    pub draws: Vec<(usize, u64)>,
+    /// The [`GameState::fingerprint`] each batch resulted in, by the index it was applied to
+    ///
+    /// Game states that were compacted away have none.
+    #[serde(default)]
+    pub state_hashes: Vec<(usize, u64)>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum LoadError {
+    #[error(
+        "The game was saved in format {saved}, only format {SAVE_FORMAT_VERSION} can be loaded"
+    )]
+    UnsupportedFormat { saved: u32 },
+    #[error("The card {card:?} of the game is missing from the card database")]
+    MissingCard { card: CardId },
+}
+
+impl Game {
+    /// The seed and history of the game, to replay it elsewhere and check that it plays out the
+    /// same
+    pub fn export_seed(&self) -> SeedExport {
+        let first_state = self.first_gamestate().clone();
+        SeedExport {
+            game: self.id,
+            players: first_state
+                .active_player_order
+                .iter()
+                .filter_map(|p| self.players.get(p).cloned())
+                .collect(),
+            format: self.format.clone(),
+            teams: self.teams.clone(),
+            custom_zones: self.custom_zones.clone(),
+            first_state,
+            // Without any atoms applied the next ones use the current randomness
+            seed: self.initial_seed().unwrap_or(&self.rand).clone(),
+            history: self.committed_history(),
+            draws: self.rand_draws.clone(),
+            state_hashes: self
+                .committed_history()
+                .iter()
+                .filter_map(|(base, _)| {
+                    let state = self.recorded_state(base + 1)?;
+                    Some((*base, state.fingerprint()))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn save(&self) -> SavedGame {
+        SavedGame {
+            format_version: SAVE_FORMAT_VERSION,
+            game: self.clone(),
+        }
+    }
+
+    /// Turns a saved game back into a game, attaching the card database
+    ///
+    /// Every card of the decks and of the latest game state has to be in `cards`. Whether the
+    /// cards are still of the versions the game was created with is checked when it is resumed.
+    pub fn load(saved: SavedGame, cards: Arc<HashMap<CardId, Card>>) -> Result<Game, LoadError> {
+        if saved.format_version != SAVE_FORMAT_VERSION {
+            return Err(LoadError::UnsupportedFormat {
+                saved: saved.format_version,
+            });
+        }
+
+        let mut game = saved.game;
+        let decks = game.players.values().flat_map(|p| p.initial_cards.iter());
+        let objects = game
+            .latest_gamestate()
+            .zones
+            .values()
+            .flat_map(|z| z.objects.iter().filter_map(|o| o.underlying_card.as_ref()));
+        if let Some(card) = decks.chain(objects).find(|card| !cards.contains_key(*card)) {
+            return Err(LoadError::MissingCard { card: *card });
+        }
+
+        game.cards = cards;
+        Ok(game)
+    }
+}
@@ -0,0 +1,73 @@
+//! Machine readable descriptions of everything the engine sends and receives
+//!
+//! Clients can generate their types from these instead of following the Rust types by hand. The
+//! published schemas live in `core/schema`, one file per [`SCHEMA_VERSION`].
+
+use std::collections::BTreeMap;
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::event::GameEvent;
+use crate::outside::PromptContext;
+use crate::view::PlayerView;
+use crate::GameAtom;
+use crate::GameResult;
+use crate::PlayerAction;
+
+/// Bumped whenever a change to the schemas would break existing clients
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The history of a game, every batch of atoms together with the index of the state it was
+/// applied to
+pub type Replay = Vec<(usize, Vec<GameAtom>)>;
+
+/// The schemas of the current version, by name
+///
+/// Prompts are described by the types they are made of, as their requests are generated.
+pub fn schemas() -> BTreeMap<&'static str, RootSchema> {
+    BTreeMap::from([
+        ("atom", schema_for!(GameAtom)),
+        ("event", schema_for!(GameEvent)),
+        ("player_action", schema_for!(PlayerAction)),
+        ("player_view", schema_for!(PlayerView)),
+        ("prompt_context", schema_for!(PromptContext)),
+        ("replay", schema_for!(Replay)),
+        ("result", schema_for!(GameResult)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::schemas;
+    use super::SCHEMA_VERSION;
+
+    /// Fails if the schemas differ from the ones published for the current version
+    ///
+    /// Compatible changes are published by running the test with `TECHNOMANCY_BLESS_SCHEMA=1`,
+    /// breaking ones need a new [`SCHEMA_VERSION`]. A version that was not published yet is
+    /// written on the first run.
+    #[test]
+    fn check_schema_is_compatible() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("schema")
+            .join(format!("v{SCHEMA_VERSION}.json"));
+        let current = serde_json::to_string_pretty(&schemas()).unwrap() + "\n";
+
+        if !path.exists() || std::env::var_os("TECHNOMANCY_BLESS_SCHEMA").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, current).unwrap();
+            return;
+        }
+
+        let published = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            published == current,
+            "The schemas differ from {}, bump SCHEMA_VERSION if this breaks clients, otherwise \
+             publish them with TECHNOMANCY_BLESS_SCHEMA=1",
+            path.display()
+        );
+    }
+}
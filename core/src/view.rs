@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::card::CardId;
+use crate::effect::EffectInfo;
+use crate::GameObject;
+use crate::GameState;
+use crate::ObjectId;
+use crate::ObjectVisibility;
+use crate::PlayerId;
+use crate::StackTrigger;
+use crate::TargetId;
+use crate::ZoneId;
+
+/// How many objects are in the zones of a single player
+///
+/// These are public knowledge, even if the zones themselves are hidden.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+pub struct ZoneCounts {
+    pub hand: usize,
+    pub library: usize,
+    pub discard: usize,
+}
+
+/// An object on the stack as every player may see it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+pub struct StackEntry {
+    pub object: ObjectId,
+    /// The card behind the object, `None` if it is face down
+    pub card: Option<CardId>,
+    pub controller: Option<PlayerId>,
+    /// The chosen targets by effect index and choice name, face-down objects show none
+    pub targets: Vec<(usize, String, TargetId)>,
+    /// The triggered effect the object stands for, if it is a trigger
+    #[serde(default)]
+    pub trigger: Option<StackTrigger>,
+}
+
+/// The part of a [`GameState`] that a single player is allowed to see
+///
+/// Objects the player may not know about, like face-down ones, are redacted.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct PlayerView {
+    pub player: PlayerId,
+    pub hand: Vec<GameObject>,
+    pub battlefield: Vec<GameObject>,
+    pub stack: Vec<GameObject>,
+    pub exile: Vec<GameObject>,
+    pub discards: HashMap<PlayerId, Vec<GameObject>>,
+    pub zone_counts: HashMap<PlayerId, ZoneCounts>,
+}
+
+impl GameState {
+    pub fn zone_counts(&self) -> HashMap<PlayerId, ZoneCounts> {
+        let count = |zone: ZoneId| self.zones.get(&zone).map_or(0, |z| z.objects.len());
+
+        self.active_player_order
+            .iter()
+            .map(|p| {
+                (
+                    *p,
+                    ZoneCounts {
+                        hand: count(ZoneId::Hand(*p)),
+                        library: count(ZoneId::Library(*p)),
+                        discard: count(ZoneId::Discard(*p)),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The objects on the stack, the bottom one first
+    pub fn stack_entries(&self) -> Vec<StackEntry> {
+        self.get_stack()
+            .objects
+            .iter()
+            .map(|o| {
+                let face_up = o.visibility == ObjectVisibility::FaceUp;
+                let mut targets: Vec<_> = o
+                    .choices
+                    .iter()
+                    .filter(|_| face_up)
+                    .flat_map(|((effect, name), info)| match info {
+                        EffectInfo::SingleTarget(target) => vec![(*effect, name.clone(), *target)],
+                        EffectInfo::MultipleTargets(targets) => targets
+                            .iter()
+                            .map(|target| (*effect, name.clone(), *target))
+                            .collect(),
+                        _ => vec![],
+                    })
+                    .collect();
+                targets.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+
+                StackEntry {
+                    object: o.id,
+                    card: o.underlying_card.filter(|_| face_up),
+                    controller: o.controller,
+                    targets,
+                    trigger: o.trigger.clone(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn view_for(&self, player: PlayerId) -> PlayerView {
+        let objects = |zone: ZoneId| {
+            self.zones
+                .get(&zone)
+                .map(|z| {
+                    z.objects
+                        .iter()
+                        .map(|o| {
+                            if o.is_known_to(player) {
+                                o.clone()
+                            } else {
+                                o.redacted()
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        PlayerView {
+            player,
+            hand: objects(ZoneId::Hand(player)),
+            battlefield: objects(ZoneId::Battlefield),
+            stack: objects(ZoneId::Stack),
+            exile: objects(ZoneId::Exile),
+            discards: self
+                .active_player_order
+                .iter()
+                .map(|p| (*p, objects(ZoneId::Discard(*p))))
+                .collect(),
+            zone_counts: self.zone_counts(),
+        }
+    }
+
+    /// The game state with every object the player may not know about redacted
+    ///
+    /// Libraries and the hands of other players are hidden completely, `None` also hides every
+    /// hand.
+    pub fn redacted_for(&self, player: Option<PlayerId>) -> GameState {
+        let mut state = self.clone();
+        for (zone, contents) in state.zones.iter_mut() {
+            let hidden = match zone {
+                ZoneId::Library(_) | ZoneId::SharedLibrary => true,
+                ZoneId::Hand(owner) => Some(*owner) != player,
+                _ => false,
+            };
+            for object in contents.objects.iter_mut() {
+                let known = match player {
+                    Some(player) => object.is_known_to(player),
+                    None => object.visibility == ObjectVisibility::FaceUp,
+                };
+                if hidden || !known {
+                    *object = object.redacted();
+                }
+            }
+        }
+        state
+    }
+}
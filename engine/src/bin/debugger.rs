@@ -0,0 +1,187 @@
+use std::io::BufRead;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::Parser;
+use technomancy_core::schema::Replay;
+use technomancy_core::Game;
+use technomancy_engine::debugger::StateDiff;
+use technomancy_engine::debugger::Timeline;
+
+const HELP: &str = "\
+next, n            go to the next game state
+prev, p            go to the previous game state
+goto <idx>         go to the given game state
+state, s           show the phase and players of the current game state
+zones, z           show the contents of every zone
+choices, c         show the objects carrying choices
+atoms, a           show the atoms that produced the next game state
+diff <from> <to>   show what changed between two game states
+help, h            show this help
+quit, q            leave the debugger";
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// A game as persisted by the engine, in JSON
+    game: PathBuf,
+
+    /// Rebuild the game states from this replay instead of using the recorded ones
+    ///
+    /// The replay is applied to the first game state of the game.
+    #[clap(long)]
+    replay: Option<PathBuf>,
+}
+
+fn load<T: serde::de::DeserializeOwned>(path: &PathBuf) -> Result<T, String> {
+    let file = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    serde_json::from_str(&file).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn print_state(timeline: &Timeline) {
+    let state = timeline.current();
+    println!("Game state {}/{}", timeline.position(), timeline.len() - 1);
+    println!("  stage: {:?}", state.game_stage);
+    println!("  phase: {:?}", state.phase);
+    println!("  player order: {:?}", state.active_player_order);
+    println!("  unpassed players: {:?}", state.unpassed_players);
+    for (player, pool) in &state.scrip {
+        println!("  scrip of {player:?}: {pool:?}");
+    }
+    for (player, pool) in &state.floating_scrip {
+        println!("  floating scrip of {player:?}: {pool:?}");
+    }
+}
+
+fn print_zones(timeline: &Timeline) {
+    for (zone, contents) in &timeline.current().zones {
+        println!("{zone:?} ({} objects)", contents.objects.len());
+        for object in contents.objects.iter() {
+            println!(
+                "  {:?} card={:?} controller={:?} visibility={:?} exhausted={}",
+                object.id,
+                object.underlying_card,
+                object.controller,
+                object.visibility,
+                object.exhausted
+            );
+        }
+    }
+}
+
+fn print_choices(timeline: &Timeline) {
+    let objects = timeline.objects_with_choices();
+    if objects.is_empty() {
+        println!("No object carries choices");
+    }
+    for (zone, object) in objects {
+        println!("{:?} in {zone:?}", object.id);
+        for ((effect, name), info) in &object.choices {
+            println!("  effect {effect} {name}: {info:?}");
+        }
+    }
+}
+
+fn print_diff(from: usize, to: usize, diff: &StateDiff) {
+    println!("From game state {from} to {to}");
+    if diff.is_empty() {
+        println!("  nothing changed");
+    }
+    if let Some((before, after)) = &diff.game_stage {
+        println!("  stage: {before:?} -> {after:?}");
+    }
+    if let Some((before, after)) = &diff.phase {
+        println!("  phase: {before:?} -> {after:?}");
+    }
+    if let Some((before, after)) = &diff.active_player_order {
+        println!("  player order: {before:?} -> {after:?}");
+    }
+    for zone in &diff.zones {
+        println!("  {:?}", zone.zone);
+        for object in &zone.added {
+            println!("    + {object:?}");
+        }
+        for object in &zone.removed {
+            println!("    - {object:?}");
+        }
+    }
+    for (player, kind, before, after) in &diff.scrip {
+        println!("  scrip {kind:?} of {player:?}: {before} -> {after}");
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let timeline = load::<Game>(&args.game).and_then(|game| match &args.replay {
+        Some(replay) => {
+            let replay = load::<Replay>(replay)?;
+            Timeline::from_replay(game, replay).map_err(|e| format!("Could not replay: {e}"))
+        }
+        None => Ok(Timeline::new(game)),
+    });
+    let mut timeline = match timeline {
+        Ok(timeline) if !timeline.is_empty() => timeline,
+        Ok(_) => {
+            eprintln!("The game has no game states");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    print_state(&timeline);
+    let stdin = std::io::stdin();
+    loop {
+        print!("> ");
+        std::io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let words: Vec<_> = line.split_whitespace().collect();
+        let indices: Result<Vec<usize>, _> = words.iter().skip(1).map(|w| w.parse()).collect();
+
+        match (words.first().copied(), indices.as_deref()) {
+            (None, _) => (),
+            (Some("next" | "n"), _) => {
+                if timeline.forward() {
+                    print_state(&timeline);
+                } else {
+                    println!("Already at the last game state");
+                }
+            }
+            (Some("prev" | "p"), _) => {
+                if timeline.backward() {
+                    print_state(&timeline);
+                } else {
+                    println!("Already at the first game state");
+                }
+            }
+            (Some("goto"), Ok([idx])) => {
+                if timeline.seek(*idx) {
+                    print_state(&timeline);
+                } else {
+                    println!("There are only {} game states", timeline.len());
+                }
+            }
+            (Some("state" | "s"), _) => print_state(&timeline),
+            (Some("zones" | "z"), _) => print_zones(&timeline),
+            (Some("choices" | "c"), _) => print_choices(&timeline),
+            (Some("atoms" | "a"), _) => match timeline.atoms() {
+                Some(atoms) => atoms.iter().for_each(|atom| println!("{atom:?}")),
+                None => println!("No atoms were applied to this game state"),
+            },
+            (Some("diff"), Ok([from, to])) => match timeline.diff(*from, *to) {
+                Some(diff) => print_diff(*from, *to, &diff),
+                None => println!("There are only {} game states", timeline.len()),
+            },
+            (Some("help" | "h"), _) => println!("{HELP}"),
+            (Some("quit" | "q"), _) => break,
+            _ => println!("Unknown command, see `help`"),
+        }
+    }
+}
@@ -2,50 +2,237 @@ use std::collections::HashMap;
 #[cfg(test)]
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Instant;
 
 use clap::Parser;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use futures::FutureExt;
 use futures::StreamExt;
 use rand::SeedableRng;
 use rand_xoshiro::Xoshiro256StarStar;
+use serde::Serialize;
 use tarpc::context::Context;
 use tarpc::server::BaseChannel;
 use tarpc::server::Channel;
 use technomancy_core::card::Card;
 use technomancy_core::card::CardId;
+use technomancy_core::matches::MatchId;
+use technomancy_core::matches::MatchPlayer;
+use technomancy_core::matches::MatchResult;
+use technomancy_core::matches::SideboardError;
 use technomancy_core::meta::spawn_twoway;
+use technomancy_core::meta::CreateGamesError;
+use technomancy_core::meta::CreationKey;
+use technomancy_core::meta::Diagnostics;
+use technomancy_core::meta::GameSpec;
+use technomancy_core::meta::GameStatus;
 use technomancy_core::meta::Meta;
+use technomancy_core::meta::Seat;
 use technomancy_core::outside::OutsideClient;
+use technomancy_core::FormatRules;
+use technomancy_core::GameError;
 use technomancy_core::GameId;
-use technomancy_core::Player;
+use technomancy_core::GameResult;
+use technomancy_core::GameStage;
+use technomancy_core::ObjectId;
+use technomancy_core::PlayerId;
+use technomancy_core::VerificationError;
+use technomancy_engine::matches::Match;
 use technomancy_engine::outside::OutsideGameClient;
+use technomancy_engine::outside::PromptObjects;
+use technomancy_engine::workload::Workload;
 use technomancy_engine::GameImplV1;
 use tokio::sync::oneshot::Sender;
 use tokio::task::AbortHandle;
+use tokio::task::JoinHandle;
 use tracing::error;
 use tracing::info;
+use tracing::warn;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
 #[derive(Debug)]
 struct GameInfo {
     handle: AbortHandle,
+    seats: Vec<Seat>,
+    prompt_objects: PromptObjects,
+    workload: Workload,
+    /// How the game ended, set once it is over
+    result: Arc<std::sync::Mutex<Option<GameResult>>>,
+}
+
+fn assert_send<'u, R>(
+    fut: impl 'u + Send + std::future::Future<Output = R>,
+) -> impl 'u + Send + std::future::Future<Output = R> {
+    fut
+}
+
+/// The game lifecycle events that can be sent to a webhook
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum WebhookEventKind {
+    Created,
+    Started,
+    Finished,
+    Error,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum WebhookEvent {
+    Created { game: GameId },
+    Started { game: GameId },
+    Finished { game: GameId, result: GameResult },
+    Error { game: GameId, error: String },
+}
+
+impl WebhookEvent {
+    fn kind(&self) -> WebhookEventKind {
+        match self {
+            WebhookEvent::Created { .. } => WebhookEventKind::Created,
+            WebhookEvent::Started { .. } => WebhookEventKind::Started,
+            WebhookEvent::Finished { .. } => WebhookEventKind::Finished,
+            WebhookEvent::Error { .. } => WebhookEventKind::Error,
+        }
+    }
+}
+
+/// POSTs game lifecycle events as JSON to an external URL
+#[derive(Debug)]
+struct Webhook {
+    client: reqwest::Client,
+    url: String,
+    /// The events to send, all of them if empty
+    events: Vec<WebhookEventKind>,
+}
+
+impl Webhook {
+    fn wants(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+
+    /// Sends the event in the background, failures are only logged
+    fn notify(self: &Arc<Self>, event: WebhookEvent) {
+        if !self.wants(event.kind()) {
+            return;
+        }
+
+        let webhook = self.clone();
+        tokio::spawn(async move {
+            let response = webhook.client.post(&webhook.url).json(&event).send().await;
+            if let Err(e) = response.and_then(|r| r.error_for_status()) {
+                warn!(?event, "Could not deliver webhook: {e}");
+            }
+        });
+    }
+}
+
+/// Runs the game until it is over
+async fn play_game(
+    mut game: GameImplV1,
+    client: OutsideGameClient,
+    webhook: Option<Arc<Webhook>>,
+) -> Result<GameResult, GameError> {
+    let notify = |event| {
+        if let Some(webhook) = &webhook {
+            webhook.notify(event);
+        }
+    };
+
+    let id = game.id();
+    notify(WebhookEvent::Created { game: id });
+
+    let mut started = false;
+    loop {
+        if let Err(error) = assert_send(game.run(&client).boxed()).await {
+            if error.is_recoverable() {
+                // The player was told and is asked again
+                warn!("Refused an answer: {error}");
+                continue;
+            }
+            notify(WebhookEvent::Error {
+                game: id,
+                error: error.to_string(),
+            });
+            return Err(error);
+        }
+
+        if !started && game.latest_gamestate().game_stage == GameStage::GameRunning {
+            started = true;
+            notify(WebhookEvent::Started { game: id });
+        }
+
+        if let Some(result) = game.result() {
+            notify(WebhookEvent::Finished {
+                game: id,
+                result: result.clone(),
+            });
+            return Ok(result);
+        }
+    }
+}
+
+/// The games of the engine, shared by all connections so that its capacity holds across them
+#[derive(Debug, Default)]
+struct EngineGames {
+    games: DashMap<GameId, GameInfo>,
+    /// The games created for each creation key
+    created: DashMap<CreationKey, GameId>,
+    /// Held while games are being created, so that capacity checks stay correct
+    creation_lock: tokio::sync::Mutex<()>,
+    max_games: Option<usize>,
+}
+
+impl EngineGames {
+    fn new(max_games: Option<usize>) -> Self {
+        EngineGames {
+            max_games,
+            ..Default::default()
+        }
+    }
+
+    /// Refuses to create `requested` games if they do not fit, the creation lock has to be held
+    fn check_capacity(&self, requested: usize) -> Result<(), CreateGamesError> {
+        let Some(max_games) = self.max_games else {
+            return Ok(());
+        };
+        let available = max_games.saturating_sub(self.games.len());
+        if requested > available {
+            return Err(CreateGamesError::NotEnoughCapacity {
+                requested,
+                available,
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
 struct EngineServer {
     client: Arc<OutsideClient>,
     cards: Arc<std::collections::HashMap<CardId, Card>>,
-    games: Arc<DashMap<GameId, GameInfo>>,
+    engine: Arc<EngineGames>,
+    matches: Arc<DashMap<MatchId, Arc<std::sync::Mutex<Match>>>>,
+    webhook: Option<Arc<Webhook>>,
+    /// When the engine started, shared by all connections
+    started: Instant,
 }
 
 impl EngineServer {
-    fn new(client: OutsideClient, cards: Arc<HashMap<CardId, Card>>) -> Self {
+    fn new(
+        client: OutsideClient,
+        cards: Arc<HashMap<CardId, Card>>,
+        engine: Arc<EngineGames>,
+        webhook: Option<Arc<Webhook>>,
+        started: Instant,
+    ) -> Self {
         EngineServer {
             client: Arc::new(client),
             cards,
-            games: Default::default(),
+            engine,
+            matches: Default::default(),
+            webhook,
+            started,
         }
     }
 
@@ -55,54 +242,265 @@ impl EngineServer {
             client: self.client.clone(),
         }
     }
-}
 
-#[tarpc::server]
-impl Meta for EngineServer {
-    async fn create_game(self, _ctx: Context, players: Vec<Player>) -> GameId {
+    /// Sets up a game for the seats, games that fail verification are refused
+    fn new_game(
+        &self,
+        seats: &[Seat],
+        format: FormatRules,
+    ) -> Result<(GameId, GameImplV1), Vec<VerificationError>> {
         let id = GameId::new();
 
         let rand = Xoshiro256StarStar::seed_from_u64(rand::random());
 
-        let players: HashMap<_, _> = players.into_iter().map(|p| (p.id, p)).collect();
-        let order = players.keys().copied().collect();
-        let game = GameImplV1::new(id, rand, self.cards.clone(), players, order);
+        let order = seats.iter().map(|s| s.player.id).collect();
+        let players: HashMap<_, _> = seats
+            .iter()
+            .map(|s| (s.player.id, s.player.clone()))
+            .collect();
+        let game =
+            GameImplV1::new(id, rand, self.cards.clone(), players, order).with_format(format);
+        game.verify()?;
+
+        Ok((id, game))
+    }
+
+    /// Registers the game with the engine and plays it in the background
+    ///
+    /// The handle resolves once the game is over, with the error that stopped it if any.
+    fn spawn_game(
+        &self,
+        id: GameId,
+        game: GameImplV1,
+        seats: Vec<Seat>,
+    ) -> JoinHandle<Result<GameResult, GameError>> {
         let client = self.get_outside_client(id);
+        let webhook = self.webhook.clone();
+        let prompt_objects = game.prompt_objects();
+        let workload = game.workload();
+        let result = Arc::new(std::sync::Mutex::new(None));
+        let finished = result.clone();
+
+        let handle = tokio::spawn(async move {
+            let played = assert_send(play_game(game, client, webhook)).await;
+            let ended = match &played {
+                Ok(result) => {
+                    info!(?result, "Game is over");
+                    result.clone()
+                }
+                Err(e) => {
+                    error!("Encountered an error: {e}");
+                    GameResult::Abandoned
+                }
+            };
+            *finished.lock().unwrap() = Some(ended);
+            played
+        });
+
+        let info = GameInfo {
+            handle: handle.abort_handle(),
+            seats,
+            prompt_objects,
+            workload,
+            result,
+        };
+
+        self.engine.games.insert(id, info);
+
+        handle
+    }
+}
 
-        fn assert_send<'u, R>(
-            fut: impl 'u + Send + std::future::Future<Output = R>,
-        ) -> impl 'u + Send + std::future::Future<Output = R> {
-            fut
+#[tarpc::server]
+impl Meta for EngineServer {
+    async fn create_game(
+        self,
+        _ctx: Context,
+        seats: Vec<Seat>,
+        key: Option<CreationKey>,
+        format: FormatRules,
+    ) -> Result<GameId, CreateGamesError> {
+        let _guard = self.engine.creation_lock.lock().await;
+
+        let invalid = |_| CreateGamesError::InvalidGame { index: 0 };
+        let Some(key) = key else {
+            self.engine.check_capacity(1)?;
+            let (id, game) = self.new_game(&seats, format).map_err(invalid)?;
+            self.spawn_game(id, game, seats);
+            return Ok(id);
+        };
+
+        // The entry stays locked until the game exists, so a concurrent request waits for it
+        match self.engine.created.entry(key) {
+            Entry::Occupied(created) => {
+                info!(key = ?created.key(), "Game was already created");
+                Ok(*created.get())
+            }
+            Entry::Vacant(vacant) => {
+                self.engine.check_capacity(1)?;
+                let (id, game) = self.new_game(&seats, format).map_err(invalid)?;
+                self.spawn_game(id, game, seats);
+                Ok(*vacant.insert(id))
+            }
         }
+    }
 
-        let handle = tokio::spawn(async move {
-            let mut game = game;
-            let client = client;
+    async fn create_games(
+        self,
+        _ctx: Context,
+        specs: Vec<GameSpec>,
+    ) -> Result<Vec<GameId>, CreateGamesError> {
+        let _guard = self.engine.creation_lock.lock().await;
+        self.engine.check_capacity(specs.len())?;
+
+        let games = specs
+            .into_iter()
+            .enumerate()
+            .map(|(index, spec)| {
+                let (id, game) = self
+                    .new_game(&spec.seats, spec.format)
+                    .map_err(|_| CreateGamesError::InvalidGame { index })?;
+                Ok((id, game, spec.seats))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(games
+            .into_iter()
+            .map(|(id, game, seats)| {
+                self.spawn_game(id, game, seats);
+                id
+            })
+            .collect())
+    }
+
+    async fn destroy_game(self, _ctx: Context, game: GameId) {
+        if let Some((_, game)) = self.engine.games.remove(&game) {
+            info!("Aborting game");
+            game.handle.abort();
+        }
+    }
+
+    async fn get_game_status(self, _ctx: Context, game: GameId) -> Option<GameStatus> {
+        let info = self.engine.games.get(&game)?;
+        let result = info.result.lock().unwrap().clone();
+        Some(GameStatus {
+            seats: info.seats.clone(),
+            result,
+        })
+    }
+
+    async fn get_prompt_objects(
+        self,
+        _ctx: Context,
+        game: GameId,
+        player: PlayerId,
+        objects: Vec<ObjectId>,
+    ) -> Vec<Option<CardId>> {
+        match self.engine.games.get(&game) {
+            Some(info) => info.prompt_objects.details(player, &objects),
+            None => vec![None; objects.len()],
+        }
+    }
+
+    async fn create_match(
+        self,
+        _ctx: Context,
+        best_of: usize,
+        players: Vec<MatchPlayer>,
+    ) -> MatchId {
+        let id = MatchId::new();
+        let running = Arc::new(std::sync::Mutex::new(Match::new(
+            best_of,
+            players,
+            self.cards.clone(),
+        )));
+        self.matches.insert(id, running.clone());
+
+        tokio::spawn(async move {
             loop {
-                let res = assert_send(game.run(&client).boxed()).await;
+                let game_id = GameId::new();
+                let (game, seats) = {
+                    let running = running.lock().unwrap();
+                    if running.is_finished() {
+                        break;
+                    }
+                    let game = running
+                        .next_game(game_id, Xoshiro256StarStar::seed_from_u64(rand::random()));
+                    // Matches are not played by web users of their own
+                    let seats = running
+                        .players()
+                        .into_iter()
+                        .map(|player| Seat {
+                            user: String::new(),
+                            player,
+                        })
+                        .collect();
+                    (game, seats)
+                };
+
+                let played = {
+                    let _guard = self.engine.creation_lock.lock().await;
+                    if let Err(e) = self.engine.check_capacity(1) {
+                        error!("Cannot start the next game of a match: {e}");
+                        break;
+                    }
+                    self.spawn_game(game_id, game, seats)
+                };
 
-                match res {
-                    Ok(_) => (),
+                match played.await {
+                    Ok(Ok(result)) => {
+                        running.lock().unwrap().record_game(result);
+                    }
+                    Ok(Err(e)) => {
+                        error!("Encountered an error during a match: {e}");
+                        break;
+                    }
                     Err(e) => {
-                        error!("Encountered an error: {e}");
+                        info!("A game of the match was destroyed: {e}");
                         break;
                     }
                 }
             }
-        })
-        .abort_handle();
-
-        let info = GameInfo { handle };
 
-        self.games.insert(id, info);
+            info!(?id, "Match is over");
+        });
 
         id
     }
 
-    async fn destroy_game(self, _ctx: Context, game: GameId) {
-        if let Some((_, game)) = self.games.remove(&game) {
-            info!("Aborting game");
-            game.handle.abort();
+    async fn swap_sideboard_card(
+        self,
+        _ctx: Context,
+        match_id: MatchId,
+        player: PlayerId,
+        out: CardId,
+        into: CardId,
+    ) -> Result<(), SideboardError> {
+        let running = self
+            .matches
+            .get(&match_id)
+            .map(|m| m.clone())
+            .ok_or(SideboardError::UnknownMatch)?;
+        let mut running = running.lock().unwrap();
+        running.swap_sideboard_card(player, out, into)
+    }
+
+    async fn get_match_result(self, _ctx: Context, match_id: MatchId) -> Option<MatchResult> {
+        let running = self.matches.get(&match_id)?;
+        let result = running.lock().unwrap().result();
+        Some(result)
+    }
+
+    async fn diagnostics(self, _ctx: Context) -> Diagnostics {
+        Diagnostics {
+            games: self
+                .engine
+                .games
+                .iter()
+                .map(|info| info.workload.diagnostics(*info.key()))
+                .collect(),
+            matches: self.matches.len(),
+            uptime: self.started.elapsed(),
         }
     }
 }
@@ -113,6 +511,18 @@ struct Args {
     /// What interface and port to listen to
     #[clap(long)]
     listen_interface: String,
+
+    /// How many games may run at the same time, unlimited if not given
+    #[clap(long)]
+    max_games: Option<usize>,
+
+    /// Where to POST game lifecycle events as JSON
+    #[clap(long)]
+    webhook_url: Option<String>,
+
+    /// Which events to send to the webhook, all of them if not given
+    #[clap(long, value_enum, value_delimiter = ',')]
+    webhook_events: Vec<WebhookEventKind>,
 }
 
 #[tokio::main]
@@ -150,6 +560,7 @@ async fn start_server(
     server_info: Sender<ServerInfo>,
 ) {
     info!("Starting technomancy engine on {}", args.listen_interface);
+    let started = Instant::now();
     let mut conn = tarpc::serde_transport::tcp::listen(
         &args.listen_interface,
         tarpc::tokio_serde::formats::Json::default,
@@ -164,12 +575,27 @@ async fn start_server(
 
     let _ = server_info.send(info);
 
+    let engine = Arc::new(EngineGames::new(args.max_games));
+    let webhook = args.webhook_url.map(|url| {
+        Arc::new(Webhook {
+            client: reqwest::Client::new(),
+            url,
+            events: args.webhook_events,
+        })
+    });
+
     while let Some(Ok(inc)) = conn.next().await {
         let addr = inc.peer_addr().unwrap();
         info!("New connection from {addr}");
         let (server, client) = spawn_twoway(inc);
         let outside_client = OutsideClient::new(tarpc::client::Config::default(), client).spawn();
-        let engine_server = EngineServer::new(outside_client, cards.clone());
+        let engine_server = EngineServer::new(
+            outside_client,
+            cards.clone(),
+            engine.clone(),
+            webhook.clone(),
+            started,
+        );
 
         tokio::spawn(BaseChannel::with_defaults(server).execute(engine_server.serve()));
     }
@@ -177,25 +603,278 @@ async fn start_server(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::sync::Arc;
+    use std::time::Duration;
 
     use tarpc::context::Context;
+    use tarpc::server::BaseChannel;
+    use tarpc::server::Channel;
+    use technomancy_core::card::AgentPower;
+    use technomancy_core::card::AgentToughness;
+    use technomancy_core::card::BaseCardKind;
+    use technomancy_core::card::Card;
+    use technomancy_core::card::CardBehaviour;
+    use technomancy_core::card::CardId;
+    use technomancy_core::card::CardKind;
+    use technomancy_core::card::CardLayout;
+    use technomancy_core::card::SubKind;
+    use technomancy_core::event::GameEvent;
+    use technomancy_core::matches::MatchPlayer;
     use technomancy_core::meta::spawn_twoway;
+    use technomancy_core::meta::CreateGamesError;
+    use technomancy_core::meta::CreationKey;
+    use technomancy_core::meta::GameSpec;
     use technomancy_core::meta::MetaClient;
+    use technomancy_core::meta::Seat;
+    use technomancy_core::outside::ObjectList;
+    use technomancy_core::outside::Outside;
     use technomancy_core::outside::OutsideRequest;
     use technomancy_core::outside::OutsideResponse;
+    use technomancy_core::outside::PromptContext;
+    use technomancy_core::view::PlayerView;
+    use technomancy_core::GameId;
+    use technomancy_core::GameResult;
+    use technomancy_core::ObjectId;
+    use technomancy_core::Player;
+    use technomancy_core::PlayerAction;
+    use technomancy_core::PlayerId;
+    use technomancy_core::TargetId;
+    use technomancy_core::ZoneId;
     use tokio::task::JoinHandle;
     use tracing::info;
 
     use crate::start_server;
     use crate::Args;
     use crate::ServerInfo;
+    use crate::Webhook;
+    use crate::WebhookEvent;
+    use crate::WebhookEventKind;
+
+    /// Answers every prompt of every player, conceding as soon as it may act
+    #[derive(Clone)]
+    struct ConcedingBot;
+
+    #[tarpc::server]
+    impl Outside for ConcedingBot {
+        async fn get_player_keeping(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            asked_players: Vec<PlayerId>,
+        ) -> Vec<PlayerId> {
+            asked_players
+        }
+
+        async fn get_next_player_action_from(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            player_actions: Vec<PlayerAction>,
+        ) -> usize {
+            player_actions
+                .iter()
+                .position(|a| matches!(a, PlayerAction::Concede))
+                .unwrap_or(0)
+        }
+
+        async fn get_target_choices_from_given(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _source: ObjectId,
+            _name: String,
+            _choices: Vec<TargetId>,
+            count: usize,
+            _optional: bool,
+        ) -> Vec<usize> {
+            (0..count).collect()
+        }
+
+        async fn get_multiple_target_choices(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _source: ObjectId,
+            _name: String,
+            _choices: Vec<TargetId>,
+            min: usize,
+            _max: usize,
+        ) -> Vec<usize> {
+            (0..min).collect()
+        }
 
-    async fn get_server() -> (ServerInfo, JoinHandle<()>) {
+        async fn get_number_choice(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _source: ObjectId,
+            _name: String,
+            min: u64,
+            _max: u64,
+        ) -> u64 {
+            min
+        }
+
+        async fn get_library_search_choice(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _source: ObjectId,
+            _name: String,
+            _choices: ObjectList,
+        ) -> Option<usize> {
+            None
+        }
+
+        async fn get_library_arrangement(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _source: ObjectId,
+            _name: String,
+            cards: ObjectList,
+            _allow_bottom: bool,
+        ) -> (Vec<usize>, Vec<usize>) {
+            ((0..cards.len()).collect(), vec![])
+        }
+
+        async fn get_zone_choice(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _source: ObjectId,
+            _name: String,
+            _zone: ZoneId,
+            _choices: ObjectList,
+            count: usize,
+        ) -> Vec<usize> {
+            (0..count).collect()
+        }
+
+        async fn get_ordering(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _source: ObjectId,
+            _name: String,
+            items: ObjectList,
+        ) -> Vec<usize> {
+            (0..items.len()).collect()
+        }
+
+        async fn get_discard_choice(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _cards: Vec<(ObjectId, CardId)>,
+            count: usize,
+        ) -> Vec<usize> {
+            (0..count).collect()
+        }
+
+        async fn get_cards_to_bottom(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _cards: Vec<(ObjectId, CardId)>,
+            count: usize,
+        ) -> Vec<usize> {
+            (0..count).collect()
+        }
+
+        async fn get_damage_assignment(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _attacker: ObjectId,
+            _blockers: Vec<(ObjectId, CardId)>,
+            damage: u64,
+        ) -> Vec<(usize, u64)> {
+            vec![(0, damage)]
+        }
+
+        async fn get_player_passing(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+        ) -> bool {
+            true
+        }
+
+        async fn get_play_first(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+        ) -> bool {
+            true
+        }
+
+        async fn notify_event(self, _: Context, _game_id: GameId, _event: GameEvent) {}
+
+        async fn notify_error(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _player: PlayerId,
+            _error_description: String,
+        ) {
+        }
+
+        async fn confirm_action(
+            self,
+            _: Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            _player: PlayerId,
+            _preview: PlayerView,
+        ) -> bool {
+            true
+        }
+    }
+
+    async fn get_server(max_games: Option<usize>) -> (ServerInfo, JoinHandle<()>) {
+        get_server_with_cards(max_games, vec![]).await
+    }
+
+    async fn get_server_with_cards(
+        max_games: Option<usize>,
+        cards: Vec<Card>,
+    ) -> (ServerInfo, JoinHandle<()>) {
         let args = Args {
             listen_interface: "localhost:0".to_string(),
+            max_games,
+            webhook_url: None,
+            webhook_events: vec![],
         };
-        let cards = Arc::new(std::collections::HashMap::new());
+        let cards = Arc::new(cards.into_iter().map(|card| (card.id, card)).collect());
 
         let (sender, recv) = tokio::sync::oneshot::channel();
 
@@ -208,7 +887,7 @@ mod tests {
 
     #[test_log::test(tokio::test)]
     async fn check_connection() {
-        let (info, handle) = get_server().await;
+        let (info, handle) = get_server(None).await;
 
         info!(?info, "Listening");
 
@@ -218,7 +897,7 @@ mod tests {
 
     #[test_log::test(tokio::test)]
     async fn check_start_game() {
-        let (info, handle) = get_server().await;
+        let (info, handle) = get_server(None).await;
         let client_conn = tarpc::serde_transport::tcp::connect(
             info.local_addr,
             tarpc::tokio_serde::formats::Json::default,
@@ -231,13 +910,460 @@ mod tests {
 
         let client = MetaClient::new(Default::default(), meta_client).spawn();
 
+        let seats: Vec<_> = ["alice", "bob"]
+            .into_iter()
+            .map(|user| Seat {
+                user: user.to_string(),
+                player: Player {
+                    id: PlayerId::new(),
+                    initial_cards: vec![],
+                    preferences: Default::default(),
+                },
+            })
+            .collect();
+        let key = CreationKey {
+            lobby: "alice_lobby".to_string(),
+            attempt: 0,
+        };
+        let (game, again) = tokio::join!(
+            client.create_game(
+                Context::current(),
+                seats.clone(),
+                Some(key.clone()),
+                Default::default()
+            ),
+            client.create_game(
+                Context::current(),
+                seats.clone(),
+                Some(key),
+                Default::default()
+            ),
+        );
+        let game = game.unwrap().unwrap();
+        assert_eq!(again.unwrap().unwrap(), game);
+
+        let status = client
+            .get_game_status(Context::current(), game)
+            .await
+            .unwrap()
+            .unwrap();
+        let seated: Vec<_> = status
+            .seats
+            .iter()
+            .map(|s| (s.user.as_str(), s.player.id))
+            .collect();
+        assert_eq!(
+            seated,
+            vec![("alice", seats[0].player.id), ("bob", seats[1].player.id)]
+        );
+
+        handle.abort();
+
+        handle.await.unwrap_err();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn check_create_games_respects_capacity() {
+        let (info, handle) = get_server(Some(2)).await;
+        let client_conn = tarpc::serde_transport::tcp::connect(
+            info.local_addr,
+            tarpc::tokio_serde::formats::Json::default,
+        )
+        .await
+        .unwrap();
+
+        let (_outside_server, meta_client) =
+            spawn_twoway::<OutsideRequest, OutsideResponse, _, _, _>(client_conn);
+
+        let client = MetaClient::new(Default::default(), meta_client).spawn();
+
+        let error = client
+            .create_games(
+                Context::current(),
+                vec![
+                    GameSpec {
+                        seats: vec![],
+                        format: Default::default()
+                    };
+                    3
+                ],
+            )
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            CreateGamesError::NotEnoughCapacity {
+                requested: 3,
+                available: 2
+            }
+        );
+
+        let ids = client
+            .create_games(
+                Context::current(),
+                vec![
+                    GameSpec {
+                        seats: vec![],
+                        format: Default::default()
+                    };
+                    2
+                ],
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ids.len(), 2);
+
+        handle.abort();
+
+        handle.await.unwrap_err();
+    }
+
+    async fn connect(info: &ServerInfo) -> MetaClient {
+        let client_conn = tarpc::serde_transport::tcp::connect(
+            info.local_addr,
+            tarpc::tokio_serde::formats::Json::default,
+        )
+        .await
+        .unwrap();
+
+        let (_outside_server, meta_client) =
+            spawn_twoway::<OutsideRequest, OutsideResponse, _, _, _>(client_conn);
+
+        MetaClient::new(Default::default(), meta_client).spawn()
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn check_capacity_is_shared_by_all_connections() {
+        let (info, handle) = get_server(Some(2)).await;
+        let first = connect(&info).await;
+        let second = connect(&info).await;
+
+        let game = first
+            .create_game(Context::current(), vec![], None, Default::default())
+            .await
+            .unwrap()
+            .unwrap();
+        let error = second
+            .create_games(
+                Context::current(),
+                vec![
+                    GameSpec {
+                        seats: vec![],
+                        format: Default::default()
+                    };
+                    2
+                ],
+            )
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            CreateGamesError::NotEnoughCapacity {
+                requested: 2,
+                available: 1
+            }
+        );
+
+        // Games of other connections are known too
+        assert!(second
+            .get_game_status(Context::current(), game)
+            .await
+            .unwrap()
+            .is_some());
+
+        handle.abort();
+
+        handle.await.unwrap_err();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn check_create_game_respects_capacity() {
+        let (info, handle) = get_server(Some(1)).await;
+        let client = connect(&info).await;
+        let key = CreationKey {
+            lobby: "alice_lobby".to_string(),
+            attempt: 0,
+        };
+
+        let game = client
+            .create_game(
+                Context::current(),
+                vec![],
+                Some(key.clone()),
+                Default::default(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let error = client
+            .create_game(Context::current(), vec![], None, Default::default())
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            CreateGamesError::NotEnoughCapacity {
+                requested: 1,
+                available: 0
+            }
+        );
+
+        // Asking again for a game that was created does not need room for another one
+        let again = client
+            .create_game(Context::current(), vec![], Some(key), Default::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(again, game);
+
+        handle.abort();
+
+        handle.await.unwrap_err();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn check_create_game_refuses_invalid_games() {
+        let (info, handle) = get_server(None).await;
+        let client = connect(&info).await;
+
+        let seats = vec![Seat {
+            user: "alice".to_string(),
+            player: Player {
+                id: PlayerId::new(),
+                initial_cards: vec![CardId::with(uuid::Uuid::from_u128(404))],
+                preferences: Default::default(),
+            },
+        }];
+        let error = client
+            .create_game(Context::current(), seats, None, Default::default())
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error, CreateGamesError::InvalidGame { index: 0 });
+
+        handle.abort();
+
+        handle.await.unwrap_err();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn check_diagnostics_lists_running_games() {
+        let (info, handle) = get_server(None).await;
+        let client_conn = tarpc::serde_transport::tcp::connect(
+            info.local_addr,
+            tarpc::tokio_serde::formats::Json::default,
+        )
+        .await
+        .unwrap();
+
+        let (_outside_server, meta_client) =
+            spawn_twoway::<OutsideRequest, OutsideResponse, _, _, _>(client_conn);
+
+        let client = MetaClient::new(Default::default(), meta_client).spawn();
+
+        let ids = client
+            .create_games(
+                Context::current(),
+                vec![
+                    GameSpec {
+                        seats: vec![],
+                        format: Default::default()
+                    };
+                    2
+                ],
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        let diagnostics = client.diagnostics(Context::current()).await.unwrap();
+        let listed: HashSet<_> = diagnostics.games.iter().map(|g| g.game).collect();
+        assert_eq!(listed, ids.into_iter().collect());
+        assert_eq!(diagnostics.matches, 0);
+
+        handle.abort();
+
+        handle.await.unwrap_err();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn check_match_games_are_registered() {
+        let (info, handle) = get_server(None).await;
+        let client_conn = tarpc::serde_transport::tcp::connect(
+            info.local_addr,
+            tarpc::tokio_serde::formats::Json::default,
+        )
+        .await
+        .unwrap();
+
+        let (_outside_server, meta_client) =
+            spawn_twoway::<OutsideRequest, OutsideResponse, _, _, _>(client_conn);
+
+        let client = MetaClient::new(Default::default(), meta_client).spawn();
+
+        let players = (0..2)
+            .map(|_| MatchPlayer {
+                player: Player {
+                    id: PlayerId::new(),
+                    initial_cards: vec![],
+                    preferences: Default::default(),
+                },
+                sideboard: vec![],
+            })
+            .collect();
         client
-            .create_game(Context::current(), vec![])
+            .create_match(Context::current(), 3, players)
+            .await
+            .unwrap();
+
+        let game = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let diagnostics = client.diagnostics(Context::current()).await.unwrap();
+                if let Some(game) = diagnostics.games.first() {
+                    break game.game;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let status = client
+            .get_game_status(Context::current(), game)
             .await
+            .unwrap()
             .unwrap();
+        assert_eq!(status.seats.len(), 2);
+
+        client.destroy_game(Context::current(), game).await.unwrap();
+        assert!(client
+            .get_game_status(Context::current(), game)
+            .await
+            .unwrap()
+            .is_none());
 
         handle.abort();
 
         handle.await.unwrap_err();
     }
+
+    /// Plays a game from creation to its result over the real RPC path
+    ///
+    /// The web server does not talk to engines yet, so user registration, lobbies and the
+    /// WebSocket are left out and the game is created through the Meta protocol the way the web
+    /// server would. Run it with `cargo test -- --ignored`.
+    #[ignore]
+    #[test_log::test(tokio::test)]
+    async fn check_game_is_played_to_its_result() {
+        let agent = Card {
+            id: CardId::with(uuid::Uuid::from_u128(1)),
+            version: 1,
+            behaviour: CardBehaviour {
+                cost: None,
+                kind: vec![CardKind {
+                    kind: BaseCardKind::Agent {
+                        subkind: SubKind::new("Mercenary"),
+                        power: AgentPower::Fixed(3),
+                        toughness: AgentToughness::Fixed(6),
+                    },
+                }],
+                effects: vec![],
+                keywords: vec![],
+                alternative_costs: vec![],
+            },
+            layout: CardLayout::Single,
+        };
+        let deck = vec![agent.id; 20];
+        let (info, handle) = get_server_with_cards(None, vec![agent]).await;
+        let client_conn = tarpc::serde_transport::tcp::connect(
+            info.local_addr,
+            tarpc::tokio_serde::formats::Json::default,
+        )
+        .await
+        .unwrap();
+
+        let (outside_server, meta_client) =
+            spawn_twoway::<OutsideRequest, OutsideResponse, _, _, _>(client_conn);
+        tokio::spawn(BaseChannel::with_defaults(outside_server).execute(ConcedingBot.serve()));
+
+        let client = MetaClient::new(Default::default(), meta_client).spawn();
+
+        let seats: Vec<_> = ["alice", "bob"]
+            .into_iter()
+            .map(|user| Seat {
+                user: user.to_string(),
+                player: Player {
+                    id: PlayerId::new(),
+                    initial_cards: deck.clone(),
+                    preferences: Default::default(),
+                },
+            })
+            .collect();
+        let game = client
+            .create_game(Context::current(), seats.clone(), None, Default::default())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let status = client
+                    .get_game_status(Context::current(), game)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                if let Some(result) = status.result {
+                    break result;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // The first player concedes as soon as they may act
+        assert_eq!(
+            result,
+            GameResult::Conceded {
+                winners: vec![seats[1].player.id],
+                conceded: vec![seats[0].player.id],
+            }
+        );
+
+        handle.abort();
+
+        handle.await.unwrap_err();
+    }
+
+    #[test]
+    fn check_webhook_event_json() {
+        let game = GameId::new();
+        let winner = PlayerId::new();
+        let event = WebhookEvent::Finished {
+            game,
+            result: GameResult::Won {
+                winners: vec![winner],
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "event": "finished",
+                "game": game,
+                "result": { "Won": { "winners": [winner] } },
+            })
+        );
+
+        let webhook = Webhook {
+            client: reqwest::Client::new(),
+            url: String::new(),
+            events: vec![WebhookEventKind::Finished, WebhookEventKind::Error],
+        };
+        assert!(webhook.wants(event.kind()));
+        assert!(!webhook.wants(WebhookEvent::Created { game }.kind()));
+    }
 }
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+use tarpc::server::BaseChannel;
+use tarpc::server::Channel;
+use tarpc::server::Serve;
+use technomancy_core::card::Card;
+use technomancy_core::card::CardId;
+use technomancy_core::card::Cost;
+use technomancy_core::card::SubKindRegistry;
+use technomancy_core::outside::Outside;
+use technomancy_core::outside::OutsideClient;
+use technomancy_core::outside::OutsideRequest;
+use technomancy_core::outside::ServeOutside;
+use technomancy_core::FormatRules;
+use technomancy_core::GameId;
+use technomancy_core::Player;
+use technomancy_core::PlayerId;
+use technomancy_core::ZoneKey;
+use technomancy_core::ZoneMetadata;
+
+use crate::outside::OutsideGameClient;
+use crate::GameImplV1;
+use crate::DEFAULT_EFFECT_TIME_LIMIT;
+
+/// Sets up games for programs embedding the engine
+///
+/// The same builder can be used to create any number of games with the same cards and
+/// configuration. Everything that happens in a game is sent to the outside it is run with, see
+/// [`spawn_outside`] to use an in-process one.
+#[derive(Debug)]
+pub struct EngineBuilder {
+    cards: Arc<HashMap<CardId, Card>>,
+    format: FormatRules,
+    subkinds: Arc<SubKindRegistry>,
+    custom_zones: HashMap<ZoneKey, ZoneMetadata>,
+    teams: Vec<Vec<PlayerId>>,
+    effect_time_limit: Duration,
+    default_choice_on_disconnect: bool,
+    seed: Option<u64>,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        EngineBuilder {
+            cards: Default::default(),
+            format: Default::default(),
+            subkinds: Arc::new(SubKindRegistry::base()),
+            custom_zones: Default::default(),
+            teams: vec![],
+            effect_time_limit: DEFAULT_EFFECT_TIME_LIMIT,
+            default_choice_on_disconnect: false,
+            seed: None,
+        }
+    }
+}
+
+impl EngineBuilder {
+    pub fn new() -> EngineBuilder {
+        Self::default()
+    }
+
+    /// The cards games can be played with, their costs are put in their canonical form
+    pub fn cards(mut self, cards: impl IntoIterator<Item = Card>) -> EngineBuilder {
+        self.cards = Arc::new(
+            cards
+                .into_iter()
+                .map(|mut c| {
+                    c.behaviour.cost = Cost::canonical(c.behaviour.cost.take());
+                    (c.id, c)
+                })
+                .collect(),
+        );
+        self
+    }
+
+    pub fn format(mut self, format: FormatRules) -> EngineBuilder {
+        self.format = format;
+        self
+    }
+
+    pub fn subkinds(mut self, subkinds: Arc<SubKindRegistry>) -> EngineBuilder {
+        self.subkinds = subkinds;
+        self
+    }
+
+    pub fn custom_zones(mut self, custom_zones: HashMap<ZoneKey, ZoneMetadata>) -> EngineBuilder {
+        self.custom_zones = custom_zones;
+        self
+    }
+
+    /// Lets the given players play in teams, see [`GameImplV1::with_teams`]
+    pub fn teams(mut self, teams: Vec<Vec<PlayerId>>) -> EngineBuilder {
+        self.teams = teams;
+        self
+    }
+
+    pub fn effect_time_limit(mut self, limit: Duration) -> EngineBuilder {
+        self.effect_time_limit = limit;
+        self
+    }
+
+    pub fn default_choice_on_disconnect(mut self, enabled: bool) -> EngineBuilder {
+        self.default_choice_on_disconnect = enabled;
+        self
+    }
+
+    /// Makes games reproducible, otherwise every game is seeded randomly
+    pub fn seed(mut self, seed: u64) -> EngineBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Creates a new game, the players take their turns in the given order
+    pub fn build(&self, players: Vec<Player>) -> GameImplV1 {
+        let rand = Xoshiro256StarStar::seed_from_u64(self.seed.unwrap_or_else(rand::random));
+        let order = players.iter().map(|p| p.id).collect();
+        let players = players.into_iter().map(|p| (p.id, p)).collect();
+
+        GameImplV1::new(GameId::new(), rand, self.cards.clone(), players, order)
+            .with_format(self.format.clone())
+            .with_subkinds(self.subkinds.clone())
+            .with_custom_zones(self.custom_zones.clone())
+            .with_teams(self.teams.clone())
+            .with_effect_time_limit(self.effect_time_limit)
+            .with_default_choice_on_disconnect(self.default_choice_on_disconnect)
+    }
+
+    /// Creates a new game together with an in-process outside to run it with
+    pub fn build_with_outside<S>(
+        &self,
+        players: Vec<Player>,
+        outside: S,
+    ) -> (GameImplV1, OutsideGameClient)
+    where
+        S: Outside + Clone + Send + 'static,
+        <ServeOutside<S> as Serve<OutsideRequest>>::Fut: Send + 'static,
+    {
+        let game = self.build(players);
+        let client = spawn_outside(game.id(), outside);
+        (game, client)
+    }
+}
+
+/// Serves `outside` in the current process, returning a client a game can be run with
+///
+/// This has to be called from within a tokio runtime.
+pub fn spawn_outside<S>(game_id: GameId, outside: S) -> OutsideGameClient
+where
+    S: Outside + Clone + Send + 'static,
+    <ServeOutside<S> as Serve<OutsideRequest>>::Fut: Send + 'static,
+{
+    let (client_transport, server_transport) = tarpc::transport::channel::unbounded();
+
+    tokio::spawn(BaseChannel::with_defaults(server_transport).execute(outside.serve()));
+
+    let client = OutsideClient::new(tarpc::client::Config::default(), client_transport).spawn();
+    OutsideGameClient::new(game_id, Arc::new(client))
+}
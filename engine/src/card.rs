@@ -1,7 +1,25 @@
+use technomancy_core::card::DynamicStat;
+use technomancy_core::Game;
+use technomancy_core::GameObject;
+use technomancy_core::GameState;
+use technomancy_core::ZoneId;
+
+/// For agents whose stat is "equal to the number of cards in your hand"
+#[derive(Debug)]
+pub struct CardsInHand;
+
+impl DynamicStat for CardsInHand {
+    fn compute(&self, _game: &Game, state: &GameState, agent: &GameObject) -> u64 {
+        agent
+            .controller
+            .and_then(|player| state.zones.get(&ZoneId::Hand(player)))
+            .map_or(0, |hand| hand.objects.len() as u64)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use technomancy_core::card::AgentPower;
-    use technomancy_core::card::AgentSubKind;
     use technomancy_core::card::AgentToughness;
     use technomancy_core::card::BaseCardKind;
     use technomancy_core::card::Card;
@@ -9,7 +27,9 @@ mod tests {
     use technomancy_core::card::CardEffect;
     use technomancy_core::card::CardId;
     use technomancy_core::card::CardKind;
+    use technomancy_core::card::CardLayout;
     use technomancy_core::card::Cost;
+    use technomancy_core::card::SubKind;
     use technomancy_core::card::TriggeredCardEffect;
     use technomancy_core::effect::Effect;
     use technomancy_core::effect::EffectTrigger;
@@ -20,23 +40,28 @@ mod tests {
     fn _simple_cards() {
         let simple_agent = Card {
             id: CardId::with(uuid::uuid!("33505f5e-dce1-4b29-914d-748375d79303")),
+            version: 1,
             behaviour: CardBehaviour {
                 cost: Some(Cost {
                     ..Default::default()
                 }),
                 kind: vec![CardKind {
                     kind: BaseCardKind::Agent {
-                        subkind: AgentSubKind::Mercenary,
+                        subkind: SubKind::new("Mercenary"),
                         power: AgentPower::Fixed(3),
                         toughness: AgentToughness::Fixed(6),
                     },
                 }],
                 effects: vec![],
+                keywords: vec![],
+                alternative_costs: vec![],
             },
+            layout: CardLayout::Single,
         };
 
         let simple_quickhack = Card {
             id: CardId::with(uuid::uuid!("02663fb0-7eb5-4d4a-ad7f-a9397b7d7b13")),
+            version: 1,
             behaviour: CardBehaviour {
                 cost: Some(Cost {
                     corp1_scrip: 1,
@@ -49,7 +74,10 @@ mod tests {
                     trigger: EffectTrigger::OnResolve,
                     effects: vec![Effect::Instant(Box::new(DealDamage(3)))],
                 })],
+                keywords: vec![],
+                alternative_costs: vec![],
             },
+            layout: CardLayout::Single,
         };
     }
 }
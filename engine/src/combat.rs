@@ -0,0 +1,201 @@
+//! Combat between agents
+//!
+//! Agents attack with [`PlayerAction::Attack`](technomancy_core::PlayerAction::Attack), this holds
+//! the rules of the fight that follows once blockers were declared and damage was assigned.
+
+use std::collections::HashMap;
+
+use technomancy_core::GameError;
+use technomancy_core::ObjectId;
+
+/// An agent taking part in a fight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Combatant {
+    pub object: ObjectId,
+    pub power: u64,
+    pub toughness: u64,
+    /// Whether it has [`Keyword::Overclocked`](technomancy_core::card::Keyword::Overclocked)
+    pub overclocked: bool,
+}
+
+/// The two sub-steps combat damage is dealt in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageStep {
+    /// Only overclocked agents deal damage
+    Overclocked,
+    /// All other agents that are still alive deal damage
+    Regular,
+}
+
+/// Deals the combat damage between an attacker and its blockers
+///
+/// `blockers` come with the damage the attacker assigned to them. Agents that were dealt lethal
+/// damage die at the end of each step, and do not deal damage in a later one. Returns the agents
+/// that died, with the step they died in.
+pub fn fight(attacker: Combatant, blockers: &[(Combatant, u64)]) -> Vec<(DamageStep, ObjectId)> {
+    let mut damage: HashMap<ObjectId, u64> = HashMap::new();
+    let mut deaths: Vec<(DamageStep, ObjectId)> = vec![];
+
+    for step in [DamageStep::Overclocked, DamageStep::Regular] {
+        let deals = |c: &Combatant| {
+            c.overclocked == (step == DamageStep::Overclocked)
+                && !deaths.iter().any(|(_, dead)| *dead == c.object)
+        };
+
+        let mut dealt = vec![];
+        if deals(&attacker) {
+            dealt.extend(blockers.iter().map(|(b, assigned)| (b.object, *assigned)));
+        }
+        dealt.extend(
+            blockers
+                .iter()
+                .filter(|(b, _)| deals(b))
+                .map(|(b, _)| (attacker.object, b.power)),
+        );
+        for (object, amount) in dealt {
+            *damage.entry(object).or_default() += amount;
+        }
+
+        let died: Vec<_> = std::iter::once(&attacker)
+            .chain(blockers.iter().map(|(b, _)| b))
+            .filter(|c| !deaths.iter().any(|(_, dead)| *dead == c.object))
+            .filter(|c| damage.get(&c.object).copied().unwrap_or(0) >= c.toughness)
+            .map(|c| (step, c.object))
+            .collect();
+        deaths.extend(died);
+    }
+
+    deaths
+}
+
+/// Checks the damage an attacker assigns to its ordered blockers
+///
+/// `blockers` are in the chosen order together with the damage that is lethal to them. All of
+/// the attacker's `damage` has to be assigned, and a blocker only gets damage once every blocker
+/// before it got lethal damage.
+pub fn validate_damage_assignment(
+    blockers: &[(ObjectId, u64)],
+    damage: u64,
+    assigned: &[u64],
+) -> Result<(), GameError> {
+    if assigned.len() != blockers.len() {
+        return Err(GameError::InvalidChoiceAmount {
+            expected: blockers.len(),
+            received: assigned.len(),
+        });
+    }
+
+    let total = assigned.iter().try_fold(0u64, |sum, d| sum.checked_add(*d));
+    if total != Some(damage) {
+        return Err(GameError::InvalidDamageTotal {
+            expected: damage,
+            assigned: total.unwrap_or(u64::MAX),
+        });
+    }
+
+    let mut all_lethal = true;
+    for ((blocker, lethal), assigned) in blockers.iter().zip(assigned) {
+        if !all_lethal && *assigned > 0 {
+            return Err(GameError::DamageAssignedOutOfOrder { blocker: *blocker });
+        }
+        all_lethal &= assigned >= lethal;
+    }
+
+    Ok(())
+}
+
+/// Assigns lethal damage to the blockers in order, the last one gets whatever is left
+pub fn default_damage_assignment(lethal: &[u64], damage: u64) -> Vec<u64> {
+    let mut left = damage;
+    let mut assigned: Vec<u64> = lethal
+        .iter()
+        .map(|lethal| {
+            let dealt = left.min(*lethal);
+            left -= dealt;
+            dealt
+        })
+        .collect();
+    if let Some(last) = assigned.last_mut() {
+        *last += left;
+    }
+    assigned
+}
+
+#[cfg(test)]
+mod tests {
+    use technomancy_core::GameError;
+    use technomancy_core::ObjectId;
+
+    use super::default_damage_assignment;
+    use super::fight;
+    use super::validate_damage_assignment;
+    use super::Combatant;
+    use super::DamageStep;
+
+    #[test]
+    fn check_damage_assignment_validation() {
+        let (first, second) = (
+            ObjectId(uuid::Uuid::from_u128(1)),
+            ObjectId(uuid::Uuid::from_u128(2)),
+        );
+        let blockers = [(first, 2), (second, 3)];
+
+        assert!(validate_damage_assignment(&blockers, 4, &[2, 2]).is_ok());
+        assert!(validate_damage_assignment(&blockers, 4, &[4, 0]).is_ok());
+        assert!(matches!(
+            validate_damage_assignment(&blockers, 4, &[1, 3]),
+            Err(GameError::DamageAssignedOutOfOrder { blocker }) if blocker == second
+        ));
+        assert!(matches!(
+            validate_damage_assignment(&blockers, 4, &[2, 1]),
+            Err(GameError::InvalidDamageTotal {
+                expected: 4,
+                assigned: 3
+            })
+        ));
+        assert!(matches!(
+            validate_damage_assignment(&blockers, 4, &[4]),
+            Err(GameError::InvalidChoiceAmount { .. })
+        ));
+
+        assert_eq!(default_damage_assignment(&[2, 3], 4), vec![2, 2]);
+        assert_eq!(default_damage_assignment(&[2, 3], 7), vec![2, 5]);
+    }
+
+    #[test]
+    fn check_overclocked_agents_strike_first() {
+        let agent = |n, power, toughness, overclocked| Combatant {
+            object: ObjectId(uuid::Uuid::from_u128(n)),
+            power,
+            toughness,
+            overclocked,
+        };
+        let attacker = agent(1, 3, 2, true);
+        let (small, big) = (agent(2, 5, 2, false), agent(3, 2, 4, false));
+
+        // The small blocker dies before it can strike back
+        assert_eq!(
+            fight(attacker, &[(small, 3)]),
+            vec![(DamageStep::Overclocked, small.object)]
+        );
+
+        // The big blocker survives and kills the attacker in the regular step
+        assert_eq!(
+            fight(attacker, &[(big, 3)]),
+            vec![(DamageStep::Regular, attacker.object)]
+        );
+
+        // Without the keyword everyone strikes at once
+        let attacker = Combatant {
+            overclocked: false,
+            ..attacker
+        };
+        assert_eq!(
+            fight(attacker, &[(small, 3)]),
+            vec![
+                (DamageStep::Regular, attacker.object),
+                (DamageStep::Regular, small.object)
+            ]
+        );
+    }
+}
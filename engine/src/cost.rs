@@ -0,0 +1,118 @@
+//! Changing what cards cost, see [`CostModifier`]
+
+use technomancy_core::card::CardBehaviour;
+use technomancy_core::card::CardEffect;
+use technomancy_core::card::Cost;
+use technomancy_core::card::ScripKind;
+use technomancy_core::effect::ContinuousEffect;
+use technomancy_core::effect::CostModifier;
+use technomancy_core::effect::Effect;
+use technomancy_core::Game;
+use technomancy_core::GameAtom;
+use technomancy_core::GameError;
+use technomancy_core::GameState;
+use technomancy_core::PlayerId;
+
+/// The cost modifiers of a card's static effects
+fn cost_modifiers(behaviour: &CardBehaviour) -> impl Iterator<Item = &CostModifier> {
+    behaviour.effects.iter().filter_map(|e| match e {
+        CardEffect::Static(static_effect) => match &static_effect.effect {
+            Effect::Continuous(ContinuousEffect::ModifyCost(modifier)) => Some(modifier),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// The cost of `card`, or the played half of it, when `player` plays it, after all cost modifiers
+/// on the battlefield and emblems
+///
+/// Increases are applied before reductions, and reductions never make a cost negative.
+pub fn modified_cost(game: &Game, player: PlayerId, card: &CardBehaviour, cost: Cost) -> Cost {
+    let changes: Vec<i64> = game
+        .latest_gamestate()
+        .effect_sources()
+        .filter_map(|o| Some((o.controller?, o.behaviour(&game.cards)?)))
+        .flat_map(|(controller, source)| cost_modifiers(source).map(move |m| (controller, m)))
+        .filter(|(controller, modifier)| {
+            modifier.kind.map_or(true, |kind| card.is_kind(kind))
+                && modifier
+                    .player
+                    .map_or(true, |relative| relative.holds(game, player, *controller))
+        })
+        .map(|(_, modifier)| modifier.any_scrip)
+        .collect();
+
+    let increase: u64 = changes
+        .iter()
+        .filter(|c| **c > 0)
+        .map(|c| c.unsigned_abs())
+        .sum();
+    let reduction: u64 = changes
+        .iter()
+        .filter(|c| **c < 0)
+        .map(|c| c.unsigned_abs())
+        .sum();
+
+    Cost {
+        any_scrip: cost
+            .any_scrip
+            .saturating_add(increase)
+            .saturating_sub(reduction),
+        ..cost
+    }
+}
+
+/// The atoms spending the scrip `player` pays `cost` with
+///
+/// The scrip of each kind is paid with that kind, scrip of any kind with whatever is left over in
+/// the order of [`ScripKind::ALL`]. X has to be fixed already, see [`Cost::with_variable`].
+pub fn payment(
+    state: &GameState,
+    player: PlayerId,
+    cost: &Cost,
+) -> Result<Vec<GameAtom>, GameError> {
+    let cannot_afford = || GameError::CannotAfford {
+        player,
+        needed: cost.total(),
+        available: ScripKind::ALL
+            .iter()
+            .map(|kind| state.available_scrip_of(player, *kind))
+            .fold(0, u64::saturating_add),
+    };
+
+    let mut any = cost.any_scrip;
+    let mut atoms = vec![];
+    for kind in ScripKind::ALL {
+        let available = state.available_scrip_of(player, kind);
+        let fixed = cost.scrip_of(kind);
+        if fixed > available {
+            return Err(cannot_afford());
+        }
+        let extra = any.min(available - fixed);
+        any -= extra;
+        if fixed + extra > 0 {
+            atoms.push(GameAtom::SpendScrip {
+                player,
+                kind,
+                amount: fixed + extra,
+            });
+        }
+    }
+
+    if any > 0 {
+        return Err(cannot_afford());
+    }
+    Ok(atoms)
+}
+
+/// Whether `player` could pay the scrip cost of `card` right now, with X as zero
+pub fn can_afford(game: &Game, player: PlayerId, card: &CardBehaviour) -> bool {
+    let cost = card
+        .cost
+        .as_ref()
+        .map(|c| c.with_variable(0))
+        .unwrap_or_default();
+    let cost = modified_cost(game, player, card, cost);
+    payment(game.latest_gamestate(), player, &cost).is_ok()
+}
@@ -0,0 +1,270 @@
+//! Stepping through the game states of a recorded game
+//!
+//! Meant for developers chasing down rules interactions, see the `debugger` binary for an
+//! interactive frontend.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use technomancy_core::card::ScripKind;
+use technomancy_core::schema::Replay;
+use technomancy_core::Game;
+use technomancy_core::GameAtom;
+use technomancy_core::GameError;
+use technomancy_core::GameObject;
+use technomancy_core::GameStage;
+use technomancy_core::GameState;
+use technomancy_core::ObjectId;
+use technomancy_core::Phase;
+use technomancy_core::PlayerId;
+use technomancy_core::ZoneId;
+
+use crate::GameImplV1;
+
+/// A recorded game together with the index of the game state being looked at
+#[derive(Debug)]
+pub struct Timeline {
+    game: Game,
+    position: usize,
+}
+
+impl Timeline {
+    pub fn new(game: Game) -> Timeline {
+        Timeline { game, position: 0 }
+    }
+
+    /// Rebuilds all game states by applying the replay to the first game state of `game`
+    pub fn from_replay(mut game: Game, replay: Replay) -> Result<Timeline, GameError> {
+        game.game_states = vec![game.first_gamestate().clone()];
+        game.first_state = 0;
+        game.snapshots.clear();
+        game.compaction = None;
+        game.history.clear();
+
+        // Replays are looked at with whatever rules the debugger was built with
+        let mut game_impl = GameImplV1::restore_migrated(game, Arc::default(), |_, _| ());
+        for (_, atoms) in replay {
+            game_impl.apply_atoms(atoms)?;
+        }
+
+        Ok(Timeline::new(game_impl.game))
+    }
+
+    pub fn len(&self) -> usize {
+        self.game.game_states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.game.game_states.is_empty()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn current(&self) -> &GameState {
+        &self.game.game_states[self.position]
+    }
+
+    pub fn state(&self, idx: usize) -> Option<&GameState> {
+        self.game.game_states.get(idx)
+    }
+
+    /// Moves to the given game state, returns whether it exists
+    pub fn seek(&mut self, idx: usize) -> bool {
+        if idx >= self.len() {
+            return false;
+        }
+        self.position = idx;
+        true
+    }
+
+    pub fn forward(&mut self) -> bool {
+        self.seek(self.position + 1)
+    }
+
+    pub fn backward(&mut self) -> bool {
+        self.position
+            .checked_sub(1)
+            .is_some_and(|idx| self.seek(idx))
+    }
+
+    /// The atoms that were applied to the current game state to produce the next one
+    pub fn atoms(&self) -> Option<&[GameAtom]> {
+        self.game
+            .history
+            .iter()
+            .rev()
+            .find(|(idx, _)| *idx == self.position)
+            .map(|(_, atoms)| atoms.as_slice())
+    }
+
+    /// The objects of the current game state which carry choices, with the zone they are in
+    pub fn objects_with_choices(&self) -> Vec<(ZoneId, &GameObject)> {
+        self.current()
+            .zones
+            .iter()
+            .flat_map(|(zone, z)| z.objects.iter().map(|o| (*zone, o)))
+            .filter(|(_, o)| !o.choices.is_empty())
+            .collect()
+    }
+
+    /// What changed between two game states, `None` if either does not exist
+    pub fn diff(&self, from: usize, to: usize) -> Option<StateDiff> {
+        Some(StateDiff::between(self.state(from)?, self.state(to)?))
+    }
+}
+
+/// The objects that entered or left a zone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneDiff {
+    pub zone: ZoneId,
+    pub added: Vec<ObjectId>,
+    pub removed: Vec<ObjectId>,
+}
+
+/// The differences between two game states
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateDiff {
+    pub game_stage: Option<(GameStage, GameStage)>,
+    pub phase: Option<(Phase, Phase)>,
+    pub active_player_order: Option<(Vec<PlayerId>, Vec<PlayerId>)>,
+    pub zones: Vec<ZoneDiff>,
+    /// The scrip pools that changed, by player and kind
+    pub scrip: Vec<(PlayerId, ScripKind, u64, u64)>,
+}
+
+impl StateDiff {
+    pub fn between(from: &GameState, to: &GameState) -> StateDiff {
+        fn changed<T: PartialEq + Clone>(from: &T, to: &T) -> Option<(T, T)> {
+            (from != to).then(|| (from.clone(), to.clone()))
+        }
+
+        let zones: Vec<_> = from
+            .zones
+            .keys()
+            .chain(to.zones.keys())
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|zone| {
+                let ids = |state: &GameState| -> Vec<ObjectId> {
+                    state
+                        .zones
+                        .get(&zone)
+                        .map(|z| z.objects.iter().map(|o| o.id).collect())
+                        .unwrap_or_default()
+                };
+                let (before, after) = (ids(from), ids(to));
+                let added: Vec<_> = after
+                    .iter()
+                    .filter(|o| !before.contains(o))
+                    .copied()
+                    .collect();
+                let removed: Vec<_> = before
+                    .iter()
+                    .filter(|o| !after.contains(o))
+                    .copied()
+                    .collect();
+
+                (!added.is_empty() || !removed.is_empty()).then_some(ZoneDiff {
+                    zone,
+                    added,
+                    removed,
+                })
+            })
+            .collect();
+
+        let pools: HashSet<_> = from
+            .scrip
+            .iter()
+            .chain(to.scrip.iter())
+            .flat_map(|(player, pool)| pool.keys().map(|kind| (*player, *kind)))
+            .collect();
+        let scrip = pools
+            .into_iter()
+            .map(|(player, kind)| {
+                let (before, after) = (from.scrip_of(player, kind), to.scrip_of(player, kind));
+                (player, kind, before, after)
+            })
+            .filter(|(_, _, before, after)| before != after)
+            .collect();
+
+        StateDiff {
+            game_stage: changed(&from.game_stage, &to.game_stage),
+            phase: changed(&from.phase, &to.phase),
+            active_player_order: changed(&from.active_player_order, &to.active_player_order),
+            zones,
+            scrip,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == StateDiff::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use technomancy_core::card::CardId;
+    use technomancy_core::GameAtom;
+    use technomancy_core::GameId;
+    use technomancy_core::Player;
+    use technomancy_core::PlayerId;
+    use technomancy_core::ZoneId;
+
+    use super::Timeline;
+    use crate::GameImplV1;
+
+    #[test]
+    fn check_timeline_steps_and_diffs() {
+        let player = Player {
+            id: PlayerId::new(),
+            initial_cards: vec![CardId::with(uuid::Uuid::from_u128(1)); 3],
+            preferences: Default::default(),
+        };
+        let id = player.id;
+        let mut game_impl = GameImplV1::new(
+            GameId::new(),
+            Xoshiro256StarStar::seed_from_u64(1337),
+            Arc::default(),
+            HashMap::from([(id, player)]),
+            vec![id],
+        );
+        let drawn = game_impl.latest_gamestate().zones[&ZoneId::Library(id)]
+            .objects
+            .last()
+            .unwrap()
+            .id;
+        game_impl
+            .apply_atoms(vec![GameAtom::DrawCards {
+                player: id,
+                count: 1,
+            }])
+            .unwrap();
+
+        let replay = game_impl.game.history.clone();
+        let mut timeline = Timeline::from_replay(game_impl.game.clone(), replay).unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert!(!timeline.backward());
+        assert_eq!(timeline.atoms().unwrap().len(), 1);
+        assert!(timeline.forward());
+        assert!(!timeline.forward());
+        assert_eq!(timeline.position(), 1);
+
+        let diff = timeline.diff(0, 1).unwrap();
+        let hand = diff
+            .zones
+            .iter()
+            .find(|z| z.zone == ZoneId::Hand(id))
+            .unwrap();
+        assert_eq!(hand.added, vec![drawn]);
+        assert!(timeline.diff(1, 1).unwrap().is_empty());
+        assert!(timeline.diff(0, 2).is_none());
+    }
+}
@@ -1,14 +1,885 @@
+use std::collections::HashMap;
+
+use technomancy_core::card::ActivatedCardEffect;
+use technomancy_core::card::BaseCardKindTag;
+use technomancy_core::card::CardEffect;
+use technomancy_core::card::CardId;
+use technomancy_core::card::Cost;
+use technomancy_core::card::ScripKind;
+use technomancy_core::card::TriggeredCardEffect;
+use technomancy_core::effect::DiscardChooser;
+use technomancy_core::effect::Effect;
+use technomancy_core::effect::EffectInfo;
+use technomancy_core::effect::EffectInfoRequest;
+use technomancy_core::effect::EffectTrigger;
+use technomancy_core::effect::ExecuteFailure;
+use technomancy_core::effect::InstantEffect;
+use technomancy_core::effect::TargetRestriction;
+use technomancy_core::EffectDuration;
+use technomancy_core::Game;
+use technomancy_core::GameAtom;
+use technomancy_core::ObjectId;
+use technomancy_core::TargetId;
+use technomancy_core::TemporaryModifier;
+use technomancy_core::ZoneId;
+use technomancy_core::ZoneKind;
+
+/// Where a card found by [`SearchLibrary`] is put
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDestination {
+    Hand,
+    Battlefield,
+    Discard,
+}
+
+/// For effects that say "Search your library for a card, reveal it, put it into your hand, then
+/// shuffle"
+///
+/// The found card is revealed to all players before it is moved. The library is shuffled even if
+/// no card was found.
+#[derive(Debug)]
+pub struct SearchLibrary {
+    pub restriction: Option<TargetRestriction>,
+    pub destination: SearchDestination,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for SearchLibrary {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("card"),
+            EffectInfoRequest::LibrarySearch {
+                restriction: self.restriction.clone(),
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        let mut atoms = vec![];
+        match info.get("card") {
+            Some(EffectInfo::LibraryCard(object)) => {
+                let to = match self.destination {
+                    SearchDestination::Hand => ZoneId::Hand(player),
+                    SearchDestination::Battlefield => ZoneId::Battlefield,
+                    SearchDestination::Discard => ZoneId::Discard(player),
+                };
+                atoms.push(GameAtom::RevealObject { object: *object });
+                atoms.push(GameAtom::MoveObject {
+                    object: *object,
+                    from: game.library_of(player),
+                    to,
+                });
+            }
+            Some(_) => {
+                return Err(ExecuteFailure::InvalidEffectInfo {
+                    name: "card".into(),
+                })
+            }
+            None => (),
+        }
+        atoms.push(GameAtom::ShuffleLibrary { player });
+
+        Ok(atoms)
+    }
+}
+
+/// For effects that say "Look at the top X cards of your library, put any number of them on the
+/// bottom and the rest back in any order"
+///
+/// Without `allow_bottom` all cards are put back on top.
+#[derive(Debug)]
+pub struct LookAtTopOfLibrary {
+    pub count: usize,
+    pub allow_bottom: bool,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for LookAtTopOfLibrary {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("cards"),
+            EffectInfoRequest::LibraryArrangement {
+                count: self.count,
+                allow_bottom: self.allow_bottom,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        let Some(EffectInfo::LibraryArrangement { top, bottom }) = info.get("cards") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "cards".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::ArrangeLibrary {
+            player,
+            top: top.clone(),
+            bottom: bottom.clone(),
+        }])
+    }
+}
+
+/// For effects that say "Put X cards from your discard on top of your library in any order"
+#[derive(Debug)]
+pub struct PutOnTopOfLibrary {
+    pub zone: ZoneKind,
+    pub count: usize,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for PutOnTopOfLibrary {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [
+            (
+                String::from("cards"),
+                EffectInfoRequest::ChooseFromZone {
+                    zone: self.zone,
+                    target: None,
+                    count: self.count,
+                },
+            ),
+            (
+                String::from("order"),
+                EffectInfoRequest::Ordering {
+                    of: String::from("cards"),
+                },
+            ),
+        ]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        let Some(EffectInfo::Ordering(objects)) = info.get("order") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "order".into(),
+            });
+        };
+
+        // The end of the library is its top, so the first object is put there last
+        let from = ZoneId::of(self.zone, player);
+        Ok(objects
+            .iter()
+            .rev()
+            .map(|object| GameAtom::MoveObject {
+                object: *object,
+                from,
+                to: game.library_of(player),
+            })
+            .collect())
+    }
+}
+
+/// For effects that say "Discard X cards"
+#[derive(Debug)]
+pub struct DiscardCards(pub usize);
+
+#[async_trait::async_trait]
+impl InstantEffect for DiscardCards {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("cards"),
+            EffectInfoRequest::Discard { count: self.0 },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        let Some(EffectInfo::Discarded(objects)) = info.get("cards") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "cards".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::DiscardCards {
+            player,
+            objects: objects.clone(),
+        }])
+    }
+}
+
+/// For effects that say "Target player discards X cards", where `chooser` picks them
+///
+/// With [`DiscardChooser::Controller`] this reads "Target player reveals their hand, you choose X
+/// cards from it, that player discards them".
+#[derive(Debug)]
+pub struct TargetDiscards {
+    pub count: usize,
+    pub chooser: DiscardChooser,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for TargetDiscards {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [
+            (
+                String::from("target"),
+                EffectInfoRequest::SingleTarget {
+                    restriction: Some(TargetRestriction::Player),
+                    optional: false,
+                },
+            ),
+            (
+                String::from("cards"),
+                EffectInfoRequest::TargetDiscard {
+                    target: String::from("target"),
+                    count: self.count,
+                    chooser: self.chooser,
+                },
+            ),
+        ]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        _source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::SingleTarget(TargetId::Player(player))) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+        let Some(EffectInfo::Discarded(objects)) = info.get("cards") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "cards".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::DiscardCards {
+            player: *player,
+            objects: objects.clone(),
+        }])
+    }
+}
+
+/// For effects that say "Exile N cards of your choice from target player's hand"
+///
+/// The controller only sees the cards if they may look at the zone.
+#[derive(Debug)]
+pub struct ExileFromZone {
+    pub zone: ZoneKind,
+    pub count: usize,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for ExileFromZone {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [
+            (
+                String::from("target"),
+                EffectInfoRequest::SingleTarget {
+                    restriction: Some(TargetRestriction::Player),
+                    optional: false,
+                },
+            ),
+            (
+                String::from("cards"),
+                EffectInfoRequest::ChooseFromZone {
+                    zone: self.zone,
+                    target: Some(String::from("target")),
+                    count: self.count,
+                },
+            ),
+        ]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        _source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::SingleTarget(TargetId::Player(player))) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+        let Some(EffectInfo::ZoneObjects(objects)) = info.get("cards") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "cards".into(),
+            });
+        };
+
+        let from = ZoneId::of(self.zone, *player);
+        Ok(objects
+            .iter()
+            .map(|object| GameAtom::MoveObject {
+                object: *object,
+                from,
+                to: ZoneId::Exile,
+            })
+            .collect())
+    }
+}
+
+/// For effects that say "Add X scrip to your pool"
+#[derive(Debug)]
+pub struct ProduceScrip {
+    pub kind: ScripKind,
+    pub amount: u64,
+    /// Whether the scrip floats, and is lost at the end of the phase
+    pub floating: bool,
+}
+
+impl ProduceScrip {
+    /// For Buildings that produce scrip at the start of each of their controller's turns
+    pub fn each_turn(kind: ScripKind, amount: u64) -> CardEffect {
+        CardEffect::Triggered(TriggeredCardEffect {
+            trigger: EffectTrigger::OnTurnStart,
+            effects: vec![Effect::Instant(Box::new(ProduceScrip {
+                kind,
+                amount,
+                floating: false,
+            }))],
+        })
+    }
+
+    /// For Buildings that can be exhausted to produce scrip
+    pub fn on_exhaust(kind: ScripKind, amount: u64) -> CardEffect {
+        CardEffect::Activated(ActivatedCardEffect {
+            cost: Cost::default(),
+            exhaust: true,
+            effect: vec![Effect::Instant(Box::new(ProduceScrip {
+                kind,
+                amount,
+                floating: false,
+            }))],
+        })
+    }
+
+    /// For cards that can be exhausted to produce scrip for the current phase only
+    pub fn floating_on_exhaust(kind: ScripKind, amount: u64) -> CardEffect {
+        CardEffect::Activated(ActivatedCardEffect {
+            cost: Cost::default(),
+            exhaust: true,
+            effect: vec![Effect::Instant(Box::new(ProduceScrip {
+                kind,
+                amount,
+                floating: true,
+            }))],
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for ProduceScrip {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        Default::default()
+    }
+
+    async fn execute(
+        &self,
+        _info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        let (kind, amount) = (self.kind, self.amount);
+        Ok(vec![if self.floating {
+            GameAtom::AddFloatingScrip {
+                player,
+                kind,
+                amount,
+            }
+        } else {
+            GameAtom::AddScrip {
+                player,
+                kind,
+                amount,
+            }
+        }])
+    }
+}
+
+/// For effects that say "Shuffle your library"
+#[derive(Debug)]
+pub struct ShuffleLibrary;
+
+#[async_trait::async_trait]
+impl InstantEffect for ShuffleLibrary {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        Default::default()
+    }
+
+    async fn execute(
+        &self,
+        _info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        Ok(vec![GameAtom::ShuffleLibrary { player }])
+    }
+}
+
+/// For effects that say "Target player puts the top X cards of their library into their discard"
+///
+/// If their library has fewer cards, all of them are put into the discard.
+#[derive(Debug)]
+pub struct Mill(pub usize);
+
+#[async_trait::async_trait]
+impl InstantEffect for Mill {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("target"),
+            EffectInfoRequest::SingleTarget {
+                restriction: Some(TargetRestriction::Player),
+                optional: false,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        _source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::SingleTarget(TargetId::Player(player))) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::Mill {
+            player: *player,
+            count: self.0,
+        }])
+    }
+}
+
+/// For effects that say "You gain X life"
+#[derive(Debug)]
+pub struct GainLife(pub usize);
+
+#[async_trait::async_trait]
+impl InstantEffect for GainLife {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        Default::default()
+    }
+
+    async fn execute(
+        &self,
+        _info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        Ok(vec![GameAtom::GainLife {
+            player,
+            amount: self.0,
+        }])
+    }
+}
+
+/// For effects that say "Target player gets X poison counters"
+#[derive(Debug)]
+pub struct Poison(pub usize);
+
+#[async_trait::async_trait]
+impl InstantEffect for Poison {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("target"),
+            EffectInfoRequest::SingleTarget {
+                restriction: Some(TargetRestriction::Player),
+                optional: false,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        _source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::SingleTarget(TargetId::Player(player))) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::AddPoison {
+            player: *player,
+            amount: self.0,
+        }])
+    }
+}
+
+/// For effects that say "You get an emblem with ..."
+///
+/// The emblem carries the static and triggered effects of the card, which has to be part of the
+/// game but is never played itself.
+#[derive(Debug)]
+pub struct CreateEmblem(pub CardId);
+
+#[async_trait::async_trait]
+impl InstantEffect for CreateEmblem {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        Default::default()
+    }
+
+    async fn execute(
+        &self,
+        _info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+
+        Ok(vec![GameAtom::CreateEmblem {
+            player,
+            card: self.0,
+        }])
+    }
+}
+
+/// For effects that say "Prevent the next X damage that would be dealt to target this turn"
+#[derive(Debug)]
+pub struct PreventDamage(pub usize);
+
+#[async_trait::async_trait]
+impl InstantEffect for PreventDamage {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("target"),
+            EffectInfoRequest::SingleTarget {
+                restriction: None,
+                optional: false,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        _source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::SingleTarget(target)) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::AddDamageShield {
+            target: *target,
+            amount: self.0,
+        }])
+    }
+}
+
+/// For effects that say "Deal X damage to each of up to N targets"
+#[derive(Debug)]
+pub struct DealDamageToTargets {
+    pub amount: usize,
+    pub max: usize,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for DealDamageToTargets {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("targets"),
+            EffectInfoRequest::MultipleTargets {
+                min: 1,
+                max: self.max,
+                restriction: None,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::MultipleTargets(targets)) = info.get("targets") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "targets".into(),
+            });
+        };
+
+        Ok(targets
+            .iter()
+            .map(|target| GameAtom::DealDamage {
+                amount: self.amount,
+                source,
+                target: *target,
+            })
+            .collect())
+    }
+}
+
+/// For effects that say "Deal X damage divided as you choose between two targets"
+///
+/// The controller chooses how much of the damage goes to the first target, the rest is dealt to
+/// the second one.
+#[derive(Debug)]
+pub struct DivideDamage(pub u64);
+
+#[async_trait::async_trait]
+impl InstantEffect for DivideDamage {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        let target = || EffectInfoRequest::SingleTarget {
+            restriction: None,
+            optional: false,
+        };
+        [
+            (String::from("first"), target()),
+            (String::from("second"), target()),
+            (
+                String::from("first_share"),
+                EffectInfoRequest::Number {
+                    min: 0,
+                    max: self.0,
+                },
+            ),
+        ]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let target = |name: &str| match info.get(name) {
+            Some(EffectInfo::SingleTarget(target)) => Ok(*target),
+            _ => Err(ExecuteFailure::InvalidEffectInfo { name: name.into() }),
+        };
+        let (first, second) = (target("first")?, target("second")?);
+        let share = match info.get("first_share") {
+            Some(EffectInfo::Number(share)) if *share <= self.0 => *share,
+            _ => {
+                return Err(ExecuteFailure::InvalidEffectInfo {
+                    name: "first_share".into(),
+                })
+            }
+        };
+
+        Ok([(first, share), (second, self.0 - share)]
+            .into_iter()
+            .filter(|(_, amount)| *amount > 0)
+            .map(|(target, amount)| GameAtom::DealDamage {
+                amount: amount as usize,
+                source,
+                target,
+            })
+            .collect())
+    }
+}
+
+/// For effects that say "Counter target quickhack"
+///
+/// Any object on the stack can be countered if `kind` is `None`. If the target left the stack in
+/// the meantime nothing happens.
+#[derive(Debug)]
+pub struct CounterObject {
+    pub kind: Option<BaseCardKindTag>,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for CounterObject {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("target"),
+            EffectInfoRequest::SingleTarget {
+                restriction: Some(TargetRestriction::on_stack(self.kind)),
+                optional: false,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        _source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::SingleTarget(TargetId::Object(object))) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+
+        let on_stack = game
+            .latest_gamestate()
+            .get_object_from_zone(ZoneId::Stack, *object)
+            .is_some();
+        Ok(if on_stack {
+            vec![GameAtom::CounterObject { object: *object }]
+        } else {
+            vec![]
+        })
+    }
+}
+
+/// For effects that say "Target agent gets +X/+Y until end of turn"
+#[derive(Debug)]
+pub struct Buff {
+    pub power: i64,
+    pub toughness: i64,
+    pub duration: EffectDuration,
+}
+
+#[async_trait::async_trait]
+impl InstantEffect for Buff {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("target"),
+            EffectInfoRequest::SingleTarget {
+                restriction: None,
+                optional: false,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        _source: ObjectId,
+        _game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let Some(EffectInfo::SingleTarget(TargetId::Object(object))) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::AddTemporaryEffect {
+            modifier: TemporaryModifier::Buff {
+                object: *object,
+                power: self.power,
+                toughness: self.toughness,
+            },
+            duration: self.duration,
+        }])
+    }
+}
+
+/// For effects that say "Gain control of target until end of turn"
+#[derive(Debug)]
+pub struct GainControl(pub EffectDuration);
+
+#[async_trait::async_trait]
+impl InstantEffect for GainControl {
+    fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+        [(
+            String::from("target"),
+            EffectInfoRequest::SingleTarget {
+                restriction: None,
+                optional: false,
+            },
+        )]
+        .into()
+    }
+
+    async fn execute(
+        &self,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+        game: &Game,
+    ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+        let player = game
+            .get_controller_of(source)
+            .ok_or(ExecuteFailure::NoControllerFound)?;
+        let Some(EffectInfo::SingleTarget(TargetId::Object(object))) = info.get("target") else {
+            return Err(ExecuteFailure::InvalidEffectInfo {
+                name: "target".into(),
+            });
+        };
+
+        Ok(vec![GameAtom::AddTemporaryEffect {
+            modifier: TemporaryModifier::GainControl {
+                object: *object,
+                player,
+            },
+            duration: self.0,
+        }])
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use std::collections::HashMap;
 
+    use technomancy_core::card::VARIABLE_COST_INFO;
+    use technomancy_core::effect::Capability;
     use technomancy_core::effect::EffectInfo;
     use technomancy_core::effect::EffectInfoRequest;
     use technomancy_core::effect::ExecuteFailure;
     use technomancy_core::effect::InstantEffect;
+    use technomancy_core::effect::RelativePlayer;
+    use technomancy_core::effect::TargetRestriction;
 
+    use super::GainLife;
     use crate::GameAtom;
     use crate::ObjectId;
+    use crate::ZoneId;
 
     #[derive(Debug)]
     pub struct DealDamage(pub usize);
@@ -18,7 +889,10 @@ pub mod tests {
         fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
             [(
                 String::from("target"),
-                EffectInfoRequest::SingleTarget { restriction: None },
+                EffectInfoRequest::SingleTarget {
+                    restriction: None,
+                    optional: false,
+                },
             )]
             .into()
         }
@@ -43,6 +917,138 @@ pub mod tests {
         }
     }
 
+    /// "Deal N damage to target opponent", or "you may..." if it is optional
+    #[derive(Debug)]
+    pub struct DamageOpponent {
+        pub amount: usize,
+        pub optional: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl InstantEffect for DamageOpponent {
+        fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+            [(
+                String::from("target"),
+                EffectInfoRequest::SingleTarget {
+                    restriction: Some(TargetRestriction::All(vec![
+                        TargetRestriction::Player,
+                        TargetRestriction::Controller(RelativePlayer::Opponent),
+                    ])),
+                    optional: self.optional,
+                },
+            )]
+            .into()
+        }
+
+        async fn execute(
+            &self,
+            info: HashMap<String, EffectInfo>,
+            source: ObjectId,
+            game: &crate::Game,
+        ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+            DealDamage(self.amount).execute(info, source, game).await
+        }
+    }
+
+    /// "You gain X life", for cards with a variable cost of up to `max`
+    #[derive(Debug)]
+    pub struct GainX {
+        pub max: u64,
+    }
+
+    #[async_trait::async_trait]
+    impl InstantEffect for GainX {
+        fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+            [(
+                String::from(VARIABLE_COST_INFO),
+                EffectInfoRequest::Number {
+                    min: 0,
+                    max: self.max,
+                },
+            )]
+            .into()
+        }
+
+        async fn execute(
+            &self,
+            info: HashMap<String, EffectInfo>,
+            source: ObjectId,
+            game: &crate::Game,
+        ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+            let Some(EffectInfo::Number(x)) = info.get(VARIABLE_COST_INFO) else {
+                return Err(ExecuteFailure::InvalidEffectInfo {
+                    name: VARIABLE_COST_INFO.into(),
+                });
+            };
+
+            GainLife(*x as usize).execute(info, source, game).await
+        }
+    }
+
+    /// An effect that never finishes in time
+    #[derive(Debug)]
+    pub struct Stall;
+
+    #[async_trait::async_trait]
+    impl InstantEffect for Stall {
+        fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+            Default::default()
+        }
+
+        async fn execute(
+            &self,
+            _info: HashMap<String, EffectInfo>,
+            _source: ObjectId,
+            _game: &crate::Game,
+        ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+            tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+            Ok(vec![])
+        }
+    }
+
+    /// A sandboxed effect with the given capabilities, which shuffles the first player's library
+    ///
+    /// Fails if it can tell which cards are in that library, or what they were before.
+    #[derive(Debug)]
+    pub struct Snoop(pub Vec<Capability>);
+
+    #[async_trait::async_trait]
+    impl InstantEffect for Snoop {
+        fn get_required_info(&self) -> HashMap<String, EffectInfoRequest> {
+            Default::default()
+        }
+
+        fn capabilities(&self) -> Vec<Capability> {
+            self.0.clone()
+        }
+
+        async fn execute(
+            &self,
+            _info: HashMap<String, EffectInfo>,
+            _source: ObjectId,
+            game: &crate::Game,
+        ) -> Result<Vec<GameAtom>, ExecuteFailure> {
+            let state = game.latest_gamestate();
+            let player = state.active_player_order[0];
+            if state.zones[&ZoneId::Library(player)]
+                .objects
+                .iter()
+                .any(|o| o.underlying_card.is_some())
+            {
+                return Err(ExecuteFailure::InvalidEffectInfo {
+                    name: "library".into(),
+                });
+            }
+            if game.game_states.len() > 1 || !game.history.is_empty() {
+                return Err(ExecuteFailure::InvalidEffectInfo {
+                    name: "history".into(),
+                });
+            }
+
+            Ok(vec![GameAtom::ShuffleLibrary { player }])
+        }
+    }
+
     /// For effects that say "You draw X cards"
     #[derive(Debug)]
     pub struct DrawCards(pub usize);
@@ -1,41 +1,106 @@
 #![allow(dead_code, clippy::too_many_arguments)]
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
-use outside::OutsideGameClient;
 use rand::seq::SliceRandom;
 use rand::Rng;
+use rand::RngCore;
+use rand::SeedableRng;
 use rand_xoshiro::Xoshiro256StarStar;
+use tarpc::client::RpcError;
+use technomancy_core::card::ActivatedCardEffect;
+use technomancy_core::card::AlternativeCost;
 use technomancy_core::card::Card;
+use technomancy_core::card::CardBehaviour;
 use technomancy_core::card::CardEffect;
 use technomancy_core::card::CardId;
+use technomancy_core::card::Cost;
+use technomancy_core::card::Keyword;
+use technomancy_core::card::PlayCondition;
+use technomancy_core::card::SubKindRegistry;
 use technomancy_core::card::TriggeredCardEffect;
+use technomancy_core::card::VARIABLE_COST_INFO;
+use technomancy_core::effect::Capability;
+use technomancy_core::effect::DelayedTrigger;
+use technomancy_core::effect::DelayedTriggerEvent;
+use technomancy_core::effect::DiscardChooser;
 use technomancy_core::effect::Effect;
 use technomancy_core::effect::EffectInfo;
 use technomancy_core::effect::EffectInfoRequest;
 use technomancy_core::effect::EffectTrigger;
+use technomancy_core::effect::ExecuteFailure;
+use technomancy_core::effect::InstantEffect;
+use technomancy_core::effect::TargetRestriction;
+use technomancy_core::event::AppliedAtoms;
+use technomancy_core::event::GameEvent;
+use technomancy_core::outside::ObjectList;
+use technomancy_core::outside::PromptContext;
+use technomancy_core::save::SeedExport;
+use technomancy_core::schema::Replay;
+use technomancy_core::Compaction;
+use technomancy_core::DamageShield;
+use technomancy_core::EliminationCause;
+use technomancy_core::FormatRules;
 use technomancy_core::Game;
 use technomancy_core::GameAtom;
 use technomancy_core::GameError;
 use technomancy_core::GameId;
 use technomancy_core::GameObject;
+use technomancy_core::GameOutcome;
+use technomancy_core::GameResult;
 use technomancy_core::GameStage;
 use technomancy_core::GameState;
+use technomancy_core::GameVersions;
 use technomancy_core::GameZone;
 use technomancy_core::ObjectId;
+use technomancy_core::ObjectVisibility;
+use technomancy_core::Phase;
 use technomancy_core::Player;
 use technomancy_core::PlayerAction;
 use technomancy_core::PlayerId;
+use technomancy_core::ReplayError;
+use technomancy_core::StackTrigger;
 use technomancy_core::TargetId;
+use technomancy_core::TeamId;
+use technomancy_core::TemporaryEffect;
+use technomancy_core::TemporaryModifier;
 use technomancy_core::VerificationError;
+use technomancy_core::VersionSkew;
 use technomancy_core::ZoneId;
+use technomancy_core::ZoneKey;
+use technomancy_core::ZoneMetadata;
+use technomancy_core::ZoneVisibility;
+use technomancy_core::STARTING_LIFE;
+use tokio::sync::broadcast;
 use tracing::trace;
 
+use crate::combat::Combatant;
+use crate::combat::DamageStep;
+use crate::outside::asked::AskedOutside;
 use crate::outside::OutsideGame;
+use crate::outside::PromptObjects;
+use crate::side_rules::SideRuleModule;
+use crate::workload::Workload;
 
+pub mod builder;
 pub mod card;
+pub mod combat;
+pub mod cost;
+#[cfg(feature = "debugger")]
+pub mod debugger;
 pub mod effect;
+pub mod matches;
 pub mod outside;
+pub mod prelude;
+pub mod side_rules;
+pub mod target;
+pub mod validation;
+pub mod workload;
 
 fn assert_send<'u, R>(
     fut: impl 'u + Send + std::future::Future<Output = R>,
@@ -46,6 +111,64 @@ fn assert_send<'u, R>(
 #[derive(Debug)]
 pub struct GameImplV1 {
     game: Game,
+    /// Events produced by applied atoms that have not been sent to the outside yet
+    events: Vec<GameEvent>,
+    /// How long a single effect may take to execute
+    effect_time_limit: Duration,
+    /// Whether a default is chosen for players that can not be reached during resolution
+    default_choice_on_disconnect: bool,
+    /// The objects of prompts that are waiting for an answer
+    prompt_objects: PromptObjects,
+    /// What the game weighs on the engine, as of its last run
+    workload: Workload,
+    /// Every applied atom batch is sent here, see [`subscribe`](GameImplV1::subscribe)
+    applied: broadcast::Sender<AppliedAtoms>,
+}
+
+/// How long a single effect may take to execute, unless configured otherwise
+pub const DEFAULT_EFFECT_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// How many applied atom batches a subscriber may fall behind before it misses some
+pub const APPLIED_ATOMS_CAPACITY: usize = 256;
+
+fn applied_atoms_sender() -> broadcast::Sender<AppliedAtoms> {
+    broadcast::channel(APPLIED_ATOMS_CAPACITY).0
+}
+
+/// Randomness that counts how many values were drawn from it
+///
+/// A value is a step of the underlying generator, filling bytes takes one for every eight of them.
+struct CountingRng<R> {
+    rand: R,
+    draws: u64,
+}
+
+impl<R: RngCore> CountingRng<R> {
+    fn new(rand: R) -> Self {
+        Self { rand, draws: 0 }
+    }
+}
+
+impl<R: RngCore> RngCore for CountingRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.draws += 1;
+        self.rand.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.rand.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.draws += (dest.len() as u64 + 7) / 8;
+        self.rand.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.draws += (dest.len() as u64 + 7) / 8;
+        self.rand.try_fill_bytes(dest)
+    }
 }
 
 impl GameImplV1 {
@@ -57,22 +180,344 @@ impl GameImplV1 {
         order: Vec<PlayerId>,
     ) -> GameImplV1 {
         let initial_game_state = new_game_state_with(&mut rand, &players, &order);
+        let versions = GameVersions::current(&cards, players.values());
         GameImplV1 {
             game: Game {
                 id,
+                versions,
+                prompts: 0,
+                time_spent: Default::default(),
+                eliminated_by: Default::default(),
                 cards,
+                subkinds: Arc::new(SubKindRegistry::base()),
                 players,
                 rand,
+                format: FormatRules::default(),
+                custom_zones: Default::default(),
+                teams: Default::default(),
                 game_states: vec![initial_game_state],
                 history: vec![],
+                rand_history: vec![],
+                rand_draws: vec![],
+                applied_at: vec![],
+                compaction: None,
+                first_state: 0,
+                snapshots: vec![],
             },
+            events: vec![],
+            effect_time_limit: DEFAULT_EFFECT_TIME_LIMIT,
+            default_choice_on_disconnect: false,
+            prompt_objects: PromptObjects::default(),
+            workload: Workload::default(),
+            applied: applied_atoms_sender(),
+        }
+    }
+
+    /// Resumes a game loaded from persistence, for example after the engine crashed
+    ///
+    /// Atom batches that were recorded but never committed to a game state are discarded. The
+    /// decision that was in flight is then asked again by the next call to [`run`](Self::run), as
+    /// all prompts are derived from the latest game state.
+    ///
+    /// Games created with other rules, schema or card versions are refused, see
+    /// [`restore_migrated`](Self::restore_migrated) to resume them anyway.
+    pub fn restore(
+        game: Game,
+        cards: Arc<std::collections::HashMap<CardId, Card>>,
+    ) -> Result<Self, Vec<VersionSkew>> {
+        let skew = game.versions.skew(&cards);
+        if !skew.is_empty() {
+            return Err(skew);
+        }
+
+        Ok(Self::resume(game, cards))
+    }
+
+    /// Resumes a game after running `migrate` over it if its versions differ from the current ones
+    ///
+    /// The migration is given the differences and has to bring the game up to date, afterwards
+    /// the game records the current versions.
+    pub fn restore_migrated(
+        mut game: Game,
+        cards: Arc<std::collections::HashMap<CardId, Card>>,
+        migrate: impl FnOnce(&mut Game, &[VersionSkew]),
+    ) -> Self {
+        let skew = game.versions.skew(&cards);
+        if !skew.is_empty() {
+            trace!(game = ?game.id, ?skew, "Migrating game");
+            migrate(&mut game, &skew);
+            game.versions = GameVersions::current(&cards, game.players.values());
+        }
+
+        Self::resume(game, cards)
+    }
+
+    fn resume(mut game: Game, cards: Arc<std::collections::HashMap<CardId, Card>>) -> Self {
+        game.cards = cards;
+
+        let mut game_impl = GameImplV1 {
+            game,
+            events: vec![],
+            effect_time_limit: DEFAULT_EFFECT_TIME_LIMIT,
+            default_choice_on_disconnect: false,
+            prompt_objects: PromptObjects::default(),
+            workload: Workload::default(),
+            applied: applied_atoms_sender(),
+        };
+        game_impl.discard_uncommitted();
+        game_impl
+    }
+
+    /// Rebuilds all game states of a recorded game from its first one and the atom history
+    ///
+    /// `seed` is the randomness the first atoms were applied with, see [`Game::initial_seed`].
+    /// Every rebuilt game state is compared to the recorded one if there is any, so a replay that
+    /// succeeds played out exactly like the recorded game. Batches that were refused during the
+    /// game are refused again and skipped.
+    pub fn replay(
+        game: &Game,
+        seed: Xoshiro256StarStar,
+        history: Replay,
+    ) -> Result<Game, ReplayError> {
+        let mut replayed = game.clone();
+        replayed.game_states = vec![game.first_gamestate().clone()];
+        replayed.first_state = 0;
+        replayed.snapshots.clear();
+        replayed.history.clear();
+        replayed.rand_history.clear();
+        replayed.rand_draws.clear();
+        replayed.applied_at.clear();
+        replayed.rand = seed;
+        let mut game_impl = GameImplV1 {
+            game: replayed,
+            events: vec![],
+            effect_time_limit: DEFAULT_EFFECT_TIME_LIMIT,
+            default_choice_on_disconnect: false,
+            prompt_objects: PromptObjects::default(),
+            workload: Workload::default(),
+            applied: applied_atoms_sender(),
+        };
+
+        let mut batches = history.into_iter().peekable();
+        while let Some((state, atoms)) = batches.next() {
+            let latest = game_impl.game.latest_index();
+            if state != latest {
+                return Err(ReplayError::OutOfOrder { state, latest });
+            }
+            if let Err(error) = game_impl.apply_atoms(atoms) {
+                if batches.peek().is_some_and(|(next, _)| *next == state) {
+                    continue;
+                }
+                return Err(ReplayError::Atoms { state, error });
+            }
+
+            let rebuilt = state + 1;
+            if game
+                .recorded_state(rebuilt)
+                .is_some_and(|recorded| recorded != game_impl.latest_gamestate())
+            {
+                return Err(ReplayError::Diverged { state: rebuilt });
+            }
+        }
+
+        Ok(game_impl.game)
+    }
+
+    /// Sets a game up from an exported seed and plays its history again
+    ///
+    /// Every batch has to draw as many values from the randomness and arrive at the same game state
+    /// as it did when the game was exported, so a game rebuilt without an error played out bit for
+    /// bit like the original.
+    pub fn from_seed_export(
+        export: SeedExport,
+        cards: Arc<std::collections::HashMap<CardId, Card>>,
+    ) -> Result<GameImplV1, ReplayError> {
+        let order = export.players.iter().map(|p| p.id).collect();
+        let players = export.players.into_iter().map(|p| (p.id, p)).collect();
+        let mut game_impl =
+            GameImplV1::new(export.game, export.seed.clone(), cards, players, order);
+        game_impl.game.game_states = vec![export.first_state];
+        game_impl.game.rand = export.seed;
+        game_impl.game.format = export.format;
+        game_impl.game.teams = export.teams;
+        game_impl.game.custom_zones = export.custom_zones;
+
+        for (state, atoms) in export.history {
+            let latest = game_impl.game.latest_index();
+            if state != latest {
+                return Err(ReplayError::OutOfOrder { state, latest });
+            }
+            game_impl
+                .apply_atoms(atoms)
+                .map_err(|error| ReplayError::Atoms { state, error })?;
+
+            let drawn = game_impl.game.rand_draws_at(state).unwrap_or(0);
+            if let Some((_, recorded)) = export.draws.iter().find(|(idx, _)| *idx == state) {
+                if drawn != *recorded {
+                    return Err(ReplayError::DrawsDiverged {
+                        state,
+                        recorded: *recorded,
+                        drawn,
+                    });
+                }
+            }
+
+            let hash = game_impl.latest_gamestate().fingerprint();
+            if export
+                .state_hashes
+                .iter()
+                .any(|(idx, recorded)| *idx == state && *recorded != hash)
+            {
+                return Err(ReplayError::Diverged { state: state + 1 });
+            }
+        }
+
+        Ok(game_impl)
+    }
+
+    /// Removes history entries whose atoms did not lead to a new game state
+    ///
+    /// Returns how many were removed.
+    fn discard_uncommitted(&mut self) -> usize {
+        let committed = self.game.latest_index();
+        let before = self.game.history.len();
+        self.game.history.retain(|(idx, _)| *idx < committed);
+        // Of several batches based on the same state only the last one was committed
+        self.game.history.reverse();
+        self.game.history.dedup_by_key(|(idx, _)| *idx);
+        self.game.history.reverse();
+
+        let discarded = before - self.game.history.len();
+        if discarded > 0 {
+            trace!(discarded, "Discarded uncommitted atom batches");
+        }
+        discarded
+    }
+
+    /// Sets the format of the game, starting every player at its starting life total
+    pub fn with_format(mut self, format: FormatRules) -> GameImplV1 {
+        let life = format.starting_life();
+        let state = self.game.game_states.last_mut().unwrap();
+        for total in state.life.values_mut() {
+            *total = life;
+        }
+        if format.shared_library {
+            let mut shared = GameZone::empty();
+            for (zone, contents) in state.zones.iter_mut() {
+                if let ZoneId::Library(_) = zone {
+                    shared.objects.append(&mut contents.objects);
+                }
+            }
+            shared.objects.shuffle(&mut self.game.rand);
+            state.zones.insert(ZoneId::SharedLibrary, shared);
+        }
+        self.game.format = format;
+        self
+    }
+
+    /// Adds zones introduced by a card set to the game
+    pub fn with_custom_zones(
+        mut self,
+        custom_zones: std::collections::HashMap<ZoneKey, ZoneMetadata>,
+    ) -> GameImplV1 {
+        let state = self.game.game_states.last_mut().unwrap();
+        for (key, metadata) in &custom_zones {
+            if metadata.per_player {
+                for player in self.game.players.keys() {
+                    state.zones.insert(
+                        ZoneId::Custom {
+                            key: *key,
+                            player: Some(*player),
+                        },
+                        GameZone::empty(),
+                    );
+                }
+            } else {
+                state.zones.insert(
+                    ZoneId::Custom {
+                        key: *key,
+                        player: None,
+                    },
+                    GameZone::empty(),
+                );
+            }
         }
+        self.game.custom_zones = custom_zones;
+        self
+    }
+
+    /// Changes how long a single effect may take before it is considered broken
+    pub fn with_effect_time_limit(mut self, limit: Duration) -> GameImplV1 {
+        self.effect_time_limit = limit;
+        self
+    }
+
+    /// Lets the game continue without players that can not be reached
+    ///
+    /// Instead of failing, the first legal answer is chosen for mandatory choices during
+    /// resolution and cleanup, so that a single disconnected player does not stall the game.
+    pub fn with_default_choice_on_disconnect(mut self, enabled: bool) -> GameImplV1 {
+        self.default_choice_on_disconnect = enabled;
+        self
+    }
+
+    /// The objects of pending prompts, to answer requests for those not described in them
+    pub fn prompt_objects(&self) -> PromptObjects {
+        self.prompt_objects.clone()
+    }
+
+    /// A handle to the workload of the game, updated every time it runs
+    pub fn workload(&self) -> Workload {
+        self.workload.clone()
+    }
+
+    /// Lets `chooser` decide whether they play first before hands are drawn
+    ///
+    /// Without a chooser one is rolled with the game's randomness. Otherwise the first player of
+    /// the given order starts. Games without players are left as they are.
+    pub fn with_starting_player_chooser(mut self, chooser: Option<PlayerId>) -> GameImplV1 {
+        let state = self.game.game_states.last_mut().unwrap();
+        let order = &state.active_player_order;
+        let chooser = match chooser {
+            Some(chooser) => chooser,
+            None if order.is_empty() => return self,
+            None => order[self.game.rand.gen_range(0..order.len())],
+        };
+        state.game_stage = GameStage::ChooseStartingPlayer { chooser };
+        self
+    }
+
+    /// Thins out old game states as the game goes on, see [`Compaction`]
+    pub fn with_compaction(mut self, compaction: Option<Compaction>) -> GameImplV1 {
+        self.game.compaction = compaction;
+        self
+    }
+
+    /// Lets players play in teams, everyone in a team shares the same win condition
+    pub fn with_teams(mut self, teams: Vec<Vec<PlayerId>>) -> GameImplV1 {
+        self.game.teams = teams
+            .into_iter()
+            .enumerate()
+            .flat_map(|(idx, players)| players.into_iter().map(move |p| (p, TeamId(idx))))
+            .collect();
+        self
+    }
+
+    /// Replaces the base game subkinds, for example with ones extended by a card set
+    pub fn with_subkinds(mut self, subkinds: Arc<SubKindRegistry>) -> GameImplV1 {
+        self.game.subkinds = subkinds;
+        self
     }
 
     pub fn verify(&self) -> Result<(), Vec<VerificationError>> {
         let mut errors = vec![];
 
         for (id, player) in &self.game.players {
+            let size = player.initial_cards.len();
+            if !self.game.format.allows_deck_size(size) {
+                errors.push(VerificationError::InvalidDeckSize { id: *id, size });
+            }
+
             for card in &player.initial_cards {
                 if !self.game.cards.contains_key(card) {
                     errors.push(VerificationError::PlayerInvalidCard {
@@ -83,6 +528,55 @@ impl GameImplV1 {
             }
         }
 
+        for player in self.game.teams.keys() {
+            if !self.game.players.contains_key(player) {
+                errors.push(VerificationError::UnknownTeamMember { player: *player });
+            }
+        }
+
+        // Only the cards that are played in this game have to be legal in its format
+        let mut seen = HashSet::new();
+        let deck_cards = self
+            .game
+            .players
+            .values()
+            .flat_map(|p| p.initial_cards.iter())
+            .filter(|card| seen.insert(**card))
+            .filter_map(|card| self.game.cards.get(card));
+        for card in deck_cards {
+            if let Some(cost) = &card.behaviour.cost {
+                if let Err(error) = cost.validate(self.game.format.max_total_cost) {
+                    errors.push(VerificationError::InvalidCardCost {
+                        card: card.id,
+                        error,
+                    });
+                }
+            }
+
+            for subkind in card.behaviour.subkinds() {
+                if !self.game.subkinds.contains(subkind) {
+                    errors.push(VerificationError::UnknownSubKind {
+                        card: card.id,
+                        subkind: subkind.clone(),
+                    });
+                }
+            }
+
+            for effect in card.behaviour.effects.iter().flat_map(|e| e.effects()) {
+                let Effect::Instant(effect) = effect else {
+                    continue;
+                };
+                for capability in effect.capabilities() {
+                    if !self.game.format.allows_capability(capability) {
+                        errors.push(VerificationError::ForbiddenCapability {
+                            card: card.id,
+                            capability,
+                        });
+                    }
+                }
+            }
+        }
+
         if !errors.is_empty() {
             return Err(errors);
         }
@@ -94,60 +588,618 @@ impl GameImplV1 {
         self.game.latest_gamestate()
     }
 
-    pub fn apply_atoms(&mut self, atoms: Vec<GameAtom>) -> Result<(), GameError> {
+    pub fn id(&self) -> GameId {
+        self.game.id
+    }
+
+    /// Receives every atom batch applied from now on, together with the index of its game state
+    ///
+    /// Refused batches are not sent. Subscribers that fall more than
+    /// [`APPLIED_ATOMS_CAPACITY`] batches behind miss the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<AppliedAtoms> {
+        self.applied.subscribe()
+    }
+
+    /// Returns the game to the game state at the given index, dropping everything after it
+    ///
+    /// The randomness is restored as well, so applying the same atoms again leads to the same
+    /// game states. Meant for takebacks and for repairing broken games, the players are not
+    /// notified.
+    pub fn rollback_to(&mut self, state: usize) -> Result<(), GameError> {
+        let latest = self.game.latest_index();
+        let rand = match self.game.rand_history.iter().find(|(idx, _)| *idx == state) {
+            Some((_, rand)) => rand.clone(),
+            None if state == latest => self.game.rand.clone(),
+            None => return Err(GameError::RollbackUnavailable { state }),
+        };
+
+        trace!(state, latest, "Rolling back");
+        match state.checked_sub(self.game.first_state) {
+            Some(offset) => self.game.game_states.truncate(offset + 1),
+            None => {
+                self.game.game_states = vec![self.state_at(state)?];
+                self.game.first_state = state;
+                self.game.snapshots.retain(|(idx, _)| *idx < state);
+            }
+        }
+        self.game.history.retain(|(idx, _)| *idx < state);
+        self.game.rand_history.retain(|(idx, _)| *idx < state);
+        self.game.rand_draws.retain(|(idx, _)| *idx < state);
+        self.game.applied_at.retain(|(idx, _)| *idx < state);
+        self.game.rand = rand;
+        self.workload.recount();
+        let restored = self.game.game_states.last().unwrap();
         self.game
-            .history
-            .push((self.game.game_states.len() - 1, atoms.clone()));
+            .eliminated_by
+            .retain(|player, _| restored.is_eliminated(*player));
+        self.events.clear();
+        Ok(())
+    }
+
+    /// The game state at the index, rebuilt from the nearest earlier snapshot if it was compacted
+    pub fn state_at(&self, idx: usize) -> Result<GameState, GameError> {
+        if let Some(state) = self.game.recorded_state(idx) {
+            return Ok(state.clone());
+        }
+        let Some((start, snapshot)) = self
+            .game
+            .snapshots
+            .iter()
+            .filter(|(snapshot, _)| *snapshot < idx)
+            .max_by_key(|(snapshot, _)| *snapshot)
+        else {
+            return Err(GameError::StateUnavailable { state: idx });
+        };
+
+        let mut game = self.game.clone();
+        game.game_states = vec![snapshot.clone()];
+        game.first_state = *start;
+        game.snapshots.clear();
+        game.compaction = None;
+        let mut rebuilt = GameImplV1 {
+            game,
+            events: vec![],
+            effect_time_limit: self.effect_time_limit,
+            default_choice_on_disconnect: false,
+            prompt_objects: PromptObjects::default(),
+            workload: Workload::default(),
+            applied: applied_atoms_sender(),
+        };
+        for base in *start..idx {
+            let atoms = self.game.history.iter().rev().find(|(b, _)| *b == base);
+            let rand = self.game.rand_history.iter().find(|(b, _)| *b == base);
+            let (Some((_, atoms)), Some((_, rand))) = (atoms, rand) else {
+                return Err(GameError::StateUnavailable { state: idx });
+            };
+            rebuilt.game.rand = rand.clone();
+            rebuilt.apply_atoms(atoms.clone())?;
+        }
+
+        Ok(rebuilt.game.game_states.pop().unwrap())
+    }
+
+    /// Moves game states that are no longer recent into the snapshots, keeping every `every`th
+    fn compact(&mut self) {
+        let Some(Compaction { every, keep_recent }) = self.game.compaction else {
+            return;
+        };
+        let keep = keep_recent.max(1);
+        let Some(dropped) = self.game.game_states.len().checked_sub(keep) else {
+            return;
+        };
+
+        let first = self.game.first_state;
+        for (offset, state) in self.game.game_states.drain(..dropped).enumerate() {
+            if (first + offset) % every.max(1) == 0 {
+                self.game.snapshots.push((first + offset, state));
+            }
+        }
+        self.game.first_state += dropped;
+    }
+
+    /// Removes a player from the game, for example because they left it
+    pub fn eliminate_player(&mut self, player: PlayerId) -> Result<(), GameError> {
+        self.apply_atoms(vec![GameAtom::EliminatePlayer { player }])
+    }
+
+    /// How the game ended, if it is over
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        match self.latest_gamestate().game_stage {
+            GameStage::Finished { outcome } => Some(outcome),
+            _ => None,
+        }
+    }
+
+    /// How the game ended and why, if it is over
+    pub fn result(&self) -> Option<GameResult> {
+        let seated = &self.game.first_gamestate().active_player_order;
+        let winners: Vec<PlayerId> = match self.outcome()? {
+            GameOutcome::Draw => return Some(GameResult::Draw),
+            GameOutcome::Won { winner } => vec![winner],
+            GameOutcome::TeamWon { team } => seated
+                .iter()
+                .filter(|p| self.game.team_of(**p) == Some(team))
+                .copied()
+                .collect(),
+        };
+        let losers: Vec<PlayerId> = seated
+            .iter()
+            .filter(|p| !winners.contains(p))
+            .copied()
+            .collect();
+        let eliminated_by = |cause: EliminationCause| -> Vec<PlayerId> {
+            losers
+                .iter()
+                .filter(|p| self.game.eliminated_by.get(p) == Some(&cause))
+                .copied()
+                .collect()
+        };
+
+        let timed_out = eliminated_by(EliminationCause::TimedOut);
+        if !timed_out.is_empty() {
+            return Some(GameResult::TimedOut { winners, timed_out });
+        }
+        let conceded = eliminated_by(EliminationCause::Conceded);
+        if !conceded.is_empty() && conceded.len() == losers.len() {
+            return Some(GameResult::Conceded { winners, conceded });
+        }
+        Some(GameResult::Won { winners })
+    }
+
+    /// What a player needs to know about the game to make sense of a prompt
+    pub fn prompt_context(&self) -> PromptContext {
+        let state = self.latest_gamestate();
+        PromptContext {
+            active_player: state.active_player_order.first().copied(),
+            priority_holder: state.unpassed_players.first().copied(),
+            phase: state.phase,
+            stack_depth: state.get_stack().objects.len(),
+            sequence: self.game.prompts,
+        }
+    }
+
+    /// Returns the state the game would be in after applying the atoms, without committing them
+    pub fn dry_run(&self, atoms: Vec<GameAtom>) -> Result<GameState, GameError> {
+        let mut preview = GameImplV1 {
+            game: self.game.without_history(),
+            events: vec![],
+            effect_time_limit: self.effect_time_limit,
+            default_choice_on_disconnect: self.default_choice_on_disconnect,
+            prompt_objects: PromptObjects::default(),
+            workload: Workload::default(),
+            applied: applied_atoms_sender(),
+        };
+        preview.apply_atoms(atoms)?;
+        Ok(preview.latest_gamestate().clone())
+    }
+
+    /// Shows players that asked for it the result of their action before it is committed
+    ///
+    /// Returns whether the action should go ahead.
+    async fn confirm_action(
+        &self,
+        outside: &impl OutsideGame,
+        player: PlayerId,
+        atoms: &[GameAtom],
+    ) -> Result<bool, GameError> {
+        if !self
+            .game
+            .preferences_of(player)
+            .is_some_and(|p| p.preview_actions)
+        {
+            return Ok(true);
+        }
+
+        let preview = self.dry_run(atoms.to_vec())?.view_for(player);
+        Ok(assert_send(outside.confirm_action(self.prompt_context(), player, preview)).await?)
+    }
+
+    /// Applies the atoms to the latest game state, creating a new one
+    ///
+    /// The batch is atomic: if any atom is refused, the game is left as it was, with neither the
+    /// history nor the randomness nor the pending events changed.
+    pub fn apply_atoms(&mut self, atoms: Vec<GameAtom>) -> Result<(), GameError> {
+        let base = self.game.latest_index();
+        let rand = self.game.rand.clone();
+        let events = self.events.len();
+        let mut counting = CountingRng::new(rand.clone());
+        let next_state = match self.next_state(atoms.clone(), &mut counting) {
+            Ok(state) => state,
+            Err(error) => {
+                self.events.truncate(events);
+                return Err(error);
+            }
+        };
+
+        self.game.rand = counting.rand;
+        self.game.rand_draws.push((base, counting.draws));
+        self.game.history.push((base, atoms));
+        self.game.rand_history.push((base, rand));
+        self.game.game_states.push(next_state);
+        self.compact();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        self.game.applied_at.push((base, now));
+
+        if self.applied.receiver_count() > 0 {
+            let (_, atoms) = self.game.history.last().unwrap();
+            // Nobody listening anymore is fine, the game goes on without them
+            let _ = self.applied.send(AppliedAtoms {
+                game: self.game.id,
+                base,
+                state: self.game.latest_index(),
+                atoms: atoms.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The game state the atoms turn the latest one into, using up randomness and adding events
+    fn next_state(
+        &mut self,
+        atoms: Vec<GameAtom>,
+        rand: &mut impl Rng,
+    ) -> Result<GameState, GameError> {
         let mut next_state = self.latest_gamestate().clone();
-        for atom in atoms {
+        // Random choices put the atoms of the picked option in front of the remaining ones
+        let mut atoms = VecDeque::from(atoms);
+        while let Some(atom) = atoms.pop_front() {
+            validation::check_atom(&self.game, &next_state, &atom)?;
             match atom {
                 GameAtom::StartGame => {
-                    if next_state.game_stage == GameStage::GameRunning {
+                    if !matches!(next_state.game_stage, GameStage::KeepHand { .. }) {
                         return Err(GameError::GameAlreadyRunning);
                     } else {
                         next_state.game_stage = GameStage::GameRunning
                     }
                 }
+                GameAtom::SetStartingPlayer { player } => {
+                    if !matches!(
+                        next_state.game_stage,
+                        GameStage::ChooseStartingPlayer { .. }
+                    ) {
+                        return Err(GameError::StartingPlayerAlreadyChosen);
+                    }
+                    let Some(idx) = next_state
+                        .active_player_order
+                        .iter()
+                        .position(|p| *p == player)
+                    else {
+                        return Err(GameError::PlayerNotInGame { player });
+                    };
+                    next_state.active_player_order.rotate_left(idx);
+                    next_state.unpassed_players = next_state.active_player_order.clone();
+                    next_state.game_stage = GameStage::KeepHand {
+                        players_keeping: Default::default(),
+                        mulligans: Default::default(),
+                    };
+                    self.events.push(GameEvent::StartingPlayerChosen { player });
+                }
                 GameAtom::DealDamage {
                     amount,
                     source: _,
                     target,
-                } => match target {
-                    TargetId::Player(_ply) => {
-                        todo!("Do something with health {amount}")
+                } => match (target, next_state.absorb_damage(target, amount)) {
+                    (_, 0) => (),
+                    (TargetId::Player(player), amount) => {
+                        let life = next_state.life.entry(player).or_default();
+                        *life = life.saturating_sub(amount);
+                    }
+                    (TargetId::Object(object), _) => {
+                        return Err(GameError::ObjectDamageUnsupported { object })
                     }
-                    TargetId::Object(_) => todo!(),
                 },
+                GameAtom::AddDamageShield { target, amount } => {
+                    next_state.shields.push(DamageShield { target, amount });
+                }
+                GameAtom::CounterObject { object } => {
+                    let obj = next_state
+                        .get_object_from_zone(ZoneId::Stack, object)
+                        .ok_or(GameError::ObjectNotFoundInZone {
+                            zone: ZoneId::Stack,
+                            object,
+                        })?;
+                    if obj.trigger.is_some() {
+                        atoms.push_front(GameAtom::RemoveTrigger { object });
+                        continue;
+                    }
+                    let owner = obj
+                        .owner_or_controller()
+                        .ok_or(GameError::NoControllerFound { object })?;
+                    atoms.push_front(GameAtom::MoveObject {
+                        object,
+                        from: ZoneId::Stack,
+                        to: ZoneId::Discard(owner),
+                    });
+                }
+                GameAtom::PutTriggerOnStack {
+                    controller,
+                    trigger,
+                } => {
+                    let object = ObjectId::new(rand);
+                    next_state
+                        .zone_mut(ZoneId::Stack)?
+                        .objects
+                        .push(GameObject::from_trigger(object, controller, trigger));
+                }
+                GameAtom::ChooseTriggerInfo { object, choices } => {
+                    let obj = next_state
+                        .zone_mut(ZoneId::Stack)?
+                        .objects
+                        .iter_mut()
+                        .find(|o| o.id == object && o.trigger.is_some())
+                        .ok_or(GameError::ObjectNotFoundInZone {
+                            zone: ZoneId::Stack,
+                            object,
+                        })?;
+                    obj.choices = choices;
+                    if let Some(trigger) = &mut obj.trigger {
+                        trigger.choices_made = true;
+                    }
+                }
+                GameAtom::RemoveTrigger { object } => {
+                    let stack = next_state.zone_mut(ZoneId::Stack)?;
+                    let idx = stack
+                        .objects
+                        .iter()
+                        .position(|o| o.id == object && o.trigger.is_some())
+                        .ok_or(GameError::ObjectNotFoundInZone {
+                            zone: ZoneId::Stack,
+                            object,
+                        })?;
+                    stack.objects.remove(idx);
+                }
+                GameAtom::TransformObject { object } => {
+                    let obj = next_state
+                        .zone_mut(ZoneId::Battlefield)?
+                        .objects
+                        .iter_mut()
+                        .find(|o| o.id == object)
+                        .ok_or(GameError::ObjectNotFoundInZone {
+                            zone: ZoneId::Battlefield,
+                            object,
+                        })?;
+                    let double_faced = obj
+                        .underlying_card
+                        .and_then(|card| self.game.cards.get(&card))
+                        .is_some_and(|card| card.is_double_faced());
+                    if !double_faced {
+                        return Err(GameError::SingleFaced { object });
+                    }
+
+                    obj.transformed = !obj.transformed;
+                    self.events.push(GameEvent::ObjectTransformed {
+                        object,
+                        transformed: obj.transformed,
+                    });
+                }
+                GameAtom::AddCounters { object, amount } => {
+                    let obj = next_state
+                        .zone_mut(ZoneId::Battlefield)?
+                        .objects
+                        .iter_mut()
+                        .find(|o| o.id == object)
+                        .ok_or(GameError::ObjectNotFoundInZone {
+                            zone: ZoneId::Battlefield,
+                            object,
+                        })?;
+                    obj.counters += amount;
+                }
+                GameAtom::AddTemporaryEffect { modifier, duration } => {
+                    let previous_controller = match modifier {
+                        TemporaryModifier::Buff { .. } => None,
+                        TemporaryModifier::GainControl { object, player } => {
+                            let obj = next_state
+                                .zone_mut(ZoneId::Battlefield)?
+                                .objects
+                                .iter_mut()
+                                .find(|o| o.id == object)
+                                .ok_or(GameError::ObjectNotFoundInZone {
+                                    zone: ZoneId::Battlefield,
+                                    object,
+                                })?;
+                            std::mem::replace(&mut obj.controller, Some(player))
+                        }
+                    };
+                    next_state.temporary_effects.push(TemporaryEffect {
+                        modifier,
+                        last_turn: duration.last_turn(next_state.turn),
+                        previous_controller,
+                    });
+                }
+                GameAtom::GainLife { player, amount } => {
+                    let life = next_state.life.entry(player).or_default();
+                    *life = life.saturating_add(amount);
+                }
+                GameAtom::LoseLife { player, amount } => {
+                    let life = next_state.life.entry(player).or_default();
+                    *life = life.saturating_sub(amount);
+                }
+                GameAtom::CreateEmblem { player, card } => {
+                    if !self.game.cards.contains_key(&card) {
+                        return Err(GameError::CardNotFound { card });
+                    }
+                    let zone = next_state
+                        .zones
+                        .entry(ZoneId::Emblems(player))
+                        .or_insert_with(GameZone::empty);
+                    zone.objects.push(GameObject {
+                        library_card_id: None,
+                        controller: Some(player),
+                        ..GameObject::from_card(rand, card)
+                    });
+                    self.events.push(GameEvent::EmblemCreated { player, card });
+                }
+                GameAtom::AddPoison { player, amount } => {
+                    let poison = next_state.poison.entry(player).or_default();
+                    *poison = poison.saturating_add(amount);
+                }
+                GameAtom::EndGame { outcome } => {
+                    next_state.game_stage = GameStage::Finished { outcome };
+                }
+                GameAtom::EliminatePlayer { player } => {
+                    if next_state.game_stage != GameStage::GameRunning {
+                        return Err(GameError::EliminationOutsideGame);
+                    }
+                    let Some(idx) = next_state
+                        .active_player_order
+                        .iter()
+                        .position(|p| *p == player)
+                    else {
+                        return Err(GameError::PlayerNotInGame { player });
+                    };
+
+                    next_state.active_player_order.remove(idx);
+                    next_state.unpassed_players.retain(|p| *p != player);
+                    if idx == 0 {
+                        // Their turn ends right away, the next player starts theirs
+                        next_state.phase = Phase::Recovery;
+                        next_state.unpassed_players = next_state.active_player_order.clone();
+                        next_state.end_turn();
+                    }
+
+                    let mut exiled = vec![];
+                    for zone in [ZoneId::Battlefield, ZoneId::Stack] {
+                        let objects = &mut next_state.zone_mut(zone)?.objects;
+                        let (controlled, kept) = std::mem::take(&mut **objects)
+                            .into_iter()
+                            .partition(|o| o.controller == Some(player));
+                        **objects = kept;
+                        // Their triggers cease to exist instead
+                        exiled.extend(
+                            controlled
+                                .into_iter()
+                                .filter(|o: &GameObject| o.trigger.is_none())
+                                .map(|o| (zone, o)),
+                        );
+                    }
+                    // Their emblems cease to exist
+                    next_state.zones.remove(&ZoneId::Emblems(player));
+
+                    let exile = next_state.zone_mut(ZoneId::Exile)?;
+                    for (zone, mut obj) in exiled {
+                        obj.controller = None;
+                        self.events.push(GameEvent::ObjectMoved {
+                            object: obj.id,
+                            card: obj.underlying_card,
+                            from: zone,
+                            to: ZoneId::Exile,
+                        });
+                        exile.objects.push(obj);
+                    }
+                    self.events.push(GameEvent::PlayerEliminated { player });
+
+                    let remaining = &next_state.active_player_order;
+                    if let Some(&first) = remaining.first() {
+                        if remaining.iter().all(|p| self.game.are_allies(first, *p)) {
+                            let outcome = match self.game.team_of(first) {
+                                Some(team) => GameOutcome::TeamWon { team },
+                                None => GameOutcome::Won { winner: first },
+                            };
+                            next_state.game_stage = GameStage::Finished { outcome };
+                        }
+                    }
+                }
                 GameAtom::KeepHand { player } => {
-                    if let GameStage::KeepHand { players_keeping } = &mut next_state.game_stage {
+                    if let GameStage::KeepHand {
+                        players_keeping, ..
+                    } = &mut next_state.game_stage
+                    {
                         players_keeping.insert(player);
                     } else {
                         return Err(GameError::KeepHandDuringGame);
                     }
                 }
+                GameAtom::TakeMulligan { player } => {
+                    if let GameStage::KeepHand { mulligans, .. } = &mut next_state.game_stage {
+                        *mulligans.entry(player).or_default() += 1;
+                    } else {
+                        return Err(GameError::MulliganDuringGame);
+                    }
+                }
+                GameAtom::PutOnLibraryBottom { player, objects } => {
+                    let (hand_id, library_id) =
+                        (ZoneId::Hand(player), self.game.library_of(player));
+                    let [hand, library] = next_state.zone_pair_mut(hand_id, library_id)?;
+                    for object in objects {
+                        let idx = hand.objects.iter().position(|o| o.id == object).ok_or(
+                            GameError::ObjectNotFoundInZone {
+                                zone: hand_id,
+                                object,
+                            },
+                        )?;
+                        let obj = hand.objects.remove(idx);
+                        self.events.push(GameEvent::ObjectMoved {
+                            object,
+                            card: obj.underlying_card,
+                            from: hand_id,
+                            to: library_id,
+                        });
+                        // The start of the library is its bottom
+                        library.objects.insert(0, obj);
+                    }
+                }
                 GameAtom::ShuffleHandIntoLibrary { player } => {
-                    let Some([hand, library]) = next_state
-                        .zones
-                        .get_many_mut([&ZoneId::Hand(player), &ZoneId::Library(player)])
-                    else {
-                        unreachable!()
-                    };
+                    let library_id = self.game.library_of(player);
+                    let [hand, library] =
+                        next_state.zone_pair_mut(ZoneId::Hand(player), library_id)?;
+                    self.events
+                        .extend(hand.objects.iter().map(|o| GameEvent::ObjectMoved {
+                            object: o.id,
+                            card: o.underlying_card,
+                            from: ZoneId::Hand(player),
+                            to: library_id,
+                        }));
                     library.objects.extend(hand.objects.drain(..));
-                    library.objects.shuffle(&mut self.game.rand);
+                    library.objects.shuffle(rand);
                 }
                 GameAtom::DrawCards { player, count } => {
-                    let Some([hand, library]) = next_state
-                        .zones
-                        .get_many_mut([&ZoneId::Hand(player), &ZoneId::Library(player)])
-                    else {
-                        unreachable!()
-                    };
+                    let from = next_state.draw_zone_of(&self.game, player);
+                    let [hand, source] = next_state.zone_pair_mut(ZoneId::Hand(player), from)?;
+                    let new_count = source.objects.len().saturating_sub(count);
+                    let mut drawn: Vec<_> = source.objects.drain(new_count..).collect();
+                    if from == ZoneId::SharedLibrary {
+                        // Cards of the shared library belong to whoever drew them
+                        for object in drawn.iter_mut() {
+                            object.owner = Some(player);
+                        }
+                    }
+                    self.events
+                        .extend(drawn.iter().map(|o| GameEvent::ObjectMoved {
+                            object: o.id,
+                            card: o.underlying_card,
+                            from,
+                            to: ZoneId::Hand(player),
+                        }));
+                    if !drawn.is_empty() {
+                        self.events.push(GameEvent::CardsDrawn {
+                            player,
+                            count: drawn.len(),
+                        });
+                    }
+                    hand.objects.extend(drawn);
+                }
+                GameAtom::Mill { player, count } => {
+                    let library_id = self.game.library_of(player);
+                    let [discard, library] =
+                        next_state.zone_pair_mut(ZoneId::Discard(player), library_id)?;
                     let new_count = library.objects.len().saturating_sub(count);
-                    hand.objects.extend(library.objects.drain(new_count..));
+                    // The topmost object is milled first, so it ends up below the others
+                    let milled: Vec<_> = library.objects.drain(new_count..).rev().collect();
+                    self.events
+                        .extend(milled.iter().map(|o| GameEvent::ObjectMoved {
+                            object: o.id,
+                            card: o.underlying_card,
+                            from: library_id,
+                            to: ZoneId::Discard(player),
+                        }));
+                    discard.objects.extend(milled);
                 }
                 GameAtom::PassPriority { player } => {
                     if next_state.unpassed_players.first() == Some(&player) {
                         next_state.unpassed_players.remove(0);
+                        next_state.actions_this_priority.remove(&player);
                     } else {
                         return Err(GameError::InvalidPlayerPassing { player });
                     }
@@ -157,17 +1209,28 @@ impl GameImplV1 {
                     from,
                     object,
                     choices,
+                    second_half,
                 } => {
+                    if !self.game.format.allows_action(&next_state, player) {
+                        return Err(GameError::ActionLimitReached { player });
+                    }
+                    *next_state.actions_this_priority.entry(player).or_default() += 1;
+                    *next_state.actions_this_turn.entry(player).or_default() += 1;
+
                     let from_id = from;
-                    let Some([from, to]) = next_state.zones.get_many_mut([&from, &ZoneId::Stack])
-                    else {
-                        unreachable!()
-                    };
+                    let [from, to] = next_state.zone_pair_mut(from, ZoneId::Stack)?;
                     if let Some(obj_idx) = from.objects.iter().position(|o| o.id == object) {
                         let mut obj = from.objects.remove(obj_idx);
                         obj.choices = choices;
                         obj.controller = Some(player);
-                        to.objects.push(obj);
+                        obj.second_half = second_half;
+                        self.events.push(GameEvent::ObjectMoved {
+                            object,
+                            card: obj.underlying_card,
+                            from: from_id,
+                            to: ZoneId::Stack,
+                        });
+                        to.objects.push(obj);
                     } else {
                         return Err(GameError::ObjectNotFoundInZone {
                             zone: from_id,
@@ -177,26 +1240,306 @@ impl GameImplV1 {
                 }
                 GameAtom::ResetPriority => {
                     next_state.unpassed_players = next_state.active_player_order.clone();
+                    next_state.actions_this_priority.clear();
                 }
                 GameAtom::PopStack => {
-                    next_state
-                        .zones
-                        .get_mut(&ZoneId::Stack)
-                        .unwrap()
+                    next_state.zone_mut(ZoneId::Stack)?.objects.pop();
+                }
+                GameAtom::SetObjectVisibility { object, visibility } => {
+                    let (_, obj) = next_state
+                        .find_object_mut(object)
+                        .ok_or(GameError::ObjectNotFound { object })?;
+                    obj.visibility = visibility;
+                }
+                GameAtom::MoveObject { object, from, to } => {
+                    let Some([from_zone, to_zone]) = next_state.zones.get_many_mut([&from, &to])
+                    else {
+                        return Err(GameError::ObjectNotFoundInZone { zone: from, object });
+                    };
+                    let Some(obj_idx) = from_zone.objects.iter().position(|o| o.id == object)
+                    else {
+                        return Err(GameError::ObjectNotFoundInZone { zone: from, object });
+                    };
+                    let mut obj = from_zone.objects.remove(obj_idx);
+                    // A resolving split permanent stays the half that was played
+                    obj.second_half &= from == ZoneId::Stack && to == ZoneId::Battlefield;
+                    if !matches!(to, ZoneId::Battlefield | ZoneId::Stack) {
+                        obj.controller = None;
+                    }
+                    obj.exhausted = false;
+                    obj.entered_turn = (to == ZoneId::Battlefield).then_some(next_state.turn);
+                    obj.counters = 0;
+                    obj.transformed = false;
+                    self.events.push(GameEvent::ObjectMoved {
+                        object,
+                        card: obj.underlying_card,
+                        from,
+                        to,
+                    });
+                    to_zone.objects.push(obj);
+                }
+                GameAtom::AdvancePhase => {
+                    next_state.floating_scrip.clear();
+                    next_state.phase = match next_state.phase.next() {
+                        Some(phase) => phase,
+                        None => {
+                            next_state.active_player_order.rotate_left(1);
+                            next_state.end_turn();
+                            Phase::Recovery
+                        }
+                    };
+                }
+                GameAtom::AddScrip {
+                    player,
+                    kind,
+                    amount,
+                } => {
+                    let pool = next_state.scrip.entry(player).or_default();
+                    let scrip = pool.entry(kind).or_default();
+                    *scrip = scrip.saturating_add(amount);
+                }
+                GameAtom::AddFloatingScrip {
+                    player,
+                    kind,
+                    amount,
+                } => {
+                    let pool = next_state.floating_scrip.entry(player).or_default();
+                    let scrip = pool.entry(kind).or_default();
+                    *scrip = scrip.saturating_add(amount);
+                }
+                GameAtom::SpendScrip {
+                    player,
+                    kind,
+                    amount,
+                } => {
+                    let available = next_state.available_scrip_of(player, kind);
+                    if available < amount {
+                        return Err(GameError::CannotAfford {
+                            player,
+                            needed: amount,
+                            available,
+                        });
+                    }
+                    let floating = next_state.floating_scrip_of(player, kind).min(amount);
+                    if floating > 0 {
+                        let pool = next_state.floating_scrip.entry(player).or_default();
+                        *pool.entry(kind).or_default() -= floating;
+                    }
+                    if amount > floating {
+                        let pool = next_state.scrip.entry(player).or_default();
+                        *pool.entry(kind).or_default() -= amount - floating;
+                    }
+                }
+                GameAtom::Exhaust { object } | GameAtom::Ready { object } => {
+                    let exhaust = matches!(atom, GameAtom::Exhaust { .. });
+                    let obj = next_state
+                        .zone_mut(ZoneId::Battlefield)?
                         .objects
-                        .pop();
+                        .iter_mut()
+                        .find(|o| o.id == object)
+                        .ok_or(GameError::ObjectNotFoundInZone {
+                            zone: ZoneId::Battlefield,
+                            object,
+                        })?;
+                    if exhaust && obj.exhausted {
+                        return Err(GameError::AlreadyExhausted { object });
+                    }
+                    obj.exhausted = exhaust;
+                }
+                GameAtom::RegisterDelayedTrigger { trigger } => {
+                    next_state.delayed_triggers.push(trigger);
+                }
+                GameAtom::RemoveDelayedTrigger { trigger } => {
+                    if let Some(idx) = next_state
+                        .delayed_triggers
+                        .iter()
+                        .position(|t| *t == trigger)
+                    {
+                        next_state.delayed_triggers.remove(idx);
+                    }
+                }
+                GameAtom::RevealObject { object } => {
+                    let (zone, obj) = next_state
+                        .find_object(object)
+                        .ok_or(GameError::ObjectNotFound { object })?;
+                    let card = obj
+                        .underlying_card
+                        .ok_or(GameError::NoUnderlyingCard { object })?;
+                    self.events
+                        .push(GameEvent::ObjectRevealed { object, card, zone });
+                }
+                GameAtom::ShuffleLibrary { player } => {
+                    let library = next_state
+                        .zones
+                        .get_mut(&self.game.library_of(player))
+                        .ok_or(GameError::InvalidCardState)?;
+                    library.objects.shuffle(rand);
+                }
+                GameAtom::DiscardCards { player, objects } => {
+                    let (hand_id, discard_id) = (ZoneId::Hand(player), ZoneId::Discard(player));
+                    let Some([hand, discard]) =
+                        next_state.zones.get_many_mut([&hand_id, &discard_id])
+                    else {
+                        return Err(GameError::InvalidCardState);
+                    };
+                    for object in objects {
+                        let idx = hand.objects.iter().position(|o| o.id == object).ok_or(
+                            GameError::ObjectNotFoundInZone {
+                                zone: hand_id,
+                                object,
+                            },
+                        )?;
+                        let obj = hand.objects.remove(idx);
+                        self.events.push(GameEvent::ObjectMoved {
+                            object,
+                            card: obj.underlying_card,
+                            from: hand_id,
+                            to: discard_id,
+                        });
+                        discard.objects.push(obj);
+                    }
+                }
+                GameAtom::ArrangeLibrary {
+                    player,
+                    top,
+                    bottom,
+                } => {
+                    let zone = self.game.library_of(player);
+                    let library = next_state
+                        .zones
+                        .get_mut(&zone)
+                        .ok_or(GameError::InvalidCardState)?;
+                    let mut take = |objects: &[ObjectId]| {
+                        objects
+                            .iter()
+                            .map(|object| {
+                                let idx =
+                                    library.objects.iter().position(|o| o.id == *object).ok_or(
+                                        GameError::ObjectNotFoundInZone {
+                                            zone,
+                                            object: *object,
+                                        },
+                                    )?;
+                                Ok(library.objects.remove(idx))
+                            })
+                            .collect::<Result<Vec<_>, GameError>>()
+                    };
+                    let top = take(&top)?;
+                    let bottom = take(&bottom)?;
+
+                    // The end of the library is its top
+                    library.objects.extend(top.into_iter().rev());
+                    library.objects.splice(0..0, bottom.into_iter().rev());
+                }
+                GameAtom::RandomChoice { mut options } => {
+                    if options.is_empty() {
+                        return Err(GameError::EmptyRandomChoice);
+                    }
+                    let picked = rand.gen_range(0..options.len());
+                    trace!(picked, options = options.len(), "Made a random choice");
+                    self.events.push(GameEvent::RandomChoiceMade {
+                        picked,
+                        options: options.len(),
+                    });
+                    for atom in options.swap_remove(picked).into_iter().rev() {
+                        atoms.push_front(atom);
+                    }
                 }
             }
         }
-        self.game.game_states.push(next_state);
-        Ok(())
+        next_state.update_index(self.latest_gamestate());
+        Ok(next_state)
     }
 
     #[tracing::instrument(level = "trace", skip_all, fields(game = ?self.game.id), err)]
-    pub async fn run(&mut self, outside: &OutsideGameClient) -> Result<(), GameError> {
+    pub async fn run(&mut self, outside: &impl OutsideGame) -> Result<(), GameError> {
+        let counts_before = self.latest_gamestate().zone_counts();
+        let stack_before = self.latest_gamestate().stack_entries();
+
+        let asked = AskedOutside::new(outside, self.game.prompts, self.workload.clone())
+            .with_clock(self.game.format.clock, self.game.time_spent.clone());
+        let mut stepped = assert_send(self.step(&asked)).await;
+        self.game.prompts = asked.next_sequence();
+        self.game.time_spent = asked.time_spent();
+
+        let conceded = asked.conceded();
+        if !conceded.is_empty() {
+            // Whatever the player was asked is moot once they are out of the game
+            trace!(?conceded, "Players ran out of time");
+            stepped = Ok(());
+            for player in conceded {
+                if self.latest_gamestate().game_stage == GameStage::GameRunning {
+                    self.apply_atoms(vec![GameAtom::EliminatePlayer { player }])?;
+                    self.game
+                        .eliminated_by
+                        .insert(player, EliminationCause::TimedOut);
+                }
+            }
+        }
+        if let Err(error) = &stepped {
+            if let Some(player) = asked.last_asked().filter(|_| error.is_recoverable()) {
+                trace!(?player, %error, "Refused an answer");
+                assert_send(outside.notify_error(player, error.to_string())).await?;
+            }
+        }
+        stepped?;
+        assert_send(self.run_zone_change_triggers()).await?;
+
+        if self.latest_gamestate().game_stage == GameStage::GameRunning {
+            let state = self.latest_gamestate();
+            if let Some(outcome) = self.side_rules().iter().find_map(|r| r.outcome(state)) {
+                trace!(?outcome, "A side rule ended the game");
+                self.apply_atoms(vec![GameAtom::EndGame { outcome }])?;
+            }
+        }
+
+        for event in std::mem::take(&mut self.events) {
+            let event = self.redact_event(event);
+            assert_send(outside.notify_event(event)).await?;
+        }
+
+        let counts = self.latest_gamestate().zone_counts();
+        if counts != counts_before {
+            assert_send(outside.notify_event(GameEvent::ZoneCountsChanged { counts })).await?;
+        }
+
+        let stack = self.latest_gamestate().stack_entries();
+        if stack != stack_before {
+            assert_send(outside.notify_event(GameEvent::StackChanged { stack })).await?;
+        }
+
+        self.workload.record(&self.game);
+        Ok(())
+    }
+
+    async fn step(&mut self, outside: &impl OutsideGame) -> Result<(), GameError> {
         match self.latest_gamestate().game_stage.clone() {
-            GameStage::KeepHand { players_keeping } => {
+            GameStage::ChooseStartingPlayer { chooser } => {
+                let answer =
+                    assert_send(outside.get_play_first(self.prompt_context(), chooser)).await;
+                let play_first = self.answer_or_default(answer, chooser, || true)?;
+
+                // Drawing first hands the first turn to the next player
+                let order = &self.latest_gamestate().active_player_order;
+                let idx = order
+                    .iter()
+                    .position(|p| *p == chooser)
+                    .ok_or(GameError::PlayerNotInGame { player: chooser })?;
+                let player = if play_first {
+                    chooser
+                } else {
+                    order[(idx + 1) % order.len()]
+                };
+                trace!(?chooser, ?player, "Starting player chosen");
+                self.apply_atoms(vec![GameAtom::SetStartingPlayer { player }])?;
+            }
+            GameStage::KeepHand {
+                players_keeping,
+                mulligans,
+            } => {
                 trace!("Checking for potential mulligans");
+                let rule = self.game.format.mulligan;
+                let starting = self.game.format.starting_hand_size();
                 let latest_gamestate = self.latest_gamestate();
                 let atoms: Vec<_> = self
                     .game
@@ -206,147 +1549,502 @@ impl GameImplV1 {
                     .flat_map(|p| {
                         let hand = latest_gamestate.get_hand(*p);
 
-                        match hand.objects.len() {
-                            1 => vec![
-                                GameAtom::ShuffleHandIntoLibrary { player: *p },
-                                GameAtom::KeepHand { player: *p },
-                            ],
-                            0 => vec![GameAtom::DrawCards {
+                        if hand.objects.is_empty() {
+                            return vec![GameAtom::DrawCards {
                                 player: *p,
-                                count: 7,
-                            }],
-                            count => vec![
-                                GameAtom::ShuffleHandIntoLibrary { player: *p },
-                                GameAtom::DrawCards {
-                                    player: *p,
-                                    count: count - 1,
-                                },
-                            ],
+                                count: starting,
+                            }];
+                        }
+
+                        let taken = mulligans.get(p).copied().unwrap_or(0) + 1;
+                        let mut atoms = vec![
+                            GameAtom::ShuffleHandIntoLibrary { player: *p },
+                            GameAtom::TakeMulligan { player: *p },
+                        ];
+                        if rule.hand_size(starting, taken) == 0 {
+                            // Nothing would be left to keep
+                            atoms.push(GameAtom::KeepHand { player: *p });
+                        } else {
+                            atoms.push(GameAtom::DrawCards {
+                                player: *p,
+                                count: rule.cards_drawn(starting, taken),
+                            });
                         }
+                        atoms
                     })
                     .collect();
                 self.apply_atoms(atoms)?;
 
                 let latest_gamestate = self.latest_gamestate();
 
-                let GameStage::KeepHand { players_keeping } = &latest_gamestate.game_stage else {
+                let GameStage::KeepHand {
+                    players_keeping, ..
+                } = &latest_gamestate.game_stage
+                else {
                     unreachable!()
                 };
 
-                let players_not_kept_yet = self
+                let (mut players_keeping, players_not_kept_yet): (Vec<_>, Vec<_>) = self
                     .game
                     .players
                     .keys()
                     .filter(|p| !players_keeping.contains(p))
                     .copied()
-                    .collect();
-                let players_keeping =
-                    assert_send(outside.get_player_keeping(players_not_kept_yet)).await?;
+                    .partition(|p| {
+                        self.game
+                            .preferences_of(*p)
+                            .is_some_and(|pref| pref.always_keep_hand)
+                    });
+
+                if !players_not_kept_yet.is_empty() {
+                    let prompt = self.prompt_context();
+                    players_keeping.extend(
+                        assert_send(outside.get_player_keeping(prompt, players_not_kept_yet))
+                            .await?,
+                    );
+                }
 
-                self.apply_atoms(
-                    players_keeping
-                        .into_iter()
-                        .map(|p| GameAtom::KeepHand { player: p })
-                        .collect(),
-                )?;
+                let mut atoms = vec![];
+                for player in players_keeping {
+                    let mulligans = self.latest_gamestate().game_stage.mulligans_of(player);
+                    let to_bottom = rule.cards_to_bottom(starting, mulligans);
+                    if to_bottom > 0 {
+                        let objects =
+                            assert_send(self.choose_cards_to_bottom(outside, player, to_bottom))
+                                .await?;
+                        atoms.push(GameAtom::PutOnLibraryBottom { player, objects });
+                    }
+                    atoms.push(GameAtom::KeepHand { player });
+                }
+                self.apply_atoms(atoms)?;
 
                 let latest_gamestate = self.latest_gamestate();
 
-                let GameStage::KeepHand { players_keeping } = &latest_gamestate.game_stage else {
+                let GameStage::KeepHand {
+                    players_keeping, ..
+                } = &latest_gamestate.game_stage
+                else {
                     unreachable!()
                 };
 
                 if players_keeping.len() == self.game.players.len() {
                     trace!("All players have kept, we can start the game");
-                    self.apply_atoms(vec![GameAtom::StartGame])?;
+                    let mut atoms = vec![GameAtom::StartGame];
+                    for rule in self.side_rules() {
+                        atoms.extend(rule.on_game_start(latest_gamestate));
+                    }
+                    self.apply_atoms(atoms)?;
                     return Ok(());
                 }
             }
+            GameStage::Finished { .. } => {
+                trace!("The game is already over");
+            }
             GameStage::GameRunning => {
+                let latest_gamestate = self.latest_gamestate();
+                let limit = self.game.format.poison_limit();
+                if let Some(&player) = latest_gamestate
+                    .active_player_order
+                    .iter()
+                    .find(|p| latest_gamestate.poison_of(**p) >= limit)
+                {
+                    trace!(?player, "Player has too many poison counters");
+                    self.apply_atoms(vec![GameAtom::EliminatePlayer { player }])?;
+                    return Ok(());
+                }
+
+                let atoms = assert_send(self.enforce_uniqueness(outside)).await?;
+                if !atoms.is_empty() {
+                    self.apply_atoms(atoms)?;
+                    return Ok(());
+                }
+
+                if self.latest_gamestate().phase == Phase::Cleanup {
+                    let atoms = assert_send(self.discard_to_hand_size(outside)).await?;
+                    if !atoms.is_empty() {
+                        self.apply_atoms(atoms)?;
+                        return Ok(());
+                    }
+                }
+
+                // Triggers get their targets before anyone may respond to them
+                let pending = self
+                    .latest_gamestate()
+                    .get_stack()
+                    .objects
+                    .iter()
+                    .find_map(|o| {
+                        let trigger = o.trigger.as_ref().filter(|t| !t.choices_made)?;
+                        Some((o.id, o.controller?, trigger.clone()))
+                    });
+                if let Some((object, player, trigger)) = pending {
+                    let cards = self.game.cards.clone();
+                    let effect = cards
+                        .get(&trigger.card)
+                        .and_then(|card| trigger.effect_of(card))
+                        .ok_or(GameError::CardNotFound { card: trigger.card })?;
+                    let choices =
+                        assert_send(self.gather_choices(outside, player, object, &[(0, effect)]))
+                            .await?;
+                    self.apply_atoms(vec![GameAtom::ChooseTriggerInfo { object, choices }])?;
+                    return Ok(());
+                }
+
                 let latest_gamestate = self.latest_gamestate();
 
                 let stack = latest_gamestate.get_stack();
 
+                if !latest_gamestate.phase.has_priority() && stack.objects.is_empty() {
+                    trace!(phase = ?latest_gamestate.phase, "Skipping phase without priority");
+                    return assert_send(self.advance_phase()).await;
+                }
+
                 if latest_gamestate.unpassed_players.is_empty() {
                     // All players passed, resolve the top most stack item
                     trace!("All players passed");
 
                     if let Some(top_item) = stack.objects.last() {
                         // Resolve!
+                        // Choices made during resolution may change the game, so nothing may
+                        // stay borrowed from it
+                        let top_item = top_item.clone();
+                        let cards = self.game.cards.clone();
                         trace!(?top_item.id, "Attemption resolution");
-                        let card = top_item.underlying_card.as_ref().ok_or(
-                            GameError::NoUnderlyingCard {
+                        let owner =
+                            top_item
+                                .owner_or_controller()
+                                .ok_or(GameError::NoControllerFound {
+                                    object: top_item.id,
+                                })?;
+                        // Triggers cease to exist once they leave the stack
+                        let discard = match top_item.trigger {
+                            Some(_) => GameAtom::RemoveTrigger {
                                 object: top_item.id,
                             },
-                        )?;
+                            None => GameAtom::MoveObject {
+                                object: top_item.id,
+                                from: ZoneId::Stack,
+                                to: ZoneId::Discard(owner),
+                            },
+                        };
 
-                        let card = self
-                            .game
-                            .cards
-                            .get(card)
-                            .ok_or(GameError::CardNotFound { card: *card })?;
+                        let (card, resolve_effects, leave_stack) = match &top_item.trigger {
+                            // A trigger resolves the single effect it stands for
+                            Some(trigger) => {
+                                let effect = cards
+                                    .get(&trigger.card)
+                                    .and_then(|card| trigger.effect_of(card))
+                                    .ok_or(GameError::CardNotFound { card: trigger.card })?;
+                                (trigger.card, vec![(0, effect)], discard.clone())
+                            }
+                            None => {
+                                let card = top_item.underlying_card.ok_or(
+                                    GameError::NoUnderlyingCard {
+                                        object: top_item.id,
+                                    },
+                                )?;
+                                let behaviour = top_item
+                                    .behaviour(&cards)
+                                    .ok_or(GameError::CardNotFound { card })?;
 
-                        let resolve_effects = card
-                            .behaviour
-                            .effects
-                            .iter()
-                            .filter_map(|e| match e {
-                                CardEffect::Triggered(TriggeredCardEffect {
-                                    trigger: EffectTrigger::OnResolve,
-                                    effects,
-                                }) => Some(effects),
-                                _ => None,
-                            })
-                            .flatten()
-                            .enumerate()
-                            .collect::<Vec<_>>();
+                                let resolve_effects = behaviour
+                                    .effects
+                                    .iter()
+                                    .filter_map(|e| match e {
+                                        CardEffect::Triggered(TriggeredCardEffect {
+                                            trigger: EffectTrigger::OnResolve,
+                                            effects,
+                                        }) => Some(effects),
+                                        _ => None,
+                                    })
+                                    .flatten()
+                                    .enumerate()
+                                    .collect::<Vec<_>>();
+                                let leave_stack = if behaviour.is_permanent() {
+                                    GameAtom::MoveObject {
+                                        object: top_item.id,
+                                        from: ZoneId::Stack,
+                                        to: ZoneId::Battlefield,
+                                    }
+                                } else {
+                                    discard.clone()
+                                };
+                                (card, resolve_effects, leave_stack)
+                            }
+                        };
+
+                        if has_lost_all_targets(&self.game, &top_item, &resolve_effects) {
+                            trace!(?top_item.id, "All targets became illegal, fizzling");
+                            self.apply_atoms(vec![discard, GameAtom::ResetPriority])?;
+                            return Ok(());
+                        }
 
                         let mut atoms = vec![];
                         for (idx, effect) in resolve_effects {
                             if let Effect::Instant(eff) = effect {
-                                let info = top_item
+                                let mut info: HashMap<String, EffectInfo> = top_item
                                     .choices
                                     .iter()
                                     .filter(|((i, _), _)| *i == idx)
                                     .map(|((_, k), v)| (k.clone(), v.clone()))
                                     .collect();
 
-                                let effect_atoms =
-                                    assert_send(eff.execute(info, top_item.id, &self.game))
-                                        .await
-                                        .map_err(|e| GameError::EffectExecuteFailure {
-                                            failure: e,
-                                        })?;
+                                // Orderings need the objects of the other requests
+                                let mut requests: Vec<_> =
+                                    eff.get_required_info().into_iter().collect();
+                                requests.sort_by_key(|(_, request)| {
+                                    matches!(request, EffectInfoRequest::Ordering { .. })
+                                });
+                                for (name, request) in requests {
+                                    let found = match request {
+                                        EffectInfoRequest::LibrarySearch { restriction } => {
+                                            assert_send(self.search_library(
+                                                outside,
+                                                top_item.id,
+                                                name.clone(),
+                                                restriction.as_ref(),
+                                            ))
+                                            .await?
+                                            .map(EffectInfo::LibraryCard)
+                                        }
+                                        EffectInfoRequest::LibraryArrangement {
+                                            count,
+                                            allow_bottom,
+                                        } => Some(
+                                            assert_send(self.arrange_library(
+                                                outside,
+                                                top_item.id,
+                                                name.clone(),
+                                                count,
+                                                allow_bottom,
+                                            ))
+                                            .await?,
+                                        ),
+                                        EffectInfoRequest::Discard { count } => {
+                                            let player = self
+                                                .game
+                                                .get_controller_of(top_item.id)
+                                                .ok_or(GameError::NoControllerFound {
+                                                object: top_item.id,
+                                            })?;
+                                            Some(EffectInfo::Discarded(
+                                                assert_send(self.choose_discards(
+                                                    outside, player, player, count,
+                                                ))
+                                                .await?,
+                                            ))
+                                        }
+                                        EffectInfoRequest::TargetDiscard {
+                                            target,
+                                            count,
+                                            chooser,
+                                        } => {
+                                            // Without a targeted player the effect fails on its
+                                            // own when executed
+                                            let Some(EffectInfo::SingleTarget(TargetId::Player(
+                                                owner,
+                                            ))) = info.get(&target).cloned()
+                                            else {
+                                                continue;
+                                            };
+                                            let chooser = match chooser {
+                                                DiscardChooser::Owner => owner,
+                                                DiscardChooser::Controller => self
+                                                    .game
+                                                    .get_controller_of(top_item.id)
+                                                    .ok_or(GameError::NoControllerFound {
+                                                        object: top_item.id,
+                                                    })?,
+                                            };
+                                            Some(EffectInfo::Discarded(
+                                                assert_send(self.choose_discards(
+                                                    outside, owner, chooser, count,
+                                                ))
+                                                .await?,
+                                            ))
+                                        }
+                                        EffectInfoRequest::ChooseFromZone {
+                                            zone,
+                                            target,
+                                            count,
+                                        } => {
+                                            let owner = match target {
+                                                // Without a targeted player the effect fails on
+                                                // its own when executed
+                                                Some(target) => match info.get(&target) {
+                                                    Some(EffectInfo::SingleTarget(
+                                                        TargetId::Player(owner),
+                                                    )) => *owner,
+                                                    _ => continue,
+                                                },
+                                                None => self
+                                                    .game
+                                                    .get_controller_of(top_item.id)
+                                                    .ok_or(GameError::NoControllerFound {
+                                                        object: top_item.id,
+                                                    })?,
+                                            };
+                                            Some(EffectInfo::ZoneObjects(
+                                                assert_send(self.choose_from_zone(
+                                                    outside,
+                                                    top_item.id,
+                                                    name.clone(),
+                                                    ZoneId::of(zone, owner),
+                                                    count,
+                                                ))
+                                                .await?,
+                                            ))
+                                        }
+                                        EffectInfoRequest::Ordering { of } => {
+                                            let Some(EffectInfo::ZoneObjects(objects)) =
+                                                info.get(&of).cloned()
+                                            else {
+                                                continue;
+                                            };
+                                            Some(EffectInfo::Ordering(
+                                                assert_send(self.order_objects(
+                                                    outside,
+                                                    top_item.id,
+                                                    name.clone(),
+                                                    objects,
+                                                ))
+                                                .await?,
+                                            ))
+                                        }
+                                        _ => continue,
+                                    };
+                                    if let Some(found) = found {
+                                        info.insert(name, found);
+                                    }
+                                }
+
+                                let declined =
+                                    eff.get_required_info().into_iter().any(|(name, request)| {
+                                        request.is_optional() && !info.contains_key(&name)
+                                    });
+                                if declined {
+                                    trace!(idx, "Skipping effect with a declined optional choice");
+                                    continue;
+                                }
+
+                                let effect_atoms = assert_send(self.execute_effect(
+                                    eff.as_ref(),
+                                    card,
+                                    info,
+                                    top_item.id,
+                                ))
+                                .await?;
                                 atoms.extend(effect_atoms);
                             }
                         }
 
-                        atoms.push(GameAtom::PopStack);
+                        atoms.push(leave_stack);
                         atoms.push(GameAtom::ResetPriority);
 
                         self.apply_atoms(atoms)?;
                     } else {
-                        // Pass phases/turns
-                        todo!()
+                        assert_send(self.advance_phase()).await?;
                     }
                 } else {
                     let active_player = latest_gamestate.unpassed_players.first().unwrap();
 
+                    if !self
+                        .game
+                        .format
+                        .allows_action(latest_gamestate, *active_player)
+                    {
+                        let player = *active_player;
+                        trace!(
+                            ?player,
+                            "Player reached their action limit, passing for them"
+                        );
+                        self.apply_atoms(vec![GameAtom::PassPriority { player }])?;
+                        return Ok(());
+                    }
+
                     let mut possible_actions = vec![PlayerAction::PassPriority];
                     possible_actions.extend(
                         latest_gamestate
                             .get_hand(*active_player)
                             .objects
                             .iter()
-                            .map(|hand_obj| PlayerAction::PlayCard {
-                                from: ZoneId::Hand(*active_player),
-                                object: hand_obj.id,
+                            .flat_map(|hand_obj| {
+                                let card = hand_obj
+                                    .underlying_card
+                                    .and_then(|card| self.game.cards.get(&card));
+                                match card {
+                                    // Each half of a split card is played on its own
+                                    Some(card) => card
+                                        .halves()
+                                        .filter(|(_, half)| {
+                                            can_play_now(
+                                                &self.game.format,
+                                                latest_gamestate,
+                                                *active_player,
+                                                half,
+                                            )
+                                        })
+                                        .map(|(second_half, half)| {
+                                            (hand_obj.id, Some(half), second_half)
+                                        })
+                                        .collect(),
+                                    // Fails with a proper error once played
+                                    None => vec![(hand_obj.id, None, false)],
+                                }
+                            })
+                            .flat_map(|(object, half, second_half)| {
+                                let alternatives: Vec<_> = half
+                                    .into_iter()
+                                    .flat_map(|half| half.alternative_costs.iter())
+                                    .enumerate()
+                                    .filter(|(_, alternative)| {
+                                        can_pay_alternative(
+                                            latest_gamestate,
+                                            &self.game.cards,
+                                            *active_player,
+                                            object,
+                                            alternative,
+                                        )
+                                    })
+                                    .map(|(idx, _)| Some(idx))
+                                    .collect();
+                                // Cards whose scrip cost can not be paid may still be played
+                                // for an alternative cost
+                                let affordable = half.map_or(true, |half| {
+                                    cost::can_afford(&self.game, *active_player, half)
+                                });
+                                let from = ZoneId::Hand(*active_player);
+                                affordable
+                                    .then_some(None)
+                                    .into_iter()
+                                    .chain(alternatives)
+                                    .map(move |alternative| PlayerAction::PlayCard {
+                                        from,
+                                        object,
+                                        alternative,
+                                        second_half,
+                                    })
                             }),
                     );
-                    let action_idx = assert_send(
-                        outside
-                            .get_next_player_action_from(*active_player, possible_actions.clone()),
-                    )
+                    possible_actions.extend(
+                        activatable_abilities(&self.game, *active_player)
+                            .into_iter()
+                            .map(|(object, ability)| PlayerAction::ActivateAbility {
+                                object,
+                                ability,
+                            }),
+                    );
+                    possible_actions.extend(
+                        possible_attackers(&self.game, *active_player)
+                            .into_iter()
+                            .map(|attacker| PlayerAction::Attack { attacker }),
+                    );
+                    possible_actions.push(PlayerAction::Concede);
+                    let action_idx = assert_send(outside.get_next_player_action_from(
+                        self.prompt_context(),
+                        *active_player,
+                        possible_actions.clone(),
+                    ))
                     .await?;
 
                     let Some(action) = possible_actions.get(action_idx) else {
@@ -363,9 +2061,83 @@ impl GameImplV1 {
                             let atoms = vec![GameAtom::PassPriority {
                                 player: *active_player,
                             }];
+                            if !assert_send(self.confirm_action(outside, *active_player, &atoms))
+                                .await?
+                            {
+                                trace!("Player did not confirm passing");
+                                return Ok(());
+                            }
+                            self.apply_atoms(atoms)?;
+                        }
+                        PlayerAction::Concede => {
+                            // Conceding is elimination, which also decides whether the game is over
+                            let player = *active_player;
+                            let atoms = vec![GameAtom::EliminatePlayer { player }];
+                            if !assert_send(self.confirm_action(outside, player, &atoms)).await? {
+                                trace!("Player did not confirm conceding");
+                                return Ok(());
+                            }
+                            self.apply_atoms(atoms)?;
+                            self.game
+                                .eliminated_by
+                                .insert(player, EliminationCause::Conceded);
+                        }
+                        PlayerAction::Attack { attacker } => {
+                            assert_send(self.attack(outside, *attacker)).await?;
+                        }
+                        PlayerAction::ActivateAbility { object, ability } => {
+                            // Activated abilities do not use the stack, they happen right away
+                            let player = *active_player;
+                            let obj = latest_gamestate
+                                .get_object_from_zone(ZoneId::Battlefield, *object)
+                                .ok_or(GameError::ObjectNotFoundInZone {
+                                    zone: ZoneId::Battlefield,
+                                    object: *object,
+                                })?;
+                            let card = obj
+                                .underlying_card
+                                .ok_or(GameError::NoUnderlyingCard { object: *object })?;
+                            let cards = self.game.cards.clone();
+                            let behaviour = obj
+                                .behaviour(&cards)
+                                .ok_or(GameError::CardNotFound { card })?;
+                            let activated = activated_effects(behaviour)
+                                .nth(*ability)
+                                .ok_or(GameError::InvalidCardState)?;
+
+                            // Unlike the cost of cards, the scrip cost of abilities is not paid yet,
+                            // exhausting is paid by the atom failing for exhausted objects
+                            let mut atoms = vec![];
+                            if activated.exhaust {
+                                atoms.push(GameAtom::Exhaust { object: *object });
+                            }
+                            for effect in &activated.effect {
+                                let Effect::Instant(effect) = effect else {
+                                    return Err(GameError::InvalidCardState);
+                                };
+                                atoms.extend(
+                                    assert_send(self.execute_effect(
+                                        effect.as_ref(),
+                                        card,
+                                        HashMap::new(),
+                                        *object,
+                                    ))
+                                    .await?,
+                                );
+                            }
+
+                            if !assert_send(self.confirm_action(outside, player, &atoms)).await? {
+                                trace!("Player did not confirm activating the ability");
+                                return Ok(());
+                            }
                             self.apply_atoms(atoms)?;
                         }
-                        PlayerAction::PlayCard { from, object } => {
+                        PlayerAction::PlayCard {
+                            from,
+                            object,
+                            alternative,
+                            second_half,
+                        } => {
                             // Playing a card is a fairly involved process as it needs to be as
                             // intuitive as possible
                             //
@@ -383,7 +2155,7 @@ impl GameImplV1 {
                             let latest_gamestate = self.latest_gamestate();
 
                             let active_player =
-                                latest_gamestate.active_player_order.first().unwrap();
+                                *latest_gamestate.active_player_order.first().unwrap();
 
                             let obj = latest_gamestate
                                 .get_object_from_zone(*from, *object)
@@ -397,14 +2169,16 @@ impl GameImplV1 {
                                 .as_ref()
                                 .ok_or(GameError::NoUnderlyingCard { object: *object })?;
 
-                            let card = self
-                                .game
-                                .cards
+                            let cards = self.game.cards.clone();
+                            let behaviour = cards
                                 .get(card)
-                                .ok_or(GameError::CardNotFound { card: *card })?;
+                                .ok_or(GameError::CardNotFound { card: *card })?
+                                .halves()
+                                .find(|(second, _)| second == second_half)
+                                .map(|(_, half)| half)
+                                .ok_or(GameError::InvalidCardState)?;
 
-                            let resolve_effects = card
-                                .behaviour
+                            let resolve_effects = behaviour
                                 .effects
                                 .iter()
                                 .filter_map(|e| match e {
@@ -418,99 +2192,93 @@ impl GameImplV1 {
                                 .enumerate()
                                 .collect::<Vec<_>>();
 
-                            let mut gathered_info = HashMap::new();
-                            for (idx, e) in resolve_effects {
-                                match e {
-                                    Effect::Continuous(_) => {
-                                        return Err(GameError::InvalidCardState)
-                                    }
-                                    Effect::Instant(instant) => {
-                                        let required_info = instant.get_required_info();
-                                        for (name, question) in required_info {
-                                            match question {
-                                                EffectInfoRequest::SingleTarget { restriction } => {
-                                                    if restriction.is_some() {
-                                                        todo!()
-                                                    } else {
-                                                        // Without any restrictions targets can
-                                                        // _only_ be agents on the battlefield _or_
-                                                        // players
-                                                        let mut possible_choices = vec![];
-                                                        possible_choices.extend(
-                                                            self.game
-                                                                .players
-                                                                .keys()
-                                                                .map(|p| TargetId::Player(*p)),
-                                                        );
-                                                        possible_choices.extend(
-                                                            latest_gamestate
-                                                                .get_battlefield()
-                                                                .objects
-                                                                .iter()
-                                                                .filter(|_o| todo!())
-                                                                .map(|o| TargetId::Object(o.id)),
-                                                        );
-                                                        let choices = assert_send(
-                                                            outside.get_target_choices_from_given(
-                                                                *active_player,
-                                                                *object,
-                                                                name.clone(),
-                                                                possible_choices.clone(),
-                                                                1,
-                                                            ),
-                                                        )
-                                                        .await?;
-
-                                                        if choices.len() != 1 {
-                                                            return Err(
-                                                                GameError::InvalidChoiceAmount {
-                                                                    expected: 1,
-                                                                    received: choices.len(),
-                                                                },
-                                                            );
-                                                        }
-
-                                                        let selected_choices: Vec<TargetId> =
-                                                            possible_choices
-                                                                .into_iter()
-                                                                .enumerate()
-                                                                .filter_map(|(idx, choice)| {
-                                                                    choices
-                                                                        .contains(&idx)
-                                                                        .then_some(choice)
-                                                                })
-                                                                .collect();
-                                                        gathered_info.insert(
-                                                            (idx, name),
-                                                            EffectInfo::SingleTarget(
-                                                                selected_choices[0],
-                                                            ),
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                            let gathered_info = assert_send(self.gather_choices(
+                                outside,
+                                active_player,
+                                *object,
+                                &resolve_effects,
+                            ))
+                            .await?;
                             // Step 3
-                            // Calculate costs
+                            let x = gathered_info
+                                .iter()
+                                .find_map(|((_, name), info)| match info {
+                                    EffectInfo::Number(x) if name == VARIABLE_COST_INFO => Some(*x),
+                                    _ => None,
+                                })
+                                .unwrap_or(0);
+                            let total_cost = behaviour
+                                .cost
+                                .as_ref()
+                                .map(|c| c.with_variable(x))
+                                .unwrap_or_default();
+                            let total_cost = cost::modified_cost(
+                                &self.game,
+                                active_player,
+                                behaviour,
+                                total_cost,
+                            );
+
                             // Step 4
-                            // Pay costs
+                            // Pay costs, an alternative cost replaces the scrip cost entirely
+                            let (total_cost, payment) = match alternative {
+                                Some(idx) => {
+                                    let alternative = behaviour
+                                        .alternative_costs
+                                        .get(*idx)
+                                        .ok_or(GameError::InvalidCardState)?;
+                                    let payment = assert_send(self.pay_alternative_cost(
+                                        outside,
+                                        active_player,
+                                        *object,
+                                        alternative,
+                                    ))
+                                    .await?;
+                                    (Cost::default(), payment)
+                                }
+                                None => {
+                                    let payment = cost::payment(
+                                        self.latest_gamestate(),
+                                        active_player,
+                                        &total_cost,
+                                    )?;
+                                    (total_cost, payment)
+                                }
+                            };
+                            trace!(?total_cost, ?payment, "Calculated cost of card");
                             // Step 5
 
-                            let player_passing =
-                                assert_send(outside.get_player_passing(*active_player)).await?;
+                            let player_passing = if self
+                                .game
+                                .preferences_of(active_player)
+                                .is_some_and(|p| p.auto_pass_after_play)
+                            {
+                                true
+                            } else {
+                                assert_send(
+                                    outside
+                                        .get_player_passing(self.prompt_context(), active_player),
+                                )
+                                .await?
+                            };
 
-                            let mut atoms = vec![GameAtom::PlayerPlayCard {
-                                player: *active_player,
+                            let mut atoms = payment;
+                            atoms.push(GameAtom::PlayerPlayCard {
+                                player: active_player,
                                 from: *from,
                                 object: *object,
                                 choices: gathered_info,
-                            }];
+                                second_half: *second_half,
+                            });
                             atoms.extend(player_passing.then_some(GameAtom::PassPriority {
-                                player: *active_player,
+                                player: active_player,
                             }));
+                            if !assert_send(self.confirm_action(outside, active_player, &atoms))
+                                .await?
+                            {
+                                trace!("Player did not confirm playing the card");
+                                return Ok(());
+                            }
                             self.apply_atoms(atoms)?;
                         }
                     }
@@ -520,572 +2288,7559 @@ impl GameImplV1 {
 
         Ok(())
     }
-}
 
-fn new_game_state_with(
-    rand: &mut impl Rng,
-    players: &std::collections::HashMap<PlayerId, Player>,
-    order: &[PlayerId],
-) -> GameState {
-    GameState {
-        game_stage: GameStage::KeepHand {
-            players_keeping: Default::default(),
-        },
-        active_player_order: order.to_vec(),
-        unpassed_players: order.to_vec(),
-        zones: players
-            .values()
-            .flat_map(|p| {
-                vec![
-                    (ZoneId::Hand(p.id), GameZone::empty()),
-                    (
-                        ZoneId::Library(p.id),
-                        GameZone::with(
-                            p.initial_cards
-                                .iter()
-                                .map(|c| GameObject::from_card(rand, *c))
-                                .collect(),
-                        ),
-                    ),
-                    (ZoneId::Discard(p.id), GameZone::empty()),
-                ]
-            })
-            .chain(vec![
-                (ZoneId::Battlefield, GameZone::empty()),
-                (ZoneId::Stack, GameZone::empty()),
-            ])
-            .collect(),
+    /// Lets the controller of `source` search their library
+    ///
+    /// Returns the found object, if any.
+    async fn search_library(
+        &mut self,
+        outside: &impl OutsideGame,
+        source: ObjectId,
+        name: String,
+        restriction: Option<&TargetRestriction>,
+    ) -> Result<Option<ObjectId>, GameError> {
+        let player = self
+            .game
+            .get_controller_of(source)
+            .ok_or(GameError::NoControllerFound { object: source })?;
+
+        let choices = target::searchable_cards(&self.game, player, restriction);
+        let shown = self.show_to(player, &choices);
+        let answer = assert_send(outside.get_library_search_choice(
+            self.prompt_context(),
+            player,
+            source,
+            name,
+            shown,
+        ))
+        .await;
+        self.prompt_objects.clear(player);
+        let choice = self.answer_or_default(answer, player, || None)?;
+
+        let Some(choice) = choice else {
+            trace!(?player, "Player failed to find a card");
+            return Ok(None);
+        };
+
+        match choices.get(choice) {
+            Some((object, _)) => Ok(Some(*object)),
+            None => Err(GameError::InvalidChoice {
+                list_length: choices.len(),
+                selected: choice,
+            }),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
-    use std::str::FromStr;
-    use std::sync::Arc;
+    /// Lets the controller of `source` look at the top `count` cards of their library and decide
+    /// where they go
+    async fn arrange_library(
+        &mut self,
+        outside: &impl OutsideGame,
+        source: ObjectId,
+        name: String,
+        count: usize,
+        allow_bottom: bool,
+    ) -> Result<EffectInfo, GameError> {
+        let player = self
+            .game
+            .get_controller_of(source)
+            .ok_or(GameError::NoControllerFound { object: source })?;
 
-    use rand::SeedableRng;
-    use rand_xoshiro::Xoshiro256StarStar;
-    use tarpc::server::Channel;
-    use tarpc::transport::channel::UnboundedChannel;
-    use tarpc::ClientMessage;
-    use tarpc::Response;
-    use technomancy_core::card::BaseCardKind;
-    use technomancy_core::card::Card;
-    use technomancy_core::card::CardBehaviour;
-    use technomancy_core::card::CardEffect;
-    use technomancy_core::card::CardId;
-    use technomancy_core::card::CardKind;
-    use technomancy_core::card::Cost;
-    use technomancy_core::card::TriggeredCardEffect;
-    use technomancy_core::effect::Effect;
-    use technomancy_core::effect::EffectTrigger;
-    use technomancy_core::outside::Outside;
-    use technomancy_core::outside::OutsideClient;
-    use technomancy_core::outside::OutsideRequest;
-    use technomancy_core::outside::OutsideResponse;
-    use technomancy_core::GameId;
-    use technomancy_core::ObjectId;
-    use technomancy_core::Player;
-    use technomancy_core::PlayerAction;
-    use technomancy_core::PlayerId;
-    use technomancy_core::TargetId;
-    use technomancy_core::ZoneId;
-    use tokio::sync::Mutex;
-    use uuid::Uuid;
+        // The end of the library is its top
+        let cards: Vec<(ObjectId, CardId)> = self
+            .latest_gamestate()
+            .zones
+            .get(&self.game.library_of(player))
+            .into_iter()
+            .flat_map(|zone| zone.objects.iter().rev().take(count))
+            .filter_map(|o| Some((o.id, o.underlying_card?)))
+            .collect();
 
-    use crate::effect::tests::DealDamage;
-    use crate::effect::tests::DrawCards;
-    use crate::outside::OutsideGameClient;
-    use crate::GameImplV1;
+        let shown = self.show_to(player, &cards);
+        let answer = assert_send(outside.get_library_arrangement(
+            self.prompt_context(),
+            player,
+            source,
+            name,
+            shown,
+            allow_bottom,
+        ))
+        .await;
+        self.prompt_objects.clear(player);
+        let (top, bottom) =
+            self.answer_or_default(answer, player, || ((0..cards.len()).collect(), vec![]))?;
 
-    const BLAST_CARD: uuid::Uuid = uuid::uuid!("4abc4619-b61c-44a4-9d37-8a31bda65b48");
-    const DRAW_CARD: uuid::Uuid = uuid::uuid!("ddfbf54b-2750-41c6-b657-1d6ce1e754ef");
+        if !allow_bottom && !bottom.is_empty() {
+            return Err(GameError::InvalidChoiceAmount {
+                expected: 0,
+                received: bottom.len(),
+            });
+        }
 
-    #[allow(unused)]
-    fn check_send() {
-        let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
-        crate::assert_send(harness.game_impl.run(&harness.outside_client));
-    }
+        let mut chosen: Vec<usize> = top.iter().chain(&bottom).copied().collect();
+        chosen.sort_unstable();
+        chosen.dedup();
+        if chosen.len() != cards.len() || top.len() + bottom.len() != cards.len() {
+            return Err(GameError::InvalidChoiceAmount {
+                expected: cards.len(),
+                received: top.len() + bottom.len(),
+            });
+        }
 
-    fn existing_cards() -> HashMap<CardId, Card> {
-        let blast = Card {
-            id: CardId::with(BLAST_CARD),
-            behaviour: CardBehaviour {
-                cost: Some(Cost {
-                    corp1_scrip: 2,
-                    ..Default::default()
-                }),
-                kind: vec![CardKind {
-                    kind: BaseCardKind::Quickhack,
-                }],
-                effects: vec![CardEffect::Triggered(TriggeredCardEffect {
-                    trigger: EffectTrigger::OnResolve,
-                    effects: vec![Effect::Instant(Box::new(DealDamage(3)))],
-                })],
-            },
+        let objects = |indices: Vec<usize>| {
+            indices
+                .into_iter()
+                .map(|idx| {
+                    cards
+                        .get(idx)
+                        .map(|(object, _)| *object)
+                        .ok_or(GameError::InvalidChoice {
+                            list_length: cards.len(),
+                            selected: idx,
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()
         };
 
-        let draw = Card {
-            id: CardId::with(BLAST_CARD),
-            behaviour: CardBehaviour {
-                cost: Some(Cost {
-                    corp1_scrip: 2,
-                    ..Default::default()
-                }),
-                kind: vec![CardKind {
-                    kind: BaseCardKind::Quickhack,
-                }],
-                effects: vec![CardEffect::Triggered(TriggeredCardEffect {
-                    trigger: EffectTrigger::OnResolve,
-                    effects: vec![Effect::Instant(Box::new(DrawCards(3)))],
-                })],
-            },
-        };
+        Ok(EffectInfo::LibraryArrangement {
+            top: objects(top)?,
+            bottom: objects(bottom)?,
+        })
+    }
 
-        [(blast.id, blast), (draw.id, draw)].into()
+    /// Lets the player pay an alternative cost for playing `object`, returning the atoms paying it
+    async fn pay_alternative_cost(
+        &mut self,
+        outside: &impl OutsideGame,
+        player: PlayerId,
+        object: ObjectId,
+        alternative: &AlternativeCost,
+    ) -> Result<Vec<GameAtom>, GameError> {
+        match alternative {
+            AlternativeCost::Discard { count } => {
+                let cards =
+                    discardable_cards(self.latest_gamestate(), &self.game.cards, player, object);
+                let answer = assert_send(outside.get_discard_choice(
+                    self.prompt_context(),
+                    player,
+                    cards.clone(),
+                    *count,
+                ))
+                .await;
+                let choices = self.answer_or_default(answer, player, || (0..*count).collect())?;
+                let objects = chosen_cards(&cards, choices, *count)?;
+                Ok(vec![GameAtom::DiscardCards { player, objects }])
+            }
+            AlternativeCost::PayLife { amount } => Ok(vec![GameAtom::LoseLife {
+                player,
+                amount: *amount,
+            }]),
+            AlternativeCost::Free { .. } => Ok(vec![]),
+        }
     }
 
-    fn simple_deck() -> Vec<CardId> {
-        vec![
-            CardId::with(BLAST_CARD),
-            CardId::with(BLAST_CARD),
-            CardId::with(BLAST_CARD),
-            CardId::with(BLAST_CARD),
-            CardId::with(DRAW_CARD),
-            CardId::with(DRAW_CARD),
-            CardId::with(DRAW_CARD),
-            CardId::with(DRAW_CARD),
-        ]
+    /// Lets the chooser choose `count` cards from the player's hand to discard
+    ///
+    /// If the player does not hold more than that, their whole hand is discarded without asking.
+    async fn choose_discards(
+        &mut self,
+        outside: &impl OutsideGame,
+        player: PlayerId,
+        chooser: PlayerId,
+        count: usize,
+    ) -> Result<Vec<ObjectId>, GameError> {
+        let cards: Vec<_> = hand_cards(self.latest_gamestate(), player)
+            .into_iter()
+            .filter(|(_, card)| {
+                !self
+                    .game
+                    .cards
+                    .get(card)
+                    .is_some_and(|c| c.behaviour.has_keyword(Keyword::Persistent))
+            })
+            .collect();
+
+        if cards.len() <= count {
+            return Ok(cards.into_iter().map(|(object, _)| object).collect());
+        }
+
+        let answer = assert_send(outside.get_discard_choice(
+            self.prompt_context(),
+            chooser,
+            cards.clone(),
+            count,
+        ))
+        .await;
+        let choices = self.answer_or_default(answer, chooser, || (0..count).collect())?;
+
+        chosen_cards(&cards, choices, count)
     }
 
-    fn playtesters() -> HashMap<PlayerId, Player> {
-        vec![
-            Player {
-                id: PlayerId::new(),
-                initial_cards: simple_deck(),
-            },
-            Player {
-                initial_cards: simple_deck(),
-                id: PlayerId::new(),
-            },
-        ]
-        .into_iter()
-        .map(|p| (p.id, p))
-        .collect()
+    /// Lets the controller of `source` choose `count` objects in the zone
+    ///
+    /// The cards are only shown if the controller may look at the zone, otherwise they choose
+    /// blindly.
+    async fn choose_from_zone(
+        &mut self,
+        outside: &impl OutsideGame,
+        source: ObjectId,
+        name: String,
+        zone: ZoneId,
+        count: usize,
+    ) -> Result<Vec<ObjectId>, GameError> {
+        let player = self
+            .game
+            .get_controller_of(source)
+            .ok_or(GameError::NoControllerFound { object: source })?;
+
+        let objects: Vec<(ObjectId, CardId)> = self
+            .latest_gamestate()
+            .zones
+            .get(&zone)
+            .into_iter()
+            .flat_map(|zone| zone.objects.iter())
+            .filter_map(|o| Some((o.id, o.underlying_card?)))
+            .collect();
+        if objects.len() <= count {
+            return Ok(objects.into_iter().map(|(object, _)| object).collect());
+        }
+
+        let shown = self.show_objects(player, zone, &objects);
+        let answer = assert_send(outside.get_zone_choice(
+            self.prompt_context(),
+            player,
+            source,
+            name,
+            zone,
+            shown,
+            count,
+        ))
+        .await;
+        self.prompt_objects.clear(player);
+        let choices = self.answer_or_default(answer, player, || (0..count).collect())?;
+
+        chosen_cards(&objects, choices, count)
     }
 
-    fn outside_client(
-        game_id: GameId,
-    ) -> (
-        tarpc::transport::channel::UnboundedChannel<
-            tarpc::ClientMessage<OutsideRequest>,
-            tarpc::Response<OutsideResponse>,
-        >,
-        OutsideGameClient,
-    ) {
-        let (left, right) = tarpc::transport::channel::unbounded();
-        let client = OutsideClient::new(tarpc::client::Config::default(), left).spawn();
-        (
-            right,
-            OutsideGameClient {
-                game_id,
-                client: Arc::new(client),
-            },
-        )
+    /// Lets the controller of `source` put the objects in order, the first one first
+    async fn order_objects(
+        &mut self,
+        outside: &impl OutsideGame,
+        source: ObjectId,
+        name: String,
+        objects: Vec<ObjectId>,
+    ) -> Result<Vec<ObjectId>, GameError> {
+        let player = self
+            .game
+            .get_controller_of(source)
+            .ok_or(GameError::NoControllerFound { object: source })?;
+
+        let state = self.latest_gamestate();
+        let found: Vec<_> = objects
+            .iter()
+            .filter_map(|object| state.find_object(*object))
+            .collect();
+        let items: Vec<(ObjectId, CardId)> = found
+            .iter()
+            .filter_map(|(_, o)| Some((o.id, o.underlying_card?)))
+            .collect();
+        if items.len() <= 1 {
+            return Ok(items.into_iter().map(|(object, _)| object).collect());
+        }
+
+        let shown = if found
+            .iter()
+            .all(|(zone, _)| self.can_look_at(*zone, player))
+        {
+            self.show_to(player, &items)
+        } else {
+            ObjectList {
+                ids: items.iter().map(|(object, _)| *object).collect(),
+                cards: vec![],
+            }
+        };
+        let answer =
+            assert_send(outside.get_ordering(self.prompt_context(), player, source, name, shown))
+                .await;
+        self.prompt_objects.clear(player);
+        let order = self.answer_or_default(answer, player, || (0..items.len()).collect())?;
+
+        chosen_cards(&items, order, items.len())
     }
 
-    struct ServerAnswers {
-        get_player_keeping: Option<Box<dyn FnMut(Vec<PlayerId>) -> Vec<PlayerId> + Send>>,
-        get_next_player_action_from:
-            Option<Box<dyn FnMut(PlayerId, Vec<PlayerAction>) -> usize + Send>>,
-        get_target_choices_from_given: Option<
-            Box<dyn FnMut(PlayerId, ObjectId, String, Vec<TargetId>, usize) -> Vec<usize> + Send>,
-        >,
-        get_player_passing: Option<Box<dyn FnMut(PlayerId) -> bool + Send>>,
+    /// Describes the objects for a prompt to `player`, remembering them until it is answered
+    ///
+    /// Face-down objects the player may not know are described without their card.
+    fn show_to(&self, player: PlayerId, objects: &[(ObjectId, CardId)]) -> ObjectList {
+        let state = self.latest_gamestate();
+        let known: Vec<_> = objects
+            .iter()
+            .map(|(object, card)| {
+                let hidden = state
+                    .find_object(*object)
+                    .is_some_and(|(_, o)| !o.is_known_to(player));
+                (*object, (!hidden).then_some(*card))
+            })
+            .collect();
+        self.prompt_objects.show(player, &known)
     }
 
-    impl Default for ServerAnswers {
-        fn default() -> Self {
-            Self {
-                get_player_keeping: Some(Box::new(|players| players)),
-                get_next_player_action_from: Default::default(),
-                get_target_choices_from_given: Default::default(),
-                get_player_passing: Default::default(),
-            }
+    /// Whether `player` may look at the cards of the objects in the zone
+    fn can_look_at(&self, zone: ZoneId, player: PlayerId) -> bool {
+        match self.game.zone_metadata(zone).map(|m| m.visibility) {
+            Some(ZoneVisibility::Public) => true,
+            Some(ZoneVisibility::Owner) => zone.player() == Some(player),
+            Some(ZoneVisibility::Hidden) | None => false,
         }
     }
 
-    #[derive(Clone)]
-    struct SimpleOutsideServer {
-        answers: Arc<Mutex<ServerAnswers>>,
+    /// Describes the objects of the zone for a prompt to `player`, without their cards if the
+    /// player may not look at the zone
+    fn show_objects(
+        &self,
+        player: PlayerId,
+        zone: ZoneId,
+        objects: &[(ObjectId, CardId)],
+    ) -> ObjectList {
+        if self.can_look_at(zone, player) {
+            self.show_to(player, objects)
+        } else {
+            ObjectList {
+                ids: objects.iter().map(|(object, _)| *object).collect(),
+                cards: vec![],
+            }
+        }
     }
 
-    #[tarpc::server]
-    impl Outside for SimpleOutsideServer {
-        async fn get_player_keeping(
-            self,
-            _context: tarpc::context::Context,
-            _game_id: GameId,
-            asked_players: Vec<PlayerId>,
-        ) -> Vec<PlayerId> {
-            self.answers
-                .lock()
-                .await
-                .get_player_keeping
-                .as_mut()
-                .expect("No method set: get_player_keeping")(asked_players)
+    /// Attacks the defending player with `attacker`, see [`PlayerAction::Attack`]
+    ///
+    /// The defender declares which of their ready agents block, then the attacking player assigns
+    /// its damage among them. Blocking does not exhaust, an unblocked attacker deals its damage to
+    /// the defender instead.
+    async fn attack(
+        &mut self,
+        outside: &impl OutsideGame,
+        attacker: ObjectId,
+    ) -> Result<(), GameError> {
+        let player = self
+            .game
+            .get_controller_of(attacker)
+            .ok_or(GameError::NoControllerFound { object: attacker })?;
+        let defender = defender_of(&self.game, player).ok_or(GameError::InvalidCardState)?;
+
+        let candidates: Vec<_> = possible_blockers(&self.game, defender)
+            .into_iter()
+            .map(TargetId::Object)
+            .collect();
+        let chosen = if candidates.is_empty() {
+            vec![]
+        } else {
+            let answer = assert_send(outside.get_multiple_target_choices(
+                self.prompt_context(),
+                defender,
+                attacker,
+                String::from("blockers"),
+                candidates.clone(),
+                0,
+                candidates.len(),
+            ))
+            .await;
+            self.answer_or_default(answer, defender, Vec::new)?
+        };
+        let mut blockers = Vec::with_capacity(chosen.len());
+        for choice in chosen {
+            match candidates.get(choice) {
+                Some(TargetId::Object(blocker)) if !blockers.contains(blocker) => {
+                    blockers.push(*blocker)
+                }
+                _ => {
+                    return Err(GameError::InvalidChoice {
+                        list_length: candidates.len(),
+                        selected: choice,
+                    })
+                }
+            }
         }
-        async fn get_next_player_action_from(
-            self,
-            _context: tarpc::context::Context,
-            _game_id: GameId,
-            player: PlayerId,
-            player_actions: Vec<PlayerAction>,
-        ) -> usize {
-            self.answers
-                .lock()
-                .await
-                .get_next_player_action_from
-                .as_mut()
-                .expect("No method set: get_next_player_action_from")(
-                player, player_actions
-            )
+
+        // Both choices are made before anything happens, so that refused answers leave the game as
+        // it was
+        let assigned = assert_send(self.assign_combat_damage(outside, attacker, &blockers)).await?;
+        trace!(?attacker, ?defender, ?assigned, "Attacking");
+        self.apply_atoms(vec![GameAtom::Exhaust { object: attacker }])?;
+        if blockers.is_empty() {
+            let power = combatant(&self.game, attacker)?.1.power;
+            self.apply_atoms(vec![GameAtom::DealDamage {
+                amount: power as usize,
+                source: attacker,
+                target: TargetId::Player(defender),
+            }])
+        } else {
+            self.deal_combat_damage(attacker, assigned)
         }
-        async fn get_target_choices_from_given(
-            self,
-            _context: tarpc::context::Context,
-            _game_id: GameId,
-            player: PlayerId,
-            source: ObjectId,
-            name: String,
-            choices: Vec<TargetId>,
-            count: usize,
-        ) -> Vec<usize> {
-            self.answers
-                .lock()
-                .await
-                .get_target_choices_from_given
-                .as_mut()
-                .expect("No method set: get_target_choices_from_given")(
-                player, source, name, choices, count,
-            )
+    }
+
+    /// Lets the controller of `attacker` order its `blockers` and split its damage among them
+    ///
+    /// Returns the blockers in the chosen order with the damage each is dealt.
+    pub async fn assign_combat_damage(
+        &mut self,
+        outside: &impl OutsideGame,
+        attacker: ObjectId,
+        blockers: &[ObjectId],
+    ) -> Result<Vec<(ObjectId, u64)>, GameError> {
+        let player = self
+            .game
+            .get_controller_of(attacker)
+            .ok_or(GameError::NoControllerFound { object: attacker })?;
+
+        let damage = combatant(&self.game, attacker)?.1.power;
+        let blockers = blockers
+            .iter()
+            .map(|b| combatant(&self.game, *b).map(|(card, c)| (*b, card, c.toughness)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let [(blocker, _, _)] = blockers[..] {
+            // There is nothing to order or split
+            return Ok(vec![(blocker, damage)]);
+        }
+        if blockers.is_empty() {
+            return Ok(vec![]);
         }
 
-        async fn get_player_passing(
-            self,
-            _context: tarpc::context::Context,
-            _game_id: GameId,
-            player: PlayerId,
-        ) -> bool {
-            self.answers
-                .lock()
-                .await
-                .get_player_passing
-                .as_mut()
-                .expect("No method set: get_player_passing")(player)
+        let answer = assert_send(outside.get_damage_assignment(
+            self.prompt_context(),
+            player,
+            attacker,
+            blockers.iter().map(|(b, card, _)| (*b, *card)).collect(),
+            damage,
+        ))
+        .await;
+        let assignment = self.answer_or_default(answer, player, || {
+            let lethal: Vec<_> = blockers
+                .iter()
+                .map(|(_, _, toughness)| *toughness)
+                .collect();
+            combat::default_damage_assignment(&lethal, damage)
+                .into_iter()
+                .enumerate()
+                .collect()
+        })?;
+
+        let mut order: Vec<_> = assignment.iter().map(|(idx, _)| *idx).collect();
+        order.sort_unstable();
+        order.dedup();
+        if order.len() != blockers.len() || assignment.len() != blockers.len() {
+            return Err(GameError::InvalidChoiceAmount {
+                expected: blockers.len(),
+                received: assignment.len(),
+            });
         }
+
+        let ordered = assignment
+            .iter()
+            .map(|(idx, _)| {
+                blockers
+                    .get(*idx)
+                    .map(|(b, _, toughness)| (*b, *toughness))
+                    .ok_or(GameError::InvalidChoice {
+                        list_length: blockers.len(),
+                        selected: *idx,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let dealt: Vec<_> = assignment.iter().map(|(_, dealt)| *dealt).collect();
+        combat::validate_damage_assignment(&ordered, damage, &dealt)?;
+
+        Ok(ordered
+            .into_iter()
+            .map(|(blocker, _)| blocker)
+            .zip(dealt)
+            .collect())
     }
 
-    struct SimpleTestHarness {
-        player_order: Vec<PlayerId>,
-        game_impl: GameImplV1,
-        outside_client: OutsideGameClient,
-        answers: Arc<Mutex<ServerAnswers>>,
+    /// Deals the combat damage between `attacker` and the blockers it `assigned` damage to, see
+    /// [`combat::fight`]
+    ///
+    /// The agents that die are put into the discard pile of their controller after each damage
+    /// step, so that agents killed by overclocked ones do not deal damage themselves.
+    fn deal_combat_damage(
+        &mut self,
+        attacker: ObjectId,
+        assigned: Vec<(ObjectId, u64)>,
+    ) -> Result<(), GameError> {
+        let attacker = combatant(&self.game, attacker)?.1;
+        let blockers = assigned
+            .into_iter()
+            .map(|(b, dealt)| Ok((combatant(&self.game, b)?.1, dealt)))
+            .collect::<Result<Vec<_>, GameError>>()?;
+
+        let deaths = combat::fight(attacker, &blockers);
+        for step in [DamageStep::Overclocked, DamageStep::Regular] {
+            let atoms = deaths
+                .iter()
+                .filter(|(s, _)| *s == step)
+                .map(|(_, object)| {
+                    let owner = self
+                        .latest_gamestate()
+                        .get_object_from_zone(ZoneId::Battlefield, *object)
+                        .and_then(|o| o.owner_or_controller())
+                        .ok_or(GameError::NoControllerFound { object: *object })?;
+                    Ok(GameAtom::MoveObject {
+                        object: *object,
+                        from: ZoneId::Battlefield,
+                        to: ZoneId::Discard(owner),
+                    })
+                })
+                .collect::<Result<Vec<_>, GameError>>()?;
+            if !atoms.is_empty() {
+                trace!(?step, ?atoms, "Agents died in combat");
+                self.apply_atoms(atoms)?;
+            }
+        }
+
+        Ok(())
     }
 
-    fn init_harness(
-        seed: Option<u64>,
-    ) -> (
-        Vec<PlayerId>,
-        GameImplV1,
-        tarpc::transport::channel::UnboundedChannel<
-            tarpc::ClientMessage<OutsideRequest>,
-            tarpc::Response<OutsideResponse>,
-        >,
-        OutsideGameClient,
-    ) {
-        let rand = Xoshiro256StarStar::seed_from_u64(seed.unwrap_or(1337));
-        let players = playtesters();
-        let player_order: Vec<_> = players.keys().copied().collect();
-        let cards = existing_cards();
+    /// Lets the player choose `count` cards from their hand to put on the bottom of their library
+    async fn choose_cards_to_bottom(
+        &mut self,
+        outside: &impl OutsideGame,
+        player: PlayerId,
+        count: usize,
+    ) -> Result<Vec<ObjectId>, GameError> {
+        let cards = hand_cards(self.latest_gamestate(), player);
 
-        let id = GameId::new();
-        let game_impl = GameImplV1::new(id, rand, Arc::new(cards), players, player_order.clone());
+        if cards.len() <= count {
+            return Ok(cards.into_iter().map(|(object, _)| object).collect());
+        }
 
-        let (server, outside_client) = outside_client(game_impl.game.id);
+        let answer = assert_send(outside.get_cards_to_bottom(
+            self.prompt_context(),
+            player,
+            cards.clone(),
+            count,
+        ))
+        .await;
+        let choices = self.answer_or_default(answer, player, || (0..count).collect())?;
 
-        (player_order, game_impl, server, outside_client)
+        chosen_cards(&cards, choices, count)
     }
 
-    impl SimpleTestHarness {
-        fn new(seed: Option<u64>, answers: ServerAnswers) -> Self {
-            let (harness, server) = Self::new_with_server(seed, answers);
+    /// Makes the active player discard down to the maximum hand size of the format
+    async fn discard_to_hand_size(
+        &mut self,
+        outside: &impl OutsideGame,
+    ) -> Result<Vec<GameAtom>, GameError> {
+        let Some(max_hand_size) = self.game.format.max_hand_size else {
+            return Ok(vec![]);
+        };
 
-            let server = tarpc::server::BaseChannel::with_defaults(server);
-            let _outside_server = tokio::spawn(
-                server.execute(
-                    SimpleOutsideServer {
-                        answers: harness.answers.clone(),
-                    }
-                    .serve(),
-                ),
-            );
-
-            harness
+        let state = self.latest_gamestate();
+        let player = *state.active_player_order.first().unwrap();
+        let excess = state
+            .get_hand(player)
+            .objects
+            .len()
+            .saturating_sub(max_hand_size);
+        if excess == 0 {
+            return Ok(vec![]);
         }
-        fn new_with_server(
-            seed: Option<u64>,
-            answers: ServerAnswers,
-        ) -> (
-            SimpleTestHarness,
-            UnboundedChannel<ClientMessage<OutsideRequest>, Response<OutsideResponse>>,
-        ) {
-            let (player_order, game_impl, server, outside_client) = init_harness(seed);
 
-            (
-                SimpleTestHarness {
-                    player_order,
-                    game_impl,
-                    outside_client,
-                    answers: Arc::new(Mutex::new(answers)),
-                },
-                server,
-            )
-        }
+        trace!(?player, excess, "Player is above the maximum hand size");
+        let objects = assert_send(self.choose_discards(outside, player, player, excess)).await?;
+        Ok(vec![GameAtom::DiscardCards { player, objects }])
     }
 
-    macro_rules! game_steps {
-        (@set $harness:ident $action:ident = $($func:tt)*) => {
-            $harness.answers.lock().await.$action = Some(Box::new($($func)*));
+    /// Puts extra copies of a [`Keyword::Unique`] card into the discard pile of their controller,
+    /// who chooses which copy to keep
+    ///
+    /// Only one card is checked at a time, the game runs again for the next one.
+    async fn enforce_uniqueness(
+        &mut self,
+        outside: &impl OutsideGame,
+    ) -> Result<Vec<GameAtom>, GameError> {
+        let battlefield = &self
+            .latest_gamestate()
+            .zones
+            .get(&ZoneId::Battlefield)
+            .unwrap()
+            .objects;
+        let controlled = |o: &GameObject| Some((o.controller?, o.underlying_card?));
+        let copies_of = |player, card| {
+            battlefield
+                .iter()
+                .filter(|o| controlled(o) == Some((player, card)))
+                .map(|o| (o.id, card))
+                .collect::<Vec<_>>()
         };
-        (@unset $harness:ident) => {
-            *$harness.answers.lock().await = ServerAnswers::default();
+
+        let Some((player, card)) = battlefield.iter().filter_map(controlled).find(|(p, c)| {
+            self.game
+                .cards
+                .get(c)
+                .is_some_and(|c| c.behaviour.has_keyword(Keyword::Unique))
+                && copies_of(*p, *c).len() > 1
+        }) else {
+            return Ok(vec![]);
         };
-        (@step_game $harness:ident) => {
-            $harness.game_impl.run(&$harness.outside_client).await.unwrap();
+        let copies = copies_of(player, card);
+        let count = copies.len() - 1;
+
+        trace!(
+            ?player,
+            ?card,
+            count,
+            "Player controls extra copies of a unique card"
+        );
+        let answer = assert_send(outside.get_discard_choice(
+            self.prompt_context(),
+            player,
+            copies.clone(),
+            count,
+        ))
+        .await;
+        let choices = self.answer_or_default(answer, player, || (1..=count).collect())?;
+
+        Ok(chosen_cards(&copies, choices, count)?
+            .into_iter()
+            .map(|object| GameAtom::MoveObject {
+                object,
+                from: ZoneId::Battlefield,
+                to: ZoneId::Discard(player),
+            })
+            .collect())
+    }
+
+    /// Answers for a player that could not be reached with `default`, if the game allows it
+    ///
+    /// The substitution is announced to all players.
+    fn answer_or_default<T>(
+        &mut self,
+        answer: Result<T, RpcError>,
+        player: PlayerId,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, GameError> {
+        match answer {
+            Ok(answer) => Ok(answer),
+            Err(error) if self.default_choice_on_disconnect => {
+                trace!(?player, %error, "Player could not be reached, choosing a default");
+                self.events.push(GameEvent::DefaultChoiceMade { player });
+                Ok(default())
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Removes anything from the event that not every player is entitled to know
+    fn redact_event(&self, event: GameEvent) -> GameEvent {
+        match event {
+            GameEvent::ObjectMoved {
+                object,
+                card,
+                from,
+                to,
+            } => {
+                let public = [from, to].into_iter().any(|zone| {
+                    self.game
+                        .zone_metadata(zone)
+                        .is_some_and(|m| m.visibility == ZoneVisibility::Public)
+                });
+                let face_down = self
+                    .latest_gamestate()
+                    .find_object(object)
+                    .is_some_and(|(_, o)| o.visibility != ObjectVisibility::FaceUp);
+
+                GameEvent::ObjectMoved {
+                    object,
+                    card: (public && !face_down).then_some(card).flatten(),
+                    from,
+                    to,
+                }
+            }
+            event => event,
+        }
+    }
+
+    /// The [`GameState::effect_sources`] with their card and controller
+    fn effect_source_cards(&self) -> Vec<(ObjectId, &Card, Option<PlayerId>)> {
+        self.latest_gamestate()
+            .effect_sources()
+            .filter_map(|o| {
+                let card = self.game.cards.get(&o.underlying_card?)?;
+                Some((o.id, card, o.controller))
+            })
+            .collect()
+    }
+
+    /// Puts the effects triggered by objects moving between zones and by players drawing on the
+    /// stack
+    ///
+    /// All effects triggered by the same round of moves are put on the stack together, so that
+    /// moving many objects at once only creates a single new game state.
+    async fn run_zone_change_triggers(&mut self) -> Result<(), GameError> {
+        let mut handled = 0;
+        while handled < self.events.len() {
+            let pending = self.events[handled..].to_vec();
+            handled = self.events.len();
+
+            let mut triggered = vec![];
+            let mut changes: HashMap<ZoneId, isize> = HashMap::new();
+            for event in pending {
+                match event {
+                    GameEvent::ObjectMoved {
+                        object,
+                        card,
+                        from,
+                        to,
+                    } => {
+                        *changes.entry(from).or_default() -= 1;
+                        *changes.entry(to).or_default() += 1;
+                        let Some(card) = card else {
+                            continue;
+                        };
+
+                        triggered.push((object, card, EffectTrigger::OnEnterZone(to.kind())));
+                        match (from, to) {
+                            (from, ZoneId::Battlefield) if from != ZoneId::Battlefield => {
+                                triggered.push((object, card, EffectTrigger::OnEnterBattlefield));
+                            }
+                            (ZoneId::Battlefield, to) if to != ZoneId::Battlefield => {
+                                triggered.push((object, card, EffectTrigger::OnLeaveBattlefield));
+                            }
+                            _ => (),
+                        }
+                    }
+                    GameEvent::CardsDrawn { count, .. } => {
+                        for (object, card, _) in self.effect_source_cards() {
+                            for _ in 0..count {
+                                triggered.push((object, card.id, EffectTrigger::OnDraw));
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+
+            // Zone count triggers fire when a zone crosses their threshold
+            let state = self.latest_gamestate();
+            for (object, card, controller) in self.effect_source_cards() {
+                for trigger in card.behaviour.effects.iter().filter_map(|e| match e {
+                    CardEffect::Triggered(triggered) => Some(triggered.trigger),
+                    _ => None,
+                }) {
+                    let EffectTrigger::OnZoneCount { zone, count } = trigger else {
+                        continue;
+                    };
+                    let crossed = changes.iter().any(|(id, change)| {
+                        let now = state.zones.get(id).map_or(0, |z| z.objects.len());
+                        let before = now as isize - change;
+                        id.kind() == zone
+                            && id.player().map_or(true, |p| Some(p) == controller)
+                            && before < count as isize
+                            && now >= count
+                    });
+                    if crossed && !triggered.contains(&(object, card.id, trigger)) {
+                        triggered.push((object, card.id, trigger));
+                    }
+                }
+            }
+
+            let atoms = self.trigger_atoms(triggered)?;
+            if !atoms.is_empty() {
+                self.apply_atoms(atoms)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Asks the player for the targets and numbers the effects need, keyed by effect index
+    ///
+    /// Other requests are only asked for when the effects resolve.
+    async fn gather_choices(
+        &self,
+        outside: &impl OutsideGame,
+        player: PlayerId,
+        object: ObjectId,
+        effects: &[(usize, &Effect)],
+    ) -> Result<HashMap<(usize, String), EffectInfo>, GameError> {
+        let mut gathered_info = HashMap::new();
+        for (idx, e) in effects.iter().copied() {
+            match e {
+                Effect::Continuous(_) => return Err(GameError::InvalidCardState),
+                Effect::Instant(instant) => {
+                    let required_info = instant.get_required_info();
+                    for (name, question) in required_info {
+                        match question {
+                            EffectInfoRequest::SingleTarget {
+                                restriction,
+                                optional,
+                            } => {
+                                let possible_choices = target::possible_targets(
+                                    &self.game,
+                                    player,
+                                    restriction.as_ref(),
+                                );
+                                // Picking the only target would take away the choice to decline
+                                let auto_select = !optional
+                                    && possible_choices.len() == 1
+                                    && self
+                                        .game
+                                        .preferences_of(player)
+                                        .is_some_and(|p| p.auto_select_single_choice);
+                                let choices = if auto_select {
+                                    vec![0]
+                                } else {
+                                    assert_send(outside.get_target_choices_from_given(
+                                        self.prompt_context(),
+                                        player,
+                                        object,
+                                        name.clone(),
+                                        possible_choices.clone(),
+                                        1,
+                                        optional,
+                                    ))
+                                    .await?
+                                };
+
+                                if choices.is_empty() && optional {
+                                    trace!(%name, "Optional target declined");
+                                    continue;
+                                }
+
+                                if choices.len() != 1 {
+                                    return Err(GameError::InvalidChoiceAmount {
+                                        expected: 1,
+                                        received: choices.len(),
+                                    });
+                                }
+
+                                let Some(selected) = possible_choices.get(choices[0]).copied()
+                                else {
+                                    return Err(GameError::InvalidChoice {
+                                        list_length: possible_choices.len(),
+                                        selected: choices[0],
+                                    });
+                                };
+
+                                gathered_info
+                                    .insert((idx, name), EffectInfo::SingleTarget(selected));
+                            }
+                            EffectInfoRequest::MultipleTargets {
+                                min,
+                                max,
+                                restriction,
+                            } => {
+                                let possible_choices = target::possible_targets(
+                                    &self.game,
+                                    player,
+                                    restriction.as_ref(),
+                                );
+                                let max = max.min(possible_choices.len());
+                                let min = min.min(max);
+                                let choices = if min == max && max == possible_choices.len() {
+                                    // Every legal target has to be chosen anyway
+                                    (0..max).collect()
+                                } else {
+                                    assert_send(outside.get_multiple_target_choices(
+                                        self.prompt_context(),
+                                        player,
+                                        object,
+                                        name.clone(),
+                                        possible_choices.clone(),
+                                        min,
+                                        max,
+                                    ))
+                                    .await?
+                                };
+
+                                if !(min..=max).contains(&choices.len()) {
+                                    return Err(GameError::InvalidChoiceAmount {
+                                        expected: min,
+                                        received: choices.len(),
+                                    });
+                                }
+
+                                let mut selected = Vec::with_capacity(choices.len());
+                                for choice in choices {
+                                    match possible_choices.get(choice) {
+                                        Some(target) if !selected.contains(target) => {
+                                            selected.push(*target)
+                                        }
+                                        _ => {
+                                            return Err(GameError::InvalidChoice {
+                                                list_length: possible_choices.len(),
+                                                selected: choice,
+                                            })
+                                        }
+                                    }
+                                }
+
+                                gathered_info
+                                    .insert((idx, name), EffectInfo::MultipleTargets(selected));
+                            }
+                            EffectInfoRequest::Number { min, max } => {
+                                // All effects of a card share the same X
+                                let previous_x = gathered_info
+                                    .iter()
+                                    .find(|((_, n), _)| {
+                                        n == VARIABLE_COST_INFO && name == VARIABLE_COST_INFO
+                                    })
+                                    .map(|(_, info)| info.clone());
+
+                                let info = match previous_x {
+                                    Some(info) => info,
+                                    None => {
+                                        let number = assert_send(outside.get_number_choice(
+                                            self.prompt_context(),
+                                            player,
+                                            object,
+                                            name.clone(),
+                                            min,
+                                            max,
+                                        ))
+                                        .await?;
+
+                                        if !(min..=max).contains(&number) {
+                                            return Err(GameError::InvalidNumberChoice {
+                                                min,
+                                                max,
+                                                received: number,
+                                            });
+                                        }
+
+                                        EffectInfo::Number(number)
+                                    }
+                                };
+
+                                gathered_info.insert((idx, name), info);
+                            }
+                            EffectInfoRequest::LibrarySearch { .. }
+                            | EffectInfoRequest::LibraryArrangement { .. }
+                            | EffectInfoRequest::Discard { .. }
+                            | EffectInfoRequest::TargetDiscard { .. }
+                            | EffectInfoRequest::ChooseFromZone { .. }
+                            | EffectInfoRequest::Ordering { .. } => {
+                                // Asked when the effect resolves
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(gathered_info)
+    }
+
+    /// Atoms putting each effect the objects' cards have for the trigger on the stack
+    ///
+    /// A trigger is controlled by whoever controls its source, or owns it if the source is in one of
+    /// their zones by now. Players get priority again once triggers were put on the stack.
+    fn trigger_atoms(
+        &mut self,
+        triggered: Vec<(ObjectId, CardId, EffectTrigger)>,
+    ) -> Result<Vec<GameAtom>, GameError> {
+        let cards = self.game.cards.clone();
+        let mut atoms = vec![];
+        for (source, card, trigger) in triggered {
+            let card = cards.get(&card).ok_or(GameError::CardNotFound { card })?;
+            let Some(controller) = self
+                .latest_gamestate()
+                .find_object(source)
+                .and_then(|(zone, o)| o.controller.or(zone.player()))
+            else {
+                trace!(?source, ?trigger, "Trigger without a controller, skipping");
+                continue;
+            };
+
+            let abilities = card
+                .behaviour
+                .effects
+                .iter()
+                .enumerate()
+                .filter_map(|(ability, e)| match e {
+                    CardEffect::Triggered(triggered) if triggered.trigger == trigger => {
+                        Some((ability, &triggered.effects))
+                    }
+                    _ => None,
+                });
+            for (ability, effects) in abilities {
+                for (effect, e) in effects.iter().enumerate() {
+                    let Effect::Instant(instant) = e else {
+                        return Err(GameError::InvalidCardState);
+                    };
+                    // Other requests are only asked for when the trigger resolves
+                    let choices_made = !instant.get_required_info().values().any(|request| {
+                        matches!(
+                            request,
+                            EffectInfoRequest::SingleTarget { .. }
+                                | EffectInfoRequest::MultipleTargets { .. }
+                                | EffectInfoRequest::Number { .. }
+                        )
+                    });
+
+                    trace!(?source, ?trigger, "Putting trigger on the stack");
+                    atoms.push(GameAtom::PutTriggerOnStack {
+                        controller,
+                        trigger: StackTrigger {
+                            source,
+                            card: card.id,
+                            ability,
+                            effect,
+                            choices_made,
+                        },
+                    });
+                }
+            }
+        }
+
+        if !atoms.is_empty() {
+            atoms.push(GameAtom::ResetPriority);
+        }
+        Ok(atoms)
+    }
+
+    async fn advance_phase(&mut self) -> Result<(), GameError> {
+        let mut atoms = vec![GameAtom::AdvancePhase, GameAtom::ResetPriority];
+        let current_phase = self.latest_gamestate().phase;
+        if current_phase.next() == Some(Phase::Draw) {
+            let active_player = *self.latest_gamestate().active_player_order.first().unwrap();
+            atoms.push(GameAtom::DrawCards {
+                player: active_player,
+                count: 1,
+            });
+        }
+        self.apply_atoms(atoms)?;
+
+        let latest_gamestate = self.latest_gamestate();
+        trace!(phase = ?latest_gamestate.phase, "Entered phase");
+
+        let event = DelayedTriggerEvent::BeginningOfPhase(latest_gamestate.phase);
+        let fired: Vec<_> = latest_gamestate
+            .delayed_triggers
+            .iter()
+            .filter(|t| t.event == event)
+            .cloned()
+            .collect();
+
+        let mut atoms = self.turn_start_atoms()?;
+        for trigger in fired {
+            atoms.extend(assert_send(self.execute_delayed_trigger(&trigger)).await?);
+            atoms.push(GameAtom::RemoveDelayedTrigger { trigger });
+        }
+
+        if atoms.is_empty() {
+            return Ok(());
+        }
+        self.apply_atoms(atoms)
+    }
+
+    /// What happens to the active player's objects at the beginning of their turn
+    ///
+    /// They are readied during recovery, and their turn start triggers are put on the stack once
+    /// the turn starts.
+    fn turn_start_atoms(&mut self) -> Result<Vec<GameAtom>, GameError> {
+        let state = self.latest_gamestate();
+        let player = *state.active_player_order.first().unwrap();
+        let controlled = state
+            .get_battlefield()
+            .objects
+            .iter()
+            .filter(|o| o.controller == Some(player));
+
+        match state.phase {
+            Phase::Recovery => Ok(controlled
+                .filter(|o| o.exhausted)
+                .map(|o| GameAtom::Ready { object: o.id })
+                .collect()),
+            Phase::TurnStart => {
+                let emblems = state
+                    .zones
+                    .get(&ZoneId::Emblems(player))
+                    .into_iter()
+                    .flat_map(|z| z.objects.iter());
+                let triggered: Vec<_> = controlled
+                    .chain(emblems)
+                    .filter_map(|o| Some((o.id, o.underlying_card?, EffectTrigger::OnTurnStart)))
+                    .collect();
+                let side_rule_atoms: Vec<_> = self
+                    .side_rules()
+                    .iter()
+                    .flat_map(|rule| rule.on_turn_start(state, player))
+                    .collect();
+
+                let mut atoms = self.trigger_atoms(triggered)?;
+                atoms.extend(side_rule_atoms);
+                Ok(atoms)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn side_rules(&self) -> Vec<Box<dyn SideRuleModule>> {
+        self.game
+            .format
+            .side_rules
+            .iter()
+            .map(side_rules::module)
+            .collect()
+    }
+
+    async fn execute_delayed_trigger(
+        &self,
+        trigger: &DelayedTrigger,
+    ) -> Result<Vec<GameAtom>, GameError> {
+        trace!(?trigger, "Delayed trigger fired");
+        let card = self
+            .game
+            .cards
+            .get(&trigger.card)
+            .ok_or(GameError::CardNotFound { card: trigger.card })?;
+
+        let effect = triggered_effects(card, EffectTrigger::Delayed)
+            .nth(trigger.effect)
+            .ok_or(GameError::InvalidCardState)?;
+
+        let Effect::Instant(effect) = effect else {
+            return Err(GameError::InvalidCardState);
         };
-        (@run $harness:ident $($normal:tt)*) => {
-            $($normal)*
+
+        assert_send(self.execute_effect(
+            effect.as_ref(),
+            trigger.card,
+            trigger.info.clone(),
+            trigger.source,
+        ))
+        .await
+    }
+
+    /// Runs an effect, failing if it does not finish within the effect time limit
+    ///
+    /// Only effects that yield can be stopped, one that blocks its thread can not be interrupted.
+    /// Effects only see the hidden zones if they declared it, and may only return atoms their
+    /// capabilities cover.
+    async fn execute_effect(
+        &self,
+        effect: &dyn InstantEffect,
+        card: CardId,
+        info: HashMap<String, EffectInfo>,
+        source: ObjectId,
+    ) -> Result<Vec<GameAtom>, GameError> {
+        let capabilities = effect.capabilities();
+        let redacted;
+        let game = if capabilities.contains(&Capability::ReadHiddenZones) {
+            &self.game
+        } else {
+            let controller = self
+                .latest_gamestate()
+                .find_object(source)
+                .and_then(|(_, o)| o.controller);
+            // Earlier game states and the randomness would tell what the hidden zones hold
+            let mut game = self.game.without_history();
+            let state = game.game_states.last_mut().unwrap();
+            *state = state.redacted_for(controller);
+            game.rand = Xoshiro256StarStar::seed_from_u64(0);
+            redacted = game;
+            &redacted
         };
-        ($harness:ident, [ $(@$kind:tt { $($val:tt)* };)+ ]) => {
-            $(game_steps!(@$kind $harness $($val)*));+
+
+        let limit = self.effect_time_limit;
+        let result = match tokio::time::timeout(limit, effect.execute(info, source, game)).await {
+            Ok(result) => result,
+            Err(_) => Err(ExecuteFailure::TimedOut { card, limit }),
         };
+        let atoms = result.map_err(|failure| GameError::EffectExecuteFailure { failure })?;
+
+        if let Some(capability) = atoms
+            .iter()
+            .filter_map(Capability::required_by)
+            .find(|c| !capabilities.contains(c))
+        {
+            return Err(GameError::EffectExecuteFailure {
+                failure: ExecuteFailure::UndeclaredCapability { card, capability },
+            });
+        }
+
+        Ok(atoms)
     }
+}
 
-    macro_rules! async_test {
-        (async fn $name:ident() $($tt:tt)*) => {
-            #[test]
-            fn $name() {
-                use tracing_subscriber::layer::SubscriberExt;
-                use tracing_subscriber::util::SubscriberInitExt;
-                use tracing::Instrument;
+/// Whether the player may play the card right now
+///
+/// Fast cards can always be played, otherwise the timing of each of the card's kinds has to allow
+/// it.
+fn can_play_now(
+    format: &FormatRules,
+    state: &GameState,
+    player: PlayerId,
+    card: &CardBehaviour,
+) -> bool {
+    card.has_keyword(Keyword::Fast)
+        || card
+            .kind
+            .iter()
+            .all(|k| format.timing_of(k.kind.tag()).allows(state, player))
+}
+
+/// Whether the player could pay the alternative cost to play `object` right now
+fn can_pay_alternative(
+    state: &GameState,
+    cards: &HashMap<CardId, Card>,
+    player: PlayerId,
+    object: ObjectId,
+    alternative: &AlternativeCost,
+) -> bool {
+    match alternative {
+        AlternativeCost::Discard { count } => {
+            discardable_cards(state, cards, player, object).len() >= *count
+        }
+        AlternativeCost::PayLife { amount } => state.life_of(player) >= *amount,
+        AlternativeCost::Free {
+            condition: PlayCondition::NoAgents,
+        } => !state.get_battlefield().objects.iter().any(|o| {
+            o.controller == Some(player)
+                && o.underlying_card
+                    .and_then(|card| cards.get(&card))
+                    .is_some_and(|card| card.behaviour.is_agent())
+        }),
+        AlternativeCost::Free {
+            condition: PlayCondition::LifeAtMost(life),
+        } => state.life_of(player) <= *life,
+    }
+}
+
+/// The cards of the player's hand other than `played` that they may discard
+fn discardable_cards(
+    state: &GameState,
+    cards: &HashMap<CardId, Card>,
+    player: PlayerId,
+    played: ObjectId,
+) -> Vec<(ObjectId, CardId)> {
+    hand_cards(state, player)
+        .into_iter()
+        .filter(|(object, card)| {
+            *object != played
+                && !cards
+                    .get(card)
+                    .is_some_and(|c| c.behaviour.has_keyword(Keyword::Persistent))
+        })
+        .collect()
+}
+
+/// The cards in the player's hand, as offered in prompts
+fn hand_cards(state: &GameState, player: PlayerId) -> Vec<(ObjectId, CardId)> {
+    state
+        .get_hand(player)
+        .objects
+        .iter()
+        .filter_map(|o| Some((o.id, o.underlying_card?)))
+        .collect()
+}
+
+/// Checks that exactly `count` distinct cards were chosen, returning them in the chosen order
+fn chosen_cards(
+    cards: &[(ObjectId, CardId)],
+    choices: Vec<usize>,
+    count: usize,
+) -> Result<Vec<ObjectId>, GameError> {
+    let mut distinct = choices.clone();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if choices.len() != count || distinct.len() != count {
+        return Err(GameError::InvalidChoiceAmount {
+            expected: count,
+            received: distinct.len(),
+        });
+    }
+
+    choices
+        .into_iter()
+        .map(|idx| {
+            cards
+                .get(idx)
+                .map(|(object, _)| *object)
+                .ok_or(GameError::InvalidChoice {
+                    list_length: cards.len(),
+                    selected: idx,
+                })
+        })
+        .collect()
+}
+
+/// All effects of the card that run when the given trigger happens, in order
+fn triggered_effects(card: &Card, trigger: EffectTrigger) -> impl Iterator<Item = &Effect> {
+    card.behaviour
+        .effects
+        .iter()
+        .filter_map(move |e| match e {
+            CardEffect::Triggered(triggered) if triggered.trigger == trigger => {
+                Some(&triggered.effects)
+            }
+            _ => None,
+        })
+        .flatten()
+}
+
+fn activated_effects(behaviour: &CardBehaviour) -> impl Iterator<Item = &ActivatedCardEffect> {
+    behaviour.effects.iter().filter_map(|e| match e {
+        CardEffect::Activated(activated) => Some(activated),
+        _ => None,
+    })
+}
+
+/// The abilities the player could activate right now, by object and index of the activated effect
+fn activatable_abilities(game: &Game, player: PlayerId) -> Vec<(ObjectId, usize)> {
+    game.latest_gamestate()
+        .get_battlefield()
+        .objects
+        .iter()
+        .filter(|o| o.controller == Some(player))
+        .filter_map(|o| Some((o, o.behaviour(&game.cards)?)))
+        .flat_map(|(o, behaviour)| {
+            let arriving = is_arriving(game, o, behaviour);
+            activated_effects(behaviour)
+                .enumerate()
+                .filter(move |(_, activated)| !(activated.exhaust && (o.exhausted || arriving)))
+                .map(|(idx, _)| (o.id, idx))
+        })
+        .collect()
+}
+
+/// The agents `player` may attack with right now, see [`PlayerAction::Attack`]
+///
+/// Only the active player attacks, during their main phase while the stack is empty, with ready
+/// agents that did not just arrive.
+fn possible_attackers(game: &Game, player: PlayerId) -> Vec<ObjectId> {
+    let state = game.latest_gamestate();
+    if state.phase != Phase::Main
+        || state.active_player_order.first() != Some(&player)
+        || !state.get_stack().objects.is_empty()
+        || defender_of(game, player).is_none()
+    {
+        return vec![];
+    }
+
+    state
+        .get_battlefield()
+        .objects
+        .iter()
+        .filter(|o| o.controller == Some(player) && !o.exhausted)
+        .filter(|o| {
+            o.behaviour(&game.cards)
+                .is_some_and(|b| b.is_agent() && !is_arriving(game, o, b))
+        })
+        .map(|o| o.id)
+        .collect()
+}
+
+/// The agents `player` may block with, their ready ones
+fn possible_blockers(game: &Game, player: PlayerId) -> Vec<ObjectId> {
+    game.latest_gamestate()
+        .get_battlefield()
+        .objects
+        .iter()
+        .filter(|o| o.controller == Some(player) && !o.exhausted)
+        .filter(|o| o.behaviour(&game.cards).is_some_and(|b| b.is_agent()))
+        .map(|o| o.id)
+        .collect()
+}
+
+/// The player attacked by `player`, the next one in turn order who is not in their team
+fn defender_of(game: &Game, player: PlayerId) -> Option<PlayerId> {
+    let team = game.teams.get(&player);
+    game.latest_gamestate()
+        .active_player_order
+        .iter()
+        .find(|p| **p != player && (team.is_none() || game.teams.get(p) != team))
+        .copied()
+}
+
+/// Whether the stack object chose targets, none of which may still be targeted
+///
+/// Such objects fizzle, they are discarded without any of their effects happening.
+fn has_lost_all_targets(game: &Game, object: &GameObject, effects: &[(usize, &Effect)]) -> bool {
+    let Some(controller) = object.controller else {
+        return false;
+    };
+
+    let mut targets = effects
+        .iter()
+        .filter_map(|(idx, effect)| match effect {
+            Effect::Instant(eff) => Some((*idx, eff)),
+            _ => None,
+        })
+        .flat_map(|(idx, eff)| {
+            eff.get_required_info()
+                .into_iter()
+                .filter_map(move |(name, request)| match request {
+                    EffectInfoRequest::SingleTarget { restriction, .. }
+                    | EffectInfoRequest::MultipleTargets { restriction, .. } => {
+                        Some(((idx, name), restriction))
+                    }
+                    _ => None,
+                })
+        })
+        .flat_map(|(key, restriction)| {
+            let chosen = match object.choices.get(&key) {
+                Some(EffectInfo::SingleTarget(target)) => vec![*target],
+                Some(EffectInfo::MultipleTargets(targets)) => targets.clone(),
+                _ => vec![],
+            };
+            chosen
+                .into_iter()
+                .map(move |target| (target, restriction.clone()))
+        })
+        .peekable();
+
+    targets.peek().is_some()
+        && targets.all(|(target, restriction)| {
+            !target::possible_targets(game, controller, restriction.as_ref()).contains(&target)
+        })
+}
+
+/// The card of an agent on the battlefield, and how it fights
+fn combatant(game: &Game, object: ObjectId) -> Result<(CardId, Combatant), GameError> {
+    let obj = game
+        .latest_gamestate()
+        .get_object_from_zone(ZoneId::Battlefield, object)
+        .ok_or(GameError::ObjectNotFoundInZone {
+            zone: ZoneId::Battlefield,
+            object,
+        })?;
+    let card = obj
+        .underlying_card
+        .ok_or(GameError::NoUnderlyingCard { object })?;
+    let behaviour = obj
+        .behaviour(&game.cards)
+        .ok_or(GameError::CardNotFound { card })?;
+    let (power, toughness) = game
+        .latest_gamestate()
+        .agent_stats(game, object)
+        .ok_or(GameError::InvalidCardState)?;
+
+    Ok((
+        card,
+        Combatant {
+            object,
+            power,
+            toughness,
+            overclocked: behaviour.has_keyword(Keyword::Overclocked),
+        },
+    ))
+}
+
+/// Whether the object is an agent that entered the battlefield this turn
+///
+/// Arriving agents can not exhaust yet, unless they have [`Keyword::Rush`].
+fn is_arriving(game: &Game, object: &GameObject, behaviour: &CardBehaviour) -> bool {
+    object.entered_turn == Some(game.latest_gamestate().turn)
+        && behaviour.is_agent()
+        && !behaviour.has_keyword(Keyword::Rush)
+}
+
+fn new_game_state_with(
+    rand: &mut impl Rng,
+    players: &std::collections::HashMap<PlayerId, Player>,
+    order: &[PlayerId],
+) -> GameState {
+    GameState {
+        game_stage: GameStage::KeepHand {
+            players_keeping: Default::default(),
+            mulligans: Default::default(),
+        },
+        active_player_order: order.to_vec(),
+        unpassed_players: order.to_vec(),
+        // The first turn starts directly in its main phase
+        phase: Phase::Main,
+        delayed_triggers: vec![],
+        actions_this_priority: Default::default(),
+        actions_this_turn: Default::default(),
+        turn: 0,
+        shields: vec![],
+        temporary_effects: vec![],
+        floating_scrip: Default::default(),
+        scrip: Default::default(),
+        life: order.iter().map(|p| (*p, STARTING_LIFE)).collect(),
+        poison: Default::default(),
+        index: Default::default(),
+        zones: players
+            .values()
+            .flat_map(|p| {
+                vec![
+                    (ZoneId::Hand(p.id), GameZone::empty()),
+                    (
+                        ZoneId::Library(p.id),
+                        GameZone::with(
+                            p.initial_cards
+                                .iter()
+                                .map(|c| GameObject {
+                                    owner: Some(p.id),
+                                    ..GameObject::from_card(rand, *c)
+                                })
+                                .collect(),
+                        ),
+                    ),
+                    (ZoneId::Discard(p.id), GameZone::empty()),
+                    (ZoneId::Emblems(p.id), GameZone::empty()),
+                ]
+            })
+            .chain(vec![
+                (ZoneId::Battlefield, GameZone::empty()),
+                (ZoneId::Stack, GameZone::empty()),
+                (ZoneId::Exile, GameZone::empty()),
+            ])
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use tarpc::server::Channel;
+    use tarpc::transport::channel::UnboundedChannel;
+    use tarpc::ClientMessage;
+    use tarpc::Response;
+    use technomancy_core::card::AgentPower;
+    use technomancy_core::card::AgentToughness;
+    use technomancy_core::card::AlternativeCost;
+    use technomancy_core::card::BaseCardKind;
+    use technomancy_core::card::BaseCardKindTag;
+    use technomancy_core::card::Card;
+    use technomancy_core::card::CardBehaviour;
+    use technomancy_core::card::CardEffect;
+    use technomancy_core::card::CardId;
+    use technomancy_core::card::CardKind;
+    use technomancy_core::card::CardLayout;
+    use technomancy_core::card::Cost;
+    use technomancy_core::card::Keyword;
+    use technomancy_core::card::PlayCondition;
+    use technomancy_core::card::ScripKind;
+    use technomancy_core::card::StaticCardEffect;
+    use technomancy_core::card::SubKind;
+    use technomancy_core::card::SubKindRegistry;
+    use technomancy_core::card::TriggeredCardEffect;
+    use technomancy_core::card::BASE_AGENT_SUBKINDS;
+    use technomancy_core::card::BASE_BUILDING_SUBKINDS;
+    use technomancy_core::card::MAX_SCRIP_PER_KIND;
+    use technomancy_core::card::VARIABLE_COST_INFO;
+    use technomancy_core::delta::GameStateDelta;
+    use technomancy_core::effect::Capability;
+    use technomancy_core::effect::ContinuousEffect;
+    use technomancy_core::effect::CostModifier;
+    use technomancy_core::effect::DelayedTrigger;
+    use technomancy_core::effect::DelayedTriggerEvent;
+    use technomancy_core::effect::DiscardChooser;
+    use technomancy_core::effect::Effect;
+    use technomancy_core::effect::EffectInfo;
+    use technomancy_core::effect::EffectInfoRequest;
+    use technomancy_core::effect::EffectTrigger;
+    use technomancy_core::effect::ExecuteFailure;
+    use technomancy_core::effect::InstantEffect;
+    use technomancy_core::effect::RelativePlayer;
+    use technomancy_core::effect::Replacement;
+    use technomancy_core::effect::StatModifier;
+    use technomancy_core::effect::TargetRestriction;
+    use technomancy_core::effect::TargetZone;
+    use technomancy_core::event::AppliedAtoms;
+    use technomancy_core::event::GameEvent;
+    use technomancy_core::log::LogLine;
+    use technomancy_core::outside::ObjectList;
+    use technomancy_core::outside::Outside;
+    use technomancy_core::outside::OutsideClient;
+    use technomancy_core::outside::OutsideRequest;
+    use technomancy_core::outside::OutsideResponse;
+    use technomancy_core::outside::PromptContext;
+    use technomancy_core::outside::OBJECT_PAGE_SIZE;
+    use technomancy_core::save::LoadError;
+    use technomancy_core::save::SavedGame;
+    use technomancy_core::save::SeedExport;
+    use technomancy_core::save::SAVE_FORMAT_VERSION;
+    use technomancy_core::schema::Replay;
+    use technomancy_core::view::PlayerView;
+    use technomancy_core::view::StackEntry;
+    use technomancy_core::ChessClock;
+    use technomancy_core::Compaction;
+    use technomancy_core::DamageShield;
+    use technomancy_core::EffectDuration;
+    use technomancy_core::FormatRules;
+    use technomancy_core::Game;
+    use technomancy_core::GameAtom;
+    use technomancy_core::GameError;
+    use technomancy_core::GameId;
+    use technomancy_core::GameObject;
+    use technomancy_core::GameOutcome;
+    use technomancy_core::GameResult;
+    use technomancy_core::GameState;
+    use technomancy_core::GameVersions;
+    use technomancy_core::MulliganRule;
+    use technomancy_core::ObjectId;
+    use technomancy_core::ObjectVisibility;
+    use technomancy_core::Player;
+    use technomancy_core::PlayerAction;
+    use technomancy_core::PlayerId;
+    use technomancy_core::ReplayError;
+    use technomancy_core::SideRule;
+    use technomancy_core::StackTrigger;
+    use technomancy_core::TargetId;
+    use technomancy_core::TeamId;
+    use technomancy_core::TemporaryModifier;
+    use technomancy_core::TimeoutAction;
+    use technomancy_core::Timing;
+    use technomancy_core::VerificationError;
+    use technomancy_core::VersionSkew;
+    use technomancy_core::ZoneId;
+    use technomancy_core::ZoneKind;
+    use technomancy_core::RULES_VERSION;
+    use technomancy_core::STARTING_HAND_SIZE;
+    use technomancy_core::STARTING_LIFE;
+    use tokio::sync::Mutex;
+    use uuid::Uuid;
+
+    use crate::builder::EngineBuilder;
+    use crate::card::CardsInHand;
+    use crate::effect::tests::DealDamage;
+    use crate::effect::tests::DrawCards;
+    use crate::effect::tests::GainX;
+    use crate::effect::tests::Snoop;
+    use crate::effect::tests::Stall;
+    use crate::effect::Buff;
+    use crate::effect::CounterObject;
+    use crate::effect::GainLife;
+    use crate::effect::Mill;
+    use crate::effect::ProduceScrip;
+    use crate::effect::TargetDiscards;
+    use crate::hand_cards;
+    use crate::outside::bot::BotOutside;
+    use crate::outside::faults::Faults;
+    use crate::outside::faults::FaultyOutside;
+    use crate::outside::hot_seat::HotSeatOutside;
+    use crate::outside::OutsideGameClient;
+    use crate::GameImplV1;
+
+    const BLAST_CARD: uuid::Uuid = uuid::uuid!("4abc4619-b61c-44a4-9d37-8a31bda65b48");
+    const DRAW_CARD: uuid::Uuid = uuid::uuid!("ddfbf54b-2750-41c6-b657-1d6ce1e754ef");
+
+    #[allow(unused)]
+    fn check_send() {
+        let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+        crate::assert_send(harness.game_impl.run(&harness.outside_client));
+    }
+
+    fn existing_cards() -> HashMap<CardId, Card> {
+        let blast = Card {
+            id: CardId::with(BLAST_CARD),
+            version: 1,
+            behaviour: CardBehaviour {
+                cost: Some(Cost {
+                    corp1_scrip: 2,
+                    ..Default::default()
+                }),
+                kind: vec![CardKind {
+                    kind: BaseCardKind::Quickhack,
+                }],
+                effects: vec![CardEffect::Triggered(TriggeredCardEffect {
+                    trigger: EffectTrigger::OnResolve,
+                    effects: vec![Effect::Instant(Box::new(DealDamage(3)))],
+                })],
+                keywords: vec![],
+                alternative_costs: vec![],
+            },
+            layout: CardLayout::Single,
+        };
+
+        let draw = Card {
+            id: CardId::with(DRAW_CARD),
+            version: 1,
+            behaviour: CardBehaviour {
+                cost: Some(Cost {
+                    corp1_scrip: 2,
+                    ..Default::default()
+                }),
+                kind: vec![CardKind {
+                    kind: BaseCardKind::Quickhack,
+                }],
+                effects: vec![CardEffect::Triggered(TriggeredCardEffect {
+                    trigger: EffectTrigger::OnResolve,
+                    effects: vec![Effect::Instant(Box::new(DrawCards(3)))],
+                })],
+                keywords: vec![],
+                alternative_costs: vec![],
+            },
+            layout: CardLayout::Single,
+        };
+
+        [(blast.id, blast), (draw.id, draw)].into()
+    }
+
+    fn agent_card(id: Uuid, keywords: Vec<Keyword>) -> Card {
+        Card {
+            id: CardId::with(id),
+            version: 1,
+            behaviour: CardBehaviour {
+                cost: None,
+                kind: vec![CardKind {
+                    kind: BaseCardKind::Agent {
+                        subkind: SubKind::new("Mercenary"),
+                        power: AgentPower::Fixed(3),
+                        toughness: AgentToughness::Fixed(6),
+                    },
+                }],
+                effects: vec![],
+                keywords,
+                alternative_costs: vec![],
+            },
+            layout: CardLayout::Single,
+        }
+    }
+
+    fn simple_deck() -> Vec<CardId> {
+        vec![
+            CardId::with(BLAST_CARD),
+            CardId::with(BLAST_CARD),
+            CardId::with(BLAST_CARD),
+            CardId::with(BLAST_CARD),
+            CardId::with(DRAW_CARD),
+            CardId::with(DRAW_CARD),
+            CardId::with(DRAW_CARD),
+            CardId::with(DRAW_CARD),
+        ]
+    }
+
+    fn playtesters() -> HashMap<PlayerId, Player> {
+        vec![
+            Player {
+                id: PlayerId::new(),
+                initial_cards: simple_deck(),
+                preferences: Default::default(),
+            },
+            Player {
+                initial_cards: simple_deck(),
+                id: PlayerId::new(),
+                preferences: Default::default(),
+            },
+        ]
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect()
+    }
+
+    fn outside_client(
+        game_id: GameId,
+    ) -> (
+        tarpc::transport::channel::UnboundedChannel<
+            tarpc::ClientMessage<OutsideRequest>,
+            tarpc::Response<OutsideResponse>,
+        >,
+        OutsideGameClient,
+    ) {
+        let (left, right) = tarpc::transport::channel::unbounded();
+        let client = OutsideClient::new(tarpc::client::Config::default(), left).spawn();
+        (
+            right,
+            OutsideGameClient {
+                game_id,
+                client: Arc::new(client),
+            },
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    struct ServerAnswers {
+        get_player_keeping: Option<Box<dyn FnMut(Vec<PlayerId>) -> Vec<PlayerId> + Send>>,
+        get_next_player_action_from:
+            Option<Box<dyn FnMut(PlayerId, Vec<PlayerAction>) -> usize + Send>>,
+        get_target_choices_from_given: Option<
+            Box<
+                dyn FnMut(PlayerId, ObjectId, String, Vec<TargetId>, usize, bool) -> Vec<usize>
+                    + Send,
+            >,
+        >,
+        get_multiple_target_choices: Option<
+            Box<
+                dyn FnMut(PlayerId, ObjectId, String, Vec<TargetId>, usize, usize) -> Vec<usize>
+                    + Send,
+            >,
+        >,
+        get_number_choice:
+            Option<Box<dyn FnMut(PlayerId, ObjectId, String, u64, u64) -> u64 + Send>>,
+        get_library_search_choice:
+            Option<Box<dyn FnMut(PlayerId, ObjectId, String, ObjectList) -> Option<usize> + Send>>,
+        get_library_arrangement: Option<
+            Box<
+                dyn FnMut(PlayerId, ObjectId, String, ObjectList, bool) -> (Vec<usize>, Vec<usize>)
+                    + Send,
+            >,
+        >,
+        get_zone_choice: Option<
+            Box<
+                dyn FnMut(PlayerId, ObjectId, String, ZoneId, ObjectList, usize) -> Vec<usize>
+                    + Send,
+            >,
+        >,
+        get_ordering:
+            Option<Box<dyn FnMut(PlayerId, ObjectId, String, ObjectList) -> Vec<usize> + Send>>,
+        get_discard_choice:
+            Option<Box<dyn FnMut(PlayerId, Vec<(ObjectId, CardId)>, usize) -> Vec<usize> + Send>>,
+        get_cards_to_bottom:
+            Option<Box<dyn FnMut(PlayerId, Vec<(ObjectId, CardId)>, usize) -> Vec<usize> + Send>>,
+        get_damage_assignment: Option<
+            Box<
+                dyn FnMut(PlayerId, ObjectId, Vec<(ObjectId, CardId)>, u64) -> Vec<(usize, u64)>
+                    + Send,
+            >,
+        >,
+        get_player_passing: Option<Box<dyn FnMut(PlayerId) -> bool + Send>>,
+        get_play_first: Option<Box<dyn FnMut(PlayerId) -> bool + Send>>,
+        notify_event: Option<Box<dyn FnMut(GameEvent) + Send>>,
+        notify_error: Option<Box<dyn FnMut(PlayerId, String) + Send>>,
+        confirm_action: Option<Box<dyn FnMut(PlayerId, PlayerView) -> bool + Send>>,
+    }
+
+    impl Default for ServerAnswers {
+        fn default() -> Self {
+            Self {
+                get_player_keeping: Some(Box::new(|players| players)),
+                get_next_player_action_from: Default::default(),
+                get_target_choices_from_given: Default::default(),
+                get_multiple_target_choices: Default::default(),
+                get_number_choice: Default::default(),
+                get_library_search_choice: Default::default(),
+                get_library_arrangement: Default::default(),
+                get_zone_choice: Default::default(),
+                get_ordering: Default::default(),
+                get_discard_choice: Default::default(),
+                get_cards_to_bottom: Default::default(),
+                get_damage_assignment: Default::default(),
+                get_player_passing: Default::default(),
+                get_play_first: Default::default(),
+                notify_event: Some(Box::new(|_event| ())),
+                notify_error: Some(Box::new(|_player, _error| ())),
+                confirm_action: Default::default(),
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct SimpleOutsideServer {
+        answers: Arc<Mutex<ServerAnswers>>,
+    }
+
+    #[tarpc::server]
+    impl Outside for SimpleOutsideServer {
+        async fn get_player_keeping(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            asked_players: Vec<PlayerId>,
+        ) -> Vec<PlayerId> {
+            self.answers
+                .lock()
+                .await
+                .get_player_keeping
+                .as_mut()
+                .expect("No method set: get_player_keeping")(asked_players)
+        }
+        async fn get_next_player_action_from(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            player_actions: Vec<PlayerAction>,
+        ) -> usize {
+            self.answers
+                .lock()
+                .await
+                .get_next_player_action_from
+                .as_mut()
+                .expect("No method set: get_next_player_action_from")(
+                player, player_actions
+            )
+        }
+        async fn get_target_choices_from_given(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            source: ObjectId,
+            name: String,
+            choices: Vec<TargetId>,
+            count: usize,
+            optional: bool,
+        ) -> Vec<usize> {
+            self.answers
+                .lock()
+                .await
+                .get_target_choices_from_given
+                .as_mut()
+                .expect("No method set: get_target_choices_from_given")(
+                player, source, name, choices, count, optional,
+            )
+        }
+
+        async fn get_multiple_target_choices(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            source: ObjectId,
+            name: String,
+            choices: Vec<TargetId>,
+            min: usize,
+            max: usize,
+        ) -> Vec<usize> {
+            self.answers
+                .lock()
+                .await
+                .get_multiple_target_choices
+                .as_mut()
+                .expect("No method set: get_multiple_target_choices")(
+                player, source, name, choices, min, max,
+            )
+        }
+
+        async fn get_number_choice(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            source: ObjectId,
+            name: String,
+            min: u64,
+            max: u64,
+        ) -> u64 {
+            self.answers
+                .lock()
+                .await
+                .get_number_choice
+                .as_mut()
+                .expect("No method set: get_number_choice")(
+                player, source, name, min, max
+            )
+        }
+
+        async fn get_library_search_choice(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            source: ObjectId,
+            name: String,
+            choices: ObjectList,
+        ) -> Option<usize> {
+            self.answers
+                .lock()
+                .await
+                .get_library_search_choice
+                .as_mut()
+                .expect("No method set: get_library_search_choice")(
+                player, source, name, choices
+            )
+        }
+
+        async fn get_library_arrangement(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            source: ObjectId,
+            name: String,
+            cards: ObjectList,
+            allow_bottom: bool,
+        ) -> (Vec<usize>, Vec<usize>) {
+            self.answers
+                .lock()
+                .await
+                .get_library_arrangement
+                .as_mut()
+                .expect("No method set: get_library_arrangement")(
+                player,
+                source,
+                name,
+                cards,
+                allow_bottom,
+            )
+        }
+
+        async fn get_zone_choice(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            source: ObjectId,
+            name: String,
+            zone: ZoneId,
+            choices: ObjectList,
+            count: usize,
+        ) -> Vec<usize> {
+            self.answers
+                .lock()
+                .await
+                .get_zone_choice
+                .as_mut()
+                .expect("No method set: get_zone_choice")(
+                player, source, name, zone, choices, count,
+            )
+        }
+
+        async fn get_ordering(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            source: ObjectId,
+            name: String,
+            items: ObjectList,
+        ) -> Vec<usize> {
+            self.answers
+                .lock()
+                .await
+                .get_ordering
+                .as_mut()
+                .expect("No method set: get_ordering")(player, source, name, items)
+        }
+
+        async fn get_discard_choice(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            cards: Vec<(ObjectId, CardId)>,
+            count: usize,
+        ) -> Vec<usize> {
+            self.answers
+                .lock()
+                .await
+                .get_discard_choice
+                .as_mut()
+                .expect("No method set: get_discard_choice")(player, cards, count)
+        }
+
+        async fn get_cards_to_bottom(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            cards: Vec<(ObjectId, CardId)>,
+            count: usize,
+        ) -> Vec<usize> {
+            self.answers
+                .lock()
+                .await
+                .get_cards_to_bottom
+                .as_mut()
+                .expect("No method set: get_cards_to_bottom")(player, cards, count)
+        }
+
+        async fn get_damage_assignment(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            attacker: ObjectId,
+            blockers: Vec<(ObjectId, CardId)>,
+            damage: u64,
+        ) -> Vec<(usize, u64)> {
+            self.answers
+                .lock()
+                .await
+                .get_damage_assignment
+                .as_mut()
+                .expect("No method set: get_damage_assignment")(
+                player, attacker, blockers, damage
+            )
+        }
+
+        async fn get_player_passing(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+        ) -> bool {
+            self.answers
+                .lock()
+                .await
+                .get_player_passing
+                .as_mut()
+                .expect("No method set: get_player_passing")(player)
+        }
+
+        async fn get_play_first(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+        ) -> bool {
+            self.answers
+                .lock()
+                .await
+                .get_play_first
+                .as_mut()
+                .expect("No method set: get_play_first")(player)
+        }
+
+        async fn notify_event(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            event: GameEvent,
+        ) {
+            self.answers
+                .lock()
+                .await
+                .notify_event
+                .as_mut()
+                .expect("No method set: notify_event")(event)
+        }
+
+        async fn notify_error(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            player: PlayerId,
+            error_description: String,
+        ) {
+            self.answers
+                .lock()
+                .await
+                .notify_error
+                .as_mut()
+                .expect("No method set: notify_error")(player, error_description)
+        }
+
+        async fn confirm_action(
+            self,
+            _context: tarpc::context::Context,
+            _game_id: GameId,
+            _prompt: PromptContext,
+            player: PlayerId,
+            preview: PlayerView,
+        ) -> bool {
+            self.answers
+                .lock()
+                .await
+                .confirm_action
+                .as_mut()
+                .expect("No method set: confirm_action")(player, preview)
+        }
+    }
+
+    struct SimpleTestHarness {
+        player_order: Vec<PlayerId>,
+        game_impl: GameImplV1,
+        outside_client: OutsideGameClient,
+        answers: Arc<Mutex<ServerAnswers>>,
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn init_harness(
+        seed: Option<u64>,
+    ) -> (
+        Vec<PlayerId>,
+        GameImplV1,
+        tarpc::transport::channel::UnboundedChannel<
+            tarpc::ClientMessage<OutsideRequest>,
+            tarpc::Response<OutsideResponse>,
+        >,
+        OutsideGameClient,
+    ) {
+        let rand = Xoshiro256StarStar::seed_from_u64(seed.unwrap_or(1337));
+        let players = playtesters();
+        let player_order: Vec<_> = players.keys().copied().collect();
+        let cards = existing_cards();
+
+        let id = GameId::new();
+        let game_impl = GameImplV1::new(id, rand, Arc::new(cards), players, player_order.clone());
+
+        let (server, outside_client) = outside_client(game_impl.game.id);
+
+        (player_order, game_impl, server, outside_client)
+    }
+
+    /// Puts the triggers of the pending events on the stack and drops the events, like
+    /// [`GameImplV1::run`] does once it stepped
+    async fn run_triggers(game_impl: &mut GameImplV1) {
+        game_impl.run_zone_change_triggers().await.unwrap();
+        game_impl.events.clear();
+    }
+
+    /// Lets all players pass until everything on the stack resolved
+    async fn resolve_stack(game_impl: &mut GameImplV1, outside: &OutsideGameClient) {
+        while !game_impl.latest_gamestate().get_stack().objects.is_empty() {
+            let state = game_impl.game.game_states.last_mut().unwrap();
+            state.unpassed_players.clear();
+            game_impl.run(outside).await.unwrap();
+        }
+    }
+
+    impl SimpleTestHarness {
+        fn new(seed: Option<u64>, answers: ServerAnswers) -> Self {
+            let (harness, server) = Self::new_with_server(seed, answers);
+
+            let server = tarpc::server::BaseChannel::with_defaults(server);
+            let _outside_server = tokio::spawn(
+                server.execute(
+                    SimpleOutsideServer {
+                        answers: harness.answers.clone(),
+                    }
+                    .serve(),
+                ),
+            );
+
+            harness
+        }
+        fn new_with_server(
+            seed: Option<u64>,
+            answers: ServerAnswers,
+        ) -> (
+            SimpleTestHarness,
+            UnboundedChannel<ClientMessage<OutsideRequest>, Response<OutsideResponse>>,
+        ) {
+            let (player_order, game_impl, server, outside_client) = init_harness(seed);
+
+            (
+                SimpleTestHarness {
+                    player_order,
+                    game_impl,
+                    outside_client,
+                    answers: Arc::new(Mutex::new(answers)),
+                },
+                server,
+            )
+        }
+    }
+
+    macro_rules! game_steps {
+        (@set $harness:ident $action:ident = $($func:tt)*) => {
+            $harness.answers.lock().await.$action = Some(Box::new($($func)*));
+        };
+        (@unset $harness:ident) => {
+            *$harness.answers.lock().await = ServerAnswers::default();
+        };
+        (@step_game $harness:ident) => {
+            $harness.game_impl.run(&$harness.outside_client).await.unwrap();
+        };
+        (@run $harness:ident $($normal:tt)*) => {
+            $($normal)*
+        };
+        ($harness:ident, [ $(@$kind:tt { $($val:tt)* };)+ ]) => {
+            $(game_steps!(@$kind $harness $($val)*));+
+        };
+    }
+
+    macro_rules! async_test {
+        (async fn $name:ident() $($tt:tt)*) => {
+            #[test]
+            fn $name() {
+                use tracing_subscriber::layer::SubscriberExt;
+                use tracing_subscriber::util::SubscriberInitExt;
+                use tracing::Instrument;
+
+                let filter = tracing_subscriber::filter::EnvFilter::from_default_env();
+                let fmt_layer = tracing_subscriber::fmt::layer()
+                    .with_timer(tracing_subscriber::fmt::time::uptime())
+                    .with_level(true)
+                    .with_file(true)
+                    .with_line_number(true)
+                    .with_test_writer()
+                    .pretty();
+
+                let _ = tracing_subscriber::registry()
+                    .with(filter)
+                    .with(fmt_layer)
+                    .try_init();
+
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+
+                rt.block_on(async {
+                    $($tt)*
+                }.instrument(tracing::info_span!("Running test", name = stringify!($name))));
+
+                rt.shutdown_background();
+            }
+        };
+    }
+
+    async_test!(
+        async fn check_initial_game_creation() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            assert!(!harness.game_impl.game.game_states.is_empty());
+        }
+    );
+
+    async_test!(
+        async fn check_initial_game_zones() {
+            let mut harness = SimpleTestHarness::new(
+                None,
+                ServerAnswers {
+                    get_player_keeping: Some(Box::new(|players| players)),
+                    ..Default::default()
+                },
+            );
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            let state = harness.game_impl.latest_gamestate();
+
+            let first_player = harness.player_order.first().copied().unwrap();
+
+            assert_eq!(harness.player_order.len() * 4 + 3, state.zones.len());
+            assert_eq!(
+                simple_deck().len(),
+                state
+                    .zones
+                    .get(&ZoneId::Library(first_player))
+                    .unwrap()
+                    .objects
+                    .len()
+                    + state
+                        .zones
+                        .get(&ZoneId::Hand(first_player))
+                        .unwrap()
+                        .objects
+                        .len()
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_game_starts_with_initial_player_order() {
+            let mut harness = SimpleTestHarness::new(
+                None,
+                ServerAnswers {
+                    ..Default::default()
+                },
+            );
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+
+            assert_eq!(&state.active_player_order, &harness.player_order);
+        }
+    );
+
+    async_test!(
+        async fn check_game_mulligan() {
+            let mut harness = SimpleTestHarness::new(
+                None,
+                ServerAnswers {
+                    ..Default::default()
+                },
+            );
+            let player = *harness.player_order.first().unwrap();
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = move |mut players| {
+                            players.retain(|p| p != &player);
+                            players
+                        }
+                    };
+                    @step_game { };
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @step_game { };
+                ]
+            );
+
+            let state = harness.game_impl.latest_gamestate();
+            assert!(
+                matches!(state.game_stage, crate::GameStage::GameRunning),
+                "Game is still not running!"
+            );
+            assert_eq!(
+                6,
+                state
+                    .zones
+                    .get(&ZoneId::Hand(player))
+                    .unwrap()
+                    .objects
+                    .len()
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_game_player_plays_card() {
+            let mut harness = SimpleTestHarness::new(
+                Some(1234),
+                ServerAnswers {
+                    ..Default::default()
+                },
+            );
+
+            let player = *harness.player_order.first().unwrap();
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @step_game {};
+                    @run {
+                        assert_eq!(
+                            harness.game_impl.latest_gamestate().game_stage,
+                            crate::GameStage::GameRunning
+                        );
+                        // A card that draws, the last card of the library replaces it in the hand
+                        let id = harness.game_impl.latest_gamestate().get_hand(player).objects.iter().find(|o| o.underlying_card == Some(CardId::with(DRAW_CARD))).unwrap().id;
+                        harness.game_impl.apply_atoms(vec![GameAtom::AddScrip { player, kind: ScripKind::Corp1, amount: 3 }]).unwrap();
+                    };
+                    @set {
+                        get_next_player_action_from = move |_player, player_actions| {
+                            player_actions.iter().position(|i| matches!(i, PlayerAction::PlayCard { object, ..} if *object == id)).unwrap()
+                        }
+                    };
+                    @set {
+                        get_player_passing = |_player: PlayerId| { true }
+                    };
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert_eq!(state.get_stack().objects.len(), 1);
+                        assert_eq!(state.scrip_of(player, ScripKind::Corp1), 1);
+                    };
+                    @unset {};
+                    @set {
+                        get_next_player_action_from = |_player, _player_actions| {
+                            0
+                        }
+                    };
+                    @step_game {};
+                    @step_game {};
+                    @step_game {};
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert_eq!(state.get_hand(player).objects.len(), 7);
+                    };
+                ]
+            );
+        }
+    );
+    async_test!(
+        async fn check_always_keep_hand_preference() {
+            let mut harness = SimpleTestHarness::new(
+                None,
+                ServerAnswers {
+                    get_player_keeping: None,
+                    ..Default::default()
+                },
+            );
+            for player in harness.game_impl.game.players.values_mut() {
+                player.preferences.always_keep_hand = true;
+            }
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                harness.game_impl.latest_gamestate().game_stage,
+                crate::GameStage::GameRunning
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_verify_rejects_cards_above_format_cost() {
+            let (_, game_impl, _, _) = init_harness(None);
+            let game_impl = game_impl.with_format(FormatRules {
+                max_total_cost: Some(1),
+                ..Default::default()
+            });
+
+            let errors = game_impl.verify().err().unwrap();
+            assert!(errors
+                .iter()
+                .any(|e| matches!(e, VerificationError::InvalidCardCost { .. })));
+        }
+    );
+
+    async_test!(
+        async fn check_verify_only_checks_cards_of_the_decks() {
+            let mut cards = existing_cards();
+            let mut expensive =
+                agent_card(uuid::uuid!("5e4b5a4c-79c1-4a5f-8d0e-4d1bd1c6f0a3"), vec![]);
+            expensive.behaviour.cost = Some(Cost {
+                corp1_scrip: MAX_SCRIP_PER_KIND + 1,
+                ..Default::default()
+            });
+            let expensive_id = expensive.id;
+            cards.insert(expensive_id, expensive);
+            let mut players = playtesters();
+            let player_order: Vec<_> = players.keys().copied().collect();
+            let rand = Xoshiro256StarStar::seed_from_u64(1337);
+            let cards = Arc::new(cards);
+
+            let game_impl = GameImplV1::new(
+                GameId::new(),
+                rand.clone(),
+                cards.clone(),
+                players.clone(),
+                player_order.clone(),
+            );
+            assert!(game_impl.verify().is_ok());
+
+            let player = players.get_mut(&player_order[0]).unwrap();
+            player.initial_cards.push(expensive_id);
+            let game_impl = GameImplV1::new(GameId::new(), rand, cards, players, player_order);
+            let errors = game_impl.verify().err().unwrap();
+            assert!(matches!(
+                errors.as_slice(),
+                [VerificationError::InvalidCardCost { card, .. }] if *card == expensive_id
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_zero_costs_are_loaded_as_no_cost() {
+            let mut free = agent_card(uuid::uuid!("0b7f1c1e-4a8e-4a54-9f43-6b7a0f6f1d52"), vec![]);
+            free.behaviour.cost = Some(Cost::default());
+            let free_id = free.id;
+            let game = EngineBuilder::new()
+                .cards(existing_cards().into_values().chain([free]))
+                .build(playtesters().into_values().collect());
+
+            assert_eq!(game.game.cards[&free_id].behaviour.cost, None);
+            assert_eq!(
+                game.game.cards[&CardId::with(BLAST_CARD)].behaviour.cost,
+                Some(Cost {
+                    corp1_scrip: 2,
+                    ..Default::default()
+                })
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_zone_counts_are_sent() {
+            let events = Arc::new(std::sync::Mutex::new(vec![]));
+            let sent_events = events.clone();
+            let mut harness = SimpleTestHarness::new(
+                None,
+                ServerAnswers {
+                    notify_event: Some(Box::new(move |event| {
+                        sent_events.lock().unwrap().push(event)
+                    })),
+                    ..Default::default()
+                },
+            );
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            let events = events.lock().unwrap();
+            let Some(GameEvent::ZoneCountsChanged { counts }) = events.last() else {
+                panic!("No zone counts were sent");
+            };
+            assert_eq!(counts.len(), harness.player_order.len());
+            assert!(counts.values().all(|c| c.hand == 7));
+        }
+    );
+
+    async_test!(
+        async fn check_phase_advances_once_all_players_passed() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @run {
+                        assert_eq!(harness.game_impl.latest_gamestate().phase, crate::Phase::Main);
+                    };
+                    @set {
+                        get_next_player_action_from = |_player, _player_actions| {
+                            0
+                        }
+                    };
+                    @step_game {};
+                    @step_game {};
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert_eq!(state.phase, crate::Phase::TurnEnd);
+                        assert_eq!(state.unpassed_players, harness.player_order);
+                    };
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_drawn_cards_are_sent_as_moved() {
+            let events = Arc::new(std::sync::Mutex::new(vec![]));
+            let sent_events = events.clone();
+            let mut harness = SimpleTestHarness::new(
+                None,
+                ServerAnswers {
+                    notify_event: Some(Box::new(move |event| {
+                        sent_events.lock().unwrap().push(event)
+                    })),
+                    ..Default::default()
+                },
+            );
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            let events = events.lock().unwrap();
+            let drawn = events
+                .iter()
+                .filter(|e| {
+                    matches!(
+                        e,
+                        GameEvent::ObjectMoved {
+                            from: ZoneId::Library(_),
+                            to: ZoneId::Hand(_),
+                            ..
+                        }
+                    )
+                })
+                .count();
+            assert_eq!(drawn, harness.player_order.len() * 7);
+        }
+    );
+
+    async_test!(
+        async fn check_revealed_objects_are_sent_with_their_card() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            let object = game_impl
+                .latest_gamestate()
+                .zones
+                .get(&ZoneId::Library(player))
+                .unwrap()
+                .objects[0]
+                .clone();
+
+            game_impl
+                .apply_atoms(vec![
+                    GameAtom::RevealObject { object: object.id },
+                    GameAtom::ShuffleLibrary { player },
+                ])
+                .unwrap();
+
+            assert_eq!(
+                game_impl.events,
+                vec![GameEvent::ObjectRevealed {
+                    object: object.id,
+                    card: object.underlying_card.unwrap(),
+                    zone: ZoneId::Library(player),
+                }]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_restore_discards_uncommitted_batches() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            // Games persisted by older engines recorded batches before applying them
+            let not_first = harness.player_order[1];
+            let mut game = harness.game_impl.game.clone();
+            let latest = game.latest_index();
+            game.history
+                .push((latest, vec![GameAtom::PassPriority { player: not_first }]));
+
+            let cards = game.cards.clone();
+            harness.game_impl = GameImplV1::restore(game, cards).unwrap();
+
+            let game = &harness.game_impl.game;
+            assert_eq!(game.history.len(), game.game_states.len() - 1);
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_next_player_action_from = |_player, _player_actions| {
+                            0
+                        }
+                    };
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert_eq!(state.unpassed_players, harness.player_order[1..]);
+                    };
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_arranged_library_order() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            let library = |game_impl: &GameImplV1| -> Vec<ObjectId> {
+                game_impl
+                    .latest_gamestate()
+                    .zones
+                    .get(&ZoneId::Library(player))
+                    .unwrap()
+                    .objects
+                    .iter()
+                    .map(|o| o.id)
+                    .collect()
+            };
+            let before = library(&game_impl);
+            let [.., fourth, third, second, first] = before[..] else {
+                panic!("Library is too small");
+            };
+
+            game_impl
+                .apply_atoms(vec![GameAtom::ArrangeLibrary {
+                    player,
+                    top: vec![second, first],
+                    bottom: vec![third, fourth],
+                }])
+                .unwrap();
+
+            let after = library(&game_impl);
+            assert_eq!(after.len(), before.len());
+            assert_eq!(after[..2], [fourth, third]);
+            assert_eq!(after[after.len() - 2..], [first, second]);
+        }
+    );
+
+    async_test!(
+        async fn check_discard_to_hand_size_in_cleanup() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            harness.game_impl.game.format.max_hand_size = Some(5);
+            let player = harness.player_order[0];
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @set {
+                        get_next_player_action_from = |_player, _player_actions| {
+                            0
+                        }
+                    };
+                    @set {
+                        get_discard_choice = |_player, _cards, count| {
+                            (0..count).collect()
+                        }
+                    };
+                    @run {
+                        for _ in 0..7 {
+                            harness.game_impl.run(&harness.outside_client).await.unwrap();
+                        }
+                    };
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert_eq!(state.phase, crate::Phase::Cleanup);
+                        assert_eq!(state.get_hand(player).objects.len(), 5);
+                        assert_eq!(
+                            state.zones.get(&ZoneId::Discard(player)).unwrap().objects.len(),
+                            2
+                        );
+                    };
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_slow_effects_time_out() {
+            let (_, game_impl, _, _) = init_harness(None);
+            let game_impl = game_impl.with_effect_time_limit(Duration::from_millis(10));
+            let card = CardId::with(BLAST_CARD);
+
+            let error = game_impl
+                .execute_effect(&Stall, card, HashMap::new(), ObjectId(Uuid::nil()))
+                .await
+                .unwrap_err();
+
+            assert!(matches!(
+                error,
+                GameError::EffectExecuteFailure {
+                    failure: ExecuteFailure::TimedOut { card: timed_out, .. }
+                } if timed_out == card
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_london_mulligan_puts_cards_on_bottom() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            harness.game_impl.game.format.mulligan = MulliganRule::London;
+            let player = *harness.player_order.first().unwrap();
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = move |mut players| {
+                            players.retain(|p| p != &player);
+                            players
+                        }
+                    };
+                    @step_game { };
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @set {
+                        get_cards_to_bottom = |_player, _cards, count| {
+                            (0..count).collect()
+                        }
+                    };
+                    @step_game { };
+                ]
+            );
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.game_stage, crate::GameStage::GameRunning);
+            assert_eq!(state.get_hand(player).objects.len(), 6);
+        }
+    );
+
+    async_test!(
+        async fn check_default_choice_for_unreachable_player() {
+            let events = Arc::new(std::sync::Mutex::new(vec![]));
+            let sent_events = events.clone();
+            let mut harness = SimpleTestHarness::new(
+                None,
+                ServerAnswers {
+                    get_next_player_action_from: Some(Box::new(|_player, _player_actions| 0)),
+                    notify_event: Some(Box::new(move |event| {
+                        sent_events.lock().unwrap().push(event)
+                    })),
+                    ..Default::default()
+                },
+            );
+            harness.game_impl = harness
+                .game_impl
+                .with_format(FormatRules {
+                    max_hand_size: Some(5),
+                    ..Default::default()
+                })
+                .with_default_choice_on_disconnect(true);
+            let player = harness.player_order[0];
+
+            // Nobody answers which cards to discard during cleanup
+            for _ in 0..8 {
+                harness
+                    .game_impl
+                    .run(&harness.outside_client)
+                    .await
+                    .unwrap();
+            }
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.phase, crate::Phase::Cleanup);
+            assert_eq!(state.get_hand(player).objects.len(), 5);
+            assert!(events
+                .lock()
+                .unwrap()
+                .contains(&GameEvent::DefaultChoiceMade { player }));
+        }
+    );
+
+    async_test!(
+        async fn check_builder_game_starts() {
+            let answers = Arc::new(Mutex::new(ServerAnswers {
+                get_player_keeping: Some(Box::new(|players| players)),
+                ..Default::default()
+            }));
+            let builder = EngineBuilder::new()
+                .cards(existing_cards().into_values())
+                .seed(1337);
+            let (mut game, outside) = builder.build_with_outside(
+                playtesters().into_values().collect(),
+                SimpleOutsideServer { answers },
+            );
+
+            game.run(&outside).await.unwrap();
+
+            assert_eq!(
+                game.latest_gamestate().game_stage,
+                crate::GameStage::GameRunning
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_elimination_until_one_player_remains() {
+            let answers = Arc::new(Mutex::new(ServerAnswers {
+                get_player_keeping: Some(Box::new(|players| players)),
+                ..Default::default()
+            }));
+            let mut players: Vec<_> = playtesters().into_values().collect();
+            players.push(Player {
+                id: PlayerId::new(),
+                initial_cards: simple_deck(),
+                preferences: Default::default(),
+            });
+            let builder = EngineBuilder::new()
+                .cards(existing_cards().into_values())
+                .seed(1337);
+            let (mut game, outside) =
+                builder.build_with_outside(players, SimpleOutsideServer { answers });
+
+            game.run(&outside).await.unwrap();
+
+            let order = game.latest_gamestate().active_player_order.clone();
+            assert_eq!(order.len(), 3);
+
+            game.eliminate_player(order[0]).unwrap();
+
+            let state = game.latest_gamestate();
+            assert!(state.is_eliminated(order[0]));
+            assert_eq!(state.active_player_order, order[1..]);
+            assert_eq!(state.unpassed_players, order[1..]);
+            assert_eq!(state.phase, crate::Phase::Recovery);
+            assert_eq!(game.outcome(), None);
+
+            assert!(matches!(
+                game.eliminate_player(order[0]),
+                Err(GameError::PlayerNotInGame { .. })
+            ));
+
+            game.eliminate_player(order[2]).unwrap();
+
+            assert_eq!(game.outcome(), Some(GameOutcome::Won { winner: order[1] }));
+        }
+    );
+
+    async_test!(
+        async fn check_eliminated_players_objects_are_exiled() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+
+            let game = &mut harness.game_impl.game;
+            let mut objects = vec![];
+            for controller in [player, opponent] {
+                let mut object = GameObject::from_card(&mut game.rand, CardId::with(BLAST_CARD));
+                object.controller = Some(controller);
+                objects.push(object);
+            }
+            let (theirs, kept) = (objects[0].id, objects[1].id);
+            let trigger = GameObject::from_trigger(
+                ObjectId::new(&mut game.rand),
+                player,
+                StackTrigger {
+                    source: theirs,
+                    card: CardId::with(BLAST_CARD),
+                    ability: 0,
+                    effect: 0,
+                    choices_made: true,
+                },
+            );
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .extend(objects);
+            state
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap()
+                .objects
+                .push(trigger);
+
+            harness.game_impl.eliminate_player(player).unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            let exiled = state.get_object_from_zone(ZoneId::Exile, theirs).unwrap();
+            assert_eq!(exiled.controller, None);
+            assert!(state
+                .get_object_from_zone(ZoneId::Battlefield, kept)
+                .is_some());
+            assert!(state.get_stack().objects.is_empty());
+            assert!(harness.game_impl.events.iter().any(|e| matches!(
+                e,
+                GameEvent::ObjectMoved { object, to: ZoneId::Exile, .. } if *object == theirs
+            )));
+        }
+    );
+
+    async_test!(
+        async fn check_action_limit_passes_priority() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            harness.game_impl.game.format.max_actions_per_priority = Some(1);
+
+            let player = *harness.player_order.first().unwrap();
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @step_game {};
+                    @run {
+                        harness.game_impl.apply_atoms(vec![GameAtom::AddScrip { player, kind: ScripKind::Corp1, amount: 2 }]).unwrap();
+                    };
+                    @set {
+                        get_next_player_action_from = |_player, player_actions| {
+                            let id = ObjectId(Uuid::from_str("2eaec1b5-94a9-4994-b038-54826e4e3ca6").unwrap());
+                            player_actions.iter().position(|i| matches!(i, PlayerAction::PlayCard { object, ..} if *object == id)).unwrap()
+                        }
+                    };
+                    @set {
+                        get_target_choices_from_given = | player: PlayerId, _source: ObjectId, _name: String, choices: Vec<TargetId>, _count: usize, _optional: bool| {
+                            choices.iter().enumerate().filter(|(_, c)| match c { TargetId::Player(ply) => *ply != player, _ => false }).map(|(idx, _c)| idx).collect()
+                        }
+                    };
+                    @set {
+                        get_player_passing = |_player: PlayerId| { false }
+                    };
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert_eq!(state.get_stack().objects.len(), 1);
+                        assert_eq!(state.unpassed_players.first(), Some(&player));
+                        assert_eq!(state.actions_this_priority[&player], 1);
+                    };
+                    // The player is not asked again, they have to pass
+                    @unset {};
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert_ne!(state.unpassed_players.first(), Some(&player));
+                        assert!(!state.actions_this_priority.contains_key(&player));
+                        assert_eq!(state.actions_this_turn[&player], 1);
+                    };
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_teams_share_a_win() {
+            let answers = Arc::new(Mutex::new(ServerAnswers {
+                get_player_keeping: Some(Box::new(|players| players)),
+                ..Default::default()
+            }));
+            let players: Vec<_> = (0..4)
+                .map(|_| Player {
+                    id: PlayerId::new(),
+                    initial_cards: simple_deck(),
+                    preferences: Default::default(),
+                })
+                .collect();
+            let ids: Vec<_> = players.iter().map(|p| p.id).collect();
+            let builder = EngineBuilder::new()
+                .cards(existing_cards().into_values())
+                .teams(vec![vec![ids[0], ids[2]], vec![ids[1], ids[3]]])
+                .seed(1337);
+            let (mut game, outside) =
+                builder.build_with_outside(players, SimpleOutsideServer { answers });
+
+            game.run(&outside).await.unwrap();
+
+            let opponents = crate::target::possible_targets(
+                &game.game,
+                ids[0],
+                Some(&TargetRestriction::Controller(RelativePlayer::Opponent)),
+            );
+            assert_eq!(opponents.len(), 2);
+            assert!(!opponents.contains(&TargetId::Player(ids[2])));
+
+            game.eliminate_player(ids[1]).unwrap();
+            assert_eq!(game.outcome(), None);
+
+            game.eliminate_player(ids[3]).unwrap();
+            assert_eq!(
+                game.outcome(),
+                Some(GameOutcome::TeamWon { team: TeamId(0) })
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_keywords_in_targeting_and_timing() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let plain = agent_card(Uuid::from_u128(1), vec![]);
+            let fast = agent_card(
+                Uuid::from_u128(2),
+                vec![Keyword::Fast, Keyword::Untargetable],
+            );
+
+            let game = &mut game_impl.game;
+            let mut objects = vec![];
+            for card in [plain.id, fast.id] {
+                let mut object = GameObject::from_card(&mut game.rand, card);
+                object.controller = Some(player_order[1]);
+                objects.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+            }
+            let cards = Arc::get_mut(&mut game.cards).unwrap();
+            cards.insert(plain.id, plain);
+            cards.insert(fast.id, fast);
+
+            let targets = crate::target::possible_targets(&game_impl.game, player_order[0], None);
+            assert!(targets.contains(&TargetId::Object(objects[0])));
+            assert!(!targets.contains(&TargetId::Object(objects[1])));
+
+            let format = FormatRules::default();
+            let state = game_impl.latest_gamestate();
+            let cards = &game_impl.game.cards;
+            let (plain, fast) = (
+                &cards[&CardId::with(Uuid::from_u128(1))].behaviour,
+                &cards[&CardId::with(Uuid::from_u128(2))].behaviour,
+            );
+            assert!(crate::can_play_now(&format, state, player_order[0], plain));
+            assert!(!crate::can_play_now(&format, state, player_order[1], plain));
+            assert!(crate::can_play_now(&format, state, player_order[1], fast));
+
+            let format = FormatRules {
+                timing: [(BaseCardKindTag::Agent, Timing::Any)].into(),
+                ..Default::default()
+            };
+            assert!(crate::can_play_now(&format, state, player_order[1], plain));
+        }
+    );
+
+    async_test!(
+        async fn check_resolved_cards_change_zones_by_kind() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            let agent = agent_card(Uuid::from_u128(1), vec![]);
+
+            let game = &mut harness.game_impl.game;
+            let mut stack = vec![];
+            for card in [CardId::with(DRAW_CARD), agent.id] {
+                let mut object = GameObject::from_card(&mut game.rand, card);
+                object.controller = Some(player);
+                stack.push(object);
+            }
+            let (quickhack, agent_object) = (stack[0].id, stack[1].id);
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(agent.id, agent);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap()
+                .objects
+                .extend(stack);
+
+            for resolved in [agent_object, quickhack] {
+                let state = harness.game_impl.game.game_states.last_mut().unwrap();
+                state.unpassed_players.clear();
+                harness
+                    .game_impl
+                    .run(&harness.outside_client)
+                    .await
+                    .unwrap();
+
+                let state = harness.game_impl.latest_gamestate();
+                assert!(state
+                    .get_object_from_zone(ZoneId::Stack, resolved)
+                    .is_none());
+            }
+
+            let state = harness.game_impl.latest_gamestate();
+            let on_battlefield = state
+                .get_object_from_zone(ZoneId::Battlefield, agent_object)
+                .unwrap();
+            assert_eq!(on_battlefield.controller, Some(player));
+            assert!(state
+                .get_object_from_zone(ZoneId::Discard(player), quickhack)
+                .is_some());
+        }
+    );
+
+    async_test!(
+        async fn check_buildings_produce_scrip() {
+            let SimpleTestHarness {
+                player_order,
+                mut game_impl,
+                outside_client,
+                ..
+            } = SimpleTestHarness::new(None, ServerAnswers::default());
+            // The turn passes to the second player
+            let player = player_order[1];
+            let building = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Building {
+                            subkind: SubKind::new("Mercenary"),
+                        },
+                    }],
+                    effects: vec![
+                        ProduceScrip::each_turn(ScripKind::Corp1, 2),
+                        ProduceScrip::on_exhaust(ScripKind::Corp2, 1),
+                    ],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut game_impl.game;
+            let mut object = GameObject::from_card(&mut game.rand, building.id);
+            object.controller = Some(player);
+            object.exhausted = true;
+            let object_id = object.id;
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(building.id, building);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state.phase = crate::Phase::Cleanup;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(object);
+
+            assert!(crate::activatable_abilities(&game_impl.game, player).is_empty());
+
+            game_impl.advance_phase().await.unwrap();
+            assert_eq!(
+                crate::activatable_abilities(&game_impl.game, player),
+                vec![(object_id, 0)]
+            );
+
+            game_impl.advance_phase().await.unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.phase, crate::Phase::TurnStart);
+            assert_eq!(state.get_stack().objects.len(), 1);
+            assert_eq!(state.scrip_of(player, ScripKind::Corp1), 0);
+
+            resolve_stack(&mut game_impl, &outside_client).await;
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.scrip_of(player, ScripKind::Corp1), 2);
+        }
+    );
+
+    async_test!(
+        async fn check_delayed_triggers_fire_once_in_their_phase() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let player = player_order[0];
+            // "At the beginning of your next main phase, you gain 3 life"
+            let card = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Building {
+                            subkind: SubKind::new("Mercenary"),
+                        },
+                    }],
+                    effects: vec![CardEffect::Triggered(TriggeredCardEffect {
+                        trigger: EffectTrigger::Delayed,
+                        effects: vec![Effect::Instant(Box::new(GainLife(3)))],
+                    })],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut game_impl.game;
+            let mut object = GameObject::from_card(&mut game.rand, card.id);
+            object.controller = Some(player);
+            let trigger = DelayedTrigger {
+                event: DelayedTriggerEvent::BeginningOfPhase(crate::Phase::Main),
+                source: object.id,
+                card: card.id,
+                effect: 0,
+                info: HashMap::new(),
+            };
+            Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state.phase = crate::Phase::Recovery;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(object);
+            game_impl
+                .apply_atoms(vec![GameAtom::RegisterDelayedTrigger { trigger }])
+                .unwrap();
+
+            for phase in [crate::Phase::TurnStart, crate::Phase::Draw] {
+                game_impl.advance_phase().await.unwrap();
+                let state = game_impl.latest_gamestate();
+                assert_eq!(state.phase, phase);
+                assert_eq!(state.life_of(player), STARTING_LIFE);
+                assert_eq!(state.delayed_triggers.len(), 1);
+            }
+
+            game_impl.advance_phase().await.unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.phase, crate::Phase::Main);
+            assert_eq!(state.life_of(player), STARTING_LIFE + 3);
+            assert!(state.delayed_triggers.is_empty());
+
+            // Through the following turn and into its main phase
+            for _ in 0..6 {
+                game_impl.advance_phase().await.unwrap();
+            }
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.phase, crate::Phase::Main);
+            assert_eq!(state.life_of(player), STARTING_LIFE + 3);
+        }
+    );
+
+    async_test!(
+        async fn check_life_gain_and_damage() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let player = player_order[0];
+            assert_eq!(game_impl.latest_gamestate().life_of(player), STARTING_LIFE);
+
+            let source = ObjectId::new(&mut game_impl.game.rand);
+            game_impl
+                .apply_atoms(vec![
+                    GameAtom::GainLife { player, amount: 4 },
+                    GameAtom::DealDamage {
+                        amount: 30,
+                        source,
+                        target: TargetId::Player(player),
+                    },
+                    GameAtom::GainLife { player, amount: 2 },
+                ])
+                .unwrap();
+
+            assert_eq!(game_impl.latest_gamestate().life_of(player), 2);
+            assert_eq!(
+                game_impl.latest_gamestate().life_of(player_order[1]),
+                STARTING_LIFE
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_restore_refuses_version_skew() {
+            let (_player_order, game_impl, _server, _client) = init_harness(None);
+            let mut game = game_impl.game.clone();
+            let cards = game.cards.clone();
+            assert_eq!(
+                game.versions,
+                GameVersions::current(&cards, game.players.values())
+            );
+
+            game.versions.rules = 0;
+            let skew = GameImplV1::restore(game.clone(), cards.clone()).unwrap_err();
+            assert_eq!(
+                skew,
+                vec![VersionSkew::Rules {
+                    recorded: 0,
+                    current: RULES_VERSION
+                }]
+            );
+
+            let mut migrated = vec![];
+            let game_impl = GameImplV1::restore_migrated(game, cards.clone(), |_, skew| {
+                migrated.extend_from_slice(skew)
+            });
+            assert_eq!(migrated, skew);
+            assert!(game_impl.game.versions.skew(&cards).is_empty());
+        }
+    );
+
+    async_test!(
+        async fn check_mill_from_library_to_discard() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let player = player_order[0];
+            let library: Vec<_> = game_impl
+                .latest_gamestate()
+                .zones
+                .get(&ZoneId::Library(player))
+                .unwrap()
+                .objects
+                .iter()
+                .map(|o| o.id)
+                .collect();
+
+            let mut info = HashMap::new();
+            info.insert(
+                String::from("target"),
+                EffectInfo::SingleTarget(TargetId::Player(player)),
+            );
+            let source = ObjectId::new(&mut game_impl.game.rand);
+            let atoms = Mill(2)
+                .execute(info, source, &game_impl.game)
+                .await
+                .unwrap();
+            game_impl.apply_atoms(atoms).unwrap();
+
+            let state = game_impl.latest_gamestate();
+            let discard: Vec<_> = state.zones[&ZoneId::Discard(player)]
+                .objects
+                .iter()
+                .map(|o| o.id)
+                .collect();
+            let top = library.len() - 1;
+            assert_eq!(discard, vec![library[top], library[top - 1]]);
+            assert_eq!(
+                state.zones[&ZoneId::Library(player)].objects.len(),
+                library.len() - 2
+            );
+
+            // Milling more than is left empties the library, milling an empty one does nothing
+            game_impl
+                .apply_atoms(vec![GameAtom::Mill {
+                    player,
+                    count: library.len(),
+                }])
+                .unwrap();
+            game_impl
+                .apply_atoms(vec![GameAtom::Mill { player, count: 1 }])
+                .unwrap();
+
+            let state = game_impl.latest_gamestate();
+            assert!(state.zones[&ZoneId::Library(player)].objects.is_empty());
+            assert_eq!(
+                state.zones[&ZoneId::Discard(player)].objects.len(),
+                library.len()
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_games_survive_a_faulty_outside() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let outside = FaultyOutside::new(
+                OutsideGameClient::new(
+                    harness.game_impl.id(),
+                    harness.outside_client.client.clone(),
+                ),
+                Faults {
+                    latency: Duration::from_millis(20),
+                    ..Default::default()
+                },
+                1337,
+            );
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @set {
+                        get_next_player_action_from = |_player, _player_actions| {
+                            0
+                        }
+                    };
+                ]
+            );
+
+            // Slow answers within the deadline do not matter
+            harness.game_impl.run(&outside).await.unwrap();
+            assert_eq!(
+                harness.game_impl.latest_gamestate().game_stage,
+                crate::GameStage::GameRunning
+            );
+
+            // Neither garbled nor lost answers change the game, the player is asked again
+            let states = harness.game_impl.game.game_states.len();
+            outside.set_faults(Faults {
+                malformed_rate: 1.0,
+                ..Default::default()
+            });
+            let error = harness.game_impl.run(&outside).await.unwrap_err();
+            assert!(matches!(error, GameError::InvalidAction { .. }));
+
+            outside.set_faults(Faults {
+                drop_rate: 1.0,
+                ..Default::default()
+            });
+            let error = harness.game_impl.run(&outside).await.unwrap_err();
+            assert!(matches!(error, GameError::RPCError(_)));
+            assert_eq!(harness.game_impl.game.game_states.len(), states);
+
+            outside.set_faults(Faults::default());
+            harness.game_impl.run(&outside).await.unwrap();
+            assert_eq!(
+                harness.game_impl.latest_gamestate().unpassed_players,
+                harness.player_order[1..]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_exhausting_is_paid_once() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let player = player_order[0];
+            let building = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Building {
+                            subkind: SubKind::new("Mercenary"),
+                        },
+                    }],
+                    effects: vec![ProduceScrip::on_exhaust(ScripKind::Corp2, 1)],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut game_impl.game;
+            let mut object = GameObject::from_card(&mut game.rand, building.id);
+            object.controller = Some(player);
+            let object_id = object.id;
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(building.id, building);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(object);
+
+            game_impl
+                .apply_atoms(vec![GameAtom::Exhaust { object: object_id }])
+                .unwrap();
+            assert!(crate::activatable_abilities(&game_impl.game, player).is_empty());
+            assert!(matches!(
+                game_impl.apply_atoms(vec![GameAtom::Exhaust { object: object_id }]),
+                Err(GameError::AlreadyExhausted { object }) if object == object_id
+            ));
+
+            game_impl
+                .apply_atoms(vec![GameAtom::Ready { object: object_id }])
+                .unwrap();
+            assert_eq!(
+                crate::activatable_abilities(&game_impl.game, player),
+                vec![(object_id, 0)]
+            );
+        }
+    );
+
+    #[test]
+    fn check_prompt_objects_are_paged() {
+        let player = PlayerId::new();
+        let objects: Vec<_> = (0..OBJECT_PAGE_SIZE as u128 + 5)
+            .map(|n| {
+                (
+                    ObjectId(Uuid::from_u128(n)),
+                    Some(CardId::with(Uuid::from_u128(n))),
+                )
+            })
+            .collect();
+
+        let prompt_objects = crate::outside::PromptObjects::default();
+        let shown = prompt_objects.show(player, &objects);
+        assert_eq!(shown.len(), objects.len());
+        assert_eq!(shown.cards.len(), OBJECT_PAGE_SIZE);
+        assert!(shown.is_partial());
+
+        let (last, card) = *objects.last().unwrap();
+        let unknown = ObjectId(Uuid::from_u128(1000));
+        assert_eq!(
+            prompt_objects.details(player, &[last, unknown]),
+            vec![card, None]
+        );
+        assert_eq!(prompt_objects.details(PlayerId::new(), &[last]), vec![None]);
+
+        prompt_objects.clear(player);
+        assert_eq!(prompt_objects.details(player, &[last]), vec![None]);
+    }
+
+    async_test!(
+        async fn check_side_rules_wager_and_bounty() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[1];
+            harness.game_impl.game.format.side_rules = vec![
+                // The test decks only keep a single card in the library after drawing
+                SideRule::Wager { cards: 1 },
+                SideRule::Bounty {
+                    kind: ScripKind::Corp3,
+                    amount: 5,
+                },
+            ];
+            let library_before =
+                harness.game_impl.latest_gamestate().zone_counts()[&player].library;
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @step_game {};
+                ]
+            );
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.game_stage, crate::GameStage::GameRunning);
+            assert_eq!(
+                state.zones[&ZoneId::Exile].objects.len(),
+                harness.player_order.len()
+            );
+            assert_eq!(
+                state.zone_counts()[&player].library,
+                library_before - STARTING_HAND_SIZE - 1
+            );
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::AddScrip {
+                    player,
+                    kind: ScripKind::Corp3,
+                    amount: 5,
+                }])
+                .unwrap();
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_next_player_action_from = |_player, player_actions| {
+                            player_actions
+                                .iter()
+                                .position(|a| matches!(a, PlayerAction::PassPriority))
+                                .unwrap()
+                        }
+                    };
+                    @step_game {};
+                ]
+            );
+
+            assert_eq!(
+                harness.game_impl.outcome(),
+                Some(GameOutcome::Won { winner: player })
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_arriving_agents_can_not_exhaust() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let player = player_order[0];
+            let agent = |n, keywords| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Agent {
+                            subkind: SubKind::new("Mercenary"),
+                            power: AgentPower::Fixed(1),
+                            toughness: AgentToughness::Fixed(1),
+                        },
+                    }],
+                    effects: vec![ProduceScrip::on_exhaust(ScripKind::Corp1, 1)],
+                    keywords,
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut game_impl.game;
+            let mut objects = vec![];
+            for card in [agent(1, vec![]), agent(2, vec![Keyword::Rush])] {
+                let mut object = GameObject::from_card(&mut game.rand, card.id);
+                object.controller = Some(player);
+                objects.push(object);
+                Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+            }
+            let (slow, rushing) = (objects[0].id, objects[1].id);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Hand(player))
+                .unwrap()
+                .objects
+                .extend(objects);
+
+            game_impl
+                .apply_atoms(
+                    [slow, rushing]
+                        .map(|object| GameAtom::MoveObject {
+                            object,
+                            from: ZoneId::Hand(player),
+                            to: ZoneId::Battlefield,
+                        })
+                        .to_vec(),
+                )
+                .unwrap();
+            assert_eq!(
+                crate::activatable_abilities(&game_impl.game, player),
+                vec![(rushing, 0)]
+            );
+
+            game_impl.game.game_states.last_mut().unwrap().turn += 1;
+            let mut abilities = crate::activatable_abilities(&game_impl.game, player);
+            abilities.sort_by_key(|(object, _)| *object == rushing);
+            assert_eq!(abilities, vec![(slow, 0), (rushing, 0)]);
+        }
+    );
+
+    async_test!(
+        async fn check_combat_damage_is_assigned_in_order() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (attacking, blocking) = (harness.player_order[0], harness.player_order[1]);
+            let agent = |n, power, toughness| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Agent {
+                            subkind: SubKind::new("Mercenary"),
+                            power: AgentPower::Fixed(power),
+                            toughness: AgentToughness::Fixed(toughness),
+                        },
+                    }],
+                    effects: vec![],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut ids = vec![];
+            for (card, controller) in [
+                (agent(1, 4, 4), attacking),
+                (agent(2, 1, 2), blocking),
+                (agent(3, 1, 3), blocking),
+            ] {
+                let mut object = GameObject::from_card(&mut game.rand, card.id);
+                object.controller = Some(controller);
+                ids.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+                Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+            }
+            let (attacker, blockers) = (ids[0], &ids[1..]);
+
+            harness.answers.lock().await.get_damage_assignment =
+                Some(Box::new(|_player, _attacker, _blockers, _damage| {
+                    vec![(1, 3), (0, 1)]
+                }));
+            let assigned = harness
+                .game_impl
+                .assign_combat_damage(&harness.outside_client, attacker, blockers)
+                .await
+                .unwrap();
+            assert_eq!(assigned, vec![(blockers[1], 3), (blockers[0], 1)]);
+
+            harness.answers.lock().await.get_damage_assignment =
+                Some(Box::new(|_player, _attacker, _blockers, _damage| {
+                    vec![(1, 2), (0, 2)]
+                }));
+            assert!(matches!(
+                harness
+                    .game_impl
+                    .assign_combat_damage(&harness.outside_client, attacker, blockers)
+                    .await,
+                Err(GameError::DamageAssignedOutOfOrder { blocker }) if blocker == blockers[0]
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_agents_attack_and_are_blocked() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let (attacking, defending) = (harness.player_order[0], harness.player_order[1]);
+            let agent = |n, power, toughness, keywords| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Agent {
+                            subkind: SubKind::new("Mercenary"),
+                            power: AgentPower::Fixed(power),
+                            toughness: AgentToughness::Fixed(toughness),
+                        },
+                    }],
+                    effects: vec![],
+                    keywords,
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            let game = &mut harness.game_impl.game;
+            let mut ids = vec![];
+            for (card, controller) in [
+                (agent(1, 3, 2, vec![Keyword::Overclocked]), attacking),
+                (agent(2, 2, 2, vec![]), attacking),
+                (agent(3, 5, 2, vec![]), defending),
+            ] {
+                let mut object = GameObject::from_card(&mut game.rand, card.id);
+                object.controller = Some(controller);
+                ids.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+                Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+            }
+            let (striker, brute, blocker) = (ids[0], ids[1], ids[2]);
+
+            {
+                let mut answers = harness.answers.lock().await;
+                answers.get_next_player_action_from = Some(Box::new(move |_player, actions| {
+                    actions
+                        .iter()
+                        .position(|action| {
+                            matches!(action, PlayerAction::Attack { attacker } if *attacker == striker)
+                        })
+                        .unwrap()
+                }));
+                answers.get_multiple_target_choices = Some(Box::new(
+                    move |player, _source, _name, choices, min, max| {
+                        assert_eq!(player, defending);
+                        assert_eq!(choices, vec![TargetId::Object(blocker)]);
+                        assert_eq!((min, max), (0, 1));
+                        vec![0]
+                    },
+                ));
+            }
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            // The overclocked striker kills its blocker before it can strike back
+            let state = harness.game_impl.latest_gamestate();
+            assert!(
+                state
+                    .get_object_from_zone(ZoneId::Battlefield, striker)
+                    .unwrap()
+                    .exhausted
+            );
+            assert!(state
+                .get_object_from_zone(ZoneId::Discard(defending), blocker)
+                .is_some());
+
+            {
+                let mut answers = harness.answers.lock().await;
+                answers.get_next_player_action_from = Some(Box::new(move |_player, actions| {
+                    let attackers: Vec<_> = actions
+                        .iter()
+                        .filter_map(|action| match action {
+                            PlayerAction::Attack { attacker } => Some(*attacker),
+                            _ => None,
+                        })
+                        .collect();
+                    // Exhausted agents do not attack again
+                    assert_eq!(attackers, vec![brute]);
+                    actions
+                        .iter()
+                        .position(|action| matches!(action, PlayerAction::Attack { .. }))
+                        .unwrap()
+                }));
+                answers.get_multiple_target_choices = None;
+            }
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            // Nothing is left to block with, the defender takes the damage
+            assert_eq!(
+                harness.game_impl.latest_gamestate().life_of(defending),
+                STARTING_LIFE - 2
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_refused_answers_are_reported() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            let reported = Arc::new(std::sync::Mutex::new(vec![]));
+            let errors = reported.clone();
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @set {
+                        get_next_player_action_from = |_player, player_actions| {
+                            player_actions.len()
+                        }
+                    };
+                    @set {
+                        notify_error = move |player, error| {
+                            errors.lock().unwrap().push((player, error));
+                        }
+                    };
+                ]
+            );
+
+            let states = harness.game_impl.game.game_states.len();
+            let error = harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap_err();
+            assert!(error.is_recoverable());
+            assert_eq!(harness.game_impl.game.game_states.len(), states);
+            assert_eq!(*reported.lock().unwrap(), vec![(player, error.to_string())]);
+        }
+    );
+
+    async_test!(
+        async fn check_prompts_are_numbered() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            assert_eq!(harness.game_impl.prompt_context().sequence, 0);
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                ]
+            );
+            // Every player was asked whether to keep their hand in a single prompt
+            let asked = harness.game_impl.game.prompts;
+            assert_eq!(asked, 1);
+            assert_eq!(harness.game_impl.prompt_context().sequence, asked);
+        }
+    );
+
+    async_test!(
+        async fn check_bots_keep_games_going() {
+            let (_, mut game_impl, _, _) = init_harness(None);
+
+            for _ in 0..100 {
+                game_impl.run(&BotOutside).await.unwrap();
+            }
+
+            assert!(game_impl.latest_gamestate().turn > 2);
+        }
+    );
+
+    async_test!(
+        async fn check_hot_seat_switches_between_players() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let outside = HotSeatOutside::new(
+                OutsideGameClient::new(
+                    harness.game_impl.id(),
+                    harness.outside_client.client.clone(),
+                ),
+                harness.player_order.clone(),
+            );
+            let switches = Arc::new(std::sync::Mutex::new(vec![]));
+            let seen = switches.clone();
+            let asked = Arc::new(std::sync::Mutex::new(vec![]));
+            let keeping = asked.clone();
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        notify_event = move |event| {
+                            if let GameEvent::SeatSwitched { player } = event {
+                                seen.lock().unwrap().push(player);
+                            }
+                        }
+                    };
+                    @set {
+                        get_player_keeping = move |players| {
+                            keeping.lock().unwrap().push(players.clone());
+                            players
+                        }
+                    };
+                ]
+            );
+            harness.game_impl.run(&outside).await.unwrap();
+
+            // Everyone decides on their hand alone, right after taking the seat
+            let switches = switches.lock().unwrap().clone();
+            let asked = asked.lock().unwrap().clone();
+            assert_eq!(switches.len(), harness.player_order.len());
+            assert_eq!(asked, switches.iter().map(|p| vec![*p]).collect::<Vec<_>>());
+            assert_eq!(outside.seat(), switches.last().copied());
+            assert_eq!(
+                harness.game_impl.latest_gamestate().game_stage,
+                crate::GameStage::GameRunning
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_damage_shields_absorb_damage_this_turn() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let player = player_order[0];
+            let target = TargetId::Player(player);
+            let source = ObjectId::new(&mut game_impl.game.rand);
+            let state = game_impl.game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state.phase = crate::Phase::Cleanup;
+
+            game_impl
+                .apply_atoms(vec![
+                    GameAtom::AddDamageShield { target, amount: 2 },
+                    GameAtom::AddDamageShield { target, amount: 3 },
+                    GameAtom::DealDamage {
+                        amount: 4,
+                        source,
+                        target,
+                    },
+                ])
+                .unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.life_of(player), STARTING_LIFE);
+            assert_eq!(state.shields, vec![DamageShield { target, amount: 1 }]);
+
+            game_impl
+                .apply_atoms(vec![GameAtom::DealDamage {
+                    amount: 3,
+                    source,
+                    target,
+                }])
+                .unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.life_of(player), STARTING_LIFE - 2);
+            assert!(state.shields.is_empty());
+
+            // Shields do not outlast the turn
+            game_impl
+                .apply_atoms(vec![
+                    GameAtom::AddDamageShield { target, amount: 5 },
+                    GameAtom::AdvancePhase,
+                    GameAtom::DealDamage {
+                        amount: 1,
+                        source,
+                        target,
+                    },
+                ])
+                .unwrap();
+            assert_eq!(
+                game_impl.latest_gamestate().life_of(player),
+                STARTING_LIFE - 3
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_effects_are_held_to_their_capabilities() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let card = CardId::with(BLAST_CARD);
+            // Earlier game states are not redacted, they must not be handed out
+            game_impl
+                .apply_atoms(vec![GameAtom::ShuffleLibrary {
+                    player: player_order[0],
+                }])
+                .unwrap();
+            let snoop = |capabilities: Vec<Capability>| {
+                let game_impl = &game_impl;
+                async move {
+                    game_impl
+                        .execute_effect(
+                            &Snoop(capabilities),
+                            card,
+                            HashMap::new(),
+                            ObjectId(Uuid::nil()),
+                        )
+                        .await
+                }
+            };
+
+            assert!(matches!(
+                snoop(vec![]).await.unwrap_err(),
+                GameError::EffectExecuteFailure {
+                    failure: ExecuteFailure::UndeclaredCapability {
+                        capability: Capability::Randomness,
+                        ..
+                    }
+                }
+            ));
+            assert_eq!(snoop(vec![Capability::Randomness]).await.unwrap().len(), 1);
+            assert!(matches!(
+                snoop(Capability::ALL.to_vec()).await.unwrap_err(),
+                GameError::EffectExecuteFailure {
+                    failure: ExecuteFailure::InvalidEffectInfo { .. }
+                }
+            ));
+
+            let game_impl = game_impl.with_format(FormatRules {
+                allowed_capabilities: Some(vec![]),
+                ..Default::default()
+            });
+            let errors = game_impl.verify().err().unwrap();
+            assert!(errors.iter().any(|e| matches!(
+                e,
+                VerificationError::ForbiddenCapability { card: forbidden, .. }
+                    if *forbidden == card
+            )));
+        }
+    );
+
+    async_test!(
+        async fn check_cost_modifiers_change_what_cards_cost() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let (you, opponent) = (player_order[0], player_order[1]);
+            let card = |n, kind, cost, effects| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost,
+                    kind: vec![CardKind { kind }],
+                    effects,
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+            let modifier = |kind, player, any_scrip| {
+                vec![CardEffect::Static(StaticCardEffect {
+                    effect: Effect::Continuous(ContinuousEffect::ModifyCost(CostModifier {
+                        kind: Some(kind),
+                        player,
+                        any_scrip,
+                    })),
+                })]
+            };
+            let building = || BaseCardKind::Building {
+                subkind: SubKind::new("Mercenary"),
+            };
+            // "Your quickhacks cost 1 less", "Quickhacks cost 2 more"
+            let sources = [
+                card(
+                    1,
+                    building(),
+                    None,
+                    modifier(BaseCardKindTag::Quickhack, Some(RelativePlayer::You), -1),
+                ),
+                card(
+                    2,
+                    building(),
+                    None,
+                    modifier(BaseCardKindTag::Quickhack, None, 2),
+                ),
+            ];
+            let quickhack = card(
+                3,
+                BaseCardKind::Quickhack,
+                Some(Cost {
+                    corp1_scrip: 1,
+                    ..Default::default()
+                }),
+                vec![],
+            );
+
+            let game = &mut game_impl.game;
+            for (source, controller) in sources.into_iter().zip([you, opponent]) {
+                let mut object = GameObject::from_card(&mut game.rand, source.id);
+                object.controller = Some(controller);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+                Arc::get_mut(&mut game.cards)
+                    .unwrap()
+                    .insert(source.id, source);
+            }
+
+            let cost = quickhack.behaviour.cost.clone().unwrap();
+            let modified = |player| {
+                crate::cost::modified_cost(
+                    &game_impl.game,
+                    player,
+                    &quickhack.behaviour,
+                    cost.clone(),
+                )
+            };
+            // The increase is applied first, so the reduction is not lost
+            assert_eq!(modified(you).any_scrip, 1);
+            assert_eq!(modified(opponent).any_scrip, 2);
+            assert_eq!(modified(you).corp1_scrip, 1);
+        }
+    );
+
+    async_test!(
+        async fn check_reduced_costs_are_charged() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let player = harness.player_order[0];
+            let card = |n, kind, cost, effects| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost,
+                    kind: vec![CardKind { kind }],
+                    effects,
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+            // "Your quickhacks cost 1 less"
+            let discount = card(
+                1,
+                BaseCardKind::Building {
+                    subkind: SubKind::new("Mercenary"),
+                },
+                None,
+                vec![CardEffect::Static(StaticCardEffect {
+                    effect: Effect::Continuous(ContinuousEffect::ModifyCost(CostModifier {
+                        kind: Some(BaseCardKindTag::Quickhack),
+                        player: Some(RelativePlayer::You),
+                        any_scrip: -1,
+                    })),
+                })],
+            );
+            let quickhack = card(
+                2,
+                BaseCardKind::Quickhack,
+                Some(Cost {
+                    corp1_scrip: 1,
+                    any_scrip: 2,
+                    ..Default::default()
+                }),
+                vec![],
+            );
+
+            let game = &mut harness.game_impl.game;
+            let mut source = GameObject::from_card(&mut game.rand, discount.id);
+            source.controller = Some(player);
+            let object = GameObject::from_card(&mut game.rand, quickhack.id);
+            let object_id = object.id;
+            let cards = Arc::get_mut(&mut game.cards).unwrap();
+            cards.insert(discount.id, discount);
+            cards.insert(quickhack.id, quickhack);
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            let state = harness.game_impl.game.game_states.last_mut().unwrap();
+            state
+                .zones
+                .get_mut(&ZoneId::Hand(player))
+                .unwrap()
+                .objects
+                .push(object);
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(source);
+            // Enough for the reduced cost of 2, but not for the printed one of 3
+            harness
+                .game_impl
+                .apply_atoms(vec![
+                    GameAtom::AddScrip {
+                        player,
+                        kind: ScripKind::Corp1,
+                        amount: 1,
+                    },
+                    GameAtom::AddFloatingScrip {
+                        player,
+                        kind: ScripKind::Corp2,
+                        amount: 1,
+                    },
+                ])
+                .unwrap();
+
+            {
+                let mut answers = harness.answers.lock().await;
+                answers.get_next_player_action_from = Some(Box::new(move |_player, actions| {
+                    actions
+                        .iter()
+                        .position(|action| {
+                            matches!(action, PlayerAction::PlayCard { object, alternative: None, .. } if *object == object_id)
+                        })
+                        .unwrap()
+                }));
+                answers.get_player_passing = Some(Box::new(|_player| false));
+            }
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.get_stack().objects[0].id, object_id);
+            for kind in ScripKind::ALL {
+                assert_eq!(state.available_scrip_of(player, kind), 0);
+            }
+            assert!(matches!(
+                harness.game_impl.apply_atoms(vec![GameAtom::SpendScrip {
+                    player,
+                    kind: ScripKind::Corp1,
+                    amount: 1,
+                }]),
+                Err(GameError::CannotAfford {
+                    needed: 1,
+                    available: 0,
+                    ..
+                })
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_alternative_costs_are_offered_and_paid() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let player = harness.player_order[0];
+            let overload = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: Some(Cost {
+                        corp1_scrip: 5,
+                        ..Default::default()
+                    }),
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Quickhack,
+                    }],
+                    effects: vec![],
+                    keywords: vec![],
+                    alternative_costs: vec![
+                        AlternativeCost::PayLife { amount: 3 },
+                        AlternativeCost::Discard { count: 1 },
+                        AlternativeCost::Free {
+                            condition: PlayCondition::LifeAtMost(5),
+                        },
+                    ],
+                },
+                layout: CardLayout::Single,
+            };
+            let game = &mut harness.game_impl.game;
+            let object = GameObject::from_card(&mut game.rand, overload.id);
+            let object_id = object.id;
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(overload.id, overload);
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            harness
+                .game_impl
+                .game
+                .game_states
+                .last_mut()
+                .unwrap()
+                .zones
+                .get_mut(&ZoneId::Hand(player))
+                .unwrap()
+                .objects
+                .push(object);
+            let hand = harness
+                .game_impl
+                .latest_gamestate()
+                .get_hand(player)
+                .objects
+                .len();
+
+            {
+                let mut answers = harness.answers.lock().await;
+                answers.get_next_player_action_from = Some(Box::new(move |_player, actions| {
+                    let offered: Vec<_> = actions
+                        .iter()
+                        .filter_map(|action| match action {
+                            PlayerAction::PlayCard {
+                                object,
+                                alternative,
+                                ..
+                            } if *object == object_id => Some(*alternative),
+                            _ => None,
+                        })
+                        .collect();
+                    // The player has no scrip to pay for it and too much life to play it for free
+                    assert_eq!(offered, vec![Some(0), Some(1)]);
+                    actions
+                        .iter()
+                        .position(|action| {
+                            matches!(action, PlayerAction::PlayCard { object, alternative: Some(1), .. } if *object == object_id)
+                        })
+                        .unwrap()
+                }));
+                answers.get_discard_choice =
+                    Some(Box::new(|_player, _cards, count| (0..count).collect()));
+                answers.get_player_passing = Some(Box::new(|_player| false));
+            }
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.get_stack().objects[0].id, object_id);
+            assert_eq!(state.get_hand(player).objects.len(), hand - 2);
+            assert_eq!(state.zones[&ZoneId::Discard(player)].objects.len(), 1);
+            assert_eq!(state.life_of(player), STARTING_LIFE);
+        }
+    );
+
+    async_test!(
+        async fn check_draws_and_zone_counts_trigger_effects() {
+            let SimpleTestHarness {
+                player_order,
+                mut game_impl,
+                outside_client,
+                ..
+            } = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = player_order[0];
+            let triggered = |trigger, amount| {
+                CardEffect::Triggered(TriggeredCardEffect {
+                    trigger,
+                    effects: vec![Effect::Instant(Box::new(GainLife(amount)))],
+                })
+            };
+            let card = |n, kind, effects| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind { kind }],
+                    effects,
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+            // "Whenever a player draws a card, you gain 1 life", "Once your discard holds two
+            // cards, you gain 5 life"
+            let archive = card(
+                1,
+                BaseCardKind::Building {
+                    subkind: SubKind::new("Mercenary"),
+                },
+                vec![
+                    triggered(EffectTrigger::OnDraw, 1),
+                    triggered(
+                        EffectTrigger::OnZoneCount {
+                            zone: ZoneKind::Discard,
+                            count: 2,
+                        },
+                        5,
+                    ),
+                ],
+            );
+            // "When you play this, you gain 3 life"
+            let hack = card(
+                2,
+                BaseCardKind::Quickhack,
+                vec![triggered(EffectTrigger::OnEnterZone(ZoneKind::Stack), 3)],
+            );
+
+            let game = &mut game_impl.game;
+            let mut building = GameObject::from_card(&mut game.rand, archive.id);
+            building.controller = Some(player);
+            let hack_object = GameObject::from_card(&mut game.rand, hack.id);
+            let hack_id = hack_object.id;
+            let cards = Arc::get_mut(&mut game.cards).unwrap();
+            cards.insert(archive.id, archive);
+            cards.insert(hack.id, hack);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(building);
+            state
+                .zones
+                .get_mut(&ZoneId::Hand(player))
+                .unwrap()
+                .objects
+                .push(hack_object);
+
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 2 }])
+                .unwrap();
+            run_triggers(&mut game_impl).await;
+            resolve_stack(&mut game_impl, &outside_client).await;
+            assert_eq!(
+                game_impl.latest_gamestate().life_of(player),
+                STARTING_LIFE + 2
+            );
+
+            let hand: Vec<_> = game_impl
+                .latest_gamestate()
+                .get_hand(player)
+                .objects
+                .iter()
+                .map(|o| o.id)
+                .filter(|o| *o != hack_id)
+                .collect();
+            for discarded in hand {
+                game_impl
+                    .apply_atoms(vec![GameAtom::DiscardCards {
+                        player,
+                        objects: vec![discarded],
+                    }])
+                    .unwrap();
+                run_triggers(&mut game_impl).await;
+            }
+            resolve_stack(&mut game_impl, &outside_client).await;
+            assert_eq!(
+                game_impl.latest_gamestate().life_of(player),
+                STARTING_LIFE + 7
+            );
+
+            game_impl
+                .apply_atoms(vec![GameAtom::PlayerPlayCard {
+                    player,
+                    from: ZoneId::Hand(player),
+                    object: hack_id,
+                    choices: HashMap::new(),
+                    second_half: false,
+                }])
+                .unwrap();
+            run_triggers(&mut game_impl).await;
+            resolve_stack(&mut game_impl, &outside_client).await;
+            assert_eq!(
+                game_impl.latest_gamestate().life_of(player),
+                STARTING_LIFE + 10
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_conceding_ends_the_game() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @set {
+                        get_next_player_action_from = |_player, player_actions| {
+                            player_actions.iter().position(|a| matches!(a, PlayerAction::Concede)).unwrap()
+                        }
+                    };
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert!(state.is_eliminated(player));
+                        assert_eq!(harness.game_impl.outcome(), Some(GameOutcome::Won { winner: opponent }));
+                        assert_eq!(
+                            harness.game_impl.result(),
+                            Some(GameResult::Conceded { winners: vec![opponent], conceded: vec![player] })
+                        );
+                    };
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_running_out_of_time_passes_or_concedes() {
+            let mut passing = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let player = passing.player_order[0];
+
+            game_steps!(
+                passing,
+                [
+                    @step_game {};
+                    @run {
+                        passing.game_impl.game.format.clock = Some(ChessClock {
+                            bank: Duration::ZERO,
+                            on_timeout: TimeoutAction::Pass,
+                        });
+                    };
+                    @set {
+                        get_next_player_action_from = |_player, player_actions| {
+                            player_actions.iter().position(|a| matches!(a, PlayerAction::Concede)).unwrap()
+                        }
+                    };
+                    @step_game {};
+                    @run {
+                        assert!(!passing.game_impl.latest_gamestate().is_eliminated(player));
+                        let (_, atoms) = passing.game_impl.game.history.last().unwrap();
+                        assert!(atoms.iter().any(|a| matches!(a, GameAtom::PassPriority { player: p } if *p == player)));
+                    };
+                ]
+            );
+
+            let mut conceding = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let (player, opponent) = (conceding.player_order[0], conceding.player_order[1]);
+
+            game_steps!(
+                conceding,
+                [
+                    @step_game {};
+                    @run {
+                        conceding.game_impl.game.format.clock = Some(ChessClock {
+                            bank: Duration::ZERO,
+                            on_timeout: TimeoutAction::Concede,
+                        });
+                    };
+                    @step_game {};
+                    @run {
+                        assert!(conceding.game_impl.latest_gamestate().is_eliminated(player));
+                        assert_eq!(conceding.game_impl.outcome(), Some(GameOutcome::Won { winner: opponent }));
+                        assert_eq!(
+                            conceding.game_impl.result(),
+                            Some(GameResult::TimedOut { winners: vec![opponent], timed_out: vec![player] })
+                        );
+                    };
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_stack_entries_hide_face_down_objects() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            let card = CardId::with(uuid::Uuid::from_u128(1));
+
+            let mut spell = GameObject::from_card(&mut harness.game_impl.game.rand, card);
+            spell.controller = Some(player);
+            spell.choices.insert(
+                (0, "target".to_string()),
+                EffectInfo::SingleTarget(TargetId::Player(opponent)),
+            );
+            spell
+                .choices
+                .insert((1, "search".to_string()), EffectInfo::LibraryCard(spell.id));
+            let mut hidden = GameObject::from_card(&mut harness.game_impl.game.rand, card);
+            hidden.controller = Some(opponent);
+            hidden.visibility = ObjectVisibility::FaceDown {
+                known_to: vec![opponent],
+            };
+            hidden.choices = spell.choices.clone();
+            let (spell_id, hidden_id) = (spell.id, hidden.id);
+
+            let stack = harness
+                .game_impl
+                .game
+                .game_states
+                .last_mut()
+                .unwrap()
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap();
+            stack.objects.push(spell);
+            stack.objects.push(hidden);
+
+            let entries = harness.game_impl.latest_gamestate().stack_entries();
+            assert_eq!(
+                entries,
+                vec![
+                    StackEntry {
+                        object: spell_id,
+                        card: Some(card),
+                        controller: Some(player),
+                        targets: vec![(0, "target".to_string(), TargetId::Player(opponent))],
+                        trigger: None,
+                    },
+                    StackEntry {
+                        object: hidden_id,
+                        card: None,
+                        controller: Some(opponent),
+                        targets: vec![],
+                        trigger: None,
+                    },
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_random_choices_are_replayed_the_same() {
+            let mut picked = vec![];
+            for _ in 0..2 {
+                let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+                let player = harness.player_order[0];
+                let life = |harness: &SimpleTestHarness| {
+                    harness
+                        .game_impl
+                        .latest_gamestate()
+                        .life
+                        .get(&player)
+                        .copied()
+                };
+                let before = life(&harness).unwrap_or(0);
+
+                let flip = GameAtom::RandomChoice {
+                    options: vec![
+                        vec![GameAtom::GainLife { player, amount: 1 }],
+                        vec![
+                            GameAtom::GainLife { player, amount: 2 },
+                            GameAtom::GainLife { player, amount: 2 },
+                        ],
+                    ],
+                };
+                harness.game_impl.apply_atoms(vec![flip]).unwrap();
+
+                let gained = life(&harness).unwrap() - before;
+                assert!(gained == 1 || gained == 4);
+                assert!(harness
+                    .game_impl
+                    .events
+                    .iter()
+                    .any(|e| matches!(e, GameEvent::RandomChoiceMade { options: 2, .. })));
+                picked.push(gained);
+
+                assert!(matches!(
+                    harness
+                        .game_impl
+                        .apply_atoms(vec![GameAtom::RandomChoice { options: vec![] }]),
+                    Err(GameError::EmptyRandomChoice)
+                ));
+            }
+            assert_eq!(picked[0], picked[1]);
+        }
+    );
+
+    async_test!(
+        async fn check_target_discards_asks_the_chooser() {
+            let asked = Arc::new(std::sync::Mutex::new(vec![]));
+            let asked_players = asked.clone();
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @set {
+                        get_discard_choice = move |player, _cards, _count| {
+                            asked_players.lock().unwrap().push(player);
+                            vec![0]
+                        }
+                    };
+                ]
+            );
+
+            let hand = hand_cards(harness.game_impl.latest_gamestate(), opponent);
+            for (chooser, expected) in [(player, player), (opponent, opponent)] {
+                let objects = harness
+                    .game_impl
+                    .choose_discards(&harness.outside_client, opponent, chooser, 1)
+                    .await
+                    .unwrap();
+                assert_eq!(objects, vec![hand[0].0]);
+                assert_eq!(asked.lock().unwrap().pop(), Some(expected));
+            }
+
+            let info = HashMap::from([
+                (
+                    String::from("target"),
+                    EffectInfo::SingleTarget(TargetId::Player(opponent)),
+                ),
+                (
+                    String::from("cards"),
+                    EffectInfo::Discarded(vec![hand[0].0]),
+                ),
+            ]);
+            let effect = TargetDiscards {
+                count: 1,
+                chooser: DiscardChooser::Controller,
+            };
+            assert!(effect.get_required_info().values().any(|r| matches!(
+                r,
+                EffectInfoRequest::TargetDiscard {
+                    chooser: DiscardChooser::Controller,
+                    ..
+                }
+            )));
+            let source = ObjectId::new(&mut harness.game_impl.game.rand);
+            let atoms = effect
+                .execute(info, source, &harness.game_impl.game)
+                .await
+                .unwrap();
+            harness.game_impl.apply_atoms(atoms).unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.get_hand(opponent).objects.len(), hand.len() - 1);
+            assert_eq!(
+                state.zones[&ZoneId::Discard(opponent)].objects[0].id,
+                hand[0].0
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_temporary_effects_expire_with_the_turn() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let (owner, thief) = (player_order[0], player_order[1]);
+            let game = &mut game_impl.game;
+            let mut object =
+                GameObject::from_card(&mut game.rand, CardId::with(Uuid::from_u128(1)));
+            object.controller = Some(owner);
+            let object_id = object.id;
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state.phase = crate::Phase::Cleanup;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(object);
+
+            game_impl
+                .apply_atoms(vec![
+                    GameAtom::AddTemporaryEffect {
+                        modifier: TemporaryModifier::GainControl {
+                            object: object_id,
+                            player: thief,
+                        },
+                        duration: EffectDuration::UntilEndOfTurn,
+                    },
+                    GameAtom::AddTemporaryEffect {
+                        modifier: TemporaryModifier::Buff {
+                            object: object_id,
+                            power: 2,
+                            toughness: 1,
+                        },
+                        duration: EffectDuration::UntilEndOfNextTurn,
+                    },
+                ])
+                .unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(game_impl.game.get_controller_of(object_id), Some(thief));
+            assert_eq!(state.buff_of(object_id), (2, 1));
+
+            game_impl.apply_atoms(vec![GameAtom::AdvancePhase]).unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(game_impl.game.get_controller_of(object_id), Some(owner));
+            assert_eq!(state.buff_of(object_id), (2, 1));
+
+            let state = game_impl.game.game_states.last_mut().unwrap();
+            state.phase = crate::Phase::Cleanup;
+            game_impl.apply_atoms(vec![GameAtom::AdvancePhase]).unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.buff_of(object_id), (0, 0));
+            assert!(state.temporary_effects.is_empty());
+        }
+    );
+
+    async_test!(
+        async fn check_extra_unique_copies_are_discarded() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            let unique = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![],
+                    effects: vec![],
+                    keywords: vec![Keyword::Unique],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut ids = vec![];
+            for _ in 0..3 {
+                let mut object = GameObject::from_card(&mut game.rand, unique.id);
+                object.controller = Some(player);
+                ids.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+            }
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(unique.id, unique);
+
+            harness.answers.lock().await.get_discard_choice =
+                Some(Box::new(|_player, _cards, _count| vec![0, 2]));
+            let atoms = harness
+                .game_impl
+                .enforce_uniqueness(&harness.outside_client)
+                .await
+                .unwrap();
+            harness.game_impl.apply_atoms(atoms).unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            let kept: Vec<_> = state.zones[&ZoneId::Battlefield]
+                .objects
+                .iter()
+                .map(|o| o.id)
+                .collect();
+            assert_eq!(kept, vec![ids[1]]);
+            assert_eq!(state.zones[&ZoneId::Discard(player)].objects.len(), 2);
+            assert!(harness
+                .game_impl
+                .enforce_uniqueness(&harness.outside_client)
+                .await
+                .unwrap()
+                .is_empty());
+        }
+    );
+
+    async_test!(
+        async fn check_agent_stats_fold_in_modifiers() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            let agent = |n, stats, effects| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Agent {
+                            subkind: SubKind::new("Mercenary"),
+                            power: AgentPower::Fixed(stats),
+                            toughness: AgentToughness::Fixed(stats),
+                        },
+                    }],
+                    effects,
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+            let captain = agent(
+                1,
+                1,
+                vec![CardEffect::Static(StaticCardEffect {
+                    effect: Effect::Continuous(ContinuousEffect::ModifyStats(StatModifier {
+                        player: Some(RelativePlayer::You),
+                        subkind: Some(SubKind::new("Mercenary")),
+                        affects_source: false,
+                        power: 1,
+                        toughness: 1,
+                    })),
+                })],
+            );
+
+            let game = &mut harness.game_impl.game;
+            let mut ids = vec![];
+            for (card, controller) in [
+                (captain, player),
+                (agent(2, 2, vec![]), player),
+                (agent(3, 2, vec![]), opponent),
+            ] {
+                let mut object = GameObject::from_card(&mut game.rand, card.id);
+                object.controller = Some(controller);
+                ids.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+                Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+            }
+
+            harness
+                .game_impl
+                .apply_atoms(vec![
+                    GameAtom::AddCounters {
+                        object: ids[1],
+                        amount: 1,
+                    },
+                    GameAtom::AddTemporaryEffect {
+                        modifier: TemporaryModifier::Buff {
+                            object: ids[1],
+                            power: 2,
+                            toughness: 0,
+                        },
+                        duration: EffectDuration::UntilEndOfTurn,
+                    },
+                    GameAtom::AddCounters {
+                        object: ids[0],
+                        amount: -3,
+                    },
+                ])
+                .unwrap();
+
+            let game = &harness.game_impl.game;
+            let stats = |object| game.latest_gamestate().agent_stats(game, object);
+            assert_eq!(stats(ids[0]), Some((0, 0)));
+            assert_eq!(stats(ids[1]), Some((6, 4)));
+            assert_eq!(stats(ids[2]), Some((2, 2)));
+            assert_eq!(crate::combatant(game, ids[1]).unwrap().1.power, 6);
+        }
+    );
+
+    async_test!(
+        async fn check_special_stats_are_computed() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            let hoarder = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Agent {
+                            subkind: SubKind::new("Mercenary"),
+                            power: AgentPower::Special(Box::new(CardsInHand)),
+                            toughness: AgentToughness::Fixed(1),
+                        },
+                    }],
+                    effects: vec![],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut object = GameObject::from_card(&mut game.rand, hoarder.id);
+            object.controller = Some(player);
+            let object_id = object.id;
+            let state = game.game_states.last_mut().unwrap();
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(object);
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(hoarder.id, hoarder);
+
+            let game = &harness.game_impl.game;
+            let hand = game.latest_gamestate().get_hand(player).objects.len() as u64;
+            assert_eq!(
+                game.latest_gamestate().agent_stats(game, object_id),
+                Some((hand, 1))
+            );
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 2 }])
+                .unwrap();
+            let game = &harness.game_impl.game;
+            assert_eq!(
+                game.latest_gamestate().agent_stats(game, object_id),
+                Some((hand + 2, 1))
+            );
+        }
+    );
+
+    #[test]
+    fn check_subkind_registry_registers_custom_subkinds() {
+        let mut registry = SubKindRegistry::base();
+        for name in BASE_AGENT_SUBKINDS
+            .into_iter()
+            .chain(BASE_BUILDING_SUBKINDS)
+        {
+            assert!(registry.contains(&SubKind::new(name)));
+        }
+        assert!(registry.get("Ripperdoc").is_none());
+        assert!(!registry.contains(&SubKind::new("Ripperdoc")));
+
+        let ripperdoc = registry.register("Ripperdoc");
+        assert_eq!(ripperdoc.name(), "Ripperdoc");
+        assert_eq!(registry.get("Ripperdoc"), Some(ripperdoc.clone()));
+        assert!(registry.contains(&SubKind::new("Ripperdoc")));
+        // Registering a subkind again hands out the identifier that is already known
+        assert_eq!(registry.register("Ripperdoc"), ripperdoc);
+        // Names are matched exactly
+        assert!(!registry.contains(&SubKind::new("ripperdoc")));
+
+        let json = serde_json::to_string(&registry).unwrap();
+        let loaded: SubKindRegistry = serde_json::from_str(&json).unwrap();
+        assert!(loaded.contains(&ripperdoc));
+        assert!(loaded.contains(&SubKind::new("Mercenary")));
+    }
+
+    async_test!(
+        async fn check_verify_rejects_unknown_subkinds() {
+            let mut cards = existing_cards();
+            let mut ripperdoc =
+                agent_card(uuid::uuid!("3c0f6f0e-58d2-4c1e-9a57-2f8e4c5b7d61"), vec![]);
+            ripperdoc.behaviour.kind = vec![CardKind {
+                kind: BaseCardKind::Agent {
+                    subkind: SubKind::new("Ripperdoc"),
+                    power: AgentPower::Fixed(2),
+                    toughness: AgentToughness::Fixed(2),
+                },
+            }];
+            let ripperdoc_id = ripperdoc.id;
+            cards.insert(ripperdoc_id, ripperdoc);
+            let mut players = playtesters();
+            let player_order: Vec<_> = players.keys().copied().collect();
+            players
+                .get_mut(&player_order[0])
+                .unwrap()
+                .initial_cards
+                .push(ripperdoc_id);
+            let game_impl = GameImplV1::new(
+                GameId::new(),
+                Xoshiro256StarStar::seed_from_u64(1337),
+                Arc::new(cards),
+                players,
+                player_order,
+            );
+
+            let errors = game_impl.verify().err().unwrap();
+            assert!(matches!(
+                errors.as_slice(),
+                [VerificationError::UnknownSubKind { card, subkind }]
+                    if *card == ripperdoc_id && subkind.name() == "Ripperdoc"
+            ));
+
+            let mut subkinds = SubKindRegistry::base();
+            subkinds.register("Ripperdoc");
+            let game_impl = game_impl.with_subkinds(Arc::new(subkinds));
+            assert!(game_impl.verify().is_ok());
+        }
+    );
+
+    #[test]
+    fn check_card_kind_queries() {
+        let registry = SubKindRegistry::base();
+        let netrunner = registry.get("Netrunner").unwrap();
+        let behaviour = CardBehaviour {
+            cost: None,
+            kind: vec![
+                CardKind {
+                    kind: BaseCardKind::Agent {
+                        subkind: netrunner.clone(),
+                        power: AgentPower::Fixed(1),
+                        toughness: AgentToughness::Fixed(1),
+                    },
+                },
+                CardKind {
+                    kind: BaseCardKind::Program,
+                },
+            ],
+            effects: vec![],
+            keywords: vec![],
+            alternative_costs: vec![],
+        };
+
+        assert!(behaviour.is_agent());
+        assert!(!behaviour.is_building());
+        assert!(behaviour.is_kind(BaseCardKindTag::Program));
+        assert!(behaviour.is_permanent());
+        assert!(behaviour.has_subkind(&netrunner));
+        assert!(!behaviour.has_subkind(&registry.get("Factory").unwrap()));
+    }
+
+    async_test!(
+        async fn check_transformed_objects_use_their_back_face() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            let face = |stats, keywords| CardBehaviour {
+                cost: None,
+                kind: vec![CardKind {
+                    kind: BaseCardKind::Agent {
+                        subkind: SubKind::new("Mercenary"),
+                        power: AgentPower::Fixed(stats),
+                        toughness: AgentToughness::Fixed(stats),
+                    },
+                }],
+                effects: vec![],
+                keywords,
+                alternative_costs: vec![],
+            };
+            let werewolf = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: face(1, vec![]),
+                layout: CardLayout::DoubleFaced {
+                    back: face(4, vec![Keyword::Overclocked]),
+                },
+            };
+            let villager = Card {
+                id: CardId::with(Uuid::from_u128(2)),
+                version: 1,
+                behaviour: face(1, vec![]),
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut ids = vec![];
+            for card in [werewolf, villager] {
+                let mut object = GameObject::from_card(&mut game.rand, card.id);
+                object.controller = Some(player);
+                ids.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+                Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+            }
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::TransformObject { object: ids[0] }])
+                .unwrap();
+            let game = &harness.game_impl.game;
+            let (_, combatant) = crate::combatant(game, ids[0]).unwrap();
+            assert_eq!((combatant.power, combatant.toughness), (4, 4));
+            assert!(combatant.overclocked);
+            assert!(harness.game_impl.events.iter().any(|e| matches!(
+                e,
+                GameEvent::ObjectTransformed {
+                    transformed: true,
+                    ..
+                }
+            )));
+
+            assert!(matches!(
+                harness
+                    .game_impl
+                    .apply_atoms(vec![GameAtom::TransformObject { object: ids[1] }]),
+                Err(GameError::SingleFaced { .. })
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_split_card_halves_are_offered_separately() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let player = harness.player_order[0];
+            let half = |any_scrip| CardBehaviour {
+                cost: Some(Cost {
+                    any_scrip,
+                    ..Default::default()
+                }),
+                kind: vec![CardKind {
+                    kind: BaseCardKind::Quickhack,
+                }],
+                effects: vec![],
+                keywords: vec![],
+                alternative_costs: vec![],
+            };
+            let split = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: half(1),
+                layout: CardLayout::Split { second: half(2) },
+            };
+            let game = &mut harness.game_impl.game;
+            let object = GameObject::from_card(&mut game.rand, split.id);
+            let object_id = object.id;
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(split.id, split);
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            harness
+                .game_impl
+                .game
+                .game_states
+                .last_mut()
+                .unwrap()
+                .zones
+                .get_mut(&ZoneId::Hand(player))
+                .unwrap()
+                .objects
+                .push(object);
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::AddScrip {
+                    player,
+                    kind: ScripKind::Corp3,
+                    amount: 2,
+                }])
+                .unwrap();
+
+            {
+                let mut answers = harness.answers.lock().await;
+                answers.get_next_player_action_from = Some(Box::new(move |_player, actions| {
+                    let offered: Vec<_> = actions
+                        .iter()
+                        .filter_map(|action| match action {
+                            PlayerAction::PlayCard {
+                                object,
+                                second_half,
+                                ..
+                            } if *object == object_id => Some(*second_half),
+                            _ => None,
+                        })
+                        .collect();
+                    assert_eq!(offered, vec![false, true]);
+                    actions
+                        .iter()
+                        .position(|action| {
+                            matches!(action, PlayerAction::PlayCard { object, second_half: true, .. } if *object == object_id)
+                        })
+                        .unwrap()
+                }));
+                answers.get_player_passing = Some(Box::new(|_player| false));
+            }
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            let game = &harness.game_impl.game;
+            let played = &game.latest_gamestate().get_stack().objects[0];
+            assert_eq!(played.id, object_id);
+            assert!(played.second_half);
+            let cost = played.behaviour(&game.cards).unwrap().cost.as_ref();
+            assert_eq!(cost.unwrap().any_scrip, 2);
+            assert_eq!(
+                game.latest_gamestate()
+                    .available_scrip_of(player, ScripKind::Corp3),
+                0
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_floating_scrip_drains_at_phase_end() {
+            let (player_order, mut game_impl, _server, _client) = init_harness(None);
+            let player = player_order[0];
+
+            game_impl
+                .apply_atoms(vec![
+                    GameAtom::AddScrip {
+                        player,
+                        kind: ScripKind::Corp1,
+                        amount: 2,
+                    },
+                    GameAtom::AddFloatingScrip {
+                        player,
+                        kind: ScripKind::Corp1,
+                        amount: 3,
+                    },
+                ])
+                .unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.floating_scrip_of(player, ScripKind::Corp1), 3);
+            assert_eq!(state.available_scrip_of(player, ScripKind::Corp1), 5);
+
+            game_impl.apply_atoms(vec![GameAtom::AdvancePhase]).unwrap();
+            let state = game_impl.latest_gamestate();
+            assert_eq!(state.floating_scrip_of(player, ScripKind::Corp1), 0);
+            assert_eq!(state.available_scrip_of(player, ScripKind::Corp1), 2);
+        }
+    );
+
+    async_test!(
+        async fn check_draws_can_be_replaced() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            // "You draw from your discard instead"
+            let archive = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Building {
+                            subkind: SubKind::new("Archive"),
+                        },
+                    }],
+                    effects: vec![CardEffect::Static(StaticCardEffect {
+                        effect: Effect::Continuous(ContinuousEffect::Replace(
+                            Replacement::DrawFrom {
+                                player: RelativePlayer::You,
+                                zone: ZoneKind::Discard,
+                            },
+                        )),
+                    })],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut object = GameObject::from_card(&mut game.rand, archive.id);
+            object.controller = Some(player);
+            let source = object.id;
+            game.game_states
+                .last_mut()
+                .unwrap()
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(object);
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(archive.id, archive);
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::Mill { player, count: 2 }])
+                .unwrap();
+            let state = harness.game_impl.latest_gamestate();
+            let library_size = state.zones[&ZoneId::Library(player)].objects.len();
+            let top_of_discard = state.zones[&ZoneId::Discard(player)].objects[1].id;
+
+            let atoms = DrawCards(1)
+                .execute(HashMap::new(), source, &harness.game_impl.game)
+                .await
+                .unwrap();
+            harness.game_impl.apply_atoms(atoms).unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.get_hand(player).objects[0].id, top_of_discard);
+            assert_eq!(state.zones[&ZoneId::Discard(player)].objects.len(), 1);
+            assert_eq!(
+                state.zones[&ZoneId::Library(player)].objects.len(),
+                library_size
+            );
+
+            // Opponents still draw from their library
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::DrawCards {
+                    player: opponent,
+                    count: 1,
+                }])
+                .unwrap();
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.get_hand(opponent).objects.len(), 1);
+            assert!(state.zones[&ZoneId::Discard(opponent)].objects.is_empty());
+        }
+    );
+
+    async_test!(
+        async fn check_quickhacks_can_be_countered() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            let card = |n, kind| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind { kind }],
+                    effects: vec![],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut ids = vec![];
+            for card in [
+                card(1, BaseCardKind::Quickhack),
+                card(
+                    2,
+                    BaseCardKind::Agent {
+                        subkind: SubKind::new("Mercenary"),
+                        power: AgentPower::Fixed(1),
+                        toughness: AgentToughness::Fixed(1),
+                    },
+                ),
+            ] {
+                let mut object = GameObject::from_card(&mut game.rand, card.id);
+                object.controller = Some(opponent);
+                ids.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Stack)
+                    .unwrap()
+                    .objects
+                    .push(object);
+                Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+            }
+
+            let effect = CounterObject {
+                kind: Some(BaseCardKindTag::Quickhack),
+            };
+            let restriction = match effect.get_required_info().remove("target") {
+                Some(EffectInfoRequest::SingleTarget { restriction, .. }) => restriction,
+                _ => panic!("Counterspells have to target"),
+            };
+            assert_eq!(
+                crate::target::possible_targets(
+                    &harness.game_impl.game,
+                    player,
+                    restriction.as_ref()
+                ),
+                vec![TargetId::Object(ids[0])]
+            );
+
+            let source = ObjectId::new(&mut harness.game_impl.game.rand);
+            let info: HashMap<_, _> = [(
+                String::from("target"),
+                EffectInfo::SingleTarget(TargetId::Object(ids[0])),
+            )]
+            .into();
+            let atoms = effect
+                .execute(info.clone(), source, &harness.game_impl.game)
+                .await
+                .unwrap();
+            harness.game_impl.apply_atoms(atoms).unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            let ids_in = |zone: ZoneId| -> Vec<_> {
+                state.zones[&zone].objects.iter().map(|o| o.id).collect()
+            };
+            assert_eq!(ids_in(ZoneId::Stack), vec![ids[1]]);
+            assert_eq!(ids_in(ZoneId::Discard(opponent)), vec![ids[0]]);
+
+            // Countering something that already left the stack does nothing
+            assert!(effect
+                .execute(info, source, &harness.game_impl.game)
+                .await
+                .unwrap()
+                .is_empty());
+            assert!(matches!(
+                harness
+                    .game_impl
+                    .apply_atoms(vec![GameAtom::CounterObject { object: ids[0] }]),
+                Err(GameError::ObjectNotFoundInZone { .. })
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_objects_without_legal_targets_fizzle() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            let agent = agent_card(Uuid::from_u128(1), vec![]);
+            // "Target agent gets +1/+1 until end of turn"
+            let pump = Card {
+                id: CardId::with(Uuid::from_u128(2)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Quickhack,
+                    }],
+                    effects: vec![CardEffect::Triggered(TriggeredCardEffect {
+                        trigger: EffectTrigger::OnResolve,
+                        effects: vec![Effect::Instant(Box::new(Buff {
+                            power: 1,
+                            toughness: 1,
+                            duration: EffectDuration::UntilEndOfTurn,
+                        }))],
+                    })],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut target = GameObject::from_card(&mut game.rand, agent.id);
+            target.controller = Some(player);
+            let target_id = target.id;
+            let mut stack = vec![];
+            for _ in 0..2 {
+                let mut object = GameObject::from_card(&mut game.rand, pump.id);
+                object.controller = Some(player);
+                object.choices.insert(
+                    (0, String::from("target")),
+                    EffectInfo::SingleTarget(TargetId::Object(target_id)),
+                );
+                stack.push(object);
+            }
+            let (fizzling, resolving) = (stack[0].id, stack[1].id);
+            let cards = Arc::get_mut(&mut game.cards).unwrap();
+            cards.insert(agent.id, agent);
+            cards.insert(pump.id, pump);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(target);
+            state
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap()
+                .objects
+                .extend(stack);
+
+            let state = harness.game_impl.game.game_states.last_mut().unwrap();
+            state.unpassed_players.clear();
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.buff_of(target_id), (1, 1));
+            assert!(state
+                .get_object_from_zone(ZoneId::Discard(player), resolving)
+                .is_some());
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::MoveObject {
+                    object: target_id,
+                    from: ZoneId::Battlefield,
+                    to: ZoneId::Discard(player),
+                }])
+                .unwrap();
+            let effects = harness.game_impl.latest_gamestate().temporary_effects.len();
+            let state = harness.game_impl.game.game_states.last_mut().unwrap();
+            state.unpassed_players.clear();
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            let state = harness.game_impl.latest_gamestate();
+            assert!(state.get_battlefield().objects.is_empty());
+            assert_eq!(state.temporary_effects.len(), effects);
+            assert!(state
+                .get_object_from_zone(ZoneId::Discard(player), fizzling)
+                .is_some());
+        }
+    );
+
+    async_test!(
+        async fn check_triggers_use_the_stack() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            // "When this enters the battlefield, it deals 2 damage to any target"
+            let sentry = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Agent {
+                            subkind: SubKind::new("Mercenary"),
+                            power: AgentPower::Fixed(1),
+                            toughness: AgentToughness::Fixed(1),
+                        },
+                    }],
+                    effects: vec![CardEffect::Triggered(TriggeredCardEffect {
+                        trigger: EffectTrigger::OnEnterBattlefield,
+                        effects: vec![Effect::Instant(Box::new(DealDamage(2)))],
+                    })],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+
+            let game = &mut harness.game_impl.game;
+            let mut object = GameObject::from_card(&mut game.rand, sentry.id);
+            object.controller = Some(player);
+            let source = object.id;
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(sentry.id, sentry);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap()
+                .objects
+                .push(object);
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::MoveObject {
+                    object: source,
+                    from: ZoneId::Stack,
+                    to: ZoneId::Battlefield,
+                }])
+                .unwrap();
+            run_triggers(&mut harness.game_impl).await;
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.life_of(opponent), STARTING_LIFE);
+            let [trigger] = state.get_stack().objects.as_slice() else {
+                panic!("Expected a single trigger on the stack");
+            };
+            assert_eq!(trigger.underlying_card, None);
+            assert_eq!(trigger.controller, Some(player));
+            assert!(trigger
+                .trigger
+                .as_ref()
+                .is_some_and(|t| t.source == source && !t.choices_made));
+            let trigger = trigger.id;
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_target_choices_from_given = move |_player: PlayerId, _source: ObjectId, _name: String, choices: Vec<TargetId>, _count: usize, _optional: bool| {
+                            vec![choices.iter().position(|c| *c == TargetId::Player(opponent)).unwrap()]
+                        }
+                    };
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        let trigger = state.get_object_from_zone(ZoneId::Stack, trigger).unwrap();
+                        assert_eq!(
+                            trigger.choices.get(&(0, String::from("target"))),
+                            Some(&EffectInfo::SingleTarget(TargetId::Player(opponent)))
+                        );
+                        harness.game_impl.game.game_states.last_mut().unwrap().unpassed_players.clear();
+                    };
+                    @step_game {};
+                    @run {
+                        let state = harness.game_impl.latest_gamestate();
+                        assert!(state.get_stack().objects.is_empty());
+                        assert_eq!(state.life_of(opponent), STARTING_LIFE - 2);
+                    };
+                ]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_optional_targets_are_never_chosen_automatically() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            harness
+                .game_impl
+                .game
+                .players
+                .get_mut(&player)
+                .unwrap()
+                .preferences
+                .auto_select_single_choice = true;
+            let source = ObjectId::new(&mut harness.game_impl.game.rand);
+
+            // The only target is picked for the player if they have to choose one
+            let mandatory = Effect::Instant(Box::new(crate::effect::tests::DamageOpponent {
+                amount: 2,
+                optional: false,
+            }));
+            game_steps!(harness, [
+                @set {
+                    get_target_choices_from_given = |_player: PlayerId, _source: ObjectId, _name: String, _choices: Vec<TargetId>, _count: usize, _optional: bool| {
+                        panic!("A single mandatory target should be chosen automatically")
+                    }
+                };
+            ]);
+            let chosen = harness
+                .game_impl
+                .gather_choices(&harness.outside_client, player, source, &[(0, &mandatory)])
+                .await
+                .unwrap();
+            assert_eq!(
+                chosen[&(0, String::from("target"))],
+                EffectInfo::SingleTarget(TargetId::Player(opponent))
+            );
+
+            // They may still decline an optional one
+            let optional = Effect::Instant(Box::new(crate::effect::tests::DamageOpponent {
+                amount: 2,
+                optional: true,
+            }));
+            game_steps!(harness, [
+                @set {
+                    get_target_choices_from_given = move |_player: PlayerId, _source: ObjectId, _name: String, choices: Vec<TargetId>, _count: usize, optional: bool| {
+                        assert!(optional);
+                        assert_eq!(choices, vec![TargetId::Player(opponent)]);
+                        vec![]
+                    }
+                };
+            ]);
+            let chosen = harness
+                .game_impl
+                .gather_choices(&harness.outside_client, player, source, &[(0, &optional)])
+                .await
+                .unwrap();
+            assert!(chosen.is_empty());
+
+            game_steps!(harness, [
+                @set {
+                    get_target_choices_from_given = |_player: PlayerId, _source: ObjectId, _name: String, _choices: Vec<TargetId>, _count: usize, _optional: bool| {
+                        vec![0]
+                    }
+                };
+            ]);
+            let chosen = harness
+                .game_impl
+                .gather_choices(&harness.outside_client, player, source, &[(0, &optional)])
+                .await
+                .unwrap();
+            assert_eq!(
+                chosen[&(0, String::from("target"))],
+                EffectInfo::SingleTarget(TargetId::Player(opponent))
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_effects_can_ask_for_several_targets() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            // "Deal 1 damage to each of up to two targets"
+            let effect = Effect::Instant(Box::new(crate::effect::DealDamageToTargets {
+                amount: 1,
+                max: 2,
+            }));
+            let source = ObjectId::new(&mut harness.game_impl.game.rand);
+            let possible_targets =
+                crate::target::possible_targets(&harness.game_impl.game, player, None);
+            assert!(possible_targets.contains(&TargetId::Player(opponent)));
+            assert_eq!(possible_targets.len(), 2);
+
+            game_steps!(harness, [
+                @set {
+                    get_multiple_target_choices = |_player: PlayerId, _source: ObjectId, _name: String, choices: Vec<TargetId>, min: usize, max: usize| {
+                        assert_eq!((choices.len(), min, max), (2, 1, 2));
+                        vec![1, 1]
+                    }
+                };
+            ]);
+            let chosen = harness
+                .game_impl
+                .gather_choices(&harness.outside_client, player, source, &[(0, &effect)])
+                .await;
+            assert!(matches!(chosen, Err(GameError::InvalidChoice { .. })));
+
+            game_steps!(harness, [
+                @set {
+                    get_multiple_target_choices = |_player: PlayerId, _source: ObjectId, _name: String, _choices: Vec<TargetId>, _min: usize, _max: usize| {
+                        vec![1, 0]
+                    }
+                };
+            ]);
+            let mut chosen = harness
+                .game_impl
+                .gather_choices(&harness.outside_client, player, source, &[(0, &effect)])
+                .await
+                .unwrap();
+            let info = chosen.remove(&(0, String::from("targets"))).unwrap();
+            assert_eq!(
+                info,
+                EffectInfo::MultipleTargets(vec![possible_targets[1], possible_targets[0]])
+            );
+
+            let Effect::Instant(instant) = &effect else {
+                unreachable!()
+            };
+            let atoms = instant
+                .execute(
+                    [(String::from("targets"), info)].into(),
+                    source,
+                    &harness.game_impl.game,
+                )
+                .await
+                .unwrap();
+            assert_eq!(atoms.len(), 2);
+        }
+    );
+
+    async_test!(
+        async fn check_effects_can_ask_for_numbers() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            // "Deal 4 damage divided as you choose between two targets"
+            let effect = Effect::Instant(Box::new(crate::effect::DivideDamage(4)));
+            let source = ObjectId::new(&mut harness.game_impl.game.rand);
+            let possible_targets =
+                crate::target::possible_targets(&harness.game_impl.game, player, None);
+
+            game_steps!(harness, [
+                @set {
+                    get_target_choices_from_given = |_player: PlayerId, _source: ObjectId, name: String, _choices: Vec<TargetId>, _count: usize, _optional: bool| {
+                        if name == "first" { vec![0] } else { vec![1] }
+                    }
+                };
+                @set {
+                    get_number_choice = |_player: PlayerId, _source: ObjectId, _name: String, min: u64, max: u64| {
+                        assert_eq!((min, max), (0, 4));
+                        5
+                    }
+                };
+            ]);
+            let chosen = harness
+                .game_impl
+                .gather_choices(&harness.outside_client, player, source, &[(0, &effect)])
+                .await;
+            assert!(matches!(
+                chosen,
+                Err(GameError::InvalidNumberChoice { received: 5, .. })
+            ));
+
+            game_steps!(harness, [
+                @set {
+                    get_number_choice = |_player: PlayerId, _source: ObjectId, _name: String, _min: u64, _max: u64| {
+                        3
+                    }
+                };
+            ]);
+            let chosen = harness
+                .game_impl
+                .gather_choices(&harness.outside_client, player, source, &[(0, &effect)])
+                .await
+                .unwrap();
+            assert_eq!(
+                chosen[&(0, String::from("first_share"))],
+                EffectInfo::Number(3)
+            );
+
+            let Effect::Instant(instant) = &effect else {
+                unreachable!()
+            };
+            let info = chosen
+                .into_iter()
+                .map(|((_, name), info)| (name, info))
+                .collect();
+            let atoms = instant
+                .execute(info, source, &harness.game_impl.game)
+                .await
+                .unwrap();
+            assert_eq!(
+                atoms,
+                vec![
+                    GameAtom::DealDamage {
+                        amount: 3,
+                        source,
+                        target: possible_targets[0],
+                    },
+                    GameAtom::DealDamage {
+                        amount: 1,
+                        source,
+                        target: possible_targets[1],
+                    },
+                ]
+            );
+        }
+    );
+
+    #[test]
+    fn check_variable_costs_are_fixed_by_x() {
+        let cost = Cost {
+            corp1_scrip: 1,
+            any_scrip: 1,
+            variable_scrip: 2,
+            ..Default::default()
+        };
+        assert_eq!(cost.total(), 2);
+
+        let fixed = |any_scrip| Cost {
+            corp1_scrip: 1,
+            any_scrip,
+            ..Default::default()
+        };
+        assert_eq!(cost.with_variable(0), fixed(1));
+        assert_eq!(cost.with_variable(3), fixed(7));
+        assert_eq!(cost.with_variable(u64::MAX), fixed(u64::MAX));
+        assert_eq!(fixed(1).with_variable(3), fixed(1));
+    }
+
+    async_test!(
+        async fn check_x_is_chosen_within_its_bounds() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+            let effect = Effect::Instant(Box::new(GainX { max: 5 }));
+            let source = ObjectId::new(&mut harness.game_impl.game.rand);
+            let x = (0, String::from(VARIABLE_COST_INFO));
+
+            for (answer, expected) in [(6, None), (5, Some(5)), (0, Some(0))] {
+                game_steps!(harness, [
+                    @set {
+                        get_number_choice = move |_player: PlayerId, _source: ObjectId, name: String, min: u64, max: u64| {
+                            assert_eq!((name.as_str(), min, max), (VARIABLE_COST_INFO, 0, 5));
+                            answer
+                        }
+                    };
+                ]);
+                let chosen = harness
+                    .game_impl
+                    .gather_choices(&harness.outside_client, player, source, &[(0, &effect)])
+                    .await;
+                match expected {
+                    Some(expected) => {
+                        assert_eq!(chosen.unwrap()[&x], EffectInfo::Number(expected))
+                    }
+                    None => assert!(matches!(
+                        chosen,
+                        Err(GameError::InvalidNumberChoice {
+                            min: 0,
+                            max: 5,
+                            received: 6
+                        })
+                    )),
+                }
+            }
+        }
+    );
+
+    async_test!(
+        async fn check_x_is_shared_by_the_effects_of_a_played_card() {
+            let mut harness = SimpleTestHarness::new(Some(1234), ServerAnswers::default());
+            let player = harness.player_order[0];
+            // "X: You gain X life, then you gain X life again"
+            let card = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: Some(Cost {
+                        variable_scrip: 1,
+                        ..Default::default()
+                    }),
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Quickhack,
+                    }],
+                    effects: vec![CardEffect::Triggered(TriggeredCardEffect {
+                        trigger: EffectTrigger::OnResolve,
+                        effects: vec![
+                            Effect::Instant(Box::new(GainX { max: 5 })),
+                            Effect::Instant(Box::new(GainX { max: 5 })),
+                        ],
+                    })],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+            let game = &mut harness.game_impl.game;
+            let object = GameObject::from_card(&mut game.rand, card.id);
+            let object_id = object.id;
+            Arc::get_mut(&mut game.cards).unwrap().insert(card.id, card);
+
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+            harness
+                .game_impl
+                .game
+                .game_states
+                .last_mut()
+                .unwrap()
+                .zones
+                .get_mut(&ZoneId::Hand(player))
+                .unwrap()
+                .objects
+                .push(object);
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::AddScrip {
+                    player,
+                    kind: ScripKind::Corp2,
+                    amount: 3,
+                }])
+                .unwrap();
+
+            let asked = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            {
+                let asked = asked.clone();
+                let mut answers = harness.answers.lock().await;
+                answers.get_next_player_action_from = Some(Box::new(move |_player, actions| {
+                    actions
+                        .iter()
+                        .position(|action| {
+                            matches!(action, PlayerAction::PlayCard { object, .. } if *object == object_id)
+                        })
+                        .unwrap()
+                }));
+                answers.get_number_choice =
+                    Some(Box::new(move |_player, _source, _name, _min, _max| {
+                        asked.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        2
+                    }));
+                answers.get_player_passing = Some(Box::new(|_player| false));
+            }
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            assert_eq!(asked.load(std::sync::atomic::Ordering::SeqCst), 1);
+            let played = &harness.game_impl.latest_gamestate().get_stack().objects[0];
+            assert_eq!(played.id, object_id);
+            assert_eq!(
+                played.choices,
+                [
+                    ((0, String::from(VARIABLE_COST_INFO)), EffectInfo::Number(2)),
+                    ((1, String::from(VARIABLE_COST_INFO)), EffectInfo::Number(2)),
+                ]
+                .into()
+            );
+            // X is paid for once
+            assert_eq!(
+                harness
+                    .game_impl
+                    .latest_gamestate()
+                    .scrip_of(player, ScripKind::Corp2),
+                1
+            );
+
+            resolve_stack(&mut harness.game_impl, &harness.outside_client).await;
+            assert_eq!(
+                harness.game_impl.latest_gamestate().life_of(player),
+                STARTING_LIFE + 4
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_each_target_restriction() {
+            let (player_order, game_impl, _server, _client) = init_harness(None);
+            let (player, opponent) = (player_order[0], player_order[1]);
+            let mercenary = agent_card(Uuid::from_u128(1), vec![]);
+            let mut netrunner = agent_card(Uuid::from_u128(2), vec![]);
+            netrunner.behaviour.kind = vec![CardKind {
+                kind: BaseCardKind::Agent {
+                    subkind: SubKind::new("Netrunner"),
+                    power: AgentPower::Fixed(1),
+                    toughness: AgentToughness::Fixed(1),
+                },
+            }];
+
+            // Our mercenary with 3 power, their netrunner with 1 power and our quickhack on the
+            // stack
+            let mut game_impl = game_impl.with_teams(vec![vec![player], vec![opponent]]);
+            let game = &mut game_impl.game;
+            let mut objects = vec![];
+            for (card, zone, controller) in [
+                (mercenary.id, ZoneId::Battlefield, player),
+                (netrunner.id, ZoneId::Battlefield, opponent),
+                (CardId::with(BLAST_CARD), ZoneId::Stack, player),
+            ] {
+                let mut object = GameObject::from_card(&mut game.rand, card);
+                object.controller = Some(controller);
+                objects.push(TargetId::Object(object.id));
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&zone)
+                    .unwrap()
+                    .objects
+                    .push(object);
+            }
+            let cards = Arc::get_mut(&mut game.cards).unwrap();
+            cards.insert(mercenary.id, mercenary);
+            cards.insert(netrunner.id, netrunner);
+
+            let (you, them) = (TargetId::Player(player), TargetId::Player(opponent));
+            let [ours, theirs, hack] = objects[..] else {
+                unreachable!()
+            };
+            let legal = |game: &Game, restriction: TargetRestriction| -> Vec<TargetId> {
+                [you, them, ours, theirs, hack]
+                    .into_iter()
+                    .filter(|t| crate::target::is_legal_target(game, player, &restriction, *t))
+                    .collect()
+            };
+            let game = &game_impl.game;
+
+            assert_eq!(legal(game, TargetRestriction::Player), vec![you, them]);
+
+            assert_eq!(
+                legal(game, TargetRestriction::Zone(TargetZone::Battlefield)),
+                vec![ours, theirs]
+            );
+            assert_eq!(
+                legal(game, TargetRestriction::Zone(TargetZone::Stack)),
+                vec![hack]
+            );
+
+            assert_eq!(
+                legal(game, TargetRestriction::CardKind(BaseCardKindTag::Agent)),
+                vec![ours, theirs]
+            );
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::CardKind(BaseCardKindTag::Quickhack)
+                ),
+                vec![hack]
+            );
+
+            assert_eq!(
+                legal(game, TargetRestriction::SubKind(SubKind::new("Netrunner"))),
+                vec![theirs]
+            );
+            assert_eq!(
+                legal(game, TargetRestriction::SubKind(SubKind::new("Fixer"))),
+                vec![]
+            );
+
+            assert_eq!(
+                legal(game, TargetRestriction::Controller(RelativePlayer::You)),
+                vec![you, ours, hack]
+            );
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::Controller(RelativePlayer::Opponent)
+                ),
+                vec![them, theirs]
+            );
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::Controller(RelativePlayer::Teammate)
+                ),
+                vec![]
+            );
+
+            let power = |min, max| TargetRestriction::Power { min, max };
+            assert_eq!(legal(game, power(Some(2), None)), vec![ours]);
+            assert_eq!(legal(game, power(None, Some(2))), vec![theirs]);
+            assert_eq!(legal(game, power(Some(1), Some(3))), vec![ours, theirs]);
+            assert_eq!(legal(game, power(Some(4), None)), vec![]);
+
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::All(vec![
+                        TargetRestriction::CardKind(BaseCardKindTag::Agent),
+                        TargetRestriction::Controller(RelativePlayer::You),
+                    ])
+                ),
+                vec![ours]
+            );
+            assert_eq!(
+                legal(game, TargetRestriction::All(vec![])),
+                vec![you, them, ours, theirs, hack]
+            );
+
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::Any(vec![
+                        TargetRestriction::Player,
+                        TargetRestriction::Zone(TargetZone::Stack),
+                    ])
+                ),
+                vec![you, them, hack]
+            );
+            assert_eq!(legal(game, TargetRestriction::Any(vec![])), vec![]);
+
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::Not(Box::new(TargetRestriction::Player))
+                ),
+                vec![ours, theirs, hack]
+            );
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::Not(Box::new(TargetRestriction::Controller(
+                        RelativePlayer::Opponent
+                    )))
+                ),
+                vec![you, ours, hack]
+            );
+
+            // Once both players are on the same team, the opponent becomes a teammate
+            let game_impl = game_impl.with_teams(vec![vec![player, opponent]]);
+            let game = &game_impl.game;
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::Controller(RelativePlayer::Teammate)
+                ),
+                vec![them, theirs]
+            );
+            assert_eq!(
+                legal(
+                    game,
+                    TargetRestriction::Controller(RelativePlayer::Opponent)
+                ),
+                vec![]
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_prompts_hide_face_down_objects() {
+            let shown = Arc::new(std::sync::Mutex::new(vec![]));
+            let shown_cards = shown.clone();
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let player = harness.player_order[0];
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @set {
+                        get_zone_choice = move |_player, _source, _name, _zone, choices: ObjectList, _count| {
+                            *shown_cards.lock().unwrap() = choices.cards;
+                            vec![0]
+                        }
+                    };
+                ]
+            );
+
+            let game = &mut harness.game_impl.game;
+            let mut source =
+                GameObject::from_card(&mut game.rand, CardId::with(Uuid::from_u128(1)));
+            source.controller = Some(player);
+            let source_id = source.id;
+            let state = game.game_states.last_mut().unwrap();
+            state
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap()
+                .objects
+                .push(source);
+            // Even its owner does not know what the first card of their hand is
+            let hand = &mut state.zones.get_mut(&ZoneId::Hand(player)).unwrap().objects;
+            hand[0].visibility = ObjectVisibility::FaceDown { known_to: vec![] };
+            let hidden = hand[0].id;
+            let hand_size = hand.len();
+
+            harness
+                .game_impl
+                .choose_from_zone(
+                    &harness.outside_client,
+                    source_id,
+                    String::from("cards"),
+                    ZoneId::Hand(player),
+                    1,
+                )
+                .await
+                .unwrap();
+            let shown = shown.lock().unwrap().clone();
+            assert_eq!(shown.len(), hand_size);
+            assert_eq!(shown[0], None);
+            assert!(shown[1..].iter().all(Option::is_some));
+
+            // Nor can its card be fetched while the prompt is pending
+            let objects = harness.game_impl.prompt_objects();
+            harness.game_impl.show_to(
+                player,
+                &hand_cards(harness.game_impl.latest_gamestate(), player),
+            );
+            assert_eq!(objects.details(player, &[hidden]), vec![None]);
+        }
+    );
+
+    async_test!(
+        async fn check_zone_choices_hide_hidden_zones() {
+            let shown = Arc::new(std::sync::Mutex::new(vec![]));
+            let shown_cards = shown.clone();
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @set {
+                        get_zone_choice = move |_player, _source, _name, zone, choices: ObjectList, _count| {
+                            shown_cards.lock().unwrap().push((zone, choices.cards.len()));
+                            vec![1]
+                        }
+                    };
+                ]
+            );
+
+            let game = &mut harness.game_impl.game;
+            let mut source =
+                GameObject::from_card(&mut game.rand, CardId::with(Uuid::from_u128(1)));
+            source.controller = Some(player);
+            let source_id = source.id;
+            game.game_states
+                .last_mut()
+                .unwrap()
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap()
+                .objects
+                .push(source);
+
+            let hand = hand_cards(harness.game_impl.latest_gamestate(), opponent);
+            let chosen = harness
+                .game_impl
+                .choose_from_zone(
+                    &harness.outside_client,
+                    source_id,
+                    String::from("cards"),
+                    ZoneId::Hand(opponent),
+                    1,
+                )
+                .await
+                .unwrap();
+            assert_eq!(chosen, vec![hand[1].0]);
+            harness
+                .game_impl
+                .choose_from_zone(
+                    &harness.outside_client,
+                    source_id,
+                    String::from("cards"),
+                    ZoneId::Hand(player),
+                    1,
+                )
+                .await
+                .unwrap();
+            let shown = shown.lock().unwrap().clone();
+            assert_eq!(shown[0], (ZoneId::Hand(opponent), 0));
+            assert_eq!(shown[1].0, ZoneId::Hand(player));
+            assert!(shown[1].1 > 0);
+
+            let effect = crate::effect::ExileFromZone {
+                zone: ZoneKind::Hand,
+                count: 1,
+            };
+            let info = HashMap::from([
+                (
+                    String::from("target"),
+                    EffectInfo::SingleTarget(TargetId::Player(opponent)),
+                ),
+                (String::from("cards"), EffectInfo::ZoneObjects(chosen)),
+            ]);
+            let atoms = effect
+                .execute(info, source_id, &harness.game_impl.game)
+                .await
+                .unwrap();
+            harness.game_impl.apply_atoms(atoms).unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.get_hand(opponent).objects.len(), hand.len() - 1);
+            assert!(state
+                .get_object_from_zone(ZoneId::Exile, hand[1].0)
+                .is_some());
+        }
+    );
+
+    async_test!(
+        async fn check_objects_can_be_ordered() {
+            let shown = Arc::new(std::sync::Mutex::new(vec![]));
+            let shown_cards = shown.clone();
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+
+            game_steps!(
+                harness,
+                [
+                    @step_game {};
+                    @set {
+                        get_ordering = move |_player, _source, _name, items: ObjectList| {
+                            shown_cards.lock().unwrap().push(items.cards.len());
+                            vec![2, 0, 1]
+                        }
+                    };
+                ]
+            );
+
+            let game = &mut harness.game_impl.game;
+            let mut source =
+                GameObject::from_card(&mut game.rand, CardId::with(Uuid::from_u128(1)));
+            source.controller = Some(player);
+            let source_id = source.id;
+            game.game_states
+                .last_mut()
+                .unwrap()
+                .zones
+                .get_mut(&ZoneId::Stack)
+                .unwrap()
+                .objects
+                .push(source);
+
+            let state = harness.game_impl.latest_gamestate();
+            let hand: Vec<_> = hand_cards(state, player)
+                .into_iter()
+                .map(|(object, _)| object)
+                .take(3)
+                .collect();
+            let other_hand: Vec<_> = hand_cards(state, opponent)
+                .into_iter()
+                .map(|(object, _)| object)
+                .take(3)
+                .collect();
+
+            let ordered = harness
+                .game_impl
+                .order_objects(
+                    &harness.outside_client,
+                    source_id,
+                    String::from("order"),
+                    hand.clone(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(ordered, vec![hand[2], hand[0], hand[1]]);
+            harness
+                .game_impl
+                .order_objects(
+                    &harness.outside_client,
+                    source_id,
+                    String::from("order"),
+                    other_hand,
+                )
+                .await
+                .unwrap();
+            assert_eq!(*shown.lock().unwrap(), vec![3, 0]);
+
+            game_steps!(harness, [
+                @set {
+                    get_ordering = |_player, _source, _name, _items| vec![0, 0, 1]
+                };
+            ]);
+            assert!(matches!(
+                harness
+                    .game_impl
+                    .order_objects(
+                        &harness.outside_client,
+                        source_id,
+                        String::from("order"),
+                        hand.clone(),
+                    )
+                    .await,
+                Err(GameError::InvalidChoiceAmount { .. })
+            ));
+
+            let effect = crate::effect::PutOnTopOfLibrary {
+                zone: ZoneKind::Hand,
+                count: 3,
+            };
+            let info = HashMap::from([(String::from("order"), EffectInfo::Ordering(ordered))]);
+            let atoms = effect
+                .execute(info, source_id, &harness.game_impl.game)
+                .await
+                .unwrap();
+            harness.game_impl.apply_atoms(atoms).unwrap();
+
+            let library: Vec<_> = harness.game_impl.latest_gamestate().zones
+                [&ZoneId::Library(player)]
+                .objects
+                .iter()
+                .rev()
+                .take(3)
+                .map(|o| o.id)
+                .collect();
+            assert_eq!(library, vec![hand[2], hand[0], hand[1]]);
+        }
+    );
+
+    async_test!(
+        async fn check_starting_player_can_draw_first() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (chooser, other) = (harness.player_order[0], harness.player_order[1]);
+            harness.game_impl = harness
+                .game_impl
+                .with_starting_player_chooser(Some(chooser));
+
+            game_steps!(harness, [
+                @set {
+                    get_play_first = |_player: PlayerId| false
+                };
+            ]);
+            harness
+                .game_impl
+                .step(&harness.outside_client)
+                .await
+                .unwrap();
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.active_player_order, vec![other, chooser]);
+            assert!(matches!(
+                state.game_stage,
+                crate::GameStage::KeepHand { .. }
+            ));
+            assert!(state.get_hand(chooser).objects.is_empty());
+            assert!(harness
+                .game_impl
+                .events
+                .contains(&GameEvent::StartingPlayerChosen { player: other }));
+            assert!(matches!(
+                harness
+                    .game_impl
+                    .apply_atoms(vec![GameAtom::SetStartingPlayer { player: chooser }]),
+                Err(GameError::StartingPlayerAlreadyChosen)
+            ));
+
+            let rolled = SimpleTestHarness::new(Some(7), ServerAnswers::default())
+                .game_impl
+                .with_starting_player_chooser(None);
+            let crate::GameStage::ChooseStartingPlayer { chooser } =
+                rolled.latest_gamestate().game_stage
+            else {
+                panic!("The starting player has to be chosen first");
+            };
+            assert!(rolled.game.players.contains_key(&chooser));
+
+            // Nobody can be rolled in a game without players
+            let empty = GameImplV1::new(
+                GameId::new(),
+                Xoshiro256StarStar::seed_from_u64(7),
+                Arc::default(),
+                HashMap::new(),
+                vec![],
+            );
+            let stage = empty.latest_gamestate().game_stage.clone();
+            let empty = empty.with_starting_player_chooser(None);
+            assert_eq!(empty.latest_gamestate().game_stage, stage);
+        }
+    );
+
+    async_test!(
+        async fn check_format_sets_starting_hand_and_life() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            harness.game_impl = harness.game_impl.with_format(FormatRules {
+                starting_hand_size: Some(5),
+                starting_life: Some(30),
+                ..Default::default()
+            });
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @step_game { };
+                ]
+            );
+
+            let state = harness.game_impl.latest_gamestate();
+            for player in &harness.player_order {
+                assert_eq!(state.get_hand(*player).objects.len(), 5);
+                assert_eq!(state.life_of(*player), 30);
+            }
+
+            harness.game_impl.game.format.min_deck_size = Some(usize::MAX);
+            let errors = harness.game_impl.verify().unwrap_err();
+            assert!(errors
+                .iter()
+                .any(|e| matches!(e, VerificationError::InvalidDeckSize { .. })));
+        }
+    );
+
+    async_test!(
+        async fn check_poison_counters_eliminate_players() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            harness.game_impl.game.format.poison_limit = Some(3);
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @step_game { };
+                ]
+            );
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(state.game_stage, crate::GameStage::GameRunning);
+            let order = state.active_player_order.clone();
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::AddPoison {
+                    player: order[1],
+                    amount: 2,
+                }])
+                .unwrap();
+            assert_eq!(harness.game_impl.latest_gamestate().poison_of(order[1]), 2);
+            assert_eq!(harness.game_impl.outcome(), None);
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::AddPoison {
+                    player: order[1],
+                    amount: 1,
+                }])
+                .unwrap();
+            harness
+                .game_impl
+                .run(&harness.outside_client)
+                .await
+                .unwrap();
+
+            assert!(harness.game_impl.latest_gamestate().is_eliminated(order[1]));
+            assert_eq!(
+                harness.game_impl.outcome(),
+                Some(GameOutcome::Won { winner: order[0] })
+            );
+        }
+    );
+
+    async_test!(
+        async fn check_emblems_apply_their_static_effects() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, opponent) = (harness.player_order[0], harness.player_order[1]);
+            let card = |n, kind, effects| Card {
+                id: CardId::with(Uuid::from_u128(n)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind,
+                    effects,
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+            let emblem = card(
+                1,
+                vec![],
+                vec![CardEffect::Static(StaticCardEffect {
+                    effect: Effect::Continuous(ContinuousEffect::ModifyStats(StatModifier {
+                        player: Some(RelativePlayer::You),
+                        subkind: None,
+                        affects_source: false,
+                        power: 2,
+                        toughness: 0,
+                    })),
+                })],
+            );
+            let agent = card(
+                2,
+                vec![CardKind {
+                    kind: BaseCardKind::Agent {
+                        subkind: SubKind::new("Mercenary"),
+                        power: AgentPower::Fixed(1),
+                        toughness: AgentToughness::Fixed(1),
+                    },
+                }],
+                vec![],
+            );
+            let emblem_id = emblem.id;
+
+            let game = &mut harness.game_impl.game;
+            let mut ids = vec![];
+            for controller in [player, opponent] {
+                let mut object = GameObject::from_card(&mut game.rand, agent.id);
+                object.controller = Some(controller);
+                ids.push(object.id);
+                game.game_states
+                    .last_mut()
+                    .unwrap()
+                    .zones
+                    .get_mut(&ZoneId::Battlefield)
+                    .unwrap()
+                    .objects
+                    .push(object);
+            }
+            let cards = Arc::get_mut(&mut game.cards).unwrap();
+            cards.insert(emblem.id, emblem);
+            cards.insert(agent.id, agent);
+
+            harness
+                .game_impl
+                .apply_atoms(vec![GameAtom::CreateEmblem {
+                    player,
+                    card: emblem_id,
+                }])
+                .unwrap();
+
+            let game = &harness.game_impl.game;
+            let state = game.latest_gamestate();
+            assert_eq!(state.zones[&ZoneId::Emblems(player)].objects.len(), 1);
+            assert_eq!(state.agent_stats(game, ids[0]), Some((3, 1)));
+            assert_eq!(state.agent_stats(game, ids[1]), Some((1, 1)));
+        }
+    );
+
+    async_test!(
+        async fn check_shared_library_gives_cards_to_whoever_draws_them() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            harness.game_impl = harness.game_impl.with_format(FormatRules {
+                shared_library: true,
+                ..Default::default()
+            });
+            let state = harness.game_impl.latest_gamestate();
+            let cards: usize = harness
+                .game_impl
+                .game
+                .players
+                .values()
+                .map(|p| p.initial_cards.len())
+                .sum();
+            assert_eq!(state.zones[&ZoneId::SharedLibrary].objects.len(), cards);
+            for player in &harness.player_order {
+                assert!(state.zones[&ZoneId::Library(*player)].objects.is_empty());
+            }
+
+            game_steps!(
+                harness,
+                [
+                    @set {
+                        get_player_keeping = |players| {
+                            players
+                        }
+                    };
+                    @step_game { };
+                ]
+            );
+
+            let state = harness.game_impl.latest_gamestate();
+            assert_eq!(
+                state.zones[&ZoneId::SharedLibrary].objects.len(),
+                cards - STARTING_HAND_SIZE * harness.player_order.len()
+            );
+            for player in &harness.player_order {
+                let hand = &state.get_hand(*player).objects;
+                assert_eq!(hand.len(), STARTING_HAND_SIZE);
+                assert!(hand.iter().all(|o| o.owner == Some(*player)));
+            }
+        }
+    );
+
+    async_test!(
+        async fn check_rollback_restores_state_and_randomness() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            let library = |game_impl: &GameImplV1| -> Vec<ObjectId> {
+                game_impl.latest_gamestate().zones[&ZoneId::Library(player)]
+                    .objects
+                    .iter()
+                    .map(|o| o.id)
+                    .collect()
+            };
+
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
+                .unwrap();
+            game_impl
+                .apply_atoms(vec![GameAtom::ShuffleLibrary { player }])
+                .unwrap();
+            let shuffled = library(&game_impl);
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 2 }])
+                .unwrap();
+
+            game_impl.rollback_to(1).unwrap();
+            assert_eq!(game_impl.game.game_states.len(), 2);
+            assert_eq!(game_impl.game.history.len(), 1);
+            assert_eq!(
+                game_impl.latest_gamestate().get_hand(player).objects.len(),
+                1
+            );
+
+            game_impl
+                .apply_atoms(vec![GameAtom::ShuffleLibrary { player }])
+                .unwrap();
+            assert_eq!(library(&game_impl), shuffled);
+
+            assert!(matches!(
+                game_impl.rollback_to(5),
+                Err(GameError::RollbackUnavailable { state: 5 })
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_memory_estimate_counts_shared_zones_once() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            let workload = game_impl.workload();
+            let estimate = |game_impl: &GameImplV1| {
+                workload.record(&game_impl.game);
+                workload.diagnostics(game_impl.game.id).memory_estimate
+            };
+
+            let first = estimate(&game_impl);
+            assert!(first > 0);
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
+                .unwrap();
+            let drawn = estimate(&game_impl);
+            // Only the library and the hand were copied, the other zones are shared
+            assert!(drawn > first);
+            assert!(drawn - first < first);
+
+            game_impl.rollback_to(0).unwrap();
+            assert_eq!(estimate(&game_impl), first);
+        }
+    );
+
+    async_test!(
+        async fn check_replay_rebuilds_recorded_states() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            game_impl
+                .apply_atoms(vec![GameAtom::ShuffleLibrary { player }])
+                .unwrap();
+            assert!(game_impl
+                .apply_atoms(vec![GameAtom::RandomChoice { options: vec![] }])
+                .is_err());
+            game_impl
+                .apply_atoms(vec![
+                    GameAtom::DrawCards { player, count: 2 },
+                    GameAtom::ShuffleLibrary { player },
+                ])
+                .unwrap();
+
+            let game = &game_impl.game;
+            let seed = game.initial_seed().unwrap().clone();
+            let replayed = GameImplV1::replay(game, seed.clone(), game.history.clone()).unwrap();
+            assert_eq!(replayed.game_states, game.game_states);
+
+            let mut tampered = game.clone();
+            tampered.game_states[1].life.insert(player, 1);
+            assert!(matches!(
+                GameImplV1::replay(&tampered, seed, tampered.history.clone()),
+                Err(ReplayError::Diverged { state: 1 })
+            ));
+        }
+    );
+
+    async_test!(
+        async fn check_replay_rebuilds_triggers_and_later_shuffles() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            // "Whenever a player draws a card, you gain 1 life"
+            let archive = Card {
+                id: CardId::with(Uuid::from_u128(1)),
+                version: 1,
+                behaviour: CardBehaviour {
+                    cost: None,
+                    kind: vec![CardKind {
+                        kind: BaseCardKind::Building {
+                            subkind: SubKind::new("Mercenary"),
+                        },
+                    }],
+                    effects: vec![CardEffect::Triggered(TriggeredCardEffect {
+                        trigger: EffectTrigger::OnDraw,
+                        effects: vec![Effect::Instant(Box::new(GainLife(1)))],
+                    })],
+                    keywords: vec![],
+                    alternative_costs: vec![],
+                },
+                layout: CardLayout::Single,
+            };
+            let game = &mut game_impl.game;
+            let mut building = GameObject::from_card(&mut game.rand, archive.id);
+            building.controller = Some(player);
+            Arc::get_mut(&mut game.cards)
+                .unwrap()
+                .insert(archive.id, archive);
+            let state = game.game_states.last_mut().unwrap();
+            state.game_stage = crate::GameStage::GameRunning;
+            state
+                .zones
+                .get_mut(&ZoneId::Battlefield)
+                .unwrap()
+                .objects
+                .push(building);
+            let seed = game.rand.clone();
+
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
+                .unwrap();
+            run_triggers(&mut game_impl).await;
+            assert_eq!(game_impl.latest_gamestate().get_stack().objects.len(), 1);
+            game_impl
+                .apply_atoms(vec![GameAtom::ShuffleLibrary { player }])
+                .unwrap();
+
+            let game = &game_impl.game;
+            let replayed = GameImplV1::replay(game, seed, game.history.clone()).unwrap();
+            assert_eq!(replayed.game_states, game.game_states);
+            assert_eq!(replayed.rand, game.rand);
+        }
+    );
 
-                let filter = tracing_subscriber::filter::EnvFilter::from_default_env();
-                let fmt_layer = tracing_subscriber::fmt::layer()
-                    .with_timer(tracing_subscriber::fmt::time::uptime())
-                    .with_level(true)
-                    .with_file(true)
-                    .with_line_number(true)
-                    .with_test_writer()
-                    .pretty();
+    async_test!(
+        async fn check_deltas_rebuild_every_game_state() {
+            let (player_order, mut game_impl, _, _) = init_harness(Some(7));
+            let mut rng = Xoshiro256StarStar::seed_from_u64(7);
+            for _ in 0..200 {
+                let player = player_order[rng.gen_range(0..player_order.len())];
+                let count = rng.gen_range(0..4);
+                let atom = match rng.gen_range(0..7) {
+                    0 => GameAtom::DrawCards { player, count },
+                    1 => GameAtom::Mill { player, count },
+                    2 => GameAtom::ShuffleLibrary { player },
+                    3 => GameAtom::ShuffleHandIntoLibrary { player },
+                    4 => GameAtom::GainLife {
+                        player,
+                        amount: count,
+                    },
+                    5 => GameAtom::LoseLife {
+                        player,
+                        amount: count,
+                    },
+                    _ => GameAtom::AddPoison {
+                        player,
+                        amount: count,
+                    },
+                };
+                // Atoms that do not apply here simply leave no game state behind
+                let _ = game_impl.apply_atoms(vec![atom]);
+            }
 
-                let _ = tracing_subscriber::registry()
-                    .with(filter)
-                    .with(fmt_layer)
-                    .try_init();
+            let states = &game_impl.game.game_states;
+            assert!(states.len() > 100);
+            for pair in states.windows(2) {
+                let delta = GameStateDelta::between(&pair[0], &pair[1]);
+                let mut rebuilt = pair[0].clone();
+                delta.apply(&mut rebuilt);
+                assert_eq!(rebuilt, pair[1]);
+            }
+            assert!(GameStateDelta::between(&states[0], &states[0]).is_empty());
+        }
+    );
 
-                let rt = tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()
-                    .unwrap();
+    async_test!(
+        async fn check_compacted_states_are_rebuilt_on_demand() {
+            let (player_order, game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            let mut game_impl = game_impl.with_compaction(Some(Compaction {
+                every: 3,
+                keep_recent: 2,
+            }));
 
-                rt.block_on(async {
-                    $($tt)*
-                }.instrument(tracing::info_span!("Running test", name = stringify!($name))));
+            let mut seen = vec![game_impl.latest_gamestate().clone()];
+            for _ in 0..8 {
+                game_impl
+                    .apply_atoms(vec![GameAtom::ShuffleLibrary { player }])
+                    .unwrap();
+                seen.push(game_impl.latest_gamestate().clone());
+            }
 
-                rt.shutdown_background();
+            assert_eq!(game_impl.game.game_states.len(), 2);
+            assert_eq!(game_impl.game.latest_index(), 8);
+            let kept: Vec<_> = game_impl
+                .game
+                .snapshots
+                .iter()
+                .map(|(idx, _)| *idx)
+                .collect();
+            assert_eq!(kept, vec![0, 3, 6]);
+            for (idx, state) in seen.iter().enumerate() {
+                assert_eq!(&game_impl.state_at(idx).unwrap(), state);
             }
-        };
-    }
+
+            game_impl.rollback_to(4).unwrap();
+            assert_eq!(game_impl.game.latest_index(), 4);
+            assert_eq!(game_impl.latest_gamestate(), &seen[4]);
+            game_impl
+                .apply_atoms(vec![GameAtom::ShuffleLibrary { player }])
+                .unwrap();
+            assert_eq!(game_impl.latest_gamestate(), &seen[5]);
+        }
+    );
 
     async_test!(
-        async fn check_initial_game_creation() {
-            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
-            harness
-                .game_impl
-                .run(&harness.outside_client)
-                .await
+        async fn check_untouched_zones_are_shared_between_states() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let (drawing, other) = (player_order[0], player_order[1]);
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards {
+                    player: drawing,
+                    count: 1,
+                }])
                 .unwrap();
 
-            assert!(!harness.game_impl.game.game_states.is_empty());
+            let [before, after] = &game_impl.game.game_states[..] else {
+                panic!("Expected exactly two game states");
+            };
+            let objects = |state: &GameState, zone| state.zones[&zone].objects.as_ptr();
+            let library = ZoneId::Library(other);
+            assert_eq!(objects(before, library), objects(after, library));
+            let library = ZoneId::Library(drawing);
+            assert_ne!(objects(before, library), objects(after, library));
         }
     );
 
     async_test!(
-        async fn check_initial_game_zones() {
-            let mut harness = SimpleTestHarness::new(
-                None,
-                ServerAnswers {
-                    get_player_keeping: Some(Box::new(|players| players)),
-                    ..Default::default()
-                },
-            );
-            harness
-                .game_impl
-                .run(&harness.outside_client)
-                .await
+        async fn check_objects_are_found_through_the_index() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
                 .unwrap();
-            let state = harness.game_impl.latest_gamestate();
 
-            let first_player = harness.player_order.first().copied().unwrap();
+            let mut state = game_impl.latest_gamestate().clone();
+            let drawn = state.get_hand(player).objects[0].id;
+            assert_eq!(
+                state.find_object(drawn).map(|(zone, _)| zone),
+                Some(ZoneId::Hand(player))
+            );
+            assert!(state
+                .get_object_from_zone(ZoneId::Library(player), drawn)
+                .is_none());
 
-            assert_eq!(harness.player_order.len() * 3 + 2, state.zones.len());
+            // Objects moved without applying atoms are still found, the index is only a shortcut
+            let object = state
+                .zones
+                .get_mut(&ZoneId::Hand(player))
+                .unwrap()
+                .objects
+                .remove(0);
+            state
+                .zones
+                .get_mut(&ZoneId::Exile)
+                .unwrap()
+                .objects
+                .push(object);
             assert_eq!(
-                simple_deck().len(),
-                state
-                    .zones
-                    .get(&ZoneId::Library(first_player))
-                    .unwrap()
-                    .objects
-                    .len()
-                    + state
-                        .zones
-                        .get(&ZoneId::Hand(first_player))
-                        .unwrap()
-                        .objects
-                        .len()
+                state.find_object(drawn).map(|(zone, _)| zone),
+                Some(ZoneId::Exile)
             );
+            assert!(state.get_object_from_zone(ZoneId::Exile, drawn).is_some());
         }
     );
 
     async_test!(
-        async fn check_game_starts_with_initial_player_order() {
-            let mut harness = SimpleTestHarness::new(
-                None,
-                ServerAnswers {
-                    ..Default::default()
-                },
+        async fn check_unchanged_zones_share_their_index() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let (player, other) = (player_order[0], player_order[1]);
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
+                .unwrap();
+            let before = game_impl.latest_gamestate().clone();
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
+                .unwrap();
+
+            let after = game_impl.latest_gamestate();
+            for zone in [
+                ZoneId::Hand(other),
+                ZoneId::Library(other),
+                ZoneId::Battlefield,
+            ] {
+                assert!(after.index.shares_zone(&before.index, zone));
+            }
+            for zone in [ZoneId::Hand(player), ZoneId::Library(player)] {
+                assert!(!after.index.shares_zone(&before.index, zone));
+            }
+            let drawn = after.get_hand(player).objects[1].id;
+            assert_eq!(
+                after.find_object(drawn).map(|(zone, _)| zone),
+                Some(ZoneId::Hand(player))
             );
-            harness
-                .game_impl
-                .run(&harness.outside_client)
-                .await
+        }
+    );
+
+    async_test!(
+        async fn check_games_load_from_json_for_the_debugger() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 2 }])
+                .unwrap();
+            game_impl
+                .apply_atoms(vec![GameAtom::DiscardCards {
+                    player,
+                    objects: vec![game_impl.latest_gamestate().get_hand(player).objects[0].id],
+                }])
                 .unwrap();
 
-            let state = harness.game_impl.latest_gamestate();
+            // The debugger reads games and replays the way they are written here
+            let json = serde_json::to_string(&game_impl.game).unwrap();
+            let loaded: Game = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded.game_states, game_impl.game.game_states);
+            assert_eq!(loaded.history, game_impl.game.history);
 
-            assert_eq!(&state.active_player_order, &harness.player_order);
+            let replay = serde_json::to_string(&game_impl.game.committed_history()).unwrap();
+            let replay: Replay = serde_json::from_str(&replay).unwrap();
+            let mut replayed =
+                GameImplV1::restore_migrated(loaded, game_impl.game.cards.clone(), |_, _| ());
+            replayed.rollback_to(0).unwrap();
+            for (_, atoms) in replay {
+                replayed.apply_atoms(atoms).unwrap();
+            }
+            assert_eq!(replayed.latest_gamestate(), game_impl.latest_gamestate());
         }
     );
 
     async_test!(
-        async fn check_game_mulligan() {
-            let mut harness = SimpleTestHarness::new(
-                None,
-                ServerAnswers {
-                    ..Default::default()
-                },
-            );
-            let player = *harness.player_order.first().unwrap();
+        async fn check_saved_games_load_with_their_cards() {
+            let (player_order, game_impl, _, _) = init_harness(None);
+            let mut subkinds = SubKindRegistry::base();
+            let ripperdoc = subkinds.register("Ripperdoc");
+            let mut game_impl = game_impl.with_subkinds(Arc::new(subkinds));
+            let player = player_order[0];
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 2 }])
+                .unwrap();
 
-            game_steps!(
-                harness,
-                [
-                    @set {
-                        get_player_keeping = move |mut players| {
-                            players.retain(|p| p != &player);
-                            players
-                        }
-                    };
-                    @step_game { };
-                    @set {
-                        get_player_keeping = |players| {
-                            players
-                        }
-                    };
-                    @step_game { };
-                ]
-            );
+            let saved = serde_json::to_string(&game_impl.game.save()).unwrap();
+            let saved: SavedGame = serde_json::from_str(&saved).unwrap();
+            let cards = game_impl.game.cards.clone();
 
-            let state = harness.game_impl.latest_gamestate();
-            assert!(
-                matches!(state.game_stage, crate::GameStage::GameRunning),
-                "Game is still not running!"
+            let loaded = Game::load(saved.clone(), cards.clone()).unwrap();
+            assert!(Arc::ptr_eq(&loaded.cards, &cards));
+            assert_eq!(loaded.game_states, game_impl.game.game_states);
+            assert_eq!(loaded.history, game_impl.game.history);
+            assert_eq!(loaded.rand, game_impl.game.rand);
+            assert!(loaded.subkinds.contains(&ripperdoc));
+
+            let card = game_impl.latest_gamestate().get_hand(player).objects[0]
+                .underlying_card
+                .unwrap();
+            let mut missing = existing_cards();
+            missing.remove(&card);
+            assert_eq!(
+                Game::load(saved.clone(), Arc::new(missing)).unwrap_err(),
+                LoadError::MissingCard { card }
             );
+
+            let future = SavedGame {
+                format_version: saved.format_version + 1,
+                ..saved
+            };
             assert_eq!(
-                6,
-                state
-                    .zones
-                    .get(&ZoneId::Hand(player))
-                    .unwrap()
-                    .objects
-                    .len()
+                Game::load(future, cards).unwrap_err(),
+                LoadError::UnsupportedFormat {
+                    saved: SAVE_FORMAT_VERSION + 1
+                }
             );
         }
     );
 
     async_test!(
-        async fn check_game_player_plays_card() {
-            let mut harness = SimpleTestHarness::new(
-                Some(1234),
-                ServerAnswers {
-                    ..Default::default()
-                },
-            );
+        async fn check_subscribers_receive_applied_atoms() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            let mut applied = game_impl.subscribe();
 
-            let player = *harness.player_order.first().unwrap();
+            let draw = vec![GameAtom::DrawCards { player, count: 1 }];
+            game_impl.apply_atoms(draw.clone()).unwrap();
+            assert!(game_impl
+                .apply_atoms(vec![GameAtom::RandomChoice { options: vec![] }])
+                .is_err());
 
-            game_steps!(
-                harness,
-                [
-                    @set {
-                        get_player_keeping = |players| {
-                            players
-                        }
-                    };
-                    @step_game {};
-                    @run {
-                        assert_eq!(
-                            harness.game_impl.latest_gamestate().game_stage,
-                            crate::GameStage::GameRunning
-                        );
-                    };
-                    @set {
-                        get_next_player_action_from = |_player, player_actions| {
-                            let id = ObjectId(Uuid::from_str("2eaec1b5-94a9-4994-b038-54826e4e3ca6").unwrap());
-                            player_actions.iter().position(|i| matches!(i, PlayerAction::PlayCard { object, ..} if *object == id)).unwrap()
-                        }
-                    };
-                    @set {
-                        get_target_choices_from_given = | player: PlayerId, _source: ObjectId, _name: String, choices: Vec<TargetId>, _count: usize,| {
-                            choices.iter().enumerate().filter(|(_, c)| match c { TargetId::Player(ply) => *ply != player, _ => false }).map(|(idx, _c)| idx).collect()
-                        }
-                    };
-                    @set {
-                        get_player_passing = |_player: PlayerId| { true }
-                    };
-                    @step_game {};
-                    @run {
-                        let state = harness.game_impl.latest_gamestate();
-                        assert_eq!(state.get_stack().objects.len(), 1);
-                    };
-                    @unset {};
-                    @set {
-                        get_next_player_action_from = |_player, _player_actions| {
-                            0
-                        }
-                    };
-                    @step_game {};
-                    @step_game {};
-                    @step_game {};
-                    @step_game {};
-                    @run {
-                        let state = harness.game_impl.latest_gamestate();
-                        assert_eq!(state.get_hand(player).objects.len(), 7);
-                    };
-                ]
+            assert_eq!(
+                applied.try_recv().unwrap(),
+                AppliedAtoms {
+                    game: game_impl.id(),
+                    base: 0,
+                    state: 1,
+                    atoms: draw,
+                }
             );
+            assert!(applied.try_recv().is_err());
+        }
+    );
+
+    async_test!(
+        async fn check_game_log_lists_committed_batches() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
+                .unwrap();
+            assert!(game_impl
+                .apply_atoms(vec![GameAtom::RandomChoice { options: vec![] }])
+                .is_err());
+            game_impl
+                .apply_atoms(vec![GameAtom::KeepHand { player }])
+                .unwrap();
+
+            let mut written = vec![];
+            game_impl.game.write_log(&mut written).unwrap();
+            let lines: Vec<LogLine> = String::from_utf8(written)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+
+            let [LogLine::Header(header), LogLine::Entry(draw), LogLine::Entry(keep)] = &lines[..]
+            else {
+                panic!("Expected a header and two entries, got {lines:?}");
+            };
+            let states = &game_impl.game.game_states;
+            assert_eq!(header.seed.as_ref(), game_impl.game.initial_seed());
+            assert_eq!(header.players.len(), player_order.len());
+            assert_eq!(header.state_hash, states[0].fingerprint());
+
+            assert_eq!((draw.base, draw.actor), (0, None));
+            assert_eq!((keep.base, keep.actor), (1, Some(player)));
+            assert_eq!(keep.state_hash, Some(states[2].fingerprint()));
+            assert!(keep.timestamp.is_some());
+        }
+    );
+
+    async_test!(
+        async fn check_malformed_atoms_are_refused_instead_of_panicking() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let stranger = PlayerId::new();
+            assert!(matches!(
+                game_impl.apply_atoms(vec![GameAtom::DrawCards {
+                    player: stranger,
+                    count: 1
+                }]),
+                Err(GameError::PlayerNotInGame { player }) if player == stranger
+            ));
+            assert!(matches!(
+                game_impl.apply_atoms(vec![GameAtom::PlayerPlayCard {
+                    player: player_order[0],
+                    from: ZoneId::Stack,
+                    object: ObjectId(uuid::Uuid::nil()),
+                    choices: HashMap::new(),
+                    second_half: false,
+                }]),
+                Err(GameError::SameZone {
+                    zone: ZoneId::Stack
+                })
+            ));
+
+            let state = game_impl.game.game_states.last_mut().unwrap();
+            state.zones.remove(&ZoneId::Stack);
+            assert!(matches!(
+                game_impl.apply_atoms(vec![GameAtom::PopStack]),
+                Err(GameError::ZoneNotFound {
+                    zone: ZoneId::Stack
+                })
+            ));
+            assert_eq!(game_impl.game.game_states.len(), 1);
+        }
+    );
+
+    async_test!(
+        async fn check_refused_batches_leave_no_trace() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            let rand = game_impl.game.rand.clone();
+
+            assert!(matches!(
+                game_impl.apply_atoms(vec![
+                    GameAtom::ShuffleLibrary { player },
+                    GameAtom::DrawCards { player, count: 1 },
+                    GameAtom::RandomChoice { options: vec![] },
+                ]),
+                Err(GameError::EmptyRandomChoice)
+            ));
+
+            let game = &game_impl.game;
+            assert_eq!(game.game_states.len(), 1);
+            assert!(game.history.is_empty());
+            assert!(game.rand_history.is_empty());
+            assert_eq!(game.rand, rand);
+            assert!(game_impl.events.is_empty());
+        }
+    );
+
+    async_test!(
+        async fn check_dry_runs_leave_the_game_untouched() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 1 }])
+                .unwrap();
+            let before = game_impl.game.clone();
+
+            let preview = game_impl
+                .dry_run(vec![GameAtom::DrawCards { player, count: 2 }])
+                .unwrap();
+            assert_eq!(preview.get_hand(player).objects.len(), 3);
+            assert_eq!(game_impl.game.game_states, before.game_states);
+            assert_eq!(game_impl.game.history, before.history);
+            assert_eq!(game_impl.game.rand, before.rand);
+
+            // Only the latest game state is copied to try the atoms out on
+            let copy = game_impl.game.without_history();
+            assert_eq!(copy.game_states.len(), 1);
+            assert_eq!(copy.latest_index(), game_impl.game.latest_index());
+            assert!(copy.history.is_empty());
+        }
+    );
+
+    async_test!(
+        async fn check_previewed_actions_wait_for_confirmation() {
+            let mut harness = SimpleTestHarness::new(None, ServerAnswers::default());
+            let (player, other) = (harness.player_order[0], harness.player_order[1]);
+            harness
+                .game_impl
+                .game
+                .players
+                .get_mut(&player)
+                .unwrap()
+                .preferences
+                .preview_actions = true;
+
+            game_steps!(harness, [
+                @set {
+                    confirm_action = move |asked: PlayerId, preview: PlayerView| {
+                        assert_eq!(asked, player);
+                        assert_eq!(preview.hand.len(), 1);
+                        false
+                    }
+                };
+            ]);
+            let atoms = [GameAtom::DrawCards { player, count: 1 }];
+            let confirmed = harness
+                .game_impl
+                .confirm_action(&harness.outside_client, player, &atoms)
+                .await
+                .unwrap();
+            assert!(!confirmed);
+            assert!(harness
+                .game_impl
+                .latest_gamestate()
+                .get_hand(player)
+                .objects
+                .is_empty());
+
+            // Players who did not ask for previews are not asked
+            let atoms = [GameAtom::DrawCards {
+                player: other,
+                count: 1,
+            }];
+            let confirmed = harness
+                .game_impl
+                .confirm_action(&harness.outside_client, other, &atoms)
+                .await
+                .unwrap();
+            assert!(confirmed);
+        }
+    );
+
+    async_test!(
+        async fn check_seed_exports_reproduce_games() {
+            let (player_order, mut game_impl, _, _) = init_harness(None);
+            let player = player_order[0];
+            game_impl
+                .apply_atoms(vec![GameAtom::ShuffleLibrary { player }])
+                .unwrap();
+            game_impl
+                .apply_atoms(vec![GameAtom::DrawCards { player, count: 2 }])
+                .unwrap();
+            assert!(game_impl.game.rand_draws_at(0).unwrap() > 0);
+            assert_eq!(game_impl.game.rand_draws_at(1), Some(0));
+            let mut stepped = game_impl.game.initial_seed().unwrap().clone();
+            for _ in 0..game_impl.game.rand_draws_at(0).unwrap() {
+                stepped.gen::<u64>();
+            }
+            assert_eq!(stepped, game_impl.game.rand);
+
+            let export = game_impl.game.export_seed();
+            let cards = game_impl.game.cards.clone();
+            let rebuilt = GameImplV1::from_seed_export(export.clone(), cards.clone()).unwrap();
+            assert_eq!(rebuilt.game.game_states, game_impl.game.game_states);
+
+            // Exports are shared as JSON
+            let json = serde_json::to_string(&export).unwrap();
+            let export: SeedExport = serde_json::from_str(&json).unwrap();
+            let rebuilt = GameImplV1::from_seed_export(export.clone(), cards.clone()).unwrap();
+            assert_eq!(rebuilt.game.game_states, game_impl.game.game_states);
+            assert_eq!(rebuilt.game.rand, game_impl.game.rand);
+
+            let mut tampered = export.clone();
+            tampered.draws[1].1 = 3;
+            assert!(matches!(
+                GameImplV1::from_seed_export(tampered, cards.clone()),
+                Err(ReplayError::DrawsDiverged {
+                    state: 1,
+                    recorded: 3,
+                    drawn: 0
+                })
+            ));
+
+            // Drawing the same amount is not enough, the game states have to match as well
+            let mut tampered = export.clone();
+            tampered.first_state.life.insert(player, 1);
+            assert!(matches!(
+                GameImplV1::from_seed_export(tampered, cards.clone()),
+                Err(ReplayError::Diverged { state: 1 })
+            ));
+
+            // Exports from before the game states were hashed only compare the draws
+            let mut unhashed = export;
+            unhashed.state_hashes.clear();
+            let rebuilt = GameImplV1::from_seed_export(unhashed, cards).unwrap();
+            assert_eq!(rebuilt.game.game_states, game_impl.game.game_states);
         }
     );
 }
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rand_xoshiro::Xoshiro256StarStar;
+use technomancy_core::card::Card;
+use technomancy_core::card::CardId;
+use technomancy_core::matches::MatchPlayer;
+use technomancy_core::matches::MatchResult;
+use technomancy_core::matches::SideboardError;
+use technomancy_core::GameId;
+use technomancy_core::GameResult;
+use technomancy_core::Player;
+use technomancy_core::PlayerId;
+
+use crate::GameImplV1;
+
+/// Several games between the same players, the first to win more than half of them wins the
+/// match
+///
+/// Between games players may swap cards of their deck with cards of their sideboard.
+#[derive(Debug)]
+pub struct Match {
+    best_of: usize,
+    /// The players in the order they sat down for the first game
+    order: Vec<PlayerId>,
+    players: HashMap<PlayerId, MatchPlayer>,
+    cards: Arc<HashMap<CardId, Card>>,
+    games: Vec<GameResult>,
+}
+
+impl Match {
+    pub fn new(
+        best_of: usize,
+        players: Vec<MatchPlayer>,
+        cards: Arc<HashMap<CardId, Card>>,
+    ) -> Match {
+        Match {
+            best_of,
+            order: players.iter().map(|p| p.player.id).collect(),
+            players: players.into_iter().map(|p| (p.player.id, p)).collect(),
+            cards,
+            games: vec![],
+        }
+    }
+
+    pub fn wins_of(&self, player: PlayerId) -> usize {
+        self.games
+            .iter()
+            .filter(|result| result.is_winner(player))
+            .count()
+    }
+
+    /// The players in their seating order, with the decks they currently play
+    pub fn players(&self) -> Vec<Player> {
+        self.order
+            .iter()
+            .map(|id| self.players[id].player.clone())
+            .collect()
+    }
+
+    /// The player who won more than half of the games, if any
+    pub fn winner(&self) -> Option<PlayerId> {
+        self.order
+            .iter()
+            .copied()
+            .find(|player| self.wins_of(*player) * 2 > self.best_of)
+    }
+
+    /// Whether the match is decided, or all of its games have been played
+    pub fn is_finished(&self) -> bool {
+        self.winner().is_some() || self.games.len() >= self.best_of
+    }
+
+    pub fn record_game(&mut self, result: GameResult) {
+        self.games.push(result);
+    }
+
+    pub fn result(&self) -> MatchResult {
+        MatchResult {
+            games: self.games.clone(),
+            winner: self.winner(),
+            finished: self.is_finished(),
+        }
+    }
+
+    /// Moves `out` from the player's deck to their sideboard, and `into` the other way around
+    pub fn swap_sideboard_card(
+        &mut self,
+        player: PlayerId,
+        out: CardId,
+        into: CardId,
+    ) -> Result<(), SideboardError> {
+        let match_player = self
+            .players
+            .get_mut(&player)
+            .ok_or(SideboardError::UnknownPlayer { player })?;
+
+        let deck = &mut match_player.player.initial_cards;
+        let sideboard = &mut match_player.sideboard;
+
+        let deck_idx = deck
+            .iter()
+            .position(|c| *c == out)
+            .ok_or(SideboardError::NotInDeck { card: out })?;
+        let sideboard_idx = sideboard
+            .iter()
+            .position(|c| *c == into)
+            .ok_or(SideboardError::NotInSideboard { card: into })?;
+
+        deck[deck_idx] = into;
+        sideboard[sideboard_idx] = out;
+
+        Ok(())
+    }
+
+    /// Sets up the next game of the match with the current decks
+    ///
+    /// The winner of the previous game decides whether to play first, a random player does for
+    /// the first game and after draws.
+    pub fn next_game(&self, id: GameId, rand: Xoshiro256StarStar) -> GameImplV1 {
+        let chooser = self
+            .games
+            .last()
+            .and_then(|last| last.winners().first().copied());
+
+        let players: HashMap<PlayerId, Player> = self
+            .players
+            .iter()
+            .map(|(id, p)| (*id, p.player.clone()))
+            .collect();
+
+        GameImplV1::new(id, rand, self.cards.clone(), players, self.order.clone())
+            .with_starting_player_chooser(chooser)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use technomancy_core::card::CardId;
+    use technomancy_core::matches::MatchPlayer;
+    use technomancy_core::matches::SideboardError;
+    use technomancy_core::GameResult;
+    use technomancy_core::Player;
+    use technomancy_core::PlayerId;
+
+    use super::Match;
+
+    fn card(n: u128) -> CardId {
+        CardId::with(uuid::Uuid::from_u128(n))
+    }
+
+    fn match_player(deck: Vec<CardId>, sideboard: Vec<CardId>) -> MatchPlayer {
+        MatchPlayer {
+            player: Player {
+                id: PlayerId::new(),
+                initial_cards: deck,
+                preferences: Default::default(),
+            },
+            sideboard,
+        }
+    }
+
+    #[test]
+    fn check_best_of_three_winner() {
+        let first = match_player(vec![], vec![]);
+        let second = match_player(vec![], vec![]);
+        let (first_id, second_id) = (first.player.id, second.player.id);
+        let mut best_of_three = Match::new(3, vec![first, second], Arc::default());
+
+        best_of_three.record_game(GameResult::Won {
+            winners: vec![first_id],
+        });
+        assert!(!best_of_three.is_finished());
+
+        best_of_three.record_game(GameResult::Conceded {
+            winners: vec![second_id],
+            conceded: vec![first_id],
+        });
+        best_of_three.record_game(GameResult::TimedOut {
+            winners: vec![first_id],
+            timed_out: vec![second_id],
+        });
+
+        assert!(best_of_three.is_finished());
+        assert_eq!(best_of_three.result().winner, Some(first_id));
+    }
+
+    #[test]
+    fn check_sideboard_swap() {
+        let player = match_player(vec![card(1), card(2)], vec![card(3)]);
+        let id = player.player.id;
+        let mut best_of_three = Match::new(3, vec![player], Arc::default());
+
+        assert_eq!(
+            best_of_three.swap_sideboard_card(id, card(3), card(1)),
+            Err(SideboardError::NotInDeck { card: card(3) })
+        );
+
+        best_of_three
+            .swap_sideboard_card(id, card(1), card(3))
+            .unwrap();
+
+        let player = &best_of_three.players[&id];
+        assert_eq!(player.player.initial_cards, vec![card(3), card(2)]);
+        assert_eq!(player.sideboard, vec![card(1)]);
+    }
+}
@@ -1,36 +1,193 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 use std::time::SystemTime;
 
 use tarpc::client::RpcError;
+use technomancy_core::card::CardId;
+use technomancy_core::event::GameEvent;
+use technomancy_core::outside::ObjectList;
 use technomancy_core::outside::OutsideClient;
+use technomancy_core::outside::PromptContext;
+use technomancy_core::view::PlayerView;
 
 use crate::GameId;
 use crate::ObjectId;
 use crate::PlayerAction;
 use crate::PlayerId;
 use crate::TargetId;
+use crate::ZoneId;
 
+pub(crate) mod asked;
+pub mod bot;
+pub mod faults;
+pub mod hot_seat;
+
+/// How the engine asks players for their decisions and tells them what happened
+///
+/// [`OutsideGameClient`] is the implementation used in real games, others can wrap it.
 #[async_trait::async_trait]
-pub trait OutsideGame {
+pub trait OutsideGame: Sync {
     async fn get_player_keeping(
         &self,
+        prompt: PromptContext,
         asked_players: Vec<PlayerId>,
     ) -> Result<Vec<PlayerId>, RpcError>;
     async fn get_next_player_action_from(
         &self,
+        prompt: PromptContext,
         player: PlayerId,
         player_actions: Vec<PlayerAction>,
     ) -> Result<usize, RpcError>;
     async fn get_target_choices_from_given(
         &self,
+        prompt: PromptContext,
         player: PlayerId,
         source: ObjectId,
         name: String,
         choices: Vec<TargetId>,
         count: usize,
+        optional: bool,
+    ) -> Result<Vec<usize>, RpcError>;
+    async fn get_multiple_target_choices(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        min: usize,
+        max: usize,
+    ) -> Result<Vec<usize>, RpcError>;
+    async fn get_number_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        min: u64,
+        max: u64,
+    ) -> Result<u64, RpcError>;
+    async fn get_library_search_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: ObjectList,
+    ) -> Result<Option<usize>, RpcError>;
+    async fn get_library_arrangement(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        cards: ObjectList,
+        allow_bottom: bool,
+    ) -> Result<(Vec<usize>, Vec<usize>), RpcError>;
+    async fn get_zone_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        zone: ZoneId,
+        choices: ObjectList,
+        count: usize,
     ) -> Result<Vec<usize>, RpcError>;
-    async fn get_player_passing(&self, player: PlayerId) -> Result<bool, RpcError>;
+    async fn get_ordering(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        items: ObjectList,
+    ) -> Result<Vec<usize>, RpcError>;
+    async fn get_discard_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError>;
+    async fn get_cards_to_bottom(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError>;
+    async fn get_damage_assignment(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        attacker: ObjectId,
+        blockers: Vec<(ObjectId, CardId)>,
+        damage: u64,
+    ) -> Result<Vec<(usize, u64)>, RpcError>;
+    async fn get_player_passing(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError>;
+    async fn get_play_first(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError>;
+    async fn notify_event(&self, event: GameEvent) -> Result<(), RpcError>;
+    async fn notify_error(
+        &self,
+        player: PlayerId,
+        error_description: String,
+    ) -> Result<(), RpcError>;
+    async fn confirm_action(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        preview: PlayerView,
+    ) -> Result<bool, RpcError>;
+}
+
+/// The objects shown in the pending prompt of each player
+///
+/// Prompts only describe the first page of their objects, whoever answers
+/// [`Meta::get_prompt_objects`](technomancy_core::meta::Meta::get_prompt_objects) looks up the
+/// rest here.
+#[derive(Debug, Clone, Default)]
+pub struct PromptObjects {
+    pending: Arc<Mutex<HashMap<PlayerId, HashMap<ObjectId, CardId>>>>,
+}
+
+impl PromptObjects {
+    /// Remembers the objects of a prompt to the player, replacing those of their previous one
+    ///
+    /// Objects given without a card stay unknown, their details can not be fetched either.
+    pub fn show(&self, player: PlayerId, objects: &[(ObjectId, Option<CardId>)]) -> ObjectList {
+        self.pending.lock().unwrap().insert(
+            player,
+            objects
+                .iter()
+                .filter_map(|(object, card)| Some((*object, (*card)?)))
+                .collect(),
+        );
+        ObjectList::paged(objects)
+    }
+
+    /// Forgets the objects of the player's prompt once it has been answered
+    pub fn clear(&self, player: PlayerId) {
+        self.pending.lock().unwrap().remove(&player);
+    }
+
+    pub fn details(&self, player: PlayerId, objects: &[ObjectId]) -> Vec<Option<CardId>> {
+        let pending = self.pending.lock().unwrap();
+        let shown = pending.get(&player);
+        objects
+            .iter()
+            .map(|o| shown.and_then(|shown| shown.get(o)).copied())
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -49,47 +206,283 @@ impl OutsideGameClient {
 impl OutsideGame for OutsideGameClient {
     async fn get_player_keeping(
         &self,
+        prompt: PromptContext,
         asked_players: Vec<PlayerId>,
     ) -> Result<Vec<PlayerId>, RpcError> {
         self.client
-            .get_player_keeping(get_context(), self.game_id, asked_players)
+            .get_player_keeping(get_context(), self.game_id, prompt, asked_players)
             .await
     }
 
     async fn get_next_player_action_from(
         &self,
+        prompt: PromptContext,
         player: PlayerId,
         player_actions: Vec<PlayerAction>,
     ) -> Result<usize, RpcError> {
         self.client
-            .get_next_player_action_from(get_context(), self.game_id, player, player_actions)
+            .get_next_player_action_from(
+                get_context(),
+                self.game_id,
+                prompt,
+                player,
+                player_actions,
+            )
             .await
     }
 
     async fn get_target_choices_from_given(
         &self,
+        prompt: PromptContext,
         player: PlayerId,
         source: ObjectId,
         name: String,
         choices: Vec<TargetId>,
         count: usize,
+        optional: bool,
     ) -> Result<Vec<usize>, RpcError> {
         self.client
             .get_target_choices_from_given(
                 get_context(),
                 self.game_id,
+                prompt,
+                player,
+                source,
+                name,
+                choices,
+                count,
+                optional,
+            )
+            .await
+    }
+
+    async fn get_multiple_target_choices(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        min: usize,
+        max: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.client
+            .get_multiple_target_choices(
+                get_context(),
+                self.game_id,
+                prompt,
+                player,
+                source,
+                name,
+                choices,
+                min,
+                max,
+            )
+            .await
+    }
+
+    async fn get_number_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        min: u64,
+        max: u64,
+    ) -> Result<u64, RpcError> {
+        self.client
+            .get_number_choice(
+                get_context(),
+                self.game_id,
+                prompt,
+                player,
+                source,
+                name,
+                min,
+                max,
+            )
+            .await
+    }
+
+    async fn get_library_search_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: ObjectList,
+    ) -> Result<Option<usize>, RpcError> {
+        self.client
+            .get_library_search_choice(
+                get_context(),
+                self.game_id,
+                prompt,
                 player,
                 source,
                 name,
                 choices,
+            )
+            .await
+    }
+
+    async fn get_library_arrangement(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        cards: ObjectList,
+        allow_bottom: bool,
+    ) -> Result<(Vec<usize>, Vec<usize>), RpcError> {
+        self.client
+            .get_library_arrangement(
+                get_context(),
+                self.game_id,
+                prompt,
+                player,
+                source,
+                name,
+                cards,
+                allow_bottom,
+            )
+            .await
+    }
+
+    async fn get_zone_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        zone: ZoneId,
+        choices: ObjectList,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.client
+            .get_zone_choice(
+                get_context(),
+                self.game_id,
+                prompt,
+                player,
+                source,
+                name,
+                zone,
+                choices,
                 count,
             )
             .await
     }
 
-    async fn get_player_passing(&self, player: PlayerId) -> Result<bool, RpcError> {
+    async fn get_ordering(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        items: ObjectList,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.client
+            .get_ordering(
+                get_context(),
+                self.game_id,
+                prompt,
+                player,
+                source,
+                name,
+                items,
+            )
+            .await
+    }
+
+    async fn get_discard_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.client
+            .get_discard_choice(get_context(), self.game_id, prompt, player, cards, count)
+            .await
+    }
+
+    async fn get_cards_to_bottom(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.client
+            .get_cards_to_bottom(get_context(), self.game_id, prompt, player, cards, count)
+            .await
+    }
+
+    async fn get_damage_assignment(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        attacker: ObjectId,
+        blockers: Vec<(ObjectId, CardId)>,
+        damage: u64,
+    ) -> Result<Vec<(usize, u64)>, RpcError> {
+        self.client
+            .get_damage_assignment(
+                get_context(),
+                self.game_id,
+                prompt,
+                player,
+                attacker,
+                blockers,
+                damage,
+            )
+            .await
+    }
+
+    async fn get_player_passing(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        self.client
+            .get_player_passing(get_context(), self.game_id, prompt, player)
+            .await
+    }
+
+    async fn get_play_first(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        self.client
+            .get_play_first(get_context(), self.game_id, prompt, player)
+            .await
+    }
+
+    async fn notify_event(&self, event: GameEvent) -> Result<(), RpcError> {
+        self.client
+            .notify_event(get_context(), self.game_id, event)
+            .await
+    }
+
+    async fn notify_error(
+        &self,
+        player: PlayerId,
+        error_description: String,
+    ) -> Result<(), RpcError> {
+        self.client
+            .notify_error(get_context(), self.game_id, player, error_description)
+            .await
+    }
+
+    async fn confirm_action(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        preview: PlayerView,
+    ) -> Result<bool, RpcError> {
         self.client
-            .get_player_passing(get_context(), self.game_id, player)
+            .confirm_action(get_context(), self.game_id, prompt, player, preview)
             .await
     }
 }
@@ -0,0 +1,389 @@
+//! Numbering prompts and remembering who was asked last
+//!
+//! [`AskedOutside`] gives every prompt its sequence number, and knows whom to tell about a refused
+//! answer, without every prompt of the engine having to keep track. It also tells the game's
+//! [`Workload`] when a prompt starts waiting, and runs the [`ChessClock`] of the asked player.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use tarpc::client::RpcError;
+use technomancy_core::card::CardId;
+use technomancy_core::event::GameEvent;
+use technomancy_core::outside::ObjectList;
+use technomancy_core::outside::PromptContext;
+use technomancy_core::view::PlayerView;
+use technomancy_core::ChessClock;
+use technomancy_core::TimeoutAction;
+
+use super::OutsideGame;
+use crate::workload::Workload;
+use crate::ObjectId;
+use crate::PlayerAction;
+use crate::PlayerId;
+use crate::TargetId;
+use crate::ZoneId;
+
+/// Wraps an [`OutsideGame`], numbers its prompts and remembers the player of the last one
+pub(crate) struct AskedOutside<'o, O> {
+    inner: &'o O,
+    asked: Mutex<Option<PlayerId>>,
+    sequence: AtomicU64,
+    workload: Workload,
+    clock: Option<ChessClock>,
+    time_spent: Mutex<HashMap<PlayerId, Duration>>,
+    conceded: Mutex<Vec<PlayerId>>,
+}
+
+impl<'o, O: OutsideGame> AskedOutside<'o, O> {
+    /// The first prompt gets the given sequence number
+    pub(crate) fn new(inner: &'o O, sequence: u64, workload: Workload) -> Self {
+        AskedOutside {
+            inner,
+            asked: Mutex::new(None),
+            sequence: AtomicU64::new(sequence),
+            workload,
+            clock: None,
+            time_spent: Default::default(),
+            conceded: Default::default(),
+        }
+    }
+
+    /// Drains the time banks of asked players, starting from the time they already spent
+    pub(crate) fn with_clock(
+        mut self,
+        clock: Option<ChessClock>,
+        time_spent: HashMap<PlayerId, Duration>,
+    ) -> Self {
+        self.clock = clock;
+        self.time_spent = Mutex::new(time_spent);
+        self
+    }
+
+    /// The time each player spent answering prompts so far
+    pub(crate) fn time_spent(&self) -> HashMap<PlayerId, Duration> {
+        self.time_spent.lock().unwrap().clone()
+    }
+
+    /// The players who ran out of time and concede because of it
+    pub(crate) fn conceded(&self) -> Vec<PlayerId> {
+        self.conceded.lock().unwrap().clone()
+    }
+
+    /// The sequence number of the next prompt
+    pub(crate) fn next_sequence(&self) -> u64 {
+        self.sequence.load(Ordering::Relaxed)
+    }
+
+    /// The player who was asked last, if a single one was
+    pub(crate) fn last_asked(&self) -> Option<PlayerId> {
+        *self.asked.lock().unwrap()
+    }
+
+    fn ask(&self, player: Option<PlayerId>, prompt: PromptContext) -> PromptContext {
+        *self.asked.lock().unwrap() = player;
+        self.workload.prompted();
+        PromptContext {
+            sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
+            ..prompt
+        }
+    }
+
+    /// Waits for the answer of the player for at most the time they have left
+    ///
+    /// Once their time is up, the prompt is answered with `default` or fails, depending on the
+    /// clock.
+    async fn timed<T>(
+        &self,
+        player: PlayerId,
+        answer: impl Future<Output = Result<T, RpcError>>,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, RpcError> {
+        let Some(clock) = self.clock else {
+            return answer.await;
+        };
+
+        let spent = self
+            .time_spent
+            .lock()
+            .unwrap()
+            .get(&player)
+            .copied()
+            .unwrap_or_default();
+        let remaining = clock.remaining(spent);
+        let answer = if remaining.is_zero() {
+            None
+        } else {
+            let started = Instant::now();
+            let answer = tokio::time::timeout(remaining, answer).await;
+            *self.time_spent.lock().unwrap().entry(player).or_default() += started.elapsed();
+            answer.ok()
+        };
+
+        match (answer, clock.on_timeout) {
+            (Some(answer), _) => answer,
+            (None, TimeoutAction::Pass) => Ok(default()),
+            (None, TimeoutAction::Concede) => {
+                let mut conceded = self.conceded.lock().unwrap();
+                if !conceded.contains(&player) {
+                    conceded.push(player);
+                }
+                Err(RpcError::DeadlineExceeded)
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<O: OutsideGame> OutsideGame for AskedOutside<'_, O> {
+    async fn get_player_keeping(
+        &self,
+        prompt: PromptContext,
+        asked_players: Vec<PlayerId>,
+    ) -> Result<Vec<PlayerId>, RpcError> {
+        let single = match asked_players.as_slice() {
+            [player] => Some(*player),
+            _ => None,
+        };
+        let prompt = self.ask(single, prompt);
+        match single {
+            // Several players answer together, nobody's clock runs
+            None => self.inner.get_player_keeping(prompt, asked_players).await,
+            Some(player) => {
+                let keeping = asked_players.clone();
+                let answer = self.inner.get_player_keeping(prompt, asked_players);
+                self.timed(player, answer, || keeping).await
+            }
+        }
+    }
+
+    async fn get_next_player_action_from(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        player_actions: Vec<PlayerAction>,
+    ) -> Result<usize, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let pass = player_actions
+            .iter()
+            .position(|a| matches!(a, PlayerAction::PassPriority))
+            .unwrap_or(0);
+        let answer = self
+            .inner
+            .get_next_player_action_from(prompt, player, player_actions);
+        self.timed(player, answer, || pass).await
+    }
+
+    async fn get_target_choices_from_given(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        count: usize,
+        optional: bool,
+    ) -> Result<Vec<usize>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let fewest = if optional {
+            0
+        } else {
+            count.min(choices.len())
+        };
+        let answer = self
+            .inner
+            .get_target_choices_from_given(prompt, player, source, name, choices, count, optional);
+        self.timed(player, answer, || (0..fewest).collect()).await
+    }
+
+    async fn get_multiple_target_choices(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        min: usize,
+        max: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let fewest = min.min(choices.len());
+        let answer = self
+            .inner
+            .get_multiple_target_choices(prompt, player, source, name, choices, min, max);
+        self.timed(player, answer, || (0..fewest).collect()).await
+    }
+
+    async fn get_number_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        min: u64,
+        max: u64,
+    ) -> Result<u64, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self
+            .inner
+            .get_number_choice(prompt, player, source, name, min, max);
+        self.timed(player, answer, || min).await
+    }
+
+    async fn get_library_search_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: ObjectList,
+    ) -> Result<Option<usize>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self
+            .inner
+            .get_library_search_choice(prompt, player, source, name, choices);
+        self.timed(player, answer, || None).await
+    }
+
+    async fn get_library_arrangement(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        cards: ObjectList,
+        allow_bottom: bool,
+    ) -> Result<(Vec<usize>, Vec<usize>), RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let count = cards.len();
+        let answer =
+            self.inner
+                .get_library_arrangement(prompt, player, source, name, cards, allow_bottom);
+        self.timed(player, answer, || ((0..count).collect(), vec![]))
+            .await
+    }
+
+    async fn get_zone_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        zone: ZoneId,
+        choices: ObjectList,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self
+            .inner
+            .get_zone_choice(prompt, player, source, name, zone, choices, count);
+        self.timed(player, answer, || (0..count).collect()).await
+    }
+
+    async fn get_ordering(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        items: ObjectList,
+    ) -> Result<Vec<usize>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let count = items.len();
+        let answer = self.inner.get_ordering(prompt, player, source, name, items);
+        self.timed(player, answer, || (0..count).collect()).await
+    }
+
+    async fn get_discard_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self.inner.get_discard_choice(prompt, player, cards, count);
+        self.timed(player, answer, || (0..count).collect()).await
+    }
+
+    async fn get_cards_to_bottom(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self.inner.get_cards_to_bottom(prompt, player, cards, count);
+        self.timed(player, answer, || (0..count).collect()).await
+    }
+
+    async fn get_damage_assignment(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        attacker: ObjectId,
+        blockers: Vec<(ObjectId, CardId)>,
+        damage: u64,
+    ) -> Result<Vec<(usize, u64)>, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        // All damage goes to the first blocker
+        let default = if blockers.is_empty() {
+            vec![]
+        } else {
+            vec![(0, damage)]
+        };
+        let answer = self
+            .inner
+            .get_damage_assignment(prompt, player, attacker, blockers, damage);
+        self.timed(player, answer, || default).await
+    }
+
+    async fn get_player_passing(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self.inner.get_player_passing(prompt, player);
+        self.timed(player, answer, || true).await
+    }
+
+    async fn get_play_first(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self.inner.get_play_first(prompt, player);
+        self.timed(player, answer, || true).await
+    }
+
+    async fn notify_event(&self, event: GameEvent) -> Result<(), RpcError> {
+        self.inner.notify_event(event).await
+    }
+
+    async fn notify_error(
+        &self,
+        player: PlayerId,
+        error_description: String,
+    ) -> Result<(), RpcError> {
+        self.inner.notify_error(player, error_description).await
+    }
+
+    async fn confirm_action(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        preview: PlayerView,
+    ) -> Result<bool, RpcError> {
+        let prompt = self.ask(Some(player), prompt);
+        let answer = self.inner.confirm_action(prompt, player, preview);
+        self.timed(player, answer, || true).await
+    }
+}
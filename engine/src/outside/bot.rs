@@ -0,0 +1,208 @@
+//! Players that answer on their own
+//!
+//! [`BotOutside`] lets the engine play games without anyone at the table, for example to check
+//! that new cards can be played at all.
+
+use tarpc::client::RpcError;
+use technomancy_core::card::CardId;
+use technomancy_core::event::GameEvent;
+use technomancy_core::outside::ObjectList;
+use technomancy_core::outside::PromptContext;
+use technomancy_core::view::PlayerView;
+
+use super::OutsideGame;
+use crate::ObjectId;
+use crate::PlayerAction;
+use crate::PlayerId;
+use crate::TargetId;
+use crate::ZoneId;
+
+/// Answers every prompt of every player right away, mostly with the first choices it is given
+///
+/// Cards are played and agents attack whenever possible, priority is only passed once nothing
+/// else can be done. The bot never concedes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BotOutside;
+
+#[async_trait::async_trait]
+impl OutsideGame for BotOutside {
+    async fn get_player_keeping(
+        &self,
+        _prompt: PromptContext,
+        asked_players: Vec<PlayerId>,
+    ) -> Result<Vec<PlayerId>, RpcError> {
+        Ok(asked_players)
+    }
+
+    async fn get_next_player_action_from(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        player_actions: Vec<PlayerAction>,
+    ) -> Result<usize, RpcError> {
+        let find = |wanted: fn(&PlayerAction) -> bool| player_actions.iter().position(wanted);
+        Ok(find(|a| matches!(a, PlayerAction::PlayCard { .. }))
+            .or_else(|| find(|a| matches!(a, PlayerAction::Attack { .. })))
+            .or_else(|| find(|a| matches!(a, PlayerAction::PassPriority)))
+            .or_else(|| find(|a| !matches!(a, PlayerAction::Concede)))
+            .unwrap_or(0))
+    }
+
+    async fn get_target_choices_from_given(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _source: ObjectId,
+        _name: String,
+        choices: Vec<TargetId>,
+        count: usize,
+        _optional: bool,
+    ) -> Result<Vec<usize>, RpcError> {
+        Ok((0..count.min(choices.len())).collect())
+    }
+
+    async fn get_multiple_target_choices(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _source: ObjectId,
+        _name: String,
+        choices: Vec<TargetId>,
+        min: usize,
+        _max: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        Ok((0..min.min(choices.len())).collect())
+    }
+
+    async fn get_number_choice(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _source: ObjectId,
+        _name: String,
+        min: u64,
+        _max: u64,
+    ) -> Result<u64, RpcError> {
+        Ok(min)
+    }
+
+    async fn get_library_search_choice(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _source: ObjectId,
+        _name: String,
+        choices: ObjectList,
+    ) -> Result<Option<usize>, RpcError> {
+        Ok((!choices.is_empty()).then_some(0))
+    }
+
+    async fn get_library_arrangement(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _source: ObjectId,
+        _name: String,
+        cards: ObjectList,
+        _allow_bottom: bool,
+    ) -> Result<(Vec<usize>, Vec<usize>), RpcError> {
+        Ok(((0..cards.len()).collect(), vec![]))
+    }
+
+    async fn get_zone_choice(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _source: ObjectId,
+        _name: String,
+        _zone: ZoneId,
+        choices: ObjectList,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        Ok((0..count.min(choices.len())).collect())
+    }
+
+    async fn get_ordering(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _source: ObjectId,
+        _name: String,
+        items: ObjectList,
+    ) -> Result<Vec<usize>, RpcError> {
+        Ok((0..items.len()).collect())
+    }
+
+    async fn get_discard_choice(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        Ok((0..count.min(cards.len())).collect())
+    }
+
+    async fn get_cards_to_bottom(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        Ok((0..count.min(cards.len())).collect())
+    }
+
+    /// Assigns all of the damage to the first blocker
+    async fn get_damage_assignment(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _attacker: ObjectId,
+        blockers: Vec<(ObjectId, CardId)>,
+        damage: u64,
+    ) -> Result<Vec<(usize, u64)>, RpcError> {
+        Ok(if blockers.is_empty() {
+            vec![]
+        } else {
+            vec![(0, damage)]
+        })
+    }
+
+    async fn get_player_passing(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        Ok(true)
+    }
+
+    async fn get_play_first(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        Ok(true)
+    }
+
+    async fn notify_event(&self, _event: GameEvent) -> Result<(), RpcError> {
+        Ok(())
+    }
+
+    async fn notify_error(
+        &self,
+        _player: PlayerId,
+        _error_description: String,
+    ) -> Result<(), RpcError> {
+        Ok(())
+    }
+
+    async fn confirm_action(
+        &self,
+        _prompt: PromptContext,
+        _player: PlayerId,
+        _preview: PlayerView,
+    ) -> Result<bool, RpcError> {
+        Ok(true)
+    }
+}
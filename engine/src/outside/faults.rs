@@ -0,0 +1,373 @@
+//! Bad network conditions for testing
+//!
+//! [`FaultyOutside`] wraps any [`OutsideGame`] and delays, drops or garbles its answers, so that
+//! tests can check that games keep going when players are badly connected.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+use tarpc::client::RpcError;
+use technomancy_core::card::CardId;
+use technomancy_core::event::GameEvent;
+use technomancy_core::outside::ObjectList;
+use technomancy_core::outside::PromptContext;
+use technomancy_core::view::PlayerView;
+
+use super::OutsideGame;
+use crate::ObjectId;
+use crate::PlayerAction;
+use crate::PlayerId;
+use crate::TargetId;
+use crate::ZoneId;
+
+/// How a [`FaultyOutside`] misbehaves
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Faults {
+    /// How long every request takes before it is answered
+    pub latency: Duration,
+    /// The chance of a request never being answered, between 0 and 1
+    ///
+    /// The request then fails as if its deadline was exceeded.
+    pub drop_rate: f64,
+    /// The chance of a choice being answered with something out of range, between 0 and 1
+    ///
+    /// Yes or no questions and notifications are never malformed.
+    pub malformed_rate: f64,
+}
+
+/// What happens to a single request
+enum Fault {
+    None,
+    Dropped,
+    Malformed,
+}
+
+/// Wraps an [`OutsideGame`] and injects [`Faults`] into its answers
+#[derive(Debug)]
+pub struct FaultyOutside<O> {
+    inner: O,
+    faults: Mutex<Faults>,
+    rng: Mutex<Xoshiro256StarStar>,
+}
+
+impl<O: OutsideGame> FaultyOutside<O> {
+    pub fn new(inner: O, faults: Faults, seed: u64) -> Self {
+        FaultyOutside {
+            inner,
+            faults: Mutex::new(faults),
+            rng: Mutex::new(Xoshiro256StarStar::seed_from_u64(seed)),
+        }
+    }
+
+    /// Changes the faults of all following requests
+    pub fn set_faults(&self, faults: Faults) {
+        *self.faults.lock().unwrap() = faults;
+    }
+
+    /// Waits out the latency, then decides what happens to the request
+    async fn fault(&self) -> Fault {
+        let faults = self.faults.lock().unwrap().clone();
+        tokio::time::sleep(faults.latency).await;
+
+        let mut rng = self.rng.lock().unwrap();
+        if rng.gen_bool(faults.drop_rate) {
+            Fault::Dropped
+        } else if rng.gen_bool(faults.malformed_rate) {
+            Fault::Malformed
+        } else {
+            Fault::None
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<O: OutsideGame + Send> OutsideGame for FaultyOutside<O> {
+    async fn get_player_keeping(
+        &self,
+        prompt: PromptContext,
+        asked_players: Vec<PlayerId>,
+    ) -> Result<Vec<PlayerId>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            // Nobody in the game has this id
+            Fault::Malformed => Ok(vec![PlayerId::new()]),
+            Fault::None => self.inner.get_player_keeping(prompt, asked_players).await,
+        }
+    }
+
+    async fn get_next_player_action_from(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        player_actions: Vec<PlayerAction>,
+    ) -> Result<usize, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(player_actions.len()),
+            Fault::None => {
+                self.inner
+                    .get_next_player_action_from(prompt, player, player_actions)
+                    .await
+            }
+        }
+    }
+
+    async fn get_target_choices_from_given(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        count: usize,
+        optional: bool,
+    ) -> Result<Vec<usize>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(vec![choices.len(); count]),
+            Fault::None => {
+                self.inner
+                    .get_target_choices_from_given(
+                        prompt, player, source, name, choices, count, optional,
+                    )
+                    .await
+            }
+        }
+    }
+
+    async fn get_multiple_target_choices(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        min: usize,
+        max: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            // The same target twice
+            Fault::Malformed => Ok(vec![0; max.max(2)]),
+            Fault::None => {
+                self.inner
+                    .get_multiple_target_choices(prompt, player, source, name, choices, min, max)
+                    .await
+            }
+        }
+    }
+
+    async fn get_number_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        min: u64,
+        max: u64,
+    ) -> Result<u64, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(max.checked_add(1).unwrap_or(min.wrapping_sub(1))),
+            Fault::None => {
+                self.inner
+                    .get_number_choice(prompt, player, source, name, min, max)
+                    .await
+            }
+        }
+    }
+
+    async fn get_library_search_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: ObjectList,
+    ) -> Result<Option<usize>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(Some(choices.len())),
+            Fault::None => {
+                self.inner
+                    .get_library_search_choice(prompt, player, source, name, choices)
+                    .await
+            }
+        }
+    }
+
+    async fn get_library_arrangement(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        cards: ObjectList,
+        allow_bottom: bool,
+    ) -> Result<(Vec<usize>, Vec<usize>), RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok((vec![cards.len()], vec![])),
+            Fault::None => {
+                self.inner
+                    .get_library_arrangement(prompt, player, source, name, cards, allow_bottom)
+                    .await
+            }
+        }
+    }
+
+    async fn get_zone_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        zone: ZoneId,
+        choices: ObjectList,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(vec![choices.len(); count]),
+            Fault::None => {
+                self.inner
+                    .get_zone_choice(prompt, player, source, name, zone, choices, count)
+                    .await
+            }
+        }
+    }
+
+    async fn get_ordering(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        items: ObjectList,
+    ) -> Result<Vec<usize>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            // The first item twice
+            Fault::Malformed => Ok(vec![0; items.len().max(2)]),
+            Fault::None => {
+                self.inner
+                    .get_ordering(prompt, player, source, name, items)
+                    .await
+            }
+        }
+    }
+
+    async fn get_discard_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(vec![cards.len(); count]),
+            Fault::None => {
+                self.inner
+                    .get_discard_choice(prompt, player, cards, count)
+                    .await
+            }
+        }
+    }
+
+    async fn get_cards_to_bottom(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(vec![cards.len(); count]),
+            Fault::None => {
+                self.inner
+                    .get_cards_to_bottom(prompt, player, cards, count)
+                    .await
+            }
+        }
+    }
+
+    async fn get_damage_assignment(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        attacker: ObjectId,
+        blockers: Vec<(ObjectId, CardId)>,
+        damage: u64,
+    ) -> Result<Vec<(usize, u64)>, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed => Ok(vec![(blockers.len(), damage)]),
+            Fault::None => {
+                self.inner
+                    .get_damage_assignment(prompt, player, attacker, blockers, damage)
+                    .await
+            }
+        }
+    }
+
+    async fn get_player_passing(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed | Fault::None => self.inner.get_player_passing(prompt, player).await,
+        }
+    }
+
+    async fn get_play_first(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed | Fault::None => self.inner.get_play_first(prompt, player).await,
+        }
+    }
+
+    async fn notify_event(&self, event: GameEvent) -> Result<(), RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed | Fault::None => self.inner.notify_event(event).await,
+        }
+    }
+
+    async fn notify_error(
+        &self,
+        player: PlayerId,
+        error_description: String,
+    ) -> Result<(), RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed | Fault::None => {
+                self.inner.notify_error(player, error_description).await
+            }
+        }
+    }
+
+    async fn confirm_action(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        preview: PlayerView,
+    ) -> Result<bool, RpcError> {
+        match self.fault().await {
+            Fault::Dropped => Err(RpcError::DeadlineExceeded),
+            Fault::Malformed | Fault::None => {
+                self.inner.confirm_action(prompt, player, preview).await
+            }
+        }
+    }
+}
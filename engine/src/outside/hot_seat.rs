@@ -0,0 +1,280 @@
+//! Several players sharing a single client
+//!
+//! [`HotSeatOutside`] lets one terminal or browser session play all seats of a game, it tells
+//! the client whenever the next prompt is meant for someone else.
+
+use std::sync::Mutex;
+
+use tarpc::client::RpcError;
+use technomancy_core::card::CardId;
+use technomancy_core::event::GameEvent;
+use technomancy_core::outside::ObjectList;
+use technomancy_core::outside::PromptContext;
+use technomancy_core::view::PlayerView;
+
+use super::OutsideGame;
+use crate::ObjectId;
+use crate::PlayerAction;
+use crate::PlayerId;
+use crate::TargetId;
+use crate::ZoneId;
+
+/// Wraps the [`OutsideGame`] of a single client and switches it between the given players
+///
+/// Before a prompt for another player than the current one, a [`GameEvent::SeatSwitched`] is
+/// sent. Players that do not sit at the client are asked without switching.
+#[derive(Debug)]
+pub struct HotSeatOutside<O> {
+    inner: O,
+    players: Vec<PlayerId>,
+    seat: Mutex<Option<PlayerId>>,
+}
+
+impl<O: OutsideGame> HotSeatOutside<O> {
+    pub fn new(inner: O, players: Vec<PlayerId>) -> Self {
+        HotSeatOutside {
+            inner,
+            players,
+            seat: Mutex::new(None),
+        }
+    }
+
+    /// The player currently sitting at the client, if any was asked something yet
+    pub fn seat(&self) -> Option<PlayerId> {
+        *self.seat.lock().unwrap()
+    }
+
+    async fn switch_to(&self, player: PlayerId) -> Result<(), RpcError> {
+        if !self.players.contains(&player) || self.seat() == Some(player) {
+            return Ok(());
+        }
+
+        self.inner
+            .notify_event(GameEvent::SeatSwitched { player })
+            .await?;
+        *self.seat.lock().unwrap() = Some(player);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<O: OutsideGame + Send> OutsideGame for HotSeatOutside<O> {
+    /// Asks every player on their own, so that nobody sees the others' hands
+    async fn get_player_keeping(
+        &self,
+        prompt: PromptContext,
+        asked_players: Vec<PlayerId>,
+    ) -> Result<Vec<PlayerId>, RpcError> {
+        let mut keeping = vec![];
+        for player in asked_players {
+            self.switch_to(player).await?;
+            keeping.extend(
+                self.inner
+                    .get_player_keeping(prompt, vec![player])
+                    .await?
+                    .into_iter()
+                    .filter(|p| *p == player),
+            );
+        }
+        Ok(keeping)
+    }
+
+    async fn get_next_player_action_from(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        player_actions: Vec<PlayerAction>,
+    ) -> Result<usize, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_next_player_action_from(prompt, player, player_actions)
+            .await
+    }
+
+    async fn get_target_choices_from_given(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        count: usize,
+        optional: bool,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_target_choices_from_given(prompt, player, source, name, choices, count, optional)
+            .await
+    }
+
+    async fn get_multiple_target_choices(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: Vec<TargetId>,
+        min: usize,
+        max: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_multiple_target_choices(prompt, player, source, name, choices, min, max)
+            .await
+    }
+
+    async fn get_number_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        min: u64,
+        max: u64,
+    ) -> Result<u64, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_number_choice(prompt, player, source, name, min, max)
+            .await
+    }
+
+    async fn get_library_search_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        choices: ObjectList,
+    ) -> Result<Option<usize>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_library_search_choice(prompt, player, source, name, choices)
+            .await
+    }
+
+    async fn get_library_arrangement(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        cards: ObjectList,
+        allow_bottom: bool,
+    ) -> Result<(Vec<usize>, Vec<usize>), RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_library_arrangement(prompt, player, source, name, cards, allow_bottom)
+            .await
+    }
+
+    async fn get_zone_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        zone: ZoneId,
+        choices: ObjectList,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_zone_choice(prompt, player, source, name, zone, choices, count)
+            .await
+    }
+
+    async fn get_ordering(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        source: ObjectId,
+        name: String,
+        items: ObjectList,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_ordering(prompt, player, source, name, items)
+            .await
+    }
+
+    async fn get_discard_choice(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_discard_choice(prompt, player, cards, count)
+            .await
+    }
+
+    async fn get_cards_to_bottom(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        cards: Vec<(ObjectId, CardId)>,
+        count: usize,
+    ) -> Result<Vec<usize>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_cards_to_bottom(prompt, player, cards, count)
+            .await
+    }
+
+    async fn get_damage_assignment(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        attacker: ObjectId,
+        blockers: Vec<(ObjectId, CardId)>,
+        damage: u64,
+    ) -> Result<Vec<(usize, u64)>, RpcError> {
+        self.switch_to(player).await?;
+        self.inner
+            .get_damage_assignment(prompt, player, attacker, blockers, damage)
+            .await
+    }
+
+    async fn get_player_passing(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        self.switch_to(player).await?;
+        self.inner.get_player_passing(prompt, player).await
+    }
+
+    async fn get_play_first(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+    ) -> Result<bool, RpcError> {
+        self.switch_to(player).await?;
+        self.inner.get_play_first(prompt, player).await
+    }
+
+    async fn notify_event(&self, event: GameEvent) -> Result<(), RpcError> {
+        self.inner.notify_event(event).await
+    }
+
+    async fn notify_error(
+        &self,
+        player: PlayerId,
+        error_description: String,
+    ) -> Result<(), RpcError> {
+        self.switch_to(player).await?;
+        self.inner.notify_error(player, error_description).await
+    }
+
+    async fn confirm_action(
+        &self,
+        prompt: PromptContext,
+        player: PlayerId,
+        preview: PlayerView,
+    ) -> Result<bool, RpcError> {
+        self.switch_to(player).await?;
+        self.inner.confirm_action(prompt, player, preview).await
+    }
+}
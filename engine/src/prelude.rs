@@ -0,0 +1,36 @@
+//! Everything needed to embed the engine
+//!
+//! ```ignore
+//! use technomancy_engine::prelude::*;
+//!
+//! let (mut game, outside) = EngineBuilder::new()
+//!     .cards(cards)
+//!     .build_with_outside(players, my_outside);
+//!
+//! while game.outcome().is_none() {
+//!     game.run(&outside).await?;
+//! }
+//! ```
+
+pub use technomancy_core::card::Card;
+pub use technomancy_core::card::CardId;
+pub use technomancy_core::event::GameEvent;
+pub use technomancy_core::outside::Outside;
+pub use technomancy_core::outside::PromptContext;
+pub use technomancy_core::view::PlayerView;
+pub use technomancy_core::FormatRules;
+pub use technomancy_core::GameError;
+pub use technomancy_core::GameId;
+pub use technomancy_core::GameOutcome;
+pub use technomancy_core::GameResult;
+pub use technomancy_core::ObjectId;
+pub use technomancy_core::Player;
+pub use technomancy_core::PlayerAction;
+pub use technomancy_core::PlayerId;
+pub use technomancy_core::SideRule;
+pub use technomancy_core::TargetId;
+
+pub use crate::builder::spawn_outside;
+pub use crate::builder::EngineBuilder;
+pub use crate::outside::OutsideGameClient;
+pub use crate::GameImplV1;
@@ -0,0 +1,82 @@
+//! The modules behind the optional [`SideRule`]s of a game
+//!
+//! Each module may add atoms when the game or a turn starts, and may end the game on its own
+//! terms. They are consulted in the order the side rules are configured.
+
+use technomancy_core::card::ScripKind;
+use technomancy_core::GameAtom;
+use technomancy_core::GameOutcome;
+use technomancy_core::GameState;
+use technomancy_core::PlayerId;
+use technomancy_core::SideRule;
+use technomancy_core::ZoneId;
+
+pub trait SideRuleModule: Send + Sync {
+    /// Atoms applied together with starting the game
+    fn on_game_start(&self, _state: &GameState) -> Vec<GameAtom> {
+        vec![]
+    }
+
+    /// Atoms applied when the given player's turn starts
+    fn on_turn_start(&self, _state: &GameState, _player: PlayerId) -> Vec<GameAtom> {
+        vec![]
+    }
+
+    /// Ends the running game if this rule decides it, checked after every step
+    fn outcome(&self, _state: &GameState) -> Option<GameOutcome> {
+        None
+    }
+}
+
+/// The module implementing the given side rule
+pub fn module(rule: &SideRule) -> Box<dyn SideRuleModule> {
+    match rule {
+        SideRule::Wager { cards } => Box::new(Wager { cards: *cards }),
+        SideRule::Bounty { kind, amount } => Box::new(Bounty {
+            kind: *kind,
+            amount: *amount,
+        }),
+    }
+}
+
+struct Wager {
+    cards: usize,
+}
+
+impl SideRuleModule for Wager {
+    fn on_game_start(&self, state: &GameState) -> Vec<GameAtom> {
+        state
+            .active_player_order
+            .iter()
+            .flat_map(|player| {
+                let library = ZoneId::Library(*player);
+                // The end of the library is its top
+                state
+                    .zones
+                    .get(&library)
+                    .into_iter()
+                    .flat_map(|zone| zone.objects.iter().rev().take(self.cards))
+                    .map(move |o| GameAtom::MoveObject {
+                        object: o.id,
+                        from: library,
+                        to: ZoneId::Exile,
+                    })
+            })
+            .collect()
+    }
+}
+
+struct Bounty {
+    kind: ScripKind,
+    amount: u64,
+}
+
+impl SideRuleModule for Bounty {
+    fn outcome(&self, state: &GameState) -> Option<GameOutcome> {
+        state
+            .active_player_order
+            .iter()
+            .find(|player| state.scrip_of(**player, self.kind) >= self.amount)
+            .map(|winner| GameOutcome::Won { winner: *winner })
+    }
+}
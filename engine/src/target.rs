@@ -0,0 +1,145 @@
+use technomancy_core::card::BaseCardKindTag;
+use technomancy_core::card::CardBehaviour;
+use technomancy_core::card::CardId;
+use technomancy_core::card::Keyword;
+use technomancy_core::effect::TargetRestriction;
+use technomancy_core::effect::TargetZone;
+use technomancy_core::Game;
+use technomancy_core::ObjectId;
+use technomancy_core::PlayerId;
+use technomancy_core::TargetId;
+use technomancy_core::ZoneId;
+
+fn default_restriction() -> TargetRestriction {
+    TargetRestriction::Any(vec![
+        TargetRestriction::Player,
+        TargetRestriction::All(vec![
+            TargetRestriction::Zone(TargetZone::Battlefield),
+            TargetRestriction::CardKind(BaseCardKindTag::Agent),
+        ]),
+    ])
+}
+
+/// All targets that `chooser` may pick given the restriction
+///
+/// Without any restriction only players and agents on the battlefield can be targeted. Untargetable
+/// objects and eliminated players are never offered.
+pub fn possible_targets(
+    game: &Game,
+    chooser: PlayerId,
+    restriction: Option<&TargetRestriction>,
+) -> Vec<TargetId> {
+    let default = default_restriction();
+    let restriction = restriction.unwrap_or(&default);
+    let state = game.latest_gamestate();
+
+    let objects = [ZoneId::Battlefield, ZoneId::Stack]
+        .into_iter()
+        .filter_map(|zone| state.zones.get(&zone))
+        .flat_map(|zone| zone.objects.iter())
+        .map(|o| TargetId::Object(o.id));
+
+    game.players
+        .keys()
+        .filter(|p| !state.is_eliminated(**p))
+        .map(|p| TargetId::Player(*p))
+        .chain(objects)
+        .filter(|target| {
+            !behaviour_of(game, *target).is_some_and(|b| b.has_keyword(Keyword::Untargetable))
+        })
+        .filter(|target| is_legal_target(game, chooser, restriction, *target))
+        .collect()
+}
+
+/// All cards in the library of `chooser` that match the restriction, in library order
+///
+/// Without any restriction every card can be found.
+pub fn searchable_cards(
+    game: &Game,
+    chooser: PlayerId,
+    restriction: Option<&TargetRestriction>,
+) -> Vec<(ObjectId, CardId)> {
+    game.latest_gamestate()
+        .zones
+        .get(&game.library_of(chooser))
+        .into_iter()
+        .flat_map(|zone| zone.objects.iter())
+        .filter(|o| {
+            restriction.map_or(true, |r| {
+                is_legal_target(game, chooser, r, TargetId::Object(o.id))
+            })
+        })
+        .filter_map(|o| Some((o.id, o.underlying_card?)))
+        .collect()
+}
+
+pub fn is_legal_target(
+    game: &Game,
+    chooser: PlayerId,
+    restriction: &TargetRestriction,
+    target: TargetId,
+) -> bool {
+    match restriction {
+        TargetRestriction::Player => matches!(target, TargetId::Player(_)),
+        TargetRestriction::Zone(zone) => {
+            let zone = match zone {
+                TargetZone::Battlefield => ZoneId::Battlefield,
+                TargetZone::Stack => ZoneId::Stack,
+            };
+            match target {
+                TargetId::Object(object) => game
+                    .latest_gamestate()
+                    .get_object_from_zone(zone, object)
+                    .is_some(),
+                TargetId::Player(_) => false,
+            }
+        }
+        TargetRestriction::CardKind(tag) => {
+            behaviour_of(game, target).is_some_and(|behaviour| behaviour.is_kind(*tag))
+        }
+        TargetRestriction::SubKind(subkind) => {
+            behaviour_of(game, target).is_some_and(|behaviour| behaviour.has_subkind(subkind))
+        }
+        TargetRestriction::Controller(relative) => {
+            let player = match target {
+                TargetId::Player(player) => Some(player),
+                TargetId::Object(object) => game.get_controller_of(object),
+            };
+
+            player.is_some_and(|player| relative.holds(game, player, chooser))
+        }
+        TargetRestriction::Power { min, max } => {
+            let TargetId::Object(object) = target else {
+                return false;
+            };
+            game.latest_gamestate()
+                .agent_stats(game, object)
+                .is_some_and(|(power, _)| {
+                    min.map_or(true, |min| power >= min) && max.map_or(true, |max| power <= max)
+                })
+        }
+        TargetRestriction::All(restrictions) => restrictions
+            .iter()
+            .all(|r| is_legal_target(game, chooser, r, target)),
+        TargetRestriction::Any(restrictions) => restrictions
+            .iter()
+            .any(|r| is_legal_target(game, chooser, r, target)),
+        TargetRestriction::Not(restriction) => !is_legal_target(game, chooser, restriction, target),
+    }
+}
+
+/// The active behaviour of the targeted object, the played half of split cards on the stack
+fn behaviour_of(game: &Game, target: TargetId) -> Option<&CardBehaviour> {
+    let TargetId::Object(object) = target else {
+        return None;
+    };
+
+    let obj = game
+        .latest_gamestate()
+        .zones
+        .values()
+        .flat_map(|zone| zone.objects.iter())
+        .find(|o| o.id == object)?;
+
+    obj.behaviour(&game.cards)
+}
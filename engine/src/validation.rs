@@ -0,0 +1,91 @@
+//! Checking atoms before they are applied, see [`check_atom`]
+
+use technomancy_core::Game;
+use technomancy_core::GameAtom;
+use technomancy_core::GameError;
+use technomancy_core::GameState;
+use technomancy_core::PlayerId;
+use technomancy_core::TemporaryModifier;
+use technomancy_core::ZoneId;
+
+/// The zones applying the atom to `state` reads or changes
+fn zones_used(game: &Game, state: &GameState, atom: &GameAtom) -> Vec<ZoneId> {
+    match atom {
+        GameAtom::PutTriggerOnStack { .. }
+        | GameAtom::ChooseTriggerInfo { .. }
+        | GameAtom::RemoveTrigger { .. }
+        | GameAtom::CounterObject { .. }
+        | GameAtom::PopStack => vec![ZoneId::Stack],
+        GameAtom::TransformObject { .. }
+        | GameAtom::AddCounters { .. }
+        | GameAtom::Exhaust { .. }
+        | GameAtom::Ready { .. }
+        | GameAtom::AddTemporaryEffect {
+            modifier: TemporaryModifier::GainControl { .. },
+            ..
+        } => vec![ZoneId::Battlefield],
+        GameAtom::EliminatePlayer { .. } => {
+            vec![ZoneId::Battlefield, ZoneId::Stack, ZoneId::Exile]
+        }
+        GameAtom::PutOnLibraryBottom { player, .. }
+        | GameAtom::ShuffleHandIntoLibrary { player } => {
+            vec![ZoneId::Hand(*player), game.library_of(*player)]
+        }
+        GameAtom::DrawCards { player, .. } => {
+            vec![ZoneId::Hand(*player), state.draw_zone_of(game, *player)]
+        }
+        GameAtom::Mill { player, .. } => {
+            vec![ZoneId::Discard(*player), game.library_of(*player)]
+        }
+        GameAtom::DiscardCards { player, .. } => {
+            vec![ZoneId::Hand(*player), ZoneId::Discard(*player)]
+        }
+        GameAtom::ShuffleLibrary { player } | GameAtom::ArrangeLibrary { player, .. } => {
+            vec![game.library_of(*player)]
+        }
+        GameAtom::PlayerPlayCard { from, .. } => vec![*from, ZoneId::Stack],
+        GameAtom::MoveObject { from, to, .. } => vec![*from, *to],
+        _ => vec![],
+    }
+}
+
+/// The player the zone belongs to, if any
+fn owner_of(zone: ZoneId) -> Option<PlayerId> {
+    match zone {
+        ZoneId::Hand(player)
+        | ZoneId::Library(player)
+        | ZoneId::Discard(player)
+        | ZoneId::Emblems(player) => Some(player),
+        _ => None,
+    }
+}
+
+/// Checks that the atom can be applied to the working state without panicking
+///
+/// Effects produce atoms from what they saw, so their batches may refer to players that left or
+/// zones that do not exist. Whether the objects of the atom are where it expects them is checked
+/// while it is applied.
+pub fn check_atom(game: &Game, state: &GameState, atom: &GameAtom) -> Result<(), GameError> {
+    let zones = zones_used(game, state, atom);
+    for zone in &zones {
+        if state.zones.contains_key(zone) {
+            continue;
+        }
+        return Err(match owner_of(*zone) {
+            Some(player) if !game.players.contains_key(&player) => {
+                GameError::PlayerNotInGame { player }
+            }
+            _ => GameError::ZoneNotFound { zone: *zone },
+        });
+    }
+
+    // Moving an object to where it is already is refused when the object is looked for
+    if !matches!(atom, GameAtom::MoveObject { .. }) {
+        if let &[from, to] = zones.as_slice() {
+            if from == to {
+                return Err(GameError::SameZone { zone: from });
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! How much a running game weighs on the engine
+//!
+//! [`Workload`] is shared between a game and whoever runs it, so that
+//! [`Meta::diagnostics`](technomancy_core::meta::Meta::diagnostics) can report on games while they
+//! wait for their players.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Instant;
+
+use technomancy_core::meta::GameDiagnostics;
+use technomancy_core::Game;
+use technomancy_core::GameAtom;
+use technomancy_core::GameId;
+use technomancy_core::GameObject;
+use technomancy_core::GameState;
+
+#[derive(Debug)]
+struct Measured {
+    states: usize,
+    memory_estimate: usize,
+    last_activity: Instant,
+    prompted_at: Option<Instant>,
+    /// The objects of every zone counted so far by their address, until no state holds them
+    ///
+    /// Game states share the objects of zones they did not change, each of them is counted once.
+    zones: HashMap<usize, (Weak<Vec<GameObject>>, usize)>,
+    zone_bytes: usize,
+    atoms: usize,
+    /// The latest state index, the number of history entries and of snapshots counted so far
+    ///
+    /// `None` counts the whole game again at the next record.
+    counted: Option<(usize, usize, usize)>,
+}
+
+/// The last known workload of a game, cheap to clone and shared between clones
+#[derive(Debug, Clone)]
+pub struct Workload {
+    measured: Arc<Mutex<Measured>>,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Workload {
+            measured: Arc::new(Mutex::new(Measured {
+                states: 0,
+                memory_estimate: 0,
+                last_activity: Instant::now(),
+                prompted_at: None,
+                zones: HashMap::new(),
+                zone_bytes: 0,
+                atoms: 0,
+                counted: None,
+            })),
+        }
+    }
+}
+
+impl Workload {
+    /// Measures the game after it made progress, any pending prompt has been answered by now
+    ///
+    /// Only the states and atoms added since the last record are looked at.
+    pub fn record(&self, game: &Game) {
+        let mut measured = self.measured.lock().unwrap();
+        measured.count(game);
+        measured.states = game.game_states.len() + game.snapshots.len();
+        measured.memory_estimate = measured.states * std::mem::size_of::<GameState>()
+            + measured.zone_bytes
+            + measured.atoms * std::mem::size_of::<GameAtom>();
+        measured.last_activity = Instant::now();
+        measured.prompted_at = None;
+    }
+
+    /// Counts the whole game again at the next record, for when states or atoms were dropped
+    pub fn recount(&self) {
+        self.measured.lock().unwrap().counted = None;
+    }
+
+    /// Notes that a player has been asked something, replacing an earlier prompt of the same run
+    pub fn prompted(&self) {
+        let now = Instant::now();
+        let mut measured = self.measured.lock().unwrap();
+        measured.last_activity = now;
+        measured.prompted_at = Some(now);
+    }
+
+    pub fn diagnostics(&self, game: GameId) -> GameDiagnostics {
+        let measured = self.measured.lock().unwrap();
+        GameDiagnostics {
+            game,
+            states: measured.states,
+            memory_estimate: measured.memory_estimate,
+            pending_prompt_age: measured.prompted_at.map(|at| at.elapsed()),
+            idle_for: measured.last_activity.elapsed(),
+        }
+    }
+}
+
+impl Measured {
+    /// Adds the zones and atoms that are new since the last count
+    fn count(&mut self, game: &Game) {
+        let latest = game.latest_index();
+        let (from_state, from_history, from_snapshot) = match self.counted {
+            Some((state, history, snapshots))
+                if state <= latest
+                    && history <= game.history.len()
+                    && snapshots <= game.snapshots.len() =>
+            {
+                (state + 1, history, snapshots)
+            }
+            _ => {
+                self.zones.clear();
+                self.zone_bytes = 0;
+                self.atoms = 0;
+                (0, 0, 0)
+            }
+        };
+
+        let states = game
+            .game_states
+            .iter()
+            .enumerate()
+            .filter(|(offset, _)| game.first_state + offset >= from_state)
+            .map(|(_, state)| state)
+            .chain(
+                game.snapshots[from_snapshot..]
+                    .iter()
+                    .map(|(_, state)| state),
+            );
+        for state in states {
+            for zone in state.zones.values() {
+                let objects = &zone.objects.0;
+                self.zones
+                    .entry(Arc::as_ptr(objects) as usize)
+                    .or_insert_with(|| {
+                        let bytes = objects.len() * std::mem::size_of::<GameObject>();
+                        self.zone_bytes += bytes;
+                        (Arc::downgrade(objects), bytes)
+                    });
+            }
+        }
+
+        // Zones of compacted or rolled back states are gone once nothing holds them anymore
+        let zone_bytes = &mut self.zone_bytes;
+        self.zones.retain(|_, (objects, bytes)| {
+            let held = objects.strong_count() > 0;
+            if !held {
+                *zone_bytes -= *bytes;
+            }
+            held
+        });
+
+        self.atoms += game.history[from_history..]
+            .iter()
+            .map(|(_, atoms)| atoms.len())
+            .sum::<usize>();
+        self.counted = Some((latest, game.history.len(), game.snapshots.len()));
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use camino::Utf8Path;
+
+use axum::body::Bytes;
+use axum::Json;
+use serde::Deserialize;
+use serde::Serialize;
+use technomancy_core::card::AgentPower;
+use technomancy_core::card::AgentToughness;
+use technomancy_core::card::BaseCardKind;
+use technomancy_core::card::Card;
+use technomancy_core::card::CardBehaviour;
+use technomancy_core::card::CardId;
+use technomancy_core::card::CardKind;
+use technomancy_core::card::CardLayout;
+use technomancy_core::card::Cost;
+use technomancy_core::card::Keyword;
+use technomancy_core::card::SubKind;
+use technomancy_core::card::SubKindRegistry;
+use technomancy_core::GameResult;
+use technomancy_core::Player;
+use technomancy_core::PlayerId;
+use technomancy_engine::builder::EngineBuilder;
+use technomancy_engine::outside::bot::BotOutside;
+
+/// How often the engine is run for every simulated game, games that have not ended by then are
+/// reported as still going
+const SIMULATED_RUNS: usize = 500;
+
+/// A card pack as submitted by card authors
+///
+/// Only the data parts of cards can be described this way, effects still have to be written in
+/// Rust.
+#[derive(Debug, Deserialize)]
+pub struct CardPack {
+    pub name: String,
+    /// The subkinds introduced by this pack
+    #[serde(default)]
+    pub subkinds: Vec<String>,
+    pub cards: Vec<CardSpec>,
+    /// Preconstructed decks made of the cards of this pack
+    #[serde(default)]
+    pub decks: Vec<PreconDeck>,
+}
+
+/// A ready to play deck, so that new players can start a game without building one
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PreconDeck {
+    pub name: String,
+    pub cards: Vec<CardId>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CardSpec {
+    pub id: CardId,
+    pub name: String,
+    #[serde(default)]
+    pub cost: Option<Cost>,
+    pub kinds: Vec<KindSpec>,
+    #[serde(default)]
+    pub keywords: Vec<Keyword>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind")]
+pub enum KindSpec {
+    Agent {
+        subkind: SubKind,
+        /// Cards without a fixed power compute it in an effect
+        power: Option<u64>,
+        toughness: Option<u64>,
+    },
+    Building {
+        subkind: SubKind,
+    },
+    Quickhack,
+    Program,
+}
+
+impl KindSpec {
+    fn subkind(&self) -> Option<&SubKind> {
+        match self {
+            KindSpec::Agent { subkind, .. } | KindSpec::Building { subkind } => Some(subkind),
+            KindSpec::Quickhack | KindSpec::Program => None,
+        }
+    }
+
+    /// The kind as the engine plays it, `None` for agents whose stats are computed by an effect
+    fn kind(&self) -> Option<CardKind> {
+        let kind = match self {
+            KindSpec::Agent {
+                subkind,
+                power,
+                toughness,
+            } => BaseCardKind::Agent {
+                subkind: subkind.clone(),
+                power: AgentPower::Fixed((*power)?),
+                toughness: AgentToughness::Fixed((*toughness)?),
+            },
+            KindSpec::Building { subkind } => BaseCardKind::Building {
+                subkind: subkind.clone(),
+            },
+            KindSpec::Quickhack => BaseCardKind::Quickhack,
+            KindSpec::Program => BaseCardKind::Program,
+        };
+        Some(CardKind { kind })
+    }
+}
+
+impl CardSpec {
+    /// The card as the engine plays it, if it can be played without effects
+    fn card(&self) -> Option<Card> {
+        Some(Card {
+            id: self.id,
+            version: 1,
+            behaviour: CardBehaviour {
+                cost: self.cost.clone(),
+                kind: self
+                    .kinds
+                    .iter()
+                    .map(KindSpec::kind)
+                    .collect::<Option<_>>()?,
+                effects: vec![],
+                keywords: self.keywords.clone(),
+                alternative_costs: vec![],
+            },
+            layout: CardLayout::Single,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    /// The card the diagnostic is about, if it is about a single one
+    card: Option<CardId>,
+    message: String,
+}
+
+impl Diagnostic {
+    fn new(card: Option<CardId>, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            card,
+            message: message.into(),
+        }
+    }
+}
+
+/// How a preconstructed deck fared when bots played it against itself
+#[derive(Debug, Serialize)]
+pub struct SimulationReport {
+    deck: String,
+    /// How the game ended, `None` if it was still going after [`SIMULATED_RUNS`] runs
+    result: Option<GameResult>,
+    /// The turn the game was in when the simulation stopped
+    turn: usize,
+    /// The error that stopped the game, if any
+    error: Option<String>,
+}
+
+/// The result of checking a card pack, a pack is valid if there are no errors
+#[derive(Debug, Serialize, Default)]
+pub struct ValidationReport {
+    valid: bool,
+    cards: usize,
+    errors: Vec<Diagnostic>,
+    warnings: Vec<Diagnostic>,
+    /// The games played with the decks of valid packs
+    simulations: Vec<SimulationReport>,
+}
+
+impl CardPack {
+    /// Reads the pack, refusing cards with costs no format allows
+    ///
+    /// Costs of zero are read as no cost at all, see [`Cost::canonical`].
+    pub fn load(path: &Utf8Path) -> std::io::Result<CardPack> {
+        let mut pack: CardPack = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        for card in &mut pack.cards {
+            card.cost = Cost::canonical(card.cost.take());
+            if let Some(Err(error)) = card.cost.as_ref().map(|c| c.validate(None)) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("`{}`: {error}", card.name),
+                ));
+            }
+        }
+        Ok(pack)
+    }
+
+    /// Checks the pack against the rules of the base game
+    pub fn lint(&self) -> ValidationReport {
+        let mut report = ValidationReport {
+            cards: self.cards.len(),
+            ..Default::default()
+        };
+
+        let mut subkinds = SubKindRegistry::base();
+        for name in &self.subkinds {
+            if subkinds.get(name).is_some() {
+                report.warnings.push(Diagnostic::new(
+                    None,
+                    format!("The subkind `{name}` already exists"),
+                ));
+            }
+            subkinds.register(name);
+        }
+
+        let mut seen = HashSet::new();
+        for card in &self.cards {
+            let id = Some(card.id);
+
+            if !seen.insert(card.id) {
+                report.errors.push(Diagnostic::new(
+                    id,
+                    format!("`{}` reuses the id of another card", card.name),
+                ));
+            }
+
+            if let Some(Err(error)) = card.cost.as_ref().map(|c| c.validate(None)) {
+                report.errors.push(Diagnostic::new(id, error.to_string()));
+            }
+
+            if card.kinds.is_empty() {
+                report
+                    .errors
+                    .push(Diagnostic::new(id, "The card has no kind"));
+            }
+
+            if card.kinds.iter().any(|kind| kind.kind().is_none()) {
+                report.warnings.push(Diagnostic::new(
+                    id,
+                    "The stats of the card are computed by an effect, decks with it are not simulated",
+                ));
+            }
+
+            for subkind in card.kinds.iter().filter_map(KindSpec::subkind) {
+                if !subkinds.contains(subkind) {
+                    report.errors.push(Diagnostic::new(
+                        id,
+                        format!("Unknown subkind `{}`", subkind.name()),
+                    ));
+                }
+            }
+
+            let mut keywords = HashSet::new();
+            for keyword in &card.keywords {
+                if !keywords.insert(keyword) {
+                    report
+                        .warnings
+                        .push(Diagnostic::new(id, format!("{keyword:?} is given twice")));
+                }
+            }
+        }
+
+        let mut deck_names = HashSet::new();
+        for deck in &self.decks {
+            if !deck_names.insert(&deck.name) {
+                report.errors.push(Diagnostic::new(
+                    None,
+                    format!("The deck `{}` is defined twice", deck.name),
+                ));
+            }
+            if deck.cards.is_empty() {
+                report.errors.push(Diagnostic::new(
+                    None,
+                    format!("The deck `{}` has no cards", deck.name),
+                ));
+            }
+            for card in deck.cards.iter().filter(|c| !seen.contains(*c)) {
+                report.errors.push(Diagnostic::new(
+                    Some(*card),
+                    format!(
+                        "The deck `{}` uses a card that is not in the pack",
+                        deck.name
+                    ),
+                ));
+            }
+        }
+
+        report.valid = report.errors.is_empty();
+        report
+    }
+
+    /// Lets bots play every deck of the pack against itself
+    ///
+    /// Packs can not describe effects, so the cards are played without them.
+    pub async fn simulate(&self) -> Vec<SimulationReport> {
+        let cards: Vec<_> = self.cards.iter().filter_map(CardSpec::card).collect();
+        let playable: HashSet<_> = cards.iter().map(|card| card.id).collect();
+
+        let mut subkinds = SubKindRegistry::base();
+        for name in &self.subkinds {
+            subkinds.register(name);
+        }
+        let builder = EngineBuilder::new()
+            .cards(cards)
+            .subkinds(Arc::new(subkinds))
+            .seed(0);
+
+        let mut reports = vec![];
+        for deck in &self.decks {
+            if !deck.cards.iter().all(|card| playable.contains(card)) {
+                continue;
+            }
+
+            let players = (0..2)
+                .map(|_| Player {
+                    id: PlayerId::new(),
+                    initial_cards: deck.cards.clone(),
+                    preferences: Default::default(),
+                })
+                .collect();
+            let mut game = builder.build(players);
+
+            let mut error = None;
+            for _ in 0..SIMULATED_RUNS {
+                if game.result().is_some() {
+                    break;
+                }
+                if let Err(e) = game.run(&BotOutside).await {
+                    error = Some(e.to_string());
+                    break;
+                }
+            }
+
+            reports.push(SimulationReport {
+                deck: deck.name.clone(),
+                result: game.result(),
+                turn: game.latest_gamestate().turn,
+                error,
+            });
+        }
+        reports
+    }
+}
+
+/// Checks an uploaded card pack without installing it anywhere, valid packs are simulated as well
+///
+/// Packs that can not be read are reported the same way as packs that break the rules, so that
+/// authors get all problems from the same place.
+pub async fn validate_card_pack(body: Bytes) -> Json<ValidationReport> {
+    let report = match serde_json::from_slice::<CardPack>(&body) {
+        Ok(pack) => {
+            let mut report = pack.lint();
+            if report.valid {
+                report.simulations = pack.simulate().await;
+            }
+            report
+        }
+        Err(error) => ValidationReport {
+            errors: vec![Diagnostic::new(None, error.to_string())],
+            ..Default::default()
+        },
+    };
+
+    Json(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::CardPack;
+
+    #[tokio::test]
+    async fn check_decks_are_simulated() {
+        let agent = "00000000-0000-0000-0000-000000000001";
+        let computed = "00000000-0000-0000-0000-000000000002";
+        let pack: CardPack = serde_json::from_value(json!({
+            "name": "Test pack",
+            "cards": [
+                {
+                    "id": agent,
+                    "name": "Hired gun",
+                    "kinds": [{ "kind": "Agent", "subkind": "Mercenary", "power": 3, "toughness": 6 }]
+                },
+                {
+                    "id": computed,
+                    "name": "Growing gun",
+                    "kinds": [{ "kind": "Agent", "subkind": "Mercenary", "power": null, "toughness": 1 }]
+                }
+            ],
+            "decks": [
+                { "name": "Guns", "cards": vec![agent; 20] },
+                { "name": "Growing guns", "cards": vec![computed; 20] }
+            ]
+        }))
+        .unwrap();
+
+        let report = pack.lint();
+        assert!(report.valid);
+        assert_eq!(report.warnings.len(), 1);
+
+        // Only the deck without computed stats can be played
+        let simulations = pack.simulate().await;
+        assert_eq!(simulations.len(), 1);
+        assert_eq!(simulations[0].deck, "Guns");
+        assert_eq!(simulations[0].error, None);
+        assert!(simulations[0].turn > 1);
+    }
+}
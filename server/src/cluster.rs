@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::extract::State;
+use axum::http::header;
+use axum::http::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Redirect;
+use axum::response::Response;
+use axum::Json;
+use serde::Deserialize;
+use serde::Serialize;
+use technomancy_core::GameId;
+use tracing::debug;
+use xxhash_rust::xxh3::xxh3_64;
+
+/// How many points each replica gets on the ring, more points spread games more evenly
+const VIRTUAL_NODES: u32 = 64;
+
+/// A web server replica that can hold engine bridges
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Replica {
+    pub name: String,
+    /// The base URL under which this replica is reachable, e.g. `https://replica-1.example.com`
+    pub address: String,
+}
+
+impl std::str::FromStr for Replica {
+    type Err = String;
+
+    /// Parses `<name>=<address>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, address) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected `<name>=<address>`, got `{s}`"))?;
+
+        Ok(Replica {
+            name: name.to_string(),
+            address: address.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+/// Assigns every game to exactly one replica using consistent hashing
+///
+/// Every replica builds the same ring from the same list of replicas, so they all agree on where
+/// a game lives without having to talk to each other. The points are xxh3 hashes of fixed bytes,
+/// so they are the same on every platform and with every Rust version, unlike `std::hash` which
+/// may change between releases. Adding or removing a replica only moves the games that were next
+/// to it on the ring.
+#[derive(Debug)]
+pub struct Cluster {
+    local: String,
+    replicas: Vec<Replica>,
+    ring: BTreeMap<u64, usize>,
+}
+
+impl Cluster {
+    /// Creates the ring, the local replica is always part of it
+    pub fn new(local: String, mut replicas: Vec<Replica>) -> Cluster {
+        if !replicas.iter().any(|r| r.name == local) {
+            // An empty address makes all links relative to the replica that answered
+            replicas.push(Replica {
+                name: local.clone(),
+                address: String::new(),
+            });
+        }
+        replicas.sort_by(|a, b| a.name.cmp(&b.name));
+        replicas.dedup_by(|a, b| a.name == b.name);
+
+        let ring = replicas
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, replica)| {
+                (0..VIRTUAL_NODES).map(move |node| {
+                    let point = [replica.name.as_bytes(), &node.to_le_bytes()].concat();
+                    (xxh3_64(&point), idx)
+                })
+            })
+            .collect();
+
+        Cluster {
+            local,
+            replicas,
+            ring,
+        }
+    }
+
+    pub fn owner_of(&self, game_id: &GameId) -> &Replica {
+        let hash = xxh3_64(game_id.as_bytes());
+        let (_, idx) = self
+            .ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .expect("The ring always contains the local replica");
+
+        &self.replicas[*idx]
+    }
+
+    pub fn is_local(&self, game_id: &GameId) -> bool {
+        self.owner_of(game_id).name == self.local
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GameRoute {
+    game_id: GameId,
+}
+
+/// The handshake a client does before opening the WebSocket of a game
+///
+/// Any replica can answer it, the client then connects directly to the returned replica.
+pub async fn locate_game(
+    State(cluster): State<Arc<Cluster>>,
+    Path(GameRoute { game_id }): Path<GameRoute>,
+) -> impl IntoResponse {
+    Json(cluster.owner_of(&game_id).clone())
+}
+
+/// Redirects requests for games that are held by another replica
+///
+/// Clients that skipped the handshake, or that are using a stale answer after the cluster
+/// changed, are sent to the replica that now owns the game. Browsers do not follow redirects when
+/// opening a WebSocket, so those are refused with `421 Misdirected Request` and the owning
+/// [`Replica`] instead, for the client to connect to it directly.
+pub async fn route_to_owner<B>(
+    State(cluster): State<Arc<Cluster>>,
+    Path(GameRoute { game_id }): Path<GameRoute>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if cluster.is_local(&game_id) {
+        return next.run(request).await;
+    }
+
+    let owner = cluster.owner_of(&game_id);
+    if request.headers().contains_key(header::UPGRADE) {
+        debug!(?game_id, owner = ?owner.name, "Refusing an upgrade meant for another replica");
+        return (StatusCode::MISDIRECTED_REQUEST, Json(owner.clone())).into_response();
+    }
+
+    debug!(?game_id, owner = ?owner.name, "Redirecting to the owning replica");
+
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or_default();
+    Redirect::temporary(&format!("{}{path}", owner.address)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use technomancy_core::GameId;
+
+    use super::Cluster;
+    use super::Replica;
+
+    fn replicas(names: &[&str]) -> Vec<Replica> {
+        names
+            .iter()
+            .map(|name| Replica {
+                name: name.to_string(),
+                address: format!("https://{name}.example.com"),
+            })
+            .collect()
+    }
+
+    fn game(id: &str) -> GameId {
+        serde_json::from_value(serde_json::json!(id)).unwrap()
+    }
+
+    fn games() -> Vec<GameId> {
+        (0..500).map(|_| GameId::new()).collect()
+    }
+
+    #[test]
+    fn check_routing_is_pinned() {
+        // Fails if the hash changes, which would route games differently on old and new builds
+        let cluster = Cluster::new(String::from("a"), replicas(&["a", "b", "c"]));
+        let owners: Vec<_> = [
+            "00000000-0000-0000-0000-000000000000",
+            "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "936da01f-9abd-4d9d-80c7-02af85c822a8",
+            "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8",
+        ]
+        .into_iter()
+        .map(|id| cluster.owner_of(&game(id)).name.as_str())
+        .collect();
+
+        assert_eq!(owners, vec!["a", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn check_all_replicas_agree() {
+        let names = ["a", "b", "c"];
+        let clusters: Vec<_> = names
+            .iter()
+            .map(|local| {
+                let mut list = replicas(&names);
+                list.rotate_left(names.iter().position(|n| n == local).unwrap());
+                Cluster::new(local.to_string(), list)
+            })
+            .collect();
+
+        for game_id in games() {
+            let owner = clusters[0].owner_of(&game_id);
+            assert!(clusters.iter().all(|c| c.owner_of(&game_id) == owner));
+            assert_eq!(clusters.iter().filter(|c| c.is_local(&game_id)).count(), 1);
+        }
+    }
+
+    #[test]
+    fn check_local_replica_is_always_on_the_ring() {
+        let cluster = Cluster::new(String::from("solo"), vec![]);
+        let game_id = GameId::new();
+
+        assert!(cluster.is_local(&game_id));
+        assert_eq!(cluster.owner_of(&game_id).address, "");
+    }
+
+    #[test]
+    fn check_removing_a_replica_only_moves_its_games() {
+        let before = Cluster::new(String::from("a"), replicas(&["a", "b", "c"]));
+        let after = Cluster::new(String::from("a"), replicas(&["a", "b"]));
+
+        let mut moved = 0;
+        for game_id in games() {
+            let owner = &before.owner_of(&game_id).name;
+            if owner == "c" {
+                moved += 1;
+            } else {
+                assert_eq!(&after.owner_of(&game_id).name, owner);
+            }
+        }
+        // Every replica gets a share of the games
+        assert!(moved > 0);
+    }
+}
@@ -0,0 +1,68 @@
+use std::net::SocketAddr;
+
+use tarpc::client::RpcError;
+use tarpc::context::Context;
+use tarpc::transport::channel::UnboundedChannel;
+use tarpc::ClientMessage;
+use tarpc::Response;
+use technomancy_core::meta::spawn_twoway;
+use technomancy_core::meta::CreateGamesError;
+use technomancy_core::meta::CreationKey;
+use technomancy_core::meta::MetaClient;
+use technomancy_core::meta::Seat;
+use technomancy_core::outside::OutsideRequest;
+use technomancy_core::outside::OutsideResponse;
+use technomancy_core::GameId;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error("Could not reach the engine: {0}")]
+    Rpc(#[from] RpcError),
+    #[error(transparent)]
+    CreateGames(#[from] CreateGamesError),
+}
+
+/// The connection to the engine that runs the games of lobbies
+///
+/// The engine asks the players of its games over the same connection. Those prompts are not
+/// relayed to the players yet, so the games wait for them until the WebSocket of a game is served.
+#[derive(Debug)]
+pub struct EngineConnection {
+    meta: MetaClient,
+    /// Receives the prompts of the engine, it is kept open so that they wait instead of failing
+    _outside: UnboundedChannel<ClientMessage<OutsideRequest>, Response<OutsideResponse>>,
+}
+
+impl EngineConnection {
+    pub async fn connect(addr: SocketAddr) -> std::io::Result<EngineConnection> {
+        let transport =
+            tarpc::serde_transport::tcp::connect(addr, tarpc::tokio_serde::formats::Json::default)
+                .await?;
+        let (outside, meta) = spawn_twoway::<OutsideRequest, OutsideResponse, _, _, _>(transport);
+
+        Ok(EngineConnection {
+            meta: MetaClient::new(Default::default(), meta).spawn(),
+            _outside: outside,
+        })
+    }
+
+    /// Creates the game of a lobby, the same key always gives the same game
+    pub async fn create_game(
+        &self,
+        key: CreationKey,
+        seats: Vec<Seat>,
+    ) -> Result<GameId, EngineError> {
+        let game = self
+            .meta
+            .create_game(Context::current(), seats, Some(key), Default::default())
+            .await??;
+        Ok(game)
+    }
+
+    /// Whether the game has a result, games the engine does not know anymore are over as well
+    pub async fn is_over(&self, game: GameId) -> Result<bool, EngineError> {
+        let status = self.meta.get_game_status(Context::current(), game).await?;
+        Ok(status.map_or(true, |status| status.result.is_some()))
+    }
+}
@@ -0,0 +1,65 @@
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::Json;
+use serde_json::json;
+
+use crate::i18n::Translations;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ApiErrorKind {
+    LobbyNotFound,
+    InvalidDeck,
+    NotLobbyOwner,
+    UnknownDeck,
+    EngineUnavailable,
+}
+
+impl ApiErrorKind {
+    fn code(self) -> &'static str {
+        match self {
+            ApiErrorKind::LobbyNotFound => "lobby_not_found",
+            ApiErrorKind::InvalidDeck => "invalid_deck",
+            ApiErrorKind::NotLobbyOwner => "not_lobby_owner",
+            ApiErrorKind::UnknownDeck => "unknown_deck",
+            ApiErrorKind::EngineUnavailable => "engine_unavailable",
+        }
+    }
+
+    fn status(self) -> StatusCode {
+        match self {
+            ApiErrorKind::LobbyNotFound => StatusCode::NOT_FOUND,
+            ApiErrorKind::InvalidDeck => StatusCode::BAD_REQUEST,
+            ApiErrorKind::NotLobbyOwner => StatusCode::FORBIDDEN,
+            ApiErrorKind::UnknownDeck => StatusCode::NOT_FOUND,
+            ApiErrorKind::EngineUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+/// An error that is sent to the client as JSON, with a message in their language
+#[derive(Debug)]
+pub struct ApiError {
+    kind: ApiErrorKind,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(translations: &Translations, locale: &str, kind: ApiErrorKind) -> ApiError {
+        let key = format!("error.{}", kind.code());
+        ApiError {
+            kind,
+            message: translations.get(locale, &key).to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.kind.status(),
+            Json(json!({ "error": self.kind.code(), "message": self.message })),
+        )
+            .into_response()
+    }
+}
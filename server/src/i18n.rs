@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use axum::extract::FromRequestParts;
+use axum::http::header::ACCEPT_LANGUAGE;
+use axum::http::request::Parts;
+use camino::Utf8Path;
+use handlebars::Context;
+use handlebars::Handlebars;
+use handlebars::Helper;
+use handlebars::HelperDef;
+use handlebars::HelperResult;
+use handlebars::Output;
+use handlebars::RenderContext;
+use handlebars::RenderError;
+use tracing::warn;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// All translated strings, by locale and then by key
+#[derive(Debug, Default)]
+pub struct Translations {
+    catalogs: HashMap<String, HashMap<String, String>>,
+}
+
+impl Translations {
+    /// Loads every `<locale>.json` file in the given directory
+    pub fn load(directory: &Utf8Path) -> std::io::Result<Translations> {
+        let mut catalogs = HashMap::new();
+
+        for entry in directory.read_dir_utf8()? {
+            let path = entry?.into_path();
+            if path.extension() != Some("json") {
+                continue;
+            }
+            let Some(locale) = path.file_stem() else {
+                continue;
+            };
+
+            let catalog = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            catalogs.insert(locale.to_string(), catalog);
+        }
+
+        Ok(Translations { catalogs })
+    }
+
+    /// Looks up the key, falling back to the default locale and then to the key itself
+    pub fn get<'t>(&'t self, locale: &str, key: &'t str) -> &'t str {
+        [locale, DEFAULT_LOCALE]
+            .into_iter()
+            .find_map(|locale| self.catalogs.get(locale)?.get(key))
+            .map(String::as_str)
+            .unwrap_or_else(|| {
+                warn!(?locale, ?key, "Missing translation");
+                key
+            })
+    }
+
+    /// Picks the first supported locale out of an `Accept-Language` header
+    pub fn negotiate(&self, accept_language: &str) -> String {
+        accept_language
+            .split(',')
+            .filter_map(|lang| lang.split(';').next())
+            .map(|lang| lang.trim().to_lowercase())
+            .find_map(|lang| {
+                let primary = lang.split('-').next().unwrap_or_default();
+                [lang.as_str(), primary]
+                    .into_iter()
+                    .find(|l| self.catalogs.contains_key(*l))
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+    }
+}
+
+/// The locale a request should be answered in
+pub struct UserLocale(pub String);
+
+#[async_trait::async_trait]
+impl<S> FromRequestParts<S> for UserLocale
+where
+    Arc<Translations>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let translations = Arc::<Translations>::from_ref(state);
+        let accept_language = parts
+            .headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or_default();
+
+        Ok(UserLocale(translations.negotiate(accept_language)))
+    }
+}
+
+/// The `{{t "key"}}` template helper, it uses the `locale` of the rendered data
+pub struct TranslateHelper {
+    pub translations: Arc<Translations>,
+}
+
+impl HelperDef for TranslateHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let key = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or_else(|| RenderError::new("`t` requires a translation key"))?;
+        let locale = ctx
+            .data()
+            .get("locale")
+            .and_then(|l| l.as_str())
+            .unwrap_or(DEFAULT_LOCALE);
+
+        out.write(self.translations.get(locale, key))?;
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::sync::Arc;
 
 use axum::extract::Path;
 use axum::extract::State;
@@ -10,8 +12,22 @@ use axum_template::RenderHtml;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::json;
+use technomancy_core::card::CardId;
+use technomancy_core::meta::CreationKey;
+use technomancy_core::meta::Seat;
+use technomancy_core::GameId;
+use technomancy_core::Player;
+use technomancy_core::PlayerId;
+use technomancy_core::PlayerPreferences;
+use tracing::debug;
+use tracing::warn;
 
+use crate::error::ApiError;
+use crate::error::ApiErrorKind;
+use crate::i18n::Translations;
+use crate::i18n::UserLocale;
 use crate::user::User;
+use crate::GameEngine;
 use crate::LobbyStorage;
 use crate::PathKey;
 use crate::TemplateEngine;
@@ -22,21 +38,86 @@ pub struct Lobby {
     pub(crate) name: String,
     pub(crate) owner: String,
     pub(crate) users: HashSet<String>,
+    /// Whether players in this lobby have to confirm a preview of each of their actions
+    pub(crate) preview_mode: bool,
+    /// The deck each user chose
+    pub(crate) decks: HashMap<String, Vec<CardId>>,
+    /// Who sits where once the game was started, in turn order
+    pub(crate) seats: Vec<Seat>,
+    /// How many games the lobby started before the one it is starting now
+    pub(crate) attempt: u64,
+    /// The game the engine created for the current attempt
+    pub(crate) game: Option<GameId>,
+}
+
+impl Lobby {
+    /// Seats every user with their chosen deck, the owner goes first and the others follow by name
+    ///
+    /// Seats are only assigned once, so that users keep their player when they come back. In
+    /// preview mode every player confirms the result of their actions before they happen.
+    pub(crate) fn assign_seats(&mut self) -> &[Seat] {
+        if self.seats.is_empty() {
+            let mut users: Vec<_> = self.users.iter().cloned().collect();
+            users.sort_by_key(|user| (*user != self.owner, user.clone()));
+
+            self.seats = users
+                .into_iter()
+                .map(|user| Seat {
+                    player: Player {
+                        id: PlayerId::new(),
+                        initial_cards: self.decks.get(&user).cloned().unwrap_or_default(),
+                        preferences: PlayerPreferences {
+                            preview_actions: self.preview_mode,
+                            ..Default::default()
+                        },
+                    },
+                    user,
+                })
+                .collect();
+        }
+
+        &self.seats
+    }
+
+    /// The key under which the engine creates the lobby's game
+    ///
+    /// Starting the game again before it is over gives the same key, so the engine hands out the
+    /// game it already created.
+    pub(crate) fn creation_key(&self) -> CreationKey {
+        CreationKey {
+            lobby: self.id.clone(),
+            attempt: self.attempt,
+        }
+    }
+
+    /// Forgets the game that is over, starting the lobby again creates a new one
+    pub(crate) fn game_ended(&mut self) {
+        if self.game.take().is_some() {
+            self.attempt += 1;
+        }
+    }
 }
 
 pub async fn list_lobbies(
     State(lobbies): State<LobbyStorage>,
     engine: TemplateEngine,
+    UserLocale(locale): UserLocale,
     PathKey(key): PathKey,
 ) -> impl IntoResponse {
     let lobbies = lobbies.read().await;
     let all_lobbies = lobbies.values().collect::<Vec<_>>();
-    RenderHtml(key, engine, json!({ "lobbies": all_lobbies }))
+    RenderHtml(
+        key,
+        engine,
+        json!({ "lobbies": all_lobbies, "locale": locale }),
+    )
 }
 
 #[derive(Debug, Deserialize)]
 pub struct NewLobbyForm {
     name: String,
+    /// Checkboxes are only sent if they are checked
+    preview_mode: Option<String>,
 }
 
 pub async fn create_lobby(
@@ -51,6 +132,11 @@ pub async fn create_lobby(
         owner: user.name.clone(),
         users: [user.name.clone()].into(),
         id: id.clone(),
+        preview_mode: new_lobby.preview_mode.is_some(),
+        decks: Default::default(),
+        seats: vec![],
+        attempt: 0,
+        game: None,
     };
     lobbies.insert(id.clone(), new_lobby);
 
@@ -59,23 +145,171 @@ pub async fn create_lobby(
 
 pub async fn join_lobby(
     State(lobbies): State<LobbyStorage>,
+    State(translations): State<Arc<Translations>>,
     Extension(user): Extension<User>,
+    UserLocale(locale): UserLocale,
     Path(lobby_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<Redirect, ApiError> {
     let mut lobbies = lobbies.write().await;
-    let lobby = lobbies.get_mut(&lobby_id).unwrap();
+    let lobby = lobbies
+        .get_mut(&lobby_id)
+        .ok_or_else(|| ApiError::new(&translations, &locale, ApiErrorKind::LobbyNotFound))?;
     lobby.users.insert(user.name.clone());
 
-    Redirect::to(&format!("/lobbies/{lobby_id}"))
+    Ok(Redirect::to(&format!("/lobbies/{lobby_id}")))
 }
 
 pub async fn show_lobby(
     State(lobbies): State<LobbyStorage>,
+    State(translations): State<Arc<Translations>>,
     engine: TemplateEngine,
+    UserLocale(locale): UserLocale,
     PathKey(key): PathKey,
     Path(lobby_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let lobbies = lobbies.read().await;
-    let lobby = lobbies.get(&lobby_id).unwrap();
-    RenderHtml(key, engine, json!({ "lobby": lobby }))
+    let lobby = lobbies
+        .get(&lobby_id)
+        .ok_or_else(|| ApiError::new(&translations, &locale, ApiErrorKind::LobbyNotFound))?;
+    Ok(RenderHtml(
+        key,
+        engine,
+        json!({ "lobby": lobby, "locale": locale }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeckForm {
+    /// The ids of the cards in the deck, separated by whitespace
+    cards: String,
+}
+
+pub async fn choose_deck(
+    State(lobbies): State<LobbyStorage>,
+    State(translations): State<Arc<Translations>>,
+    Extension(user): Extension<User>,
+    UserLocale(locale): UserLocale,
+    Path(lobby_id): Path<String>,
+    Form(deck): Form<DeckForm>,
+) -> Result<Redirect, ApiError> {
+    let mut lobbies = lobbies.write().await;
+    let lobby = lobbies
+        .get_mut(&lobby_id)
+        .ok_or_else(|| ApiError::new(&translations, &locale, ApiErrorKind::LobbyNotFound))?;
+    let cards = deck
+        .cards
+        .split_whitespace()
+        .map(|card| card.parse())
+        .collect::<Result<Vec<CardId>, _>>()
+        .map_err(|_| ApiError::new(&translations, &locale, ApiErrorKind::InvalidDeck))?;
+    lobby.decks.insert(user.name.clone(), cards);
+
+    Ok(Redirect::to(&format!("/lobbies/{lobby_id}")))
+}
+
+pub async fn start_game(
+    State(lobbies): State<LobbyStorage>,
+    State(game_engine): State<GameEngine>,
+    State(translations): State<Arc<Translations>>,
+    Extension(user): Extension<User>,
+    UserLocale(locale): UserLocale,
+    Path(lobby_id): Path<String>,
+) -> Result<Redirect, ApiError> {
+    let mut lobbies = lobbies.write().await;
+    let lobby = lobbies
+        .get_mut(&lobby_id)
+        .ok_or_else(|| ApiError::new(&translations, &locale, ApiErrorKind::LobbyNotFound))?;
+    if lobby.owner != user.name {
+        return Err(ApiError::new(
+            &translations,
+            &locale,
+            ApiErrorKind::NotLobbyOwner,
+        ));
+    }
+
+    let Some(game_engine) = game_engine else {
+        let key = lobby.creation_key();
+        let seats = lobby.assign_seats();
+        debug!(
+            ?key,
+            seats = seats.len(),
+            "Assigned seats, no engine is connected"
+        );
+        return Ok(Redirect::to(&format!("/lobbies/{lobby_id}")));
+    };
+
+    let unavailable = |error| {
+        warn!(?lobby_id, "Could not start the game: {error}");
+        ApiError::new(&translations, &locale, ApiErrorKind::EngineUnavailable)
+    };
+
+    if let Some(game) = lobby.game {
+        if game_engine.is_over(game).await.map_err(unavailable)? {
+            lobby.game_ended();
+        }
+    }
+
+    let key = lobby.creation_key();
+    let seats = lobby.assign_seats().to_vec();
+    let game = game_engine
+        .create_game(key.clone(), seats)
+        .await
+        .map_err(unavailable)?;
+    debug!(?key, ?game, "Started the game");
+    lobby.game = Some(game);
+
+    Ok(Redirect::to(&format!("/lobbies/{lobby_id}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use technomancy_core::GameId;
+
+    use super::Lobby;
+
+    fn lobby(preview_mode: bool) -> Lobby {
+        Lobby {
+            id: String::from("alice_lobby"),
+            name: String::from("Alice's lobby"),
+            owner: String::from("alice"),
+            users: [String::from("alice"), String::from("bob")].into(),
+            preview_mode,
+            decks: Default::default(),
+            seats: vec![],
+            attempt: 0,
+            game: None,
+        }
+    }
+
+    #[test]
+    fn check_preview_mode_is_given_to_all_players() {
+        let mut previewing = lobby(true);
+        assert!(previewing
+            .assign_seats()
+            .iter()
+            .all(|seat| seat.player.preferences.preview_actions));
+
+        let mut plain = lobby(false);
+        assert!(plain
+            .assign_seats()
+            .iter()
+            .all(|seat| !seat.player.preferences.preview_actions));
+    }
+
+    #[test]
+    fn check_ended_games_are_followed_by_a_new_attempt() {
+        let mut lobby = lobby(false);
+        let first = lobby.creation_key();
+
+        // Nothing was started yet, so there is nothing to end
+        lobby.game_ended();
+        assert_eq!(lobby.creation_key(), first);
+
+        lobby.game = Some(GameId::new());
+        assert_eq!(lobby.creation_key(), first);
+
+        lobby.game_ended();
+        assert_eq!(lobby.game, None);
+        assert_eq!(lobby.creation_key().attempt, first.attempt + 1);
+    }
 }
@@ -8,6 +8,8 @@ use axum::extract::FromRequestParts;
 use axum::extract::MatchedPath;
 use axum::extract::State;
 use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::middleware;
 use axum::response::IntoResponse;
 use axum::response::Redirect;
 use axum::routing::get;
@@ -25,18 +27,34 @@ use axum_sessions::SessionLayer;
 use axum_template::engine::Engine;
 use axum_template::RenderHtml;
 use camino::Utf8PathBuf;
+use card_pack::CardPack;
+use card_pack::PreconDeck;
 use clap::Parser;
+use cluster::Cluster;
+use cluster::Replica;
+use engine::EngineConnection;
 use handlebars::Handlebars;
+use i18n::TranslateHelper;
+use i18n::Translations;
+use i18n::UserLocale;
 use lobby::Lobby;
+use queue::CasualQueue;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_json::json;
 use tokio::sync::RwLock;
 use tower_http::services::ServeDir;
 use tracing::trace;
 use tracing_subscriber::EnvFilter;
 use user::User;
 
+mod card_pack;
+mod cluster;
+mod engine;
+mod error;
+mod i18n;
 mod lobby;
+mod queue;
 mod user;
 
 #[derive(Debug, clap::Parser)]
@@ -47,6 +65,27 @@ struct Args {
 
     #[arg(long, default_value_t = Utf8PathBuf::from("./server/static"))]
     static_directory: Utf8PathBuf,
+
+    #[arg(long, default_value_t = Utf8PathBuf::from("./server/locales"))]
+    locale_directory: Utf8PathBuf,
+
+    /// The name of this replica in the cluster
+    #[arg(long, default_value = "local")]
+    replica_name: String,
+
+    /// The replicas of the cluster as `<name>=<address>`, may be given multiple times
+    #[arg(long = "replica")]
+    replicas: Vec<Replica>,
+
+    /// Card packs whose preconstructed decks are offered to players, may be given multiple times
+    #[arg(long = "card-pack")]
+    card_packs: Vec<Utf8PathBuf>,
+
+    /// The address of the engine that runs the games of lobbies
+    ///
+    /// Without one, starting a lobby only assigns its seats.
+    #[arg(long)]
+    engine: Option<SocketAddr>,
 }
 
 #[tokio::main]
@@ -60,7 +99,25 @@ async fn main() {
     let args = Args::parse();
 
     trace!("Building app");
-    let app = app(args.template_directory, args.static_directory);
+    let cluster = Cluster::new(args.replica_name, args.replicas);
+    let precons = args
+        .card_packs
+        .iter()
+        .flat_map(|path| CardPack::load(path).unwrap().decks)
+        .map(|deck| (deck.name.clone(), deck))
+        .collect();
+    let game_engine = match args.engine {
+        Some(addr) => Some(EngineConnection::connect(addr).await.unwrap()),
+        None => None,
+    };
+    let app = app(
+        args.template_directory,
+        args.static_directory,
+        args.locale_directory,
+        cluster,
+        precons,
+        game_engine,
+    );
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     tracing::debug!(?addr, "listening on http://{addr}");
@@ -75,6 +132,9 @@ async fn main() {
 type TemplateEngine = Engine<Handlebars<'static>>;
 type UserStorage = Arc<RwLock<HashMap<String, User>>>;
 type LobbyStorage = Arc<RwLock<HashMap<String, Lobby>>>;
+type PreconStorage = Arc<HashMap<String, PreconDeck>>;
+type QueueStorage = Arc<RwLock<CasualQueue>>;
+type GameEngine = Option<Arc<EngineConnection>>;
 
 pub struct PathKey(pub String);
 
@@ -106,12 +166,24 @@ struct AppState {
     engine: TemplateEngine,
     user_storage: UserStorage,
     lobby_storage: LobbyStorage,
+    translations: Arc<Translations>,
+    cluster: Arc<Cluster>,
+    precons: PreconStorage,
+    casual_queue: QueueStorage,
+    game_engine: GameEngine,
 }
 
 type Auth = AuthContext<String, User, AuthMemoryStore<String, User>>;
 type RequireAuth = RequireAuthorizationLayer<String, User>;
 
-fn app(template_directory: Utf8PathBuf, static_directory: Utf8PathBuf) -> Router {
+fn app(
+    template_directory: Utf8PathBuf,
+    static_directory: Utf8PathBuf,
+    locale_directory: Utf8PathBuf,
+    cluster: Cluster,
+    precons: HashMap<String, PreconDeck>,
+    game_engine: Option<EngineConnection>,
+) -> Router {
     let secret = [0u8; 64];
 
     let session_store = SessionMemoryStore::new();
@@ -127,9 +199,18 @@ fn app(template_directory: Utf8PathBuf, static_directory: Utf8PathBuf) -> Router
     let user_store: AuthMemoryStore<String, User> = AuthMemoryStore::new(&store);
     let auth_layer = AuthLayer::new(user_store, &secret);
 
+    trace!("Loading translations");
+    let translations = Arc::new(Translations::load(&locale_directory).unwrap());
+
     trace!("Initializing handlebars");
     let mut hbs = Handlebars::new();
     hbs.set_dev_mode(true);
+    hbs.register_helper(
+        "t",
+        Box::new(TranslateHelper {
+            translations: translations.clone(),
+        }),
+    );
 
     hbs.register_templates_directory(".hbs", template_directory)
         .unwrap();
@@ -144,6 +225,11 @@ fn app(template_directory: Utf8PathBuf, static_directory: Utf8PathBuf) -> Router
             owner: "Nobody".to_string(),
             name: "The Default Lobby".to_string(),
             users: Default::default(),
+            preview_mode: false,
+            decks: Default::default(),
+            seats: vec![],
+            attempt: 0,
+            game: None,
         },
     )])));
 
@@ -151,14 +237,38 @@ fn app(template_directory: Utf8PathBuf, static_directory: Utf8PathBuf) -> Router
         engine: Engine::from(hbs),
         user_storage: store,
         lobby_storage,
+        translations,
+        cluster: Arc::new(cluster),
+        precons: Arc::new(precons),
+        casual_queue: Default::default(),
+        game_engine: game_engine.map(Arc::new),
     };
 
+    // Everything below `/games/:game_id/` is only served by the replica holding the game
+    let games = Router::new()
+        .route(
+            "/games/:game_id/ws",
+            get(|| async { StatusCode::NOT_IMPLEMENTED }),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            cluster::route_to_owner,
+        ));
+
     Router::new()
         .route("/", get(root))
         .route("/lobbies", get(lobby::list_lobbies))
         .route("/lobbies", post(lobby::create_lobby))
         .route("/lobbies/:lobby_id/join", post(lobby::join_lobby))
         .route("/lobbies/:lobby_id", get(lobby::show_lobby))
+        .route("/lobbies/:lobby_id/deck", post(lobby::choose_deck))
+        .route("/lobbies/:lobby_id/start", post(lobby::start_game))
+        .route("/games/:game_id/replica", get(cluster::locate_game))
+        .route("/card-packs/validate", post(card_pack::validate_card_pack))
+        .route("/precons", get(queue::list_precons))
+        .route("/queue/casual", get(queue::casual_queue_status))
+        .route("/queue/casual", post(queue::join_casual_queue))
+        .merge(games)
         .route_layer(RequireAuth::login())
         .route("/login", get(login_handler))
         .route("/login", post(do_login))
@@ -168,13 +278,25 @@ fn app(template_directory: Utf8PathBuf, static_directory: Utf8PathBuf) -> Router
         .with_state(state)
 }
 
-async fn root(Extension(user): Extension<User>, engine: TemplateEngine) -> impl IntoResponse {
+async fn root(
+    Extension(user): Extension<User>,
+    UserLocale(locale): UserLocale,
+    engine: TemplateEngine,
+) -> impl IntoResponse {
     #[derive(Debug, Serialize)]
     struct TplData {
         current_user: User,
+        locale: String,
     }
 
-    RenderHtml("root", engine, TplData { current_user: user })
+    RenderHtml(
+        "root",
+        engine,
+        TplData {
+            current_user: user,
+            locale,
+        },
+    )
 }
 
 #[derive(Debug, Deserialize)]
@@ -196,6 +318,10 @@ async fn do_login(
     Redirect::to("/")
 }
 
-async fn login_handler(engine: TemplateEngine, PathKey(key): PathKey) -> impl IntoResponse {
-    RenderHtml(key, engine, ())
+async fn login_handler(
+    engine: TemplateEngine,
+    UserLocale(locale): UserLocale,
+    PathKey(key): PathKey,
+) -> impl IntoResponse {
+    RenderHtml(key, engine, json!({ "locale": locale }))
 }
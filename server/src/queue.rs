@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::response::Redirect;
+use axum::Extension;
+use axum::Form;
+use axum::Json;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::card_pack::PreconDeck;
+use crate::error::ApiError;
+use crate::error::ApiErrorKind;
+use crate::i18n::Translations;
+use crate::i18n::UserLocale;
+use crate::lobby::Lobby;
+use crate::user::User;
+use crate::LobbyStorage;
+use crate::PreconStorage;
+use crate::QueueStorage;
+
+/// Pairs up users who want to play a casual game with a preconstructed deck
+#[derive(Debug, Default)]
+pub struct CasualQueue {
+    /// The user waiting for an opponent and the deck they picked
+    waiting: Option<(String, PreconDeck)>,
+    /// The lobby each paired user was put into, until they asked for it
+    paired: HashMap<String, String>,
+}
+
+pub async fn list_precons(State(precons): State<PreconStorage>) -> Json<Vec<PreconDeck>> {
+    Json(precons.values().cloned().collect())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueForm {
+    /// The name of the preconstructed deck
+    deck: String,
+}
+
+/// Puts the user into the casual queue, or into a lobby with the user who was already waiting
+pub async fn join_casual_queue(
+    State(queue): State<QueueStorage>,
+    State(lobbies): State<LobbyStorage>,
+    State(precons): State<PreconStorage>,
+    State(translations): State<Arc<Translations>>,
+    Extension(user): Extension<User>,
+    UserLocale(locale): UserLocale,
+    Form(form): Form<QueueForm>,
+) -> Result<Redirect, ApiError> {
+    let deck = precons
+        .get(&form.deck)
+        .cloned()
+        .ok_or_else(|| ApiError::new(&translations, &locale, ApiErrorKind::UnknownDeck))?;
+
+    let mut queue = queue.write().await;
+    let opponent = match queue.waiting.take() {
+        Some((opponent, _)) if opponent == user.name => None,
+        opponent => opponent,
+    };
+    let Some((opponent, opponent_deck)) = opponent else {
+        debug!(user = ?user.name, deck = ?deck.name, "Waiting in the casual queue");
+        queue.waiting = Some((user.name.clone(), deck));
+        return Ok(Redirect::to("/queue/casual"));
+    };
+
+    // Users may be paired again while their last lobby is still around
+    let id = format!("casual_{}", uuid::Uuid::new_v4().simple());
+    let mut lobby = Lobby {
+        id: id.clone(),
+        name: format!("{opponent} vs. {}", user.name),
+        owner: opponent.clone(),
+        users: [opponent.clone(), user.name.clone()].into(),
+        preview_mode: false,
+        decks: [
+            (opponent.clone(), opponent_deck.cards),
+            (user.name.clone(), deck.cards),
+        ]
+        .into(),
+        seats: vec![],
+        attempt: 0,
+        game: None,
+    };
+    lobby.assign_seats();
+    lobbies.write().await.insert(id.clone(), lobby);
+
+    debug!(?id, "Paired users from the casual queue");
+    queue.paired.insert(opponent, id.clone());
+
+    Ok(Redirect::to(&format!("/lobbies/{id}")))
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueStatus {
+    waiting: bool,
+    /// The lobby the user was put into once they were paired
+    lobby: Option<String>,
+}
+
+pub async fn casual_queue_status(
+    State(queue): State<QueueStorage>,
+    Extension(user): Extension<User>,
+) -> impl IntoResponse {
+    let mut queue = queue.write().await;
+    let lobby = queue.paired.remove(&user.name);
+    let waiting = queue
+        .waiting
+        .as_ref()
+        .is_some_and(|(waiting, _)| *waiting == user.name);
+
+    Json(QueueStatus { waiting, lobby })
+}